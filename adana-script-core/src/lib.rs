@@ -1,12 +1,36 @@
 pub mod primitive;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
 
+use indexmap::IndexMap;
+
+use constants::{
+    ADD, DIFFERENCE, INTERSECTION, IS_SET, SET, TO_ARRAY, TO_SET, UNION,
+};
+use constants::{
+    ALL, ANY, FILTER, FIND, FLATTEN, GROUP_BY, MAP, REDUCE, REVERSE, SORT,
+    SORT_BY, UNIQUE, ZIP,
+};
+use constants::{
+    BREAK, CAPITALIZE, CATCH, CEIL, DROP, ELSE, EULER_NUMBER, FALSE, FLOOR,
+    FOR, IF, IN, IS_ARRAY, IS_BOOL, IS_DOUBLE, IS_ERROR, IS_FUNCTION, IS_I8,
+    IS_INT, IS_MATCH, IS_STRUCT, IS_U8, JSONIFY, MAKE_ERROR, MATCH, MULTILINE,
+    NULL, PARSE_JSON, PI, REPLACE, REPLACE_ALL, REQUIRE, RETURN, ROUND, STRUCT,
+    TAU, TO_BINARY, TO_HEX, TO_LOWER, TO_UPPER, TRUE, WHILE,
+};
+use constants::{CHAIN, ENUMERATE, ITER, READ_LINES, SKIP, TAKE, YIELD};
+use constants::{
+    CHARS, CONTAINS, ENDS_WITH, INDEX_OF, JOIN, LINES, PAD_LEFT, PAD_RIGHT,
+    REPEAT, SPLIT, STARTS_WITH, SUBSTRING, TRIM, TRIM_END, TRIM_START,
+};
+use constants::{
+    CHECKED_ADD, CHECKED_MUL, CHECKED_SUB, WRAPPING_ADD, WRAPPING_MUL,
+    WRAPPING_SUB,
+};
+use constants::{
+    CONST, CONTINUE, ENUM, EXPORT, FINALLY, IMPORT, LET, THROW, TRY,
+};
 use constants::{
-    BREAK, CAPITALIZE, CEIL, DROP, ELSE, EULER_NUMBER, FALSE, FLOOR, FOR, IF,
-    IN, IS_ARRAY, IS_BOOL, IS_DOUBLE, IS_ERROR, IS_FUNCTION, IS_I8, IS_INT,
-    IS_MATCH, IS_STRUCT, IS_U8, JSONIFY, MAKE_ERROR, MATCH, MULTILINE, NULL,
-    PARSE_JSON, PI, REPLACE, REPLACE_ALL, REQUIRE, RETURN, ROUND, STRUCT, TAU,
-    TO_BINARY, TO_HEX, TO_LOWER, TO_UPPER, TRUE, WHILE,
+    DICT, ENTRIES, HAS_KEY, IS_DICT, KEYS, REMOVE, TO_DICT, VALUES,
 };
 pub use primitive::Primitive;
 
@@ -79,6 +103,7 @@ pub mod constants {
     pub const COS: &str = "cos";
     pub const TAN: &str = "tan";
     pub const BREAK: &str = "break";
+    pub const CONTINUE: &str = "continue";
     pub const RETURN: &str = "return";
     pub const PRINT_LN: &str = "println";
     pub const PRINT: &str = "print";
@@ -93,7 +118,76 @@ pub mod constants {
     pub const MATCH: &str = "match";
     pub const FOR: &str = "for";
     pub const IN: &str = "in";
+    pub const TRY: &str = "try";
+    pub const CATCH: &str = "catch";
+    pub const FINALLY: &str = "finally";
+    pub const THROW: &str = "throw";
     pub const REQUIRE: &str = "require";
+    pub const MAP: &str = "map";
+    pub const FILTER: &str = "filter";
+    pub const REDUCE: &str = "reduce";
+    pub const SORT: &str = "sort";
+    pub const SORT_BY: &str = "sort_by";
+    pub const FIND: &str = "find";
+    pub const ANY: &str = "any";
+    pub const ALL: &str = "all";
+    pub const ZIP: &str = "zip";
+    pub const FLATTEN: &str = "flatten";
+    pub const REVERSE: &str = "reverse";
+    pub const UNIQUE: &str = "unique";
+    pub const GROUP_BY: &str = "group_by";
+    pub const SPLIT: &str = "split";
+    pub const JOIN: &str = "join";
+    pub const TRIM: &str = "trim";
+    pub const TRIM_START: &str = "trim_start";
+    pub const TRIM_END: &str = "trim_end";
+    pub const SUBSTRING: &str = "substring";
+    pub const CONTAINS: &str = "contains";
+    pub const STARTS_WITH: &str = "starts_with";
+    pub const ENDS_WITH: &str = "ends_with";
+    pub const INDEX_OF: &str = "index_of";
+    pub const PAD_LEFT: &str = "pad_left";
+    pub const PAD_RIGHT: &str = "pad_right";
+    pub const REPEAT: &str = "repeat";
+    pub const LINES: &str = "lines";
+    pub const CHARS: &str = "chars";
+    pub const DICT: &str = "dict";
+    pub const IS_DICT: &str = "is_dict";
+    pub const TO_DICT: &str = "to_dict";
+    pub const KEYS: &str = "keys";
+    pub const VALUES: &str = "values";
+    pub const ENTRIES: &str = "entries";
+    pub const HAS_KEY: &str = "has_key";
+    pub const REMOVE: &str = "remove";
+    pub const SET: &str = "set";
+    pub const ENUM: &str = "enum";
+    pub const CONST: &str = "const";
+    pub const LET: &str = "let";
+    pub const IMPORT: &str = "import";
+    pub const EXPORT: &str = "export";
+    pub const AS: &str = "as";
+    /// the struct a method is called on, e.g. `obj.method()`
+    pub const SELF: &str = "self";
+    pub const IS_SET: &str = "is_set";
+    pub const TO_SET: &str = "to_set";
+    pub const TO_ARRAY: &str = "to_array";
+    pub const ADD: &str = "add";
+    pub const UNION: &str = "union";
+    pub const INTERSECTION: &str = "intersection";
+    pub const DIFFERENCE: &str = "difference";
+    pub const YIELD: &str = "yield";
+    pub const ITER: &str = "iter";
+    pub const TAKE: &str = "take";
+    pub const SKIP: &str = "skip";
+    pub const ENUMERATE: &str = "enumerate";
+    pub const CHAIN: &str = "chain";
+    pub const READ_LINES: &str = "read_lines";
+    pub const WRAPPING_ADD: &str = "wrapping_add";
+    pub const WRAPPING_SUB: &str = "wrapping_sub";
+    pub const WRAPPING_MUL: &str = "wrapping_mul";
+    pub const CHECKED_ADD: &str = "checked_add";
+    pub const CHECKED_SUB: &str = "checked_sub";
+    pub const CHECKED_MUL: &str = "checked_mul";
     pub const NATIVE_LIB: &[u8; 14] = b"__native_lib__";
 }
 
@@ -139,15 +233,215 @@ impl Value {
                 | Value::VariableRef(_)
         )
     }
+
+    pub fn unspanned(&self) -> &Value {
+        match self {
+            Value::Spanned { value, .. } => value.unspanned(),
+            v => v,
+        }
+    }
+}
+
+// spans are metadata: two values parsed from different places are equal
+// as long as what they describe is equal
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Spanned { value: l, .. }, r) => l.as_ref() == r,
+            (l, Value::Spanned { value: r, .. }) => l == r.as_ref(),
+            (Value::Break, Value::Break)
+            | (Value::Null, Value::Null)
+            | (Value::NoOp, Value::NoOp)
+            | (Value::VariableUnused, Value::VariableUnused) => true,
+            (
+                Value::BreakWith { label: ll, value: lv },
+                Value::BreakWith { label: rl, value: rv },
+            ) => ll == rl && lv == rv,
+            (Value::Continue(l), Value::Continue(r)) => l == r,
+            (
+                Value::Labeled { label: ll, value: lv },
+                Value::Labeled { label: rl, value: rv },
+            ) => ll == rl && lv == rv,
+            (Value::Primitive(l), Value::Primitive(r)) => l == r,
+            (Value::EarlyReturn(l), Value::EarlyReturn(r)) => l == r,
+            (Value::Drop(l), Value::Drop(r))
+            | (Value::Throw(l), Value::Throw(r))
+            | (Value::Yield(l), Value::Yield(r))
+            | (Value::ImplicitMultiply(l), Value::ImplicitMultiply(r)) => {
+                l == r
+            }
+            (
+                Value::UnitSuffix { number: ln, unit: lu },
+                Value::UnitSuffix { number: rn, unit: ru },
+            ) => ln == rn && lu == ru,
+            (Value::Expression(l), Value::Expression(r))
+            | (Value::BlockParen(l), Value::BlockParen(r))
+            | (Value::Array(l), Value::Array(r)) => l == r,
+            (Value::Operation(l), Value::Operation(r)) => l == r,
+            (
+                Value::BuiltInFunction { fn_type: lf, expr: le },
+                Value::BuiltInFunction { fn_type: rf, expr: re },
+            ) => lf == rf && le == re,
+            (
+                Value::Function { parameters: lp, exprs: le },
+                Value::Function { parameters: rp, exprs: re },
+            ) => lp == rp && le == re,
+            (
+                Value::FunctionCall { parameters: lp, function: lf },
+                Value::FunctionCall { parameters: rp, function: rf },
+            ) => lp == rp && lf == rf,
+            (Value::Decimal(l), Value::Decimal(r)) => l == r,
+            (Value::U8(l), Value::U8(r)) => l == r,
+            (Value::I8(l), Value::I8(r)) => l == r,
+            (Value::Integer(l), Value::Integer(r)) => l == r,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (
+                Value::Range { start: ls, incl_both_end: li, end: le },
+                Value::Range { start: rs, incl_both_end: ri, end: re },
+            ) => ls == rs && li == ri && le == re,
+            (Value::String(l), Value::String(r))
+            | (Value::Variable(l), Value::Variable(r))
+            | (Value::VariableRef(l), Value::VariableRef(r))
+            | (Value::Rest(l), Value::Rest(r))
+            | (Value::VariableNegate(l), Value::VariableNegate(r)) => l == r,
+            (Value::Pattern(l), Value::Pattern(r)) => l == r,
+            (Value::FString(l, lp), Value::FString(r, rp)) => {
+                l == r && lp == rp
+            }
+            (Value::Const(l), Value::Const(r)) => l == r,
+            (
+                Value::VariableExpr { name: ln, expr: le },
+                Value::VariableExpr { name: rn, expr: re },
+            ) => ln == rn && le == re,
+            (
+                Value::Declaration {
+                    constant: lc,
+                    name: ln,
+                    type_name: lt,
+                    expr: le,
+                },
+                Value::Declaration {
+                    constant: rc,
+                    name: rn,
+                    type_name: rt,
+                    expr: re,
+                },
+            ) => lc == rc && ln == rn && lt == rt && le == re,
+            (
+                Value::Typed { value: lv, type_name: lt },
+                Value::Typed { value: rv, type_name: rt },
+            ) => lv == rv && lt == rt,
+            (
+                Value::Import { path: lp, alias: la },
+                Value::Import { path: rp, alias: ra },
+            ) => lp == rp && la == ra,
+            (Value::Export(l), Value::Export(r)) => l == r,
+            (
+                Value::IfExpr { cond: lc, exprs: le, else_expr: lee },
+                Value::IfExpr { cond: rc, exprs: re, else_expr: ree },
+            ) => lc == rc && le == re && lee == ree,
+            (
+                Value::WhileExpr { cond: lc, exprs: le },
+                Value::WhileExpr { cond: rc, exprs: re },
+            ) => lc == rc && le == re,
+            (
+                Value::TryExpr {
+                    exprs: le,
+                    catch_var: lv,
+                    catch_expr: lc,
+                    finally_expr: lf,
+                },
+                Value::TryExpr {
+                    exprs: re,
+                    catch_var: rv,
+                    catch_expr: rc,
+                    finally_expr: rf,
+                },
+            ) => le == re && lv == rv && lc == rc && lf == rf,
+            (
+                Value::MatchExpr { value: lv, arms: la },
+                Value::MatchExpr { value: rv, arms: ra },
+            ) => lv == rv && la == ra,
+            (
+                Value::ForeachExpr {
+                    var: lv,
+                    index_var: li,
+                    iterator: lit,
+                    exprs: le,
+                },
+                Value::ForeachExpr {
+                    var: rv,
+                    index_var: ri,
+                    iterator: rit,
+                    exprs: re,
+                },
+            ) => lv == rv && li == ri && lit == rit && le == re,
+            (Value::Struct(l), Value::Struct(r)) => l == r,
+            (Value::Dict(l), Value::Dict(r)) => l == r,
+            (Value::Set(l), Value::Set(r)) => l == r,
+            (
+                Value::MultiDepthAccess { root: lr, next_keys: lk },
+                Value::MultiDepthAccess { root: rr, next_keys: rk },
+            ) => lr == rr && lk == rk,
+            _ => false,
+        }
+    }
+}
+/// A script (or included file) as it was handed to the parser.
+#[derive(Debug, Default)]
+pub struct Source {
+    pub name: Option<String>,
+    pub text: String,
+}
+
+/// Byte range of an instruction in its source.
+/// The source itself is not serialized, so spans restored from a
+/// snapshot only know their offsets.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    #[serde(skip)]
+    pub source: Option<Arc<Source>>,
+}
+
+impl Debug for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Span({}..{})", self.start, self.end)
+    }
 }
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Value {
+    Spanned {
+        span: Span,
+        value: Box<Value>,
+    },
     Break,
+    /// `break 'label`, `break value` or `break 'label value`
+    BreakWith {
+        label: Option<String>,
+        value: Option<Box<Value>>,
+    },
+    /// `continue` or `continue 'label`
+    Continue(Option<String>),
+    /// `'label: while ...` or `'label: for ...`
+    Labeled {
+        label: String,
+        value: Box<Value>,
+    },
     Primitive(Primitive),
     EarlyReturn(Box<Option<Value>>),
     Drop(Box<Value>),
     Expression(Vec<Value>),
     ImplicitMultiply(Box<Value>),
+    /// a number followed by a unit symbol, e.g. `2m` or `2 m`: the number
+    /// times the variable when one of that name is in scope, a quantity
+    /// otherwise
+    UnitSuffix {
+        number: Box<Value>,
+        unit: primitive::Unit,
+    },
     Operation(Operator),
     BuiltInFunction {
         fn_type: BuiltInFunctionType,
@@ -179,12 +473,40 @@ pub enum Value {
     Variable(String),
     VariableRef(String),
     VariableUnused,
+    /// `...args`, the remaining arguments of a call in an array
+    Rest(String),
+    /// `[a, b, ...rest]` or `{ name, age }`, on the left of an assignment
+    Pattern(Box<Pattern>),
     Const(char),
     VariableNegate(String),
     VariableExpr {
         name: Box<Value>,
         expr: Box<Value>,
     },
+    /// `const x = 1` or `let x: int = 1`, a new variable in the current
+    /// scope
+    Declaration {
+        constant: bool,
+        name: String,
+        type_name: Option<String>,
+        expr: Box<Value>,
+    },
+    /// `a: int`, a parameter with a type, or `(a): bool => {..}`, a
+    /// function with the type it returns. Types are checked by `adana
+    /// check`, they are ignored when running a script
+    Typed {
+        value: Box<Value>,
+        type_name: String,
+    },
+    /// `import "./utils.adana" as utils`, the names exported by the file in
+    /// a struct
+    Import {
+        path: String,
+        alias: String,
+    },
+    /// `export name = ...`, a variable visible to the files importing this
+    /// one
+    Export(Box<Value>),
     IfExpr {
         cond: Box<Value>,
         exprs: Vec<Value>,
@@ -200,20 +522,70 @@ pub enum Value {
         iterator: Box<Value>,
         exprs: Vec<Value>,
     },
+    TryExpr {
+        exprs: Vec<Value>,
+        catch_var: Option<String>,
+        catch_expr: Option<Vec<Value>>,
+        finally_expr: Option<Vec<Value>>,
+    },
+    MatchExpr {
+        value: Box<Value>,
+        arms: Vec<MatchArm>,
+    },
+    Throw(Box<Value>),
+    /// `yield value`, makes the function a generator
+    Yield(Box<Value>),
     Array(Vec<Value>),
-    Struct(BTreeMap<String, Value>),
+    Struct(IndexMap<String, Value>),
+    /// `dict { key: value }`, keys are expressions
+    Dict(Vec<(Value, Value)>),
+    /// `set { 1, 2 }`
+    Set(Vec<Value>),
 
     MultiDepthAccess {
         root: Box<Value>,
         next_keys: Vec<KeyAccess>,
     },
 }
+/// An arm of a `match` expression: `pattern if guard => exprs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Value>,
+    pub exprs: Vec<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// `_`, matches anything
+    Wildcard,
+    /// matches anything and binds it to a variable
+    Binding(String),
+    /// a number, string, bool or null, compared with `==`
+    Literal(Value),
+    /// a `Value::Range`, e.g. `1..10` or `1..=10`
+    Range(Box<Value>),
+    /// a type as returned by `type_of`, e.g. `int` or `n: int`
+    Type { binding: Option<String>, type_name: String },
+    /// `[first, ..rest, last]`
+    Array { head: Vec<Pattern>, rest: Option<Box<Pattern>>, tail: Vec<Pattern> },
+    /// `struct { name, age: a, ..rest }`. Without rest, the struct must not
+    /// have other fields. `{ name, age: a }` ignores the other fields
+    Struct { fields: Vec<(String, Pattern)>, rest: Option<Box<Pattern>> },
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum KeyAccess {
     Index(Primitive),
     Key(Primitive),
     Variable(Value),
-    FunctionCall { key: Box<KeyAccess>, parameters: Value },
+    FunctionCall {
+        key: Box<KeyAccess>,
+        parameters: Value,
+    },
+    /// `?.key` or `?[index]`, null when the value accessed is null or the
+    /// access fails
+    Optional(Box<KeyAccess>),
 }
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum BuiltInFunctionType {
@@ -259,6 +631,60 @@ pub enum BuiltInFunctionType {
     IsArray,
     ParseJson,
     Jsonify,
+    Map,
+    Filter,
+    Reduce,
+    Sort,
+    SortBy,
+    Find,
+    Any,
+    All,
+    Zip,
+    Flatten,
+    Reverse,
+    Unique,
+    GroupBy,
+    Split,
+    Join,
+    Trim,
+    TrimStart,
+    TrimEnd,
+    Substring,
+    Contains,
+    StartsWith,
+    EndsWith,
+    IndexOf,
+    PadLeft,
+    PadRight,
+    Repeat,
+    Lines,
+    Chars,
+    IsDict,
+    ToDict,
+    Keys,
+    Values,
+    Entries,
+    HasKey,
+    Remove,
+    IsSet,
+    ToSet,
+    ToArray,
+    Add,
+    Union,
+    Intersection,
+    Difference,
+    Iter,
+    Take,
+    Skip,
+    Enumerate,
+    Chain,
+    ReadLines,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    CheckedAdd,
+    CheckedSub,
+    CheckedMul,
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
@@ -278,16 +704,28 @@ pub enum Operator {
     GreaterOrEqual,
     Equal,
     NotEqual,
+    /// `state is Status.Failed`, tests the variant of an enum value
+    Is,
     And,
     Or,
+    /// `a ?? b`, `b` when `a` is null or an error
+    NullCoalesce,
     BitwiseNot,
     BitwiseAnd,
     BitwiseOr,
     BitwiseXor,
     BitwiseLShift,
     BitwiseRShift,
+    /// `90min to h` or `3 ft in cm`, converts a quantity to another unit
+    Convert,
 }
 
+/// The nodes of the tree the scripts were walked as, before they were
+/// compiled to bytecode.
+#[deprecated(
+    since = "0.18.8",
+    note = "scripts are compiled to bytecode, this type is no longer used"
+)]
 #[derive(Debug)]
 pub enum TreeNodeValue {
     Break,
@@ -357,8 +795,233 @@ impl BuiltInFunctionType {
             BuiltInFunctionType::MakeError => MAKE_ERROR,
             BuiltInFunctionType::Jsonify => JSONIFY,
             BuiltInFunctionType::ParseJson => PARSE_JSON,
+            BuiltInFunctionType::Map => MAP,
+            BuiltInFunctionType::Filter => FILTER,
+            BuiltInFunctionType::Reduce => REDUCE,
+            BuiltInFunctionType::Sort => SORT,
+            BuiltInFunctionType::SortBy => SORT_BY,
+            BuiltInFunctionType::Find => FIND,
+            BuiltInFunctionType::Any => ANY,
+            BuiltInFunctionType::All => ALL,
+            BuiltInFunctionType::Zip => ZIP,
+            BuiltInFunctionType::Flatten => FLATTEN,
+            BuiltInFunctionType::Reverse => REVERSE,
+            BuiltInFunctionType::Unique => UNIQUE,
+            BuiltInFunctionType::GroupBy => GROUP_BY,
+            BuiltInFunctionType::Split => SPLIT,
+            BuiltInFunctionType::Join => JOIN,
+            BuiltInFunctionType::Trim => TRIM,
+            BuiltInFunctionType::TrimStart => TRIM_START,
+            BuiltInFunctionType::TrimEnd => TRIM_END,
+            BuiltInFunctionType::Substring => SUBSTRING,
+            BuiltInFunctionType::Contains => CONTAINS,
+            BuiltInFunctionType::StartsWith => STARTS_WITH,
+            BuiltInFunctionType::EndsWith => ENDS_WITH,
+            BuiltInFunctionType::IndexOf => INDEX_OF,
+            BuiltInFunctionType::PadLeft => PAD_LEFT,
+            BuiltInFunctionType::PadRight => PAD_RIGHT,
+            BuiltInFunctionType::Repeat => REPEAT,
+            BuiltInFunctionType::Lines => LINES,
+            BuiltInFunctionType::Chars => CHARS,
+            BuiltInFunctionType::IsDict => IS_DICT,
+            BuiltInFunctionType::ToDict => TO_DICT,
+            BuiltInFunctionType::Keys => KEYS,
+            BuiltInFunctionType::Values => VALUES,
+            BuiltInFunctionType::Entries => ENTRIES,
+            BuiltInFunctionType::HasKey => HAS_KEY,
+            BuiltInFunctionType::Remove => REMOVE,
+            BuiltInFunctionType::IsSet => IS_SET,
+            BuiltInFunctionType::ToSet => TO_SET,
+            BuiltInFunctionType::ToArray => TO_ARRAY,
+            BuiltInFunctionType::Add => ADD,
+            BuiltInFunctionType::Union => UNION,
+            BuiltInFunctionType::Intersection => INTERSECTION,
+            BuiltInFunctionType::Difference => DIFFERENCE,
+            BuiltInFunctionType::Iter => ITER,
+            BuiltInFunctionType::Take => TAKE,
+            BuiltInFunctionType::Skip => SKIP,
+            BuiltInFunctionType::Enumerate => ENUMERATE,
+            BuiltInFunctionType::Chain => CHAIN,
+            BuiltInFunctionType::ReadLines => READ_LINES,
+            BuiltInFunctionType::WrappingAdd => WRAPPING_ADD,
+            BuiltInFunctionType::WrappingSub => WRAPPING_SUB,
+            BuiltInFunctionType::WrappingMul => WRAPPING_MUL,
+            BuiltInFunctionType::CheckedAdd => CHECKED_ADD,
+            BuiltInFunctionType::CheckedSub => CHECKED_SUB,
+            BuiltInFunctionType::CheckedMul => CHECKED_MUL,
+        }
+    }
+
+    /// the builtin called `name`, e.g. for `"abc".to_upper()`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            SQRT => Some(BuiltInFunctionType::Sqrt),
+            ABS => Some(BuiltInFunctionType::Abs),
+            LOG => Some(BuiltInFunctionType::Log),
+            LN => Some(BuiltInFunctionType::Ln),
+            LENGTH => Some(BuiltInFunctionType::Length),
+            SIN => Some(BuiltInFunctionType::Sin),
+            COS => Some(BuiltInFunctionType::Cos),
+            TAN => Some(BuiltInFunctionType::Tan),
+            TYPE_OF => Some(BuiltInFunctionType::TypeOf),
+            IS_MATCH => Some(BuiltInFunctionType::IsMatch),
+            MATCH => Some(BuiltInFunctionType::Match),
+            PRINT_LN => Some(BuiltInFunctionType::Println),
+            PRINT => Some(BuiltInFunctionType::Print),
+            EVAL => Some(BuiltInFunctionType::Eval),
+            INCLUDE => Some(BuiltInFunctionType::Include),
+            REQUIRE => Some(BuiltInFunctionType::Require),
+            TO_INT => Some(BuiltInFunctionType::ToInt),
+            TO_HEX => Some(BuiltInFunctionType::ToHex),
+            TO_BINARY => Some(BuiltInFunctionType::ToBinary),
+            TO_DOUBLE => Some(BuiltInFunctionType::ToDouble),
+            TO_BOOL => Some(BuiltInFunctionType::ToBool),
+            TO_UPPER => Some(BuiltInFunctionType::ToUpper),
+            TO_LOWER => Some(BuiltInFunctionType::ToLower),
+            CAPITALIZE => Some(BuiltInFunctionType::Capitalize),
+            REPLACE => Some(BuiltInFunctionType::Replace),
+            REPLACE_ALL => Some(BuiltInFunctionType::ReplaceAll),
+            FLOOR => Some(BuiltInFunctionType::Floor),
+            CEIL => Some(BuiltInFunctionType::Ceil),
+            ROUND => Some(BuiltInFunctionType::Round),
+            TO_STRING => Some(BuiltInFunctionType::ToString),
+            IS_ERROR => Some(BuiltInFunctionType::IsError),
+            IS_U8 => Some(BuiltInFunctionType::IsU8),
+            IS_I8 => Some(BuiltInFunctionType::IsI8),
+            IS_STRUCT => Some(BuiltInFunctionType::IsStruct),
+            IS_BOOL => Some(BuiltInFunctionType::IsBool),
+            IS_INT => Some(BuiltInFunctionType::IsInt),
+            IS_DOUBLE => Some(BuiltInFunctionType::IsDouble),
+            IS_FUNCTION => Some(BuiltInFunctionType::IsFunction),
+            IS_ARRAY => Some(BuiltInFunctionType::IsArray),
+            MAKE_ERROR => Some(BuiltInFunctionType::MakeError),
+            JSONIFY => Some(BuiltInFunctionType::Jsonify),
+            PARSE_JSON => Some(BuiltInFunctionType::ParseJson),
+            MAP => Some(BuiltInFunctionType::Map),
+            FILTER => Some(BuiltInFunctionType::Filter),
+            REDUCE => Some(BuiltInFunctionType::Reduce),
+            SORT => Some(BuiltInFunctionType::Sort),
+            SORT_BY => Some(BuiltInFunctionType::SortBy),
+            FIND => Some(BuiltInFunctionType::Find),
+            ANY => Some(BuiltInFunctionType::Any),
+            ALL => Some(BuiltInFunctionType::All),
+            ZIP => Some(BuiltInFunctionType::Zip),
+            FLATTEN => Some(BuiltInFunctionType::Flatten),
+            REVERSE => Some(BuiltInFunctionType::Reverse),
+            UNIQUE => Some(BuiltInFunctionType::Unique),
+            GROUP_BY => Some(BuiltInFunctionType::GroupBy),
+            SPLIT => Some(BuiltInFunctionType::Split),
+            JOIN => Some(BuiltInFunctionType::Join),
+            TRIM => Some(BuiltInFunctionType::Trim),
+            TRIM_START => Some(BuiltInFunctionType::TrimStart),
+            TRIM_END => Some(BuiltInFunctionType::TrimEnd),
+            SUBSTRING => Some(BuiltInFunctionType::Substring),
+            CONTAINS => Some(BuiltInFunctionType::Contains),
+            STARTS_WITH => Some(BuiltInFunctionType::StartsWith),
+            ENDS_WITH => Some(BuiltInFunctionType::EndsWith),
+            INDEX_OF => Some(BuiltInFunctionType::IndexOf),
+            PAD_LEFT => Some(BuiltInFunctionType::PadLeft),
+            PAD_RIGHT => Some(BuiltInFunctionType::PadRight),
+            REPEAT => Some(BuiltInFunctionType::Repeat),
+            LINES => Some(BuiltInFunctionType::Lines),
+            CHARS => Some(BuiltInFunctionType::Chars),
+            IS_DICT => Some(BuiltInFunctionType::IsDict),
+            TO_DICT => Some(BuiltInFunctionType::ToDict),
+            KEYS => Some(BuiltInFunctionType::Keys),
+            VALUES => Some(BuiltInFunctionType::Values),
+            ENTRIES => Some(BuiltInFunctionType::Entries),
+            HAS_KEY => Some(BuiltInFunctionType::HasKey),
+            REMOVE => Some(BuiltInFunctionType::Remove),
+            IS_SET => Some(BuiltInFunctionType::IsSet),
+            TO_SET => Some(BuiltInFunctionType::ToSet),
+            TO_ARRAY => Some(BuiltInFunctionType::ToArray),
+            ADD => Some(BuiltInFunctionType::Add),
+            UNION => Some(BuiltInFunctionType::Union),
+            INTERSECTION => Some(BuiltInFunctionType::Intersection),
+            DIFFERENCE => Some(BuiltInFunctionType::Difference),
+            ITER => Some(BuiltInFunctionType::Iter),
+            TAKE => Some(BuiltInFunctionType::Take),
+            SKIP => Some(BuiltInFunctionType::Skip),
+            ENUMERATE => Some(BuiltInFunctionType::Enumerate),
+            CHAIN => Some(BuiltInFunctionType::Chain),
+            WRAPPING_ADD => Some(BuiltInFunctionType::WrappingAdd),
+            WRAPPING_SUB => Some(BuiltInFunctionType::WrappingSub),
+            WRAPPING_MUL => Some(BuiltInFunctionType::WrappingMul),
+            CHECKED_ADD => Some(BuiltInFunctionType::CheckedAdd),
+            CHECKED_SUB => Some(BuiltInFunctionType::CheckedSub),
+            CHECKED_MUL => Some(BuiltInFunctionType::CheckedMul),
+            _ => None,
         }
     }
+
+    /// whether the arguments are passed as an array, e.g. `split(s, ",")`,
+    /// rather than as a single value, e.g. `length(arr)`
+    pub const fn takes_many_args(&self) -> bool {
+        matches!(
+            self,
+            BuiltInFunctionType::IsMatch
+                | BuiltInFunctionType::Match
+                | BuiltInFunctionType::Replace
+                | BuiltInFunctionType::Round
+                | BuiltInFunctionType::ReplaceAll
+                | BuiltInFunctionType::Map
+                | BuiltInFunctionType::Filter
+                | BuiltInFunctionType::Reduce
+                | BuiltInFunctionType::SortBy
+                | BuiltInFunctionType::Find
+                | BuiltInFunctionType::Any
+                | BuiltInFunctionType::All
+                | BuiltInFunctionType::Zip
+                | BuiltInFunctionType::GroupBy
+                | BuiltInFunctionType::Split
+                | BuiltInFunctionType::Join
+                | BuiltInFunctionType::Substring
+                | BuiltInFunctionType::Contains
+                | BuiltInFunctionType::StartsWith
+                | BuiltInFunctionType::EndsWith
+                | BuiltInFunctionType::IndexOf
+                | BuiltInFunctionType::PadLeft
+                | BuiltInFunctionType::PadRight
+                | BuiltInFunctionType::Repeat
+                | BuiltInFunctionType::HasKey
+                | BuiltInFunctionType::Remove
+                | BuiltInFunctionType::Add
+                | BuiltInFunctionType::Union
+                | BuiltInFunctionType::Intersection
+                | BuiltInFunctionType::Difference
+                | BuiltInFunctionType::Take
+                | BuiltInFunctionType::Skip
+                | BuiltInFunctionType::Chain
+                | BuiltInFunctionType::WrappingAdd
+                | BuiltInFunctionType::WrappingSub
+                | BuiltInFunctionType::WrappingMul
+                | BuiltInFunctionType::CheckedAdd
+                | BuiltInFunctionType::CheckedSub
+                | BuiltInFunctionType::CheckedMul
+        )
+    }
+
+    /// the least and the most arguments a builtin accepts
+    pub const fn arity(&self) -> (usize, usize) {
+        match self {
+            BuiltInFunctionType::Round => (1, 2),
+            BuiltInFunctionType::Substring
+            | BuiltInFunctionType::PadLeft
+            | BuiltInFunctionType::PadRight
+            | BuiltInFunctionType::Reduce => (2, 3),
+            BuiltInFunctionType::Replace | BuiltInFunctionType::ReplaceAll => {
+                (3, 3)
+            }
+            _ if self.takes_many_args() => (2, 2),
+            _ => (1, 1),
+        }
+    }
+
+    /// whether a variable can shadow the builtin, e.g. `sort = (a) => { a }`.
+    /// The builtins of the first releases stay reserved
+    pub fn is_shadowable(&self) -> bool {
+        !FORBIDDEN_VARIABLE_NAME.contains(&self.as_str())
+    }
 }
 impl Operator {
     pub const fn as_str(&self) -> &'static str {
@@ -378,14 +1041,17 @@ impl Operator {
             Operator::GreaterOrEqual => ">=",
             Operator::Equal => "==",
             Operator::NotEqual => "!=",
+            Operator::Is => "is",
             Operator::And => "&&",
             Operator::Or => "||",
+            Operator::NullCoalesce => "??",
             Operator::BitwiseNot => "~",
             Operator::BitwiseAnd => "@",
             Operator::BitwiseOr => "|",
             Operator::BitwiseXor => "$",
             Operator::BitwiseLShift => "<<",
             Operator::BitwiseRShift => ">>",
+            Operator::Convert => "to",
         }
     }
 }
@@ -433,6 +1099,7 @@ pub const FORBIDDEN_VARIABLE_NAME: &[&str] = &[
     TO_BOOL,
     SQRT,
     BREAK,
+    CONTINUE,
     NULL,
     FOR,
     IN,
@@ -450,6 +1117,18 @@ pub const FORBIDDEN_VARIABLE_NAME: &[&str] = &[
     REQUIRE,
     MULTILINE,
     STRUCT,
+    DICT,
+    SET,
+    ENUM,
+    CONST,
+    LET,
+    IMPORT,
+    EXPORT,
+    TRY,
+    CATCH,
+    FINALLY,
+    THROW,
+    YIELD,
     Operator::Add.as_str(),
     Operator::Subtr.as_str(),
     Operator::Div.as_str(),
@@ -465,8 +1144,10 @@ pub const FORBIDDEN_VARIABLE_NAME: &[&str] = &[
     Operator::GreaterOrEqual.as_str(),
     Operator::Equal.as_str(),
     Operator::NotEqual.as_str(),
+    Operator::Is.as_str(),
     Operator::And.as_str(),
     Operator::Or.as_str(),
+    Operator::NullCoalesce.as_str(),
     Operator::BitwiseNot.as_str(),
     Operator::BitwiseAnd.as_str(),
     Operator::BitwiseOr.as_str(),
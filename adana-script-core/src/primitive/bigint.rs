@@ -0,0 +1,489 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::Primitive;
+
+/// an int too large for an `i128`, an `int` operation that overflows is
+/// promoted to it, and demoted back as soon as the result fits
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BigInt {
+    negative: bool,
+    /// base 2^32, least significant first, without leading zeros
+    digits: Vec<u32>,
+}
+
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+const DECIMAL_CHUNK_LEN: usize = 9;
+
+impl BigInt {
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        BigInt { negative: negative && !digits.is_empty(), digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// number of bits of the magnitude
+    pub fn bits(&self) -> u64 {
+        match self.digits.last() {
+            Some(last) => {
+                self.digits.len() as u64 * 32 - last.leading_zeros() as u64
+            }
+            None => 0,
+        }
+    }
+
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.digits.len() > 4 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .enumerate()
+            .fold(0u128, |acc, (i, d)| acc | (*d as u128) << (32 * i));
+        if self.negative {
+            (magnitude <= i128::MIN.unsigned_abs())
+                .then(|| (magnitude as i128).wrapping_neg())
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, d| acc * 4_294_967_296.0 + *d as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    pub fn neg(&self) -> Self {
+        BigInt::from_parts(!self.negative, self.digits.clone())
+    }
+
+    pub fn abs(&self) -> Self {
+        BigInt::from_parts(false, self.digits.clone())
+    }
+
+    pub fn add(&self, rhs: &BigInt) -> Self {
+        if self.negative == rhs.negative {
+            return BigInt::from_parts(
+                self.negative,
+                add_digits(&self.digits, &rhs.digits),
+            );
+        }
+        match cmp_digits(&self.digits, &rhs.digits) {
+            Ordering::Less => BigInt::from_parts(
+                rhs.negative,
+                sub_digits(&rhs.digits, &self.digits),
+            ),
+            _ => BigInt::from_parts(
+                self.negative,
+                sub_digits(&self.digits, &rhs.digits),
+            ),
+        }
+    }
+
+    pub fn sub(&self, rhs: &BigInt) -> Self {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &BigInt) -> Self {
+        BigInt::from_parts(
+            self.negative != rhs.negative,
+            mul_digits(&self.digits, &rhs.digits),
+        )
+    }
+
+    /// quotient and remainder, truncated toward zero like an `i128`.
+    /// `None` when dividing by zero
+    pub fn div_rem(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_digits(&self.digits, &rhs.digits);
+        Some((
+            BigInt::from_parts(self.negative != rhs.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        ))
+    }
+
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(n: i128) -> Self {
+        let magnitude = n.unsigned_abs();
+        let digits = (0..4).map(|i| (magnitude >> (32 * i)) as u32).collect();
+        BigInt::from_parts(n < 0, digits)
+    }
+}
+
+impl From<BigInt> for Primitive {
+    fn from(n: BigInt) -> Self {
+        match n.to_i128() {
+            Some(n) => Primitive::Int(n),
+            None => Primitive::BigInt(n),
+        }
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_digits(&self.digits, &other.digits),
+            (true, true) => cmp_digits(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = vec![];
+        let mut digits = self.digits.clone();
+        while !digits.is_empty() {
+            let (quotient, chunk) = div_rem_small(&digits, DECIMAL_CHUNK);
+            chunks.push(chunk);
+            digits = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{first}")?;
+        }
+        for chunk in chunks {
+            write!(f, "{chunk:0width$}", width = DECIMAL_CHUNK_LEN)?;
+        }
+        Ok(())
+    }
+}
+
+impl BigInt {
+    /// the magnitude written `width` bits of a digit at a time, with its
+    /// sign, e.g. `-0x1f`
+    fn fmt_bits(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        prefix: &str,
+        digit: impl Fn(u32, usize) -> String,
+        width: usize,
+    ) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        if f.alternate() {
+            write!(f, "{prefix}")?;
+        }
+        let mut digits = self.digits.iter().rev();
+        match digits.next() {
+            Some(first) => write!(f, "{}", digit(*first, 0))?,
+            None => write!(f, "0")?,
+        }
+        for d in digits {
+            write!(f, "{}", digit(*d, width))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_bits(f, "0x", |d, width| format!("{d:0width$x}"), 8)
+    }
+}
+
+impl std::fmt::Binary for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_bits(f, "0b", |d, width| format!("{d:0width$b}"), 32)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, number) = match s.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(anyhow::format_err!("invalid digit found in {s}"));
+        }
+        let mut digits = vec![];
+        let first_len = match number.len() % DECIMAL_CHUNK_LEN {
+            0 => DECIMAL_CHUNK_LEN,
+            len => len,
+        };
+        let (first, mut rest) = number.split_at(first_len);
+        let mut chunk = first;
+        loop {
+            let value: u32 = chunk.parse()?;
+            let scale = 10u32.pow(chunk.len() as u32);
+            digits = mul_add_small(&digits, scale, value);
+            if rest.is_empty() {
+                break;
+            }
+            (chunk, rest) = rest.split_at(DECIMAL_CHUNK_LEN);
+        }
+        Ok(BigInt::from_parts(negative, digits))
+    }
+}
+
+/// both operands as bigints, when at least one of them is a bigint and the
+/// other one an int
+pub(super) fn bigint_operands(
+    l: &Primitive,
+    r: &Primitive,
+) -> Option<(BigInt, BigInt)> {
+    match (l, r) {
+        (Primitive::BigInt(_), _) | (_, Primitive::BigInt(_)) => {
+            Some((as_bigint(l)?, as_bigint(r)?))
+        }
+        _ => None,
+    }
+}
+
+pub(super) fn as_bigint(p: &Primitive) -> Option<BigInt> {
+    match p {
+        Primitive::U8(n) => Some(BigInt::from(*n as i128)),
+        Primitive::I8(n) => Some(BigInt::from(*n as i128)),
+        Primitive::Int(n) => Some(BigInt::from(*n)),
+        Primitive::BigInt(n) => Some(n.clone()),
+        _ => None,
+    }
+}
+
+// region: int arithmetic
+/// a bigint power is refused past this size, to not exhaust the memory
+pub(super) const MAX_POW_BITS: u64 = 1 << 20;
+
+/// `l + r`, promoted to a bigint when it overflows
+pub(super) fn add_int(l: i128, r: i128) -> Primitive {
+    l.checked_add(r).map_or_else(
+        || BigInt::from(l).add(&BigInt::from(r)).into(),
+        Primitive::Int,
+    )
+}
+
+pub(super) fn sub_int(l: i128, r: i128) -> Primitive {
+    l.checked_sub(r).map_or_else(
+        || BigInt::from(l).sub(&BigInt::from(r)).into(),
+        Primitive::Int,
+    )
+}
+
+pub(super) fn mul_int(l: i128, r: i128) -> Primitive {
+    l.checked_mul(r).map_or_else(
+        || BigInt::from(l).mul(&BigInt::from(r)).into(),
+        Primitive::Int,
+    )
+}
+
+/// `r` must not be zero, only `i128::MIN / -1` overflows
+pub(super) fn div_int(l: i128, r: i128) -> Primitive {
+    l.checked_div(r)
+        .map_or_else(|| BigInt::from(l).neg().into(), Primitive::Int)
+}
+
+/// `r` must not be zero, only `i128::MIN % -1` overflows
+pub(super) fn rem_int(l: i128, r: i128) -> Primitive {
+    Primitive::Int(l.checked_rem(r).unwrap_or(0))
+}
+
+pub(super) fn pow_int(l: i128, exp: u32) -> Primitive {
+    match l.checked_pow(exp) {
+        Some(n) => Primitive::Int(n),
+        None => pow_big(&BigInt::from(l), exp),
+    }
+}
+
+pub(super) fn pow_big(l: &BigInt, exp: u32) -> Primitive {
+    if l.bits().saturating_mul(exp as u64) > MAX_POW_BITS {
+        return Primitive::Error(
+            format!("illegal call to pow() => {l}^{exp} is too large").into(),
+        );
+    }
+    l.pow(exp).into()
+}
+pub(super) fn shift_left(l: &BigInt, r: &BigInt) -> Primitive {
+    match r.to_i128().and_then(|r| u32::try_from(r).ok()) {
+        Some(r) if l.bits() + r as u64 <= MAX_POW_BITS => {
+            l.mul(&BigInt::from(2).pow(r)).into()
+        }
+        _ => Primitive::Error(
+            format!("illegal call to 'l_shift' => left: {l} right: {r}").into(),
+        ),
+    }
+}
+
+/// rounds toward negative infinity, like an `i128`
+pub(super) fn shift_right(l: &BigInt, r: &BigInt) -> Primitive {
+    if r.is_negative() {
+        return Primitive::Error(
+            format!("illegal call to 'r_shift' => left: {l} right: {r}").into(),
+        );
+    }
+    match r.to_i128().and_then(|r| u32::try_from(r).ok()) {
+        Some(r) if (r as u64) < l.bits() => {
+            let (quotient, remainder) =
+                l.div_rem(&BigInt::from(2).pow(r)).expect("not zero");
+            if l.is_negative() && !remainder.is_zero() {
+                quotient.sub(&BigInt::from(1)).into()
+            } else {
+                quotient.into()
+            }
+        }
+        _ => Primitive::Int(if l.is_negative() { -1 } else { 0 }),
+    }
+}
+// endregion: int arithmetic
+
+// region: digits
+fn cmp_digits(l: &[u32], r: &[u32]) -> Ordering {
+    l.len().cmp(&r.len()).then_with(|| l.iter().rev().cmp(r.iter().rev()))
+}
+
+fn add_digits(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let (long, short) = if l.len() >= r.len() { (l, r) } else { (r, l) };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, d) in long.iter().enumerate() {
+        let sum = *d as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+/// `l - r`, `l` must be the greatest
+fn sub_digits(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(l.len());
+    let mut borrow = 0i64;
+    for (i, d) in l.iter().enumerate() {
+        let mut diff = *d as i64 - *r.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = (diff < 0) as i64;
+        if diff < 0 {
+            diff += 1 << 32;
+        }
+        result.push(diff as u32);
+    }
+    result
+}
+
+fn mul_digits(l: &[u32], r: &[u32]) -> Vec<u32> {
+    if l.is_empty() || r.is_empty() {
+        return vec![];
+    }
+    let mut result = vec![0u32; l.len() + r.len()];
+    for (i, a) in l.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, b) in r.iter().enumerate() {
+            let product = *a as u64 * *b as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + r.len()] = carry as u32;
+    }
+    result
+}
+
+fn mul_add_small(digits: &[u32], factor: u32, add: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(digits.len() + 1);
+    let mut carry = add as u64;
+    for d in digits {
+        let product = *d as u64 * factor as u64 + carry;
+        result.push(product as u32);
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+fn div_rem_small(digits: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; digits.len()];
+    let mut remainder = 0u64;
+    for (i, d) in digits.iter().enumerate().rev() {
+        let current = (remainder << 32) | *d as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    while quotient.last() == Some(&0) {
+        quotient.pop();
+    }
+    (quotient, remainder as u32)
+}
+
+/// long division, one bit at a time
+fn div_rem_digits(l: &[u32], r: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [divisor] = r {
+        let (quotient, remainder) = div_rem_small(l, *divisor);
+        return (quotient, vec![remainder]);
+    }
+    if cmp_digits(l, r) == Ordering::Less {
+        return (vec![], l.to_vec());
+    }
+    let mut quotient = vec![0u32; l.len()];
+    let mut remainder: Vec<u32> = vec![];
+    for bit in (0..l.len() * 32).rev() {
+        let mut carry = (l[bit / 32] >> (bit % 32)) & 1;
+        for d in remainder.iter_mut() {
+            let next = *d >> 31;
+            *d = (*d << 1) | carry;
+            carry = next;
+        }
+        if carry > 0 {
+            remainder.push(carry);
+        }
+        if cmp_digits(&remainder, r) != Ordering::Less {
+            remainder = sub_digits(&remainder, r);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (quotient, remainder)
+}
+// endregion: digits
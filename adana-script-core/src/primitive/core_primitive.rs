@@ -1,4 +1,5 @@
 use anyhow::Result;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -6,13 +7,26 @@ use std::any::Any;
 
 use std::{
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::Display,
+    ops::{Deref, DerefMut},
     path::{Path, PathBuf},
-    sync::{Arc, RwLock},
+    sync::{
+        Arc, LockResult, Mutex, OnceLock, PoisonError, RwLock, RwLockReadGuard,
+    },
 };
 
-use crate::{Value, constants::NULL};
+use crate::{Operator, Value, constants::NULL};
+
+use super::{
+    BigInt, Decimal, EnumType, EnumVariant, Rational, ScriptError,
+    bigint::{
+        add_int, as_bigint, bigint_operands, div_int, mul_int, pow_big,
+        pow_int, rem_int, shift_left, shift_right, sub_int,
+    },
+    exact::{exact_arithmetic, exact_cmp, exact_round},
+    quantity::{Quantity, convert, quantity_arithmetic, quantity_cmp},
+};
 
 const MAX_U32_AS_I128: i128 = u32::MAX as i128;
 
@@ -22,6 +36,9 @@ pub const TYPE_INT: &str = "int";
 pub const TYPE_BOOL: &str = "bool";
 pub const TYPE_NULL: &str = "null";
 pub const TYPE_DOUBLE: &str = "double";
+pub const TYPE_DECIMAL: &str = "decimal";
+pub const TYPE_RATIONAL: &str = "rational";
+pub const TYPE_QUANTITY: &str = "quantity";
 pub const TYPE_STRING: &str = "string";
 pub const TYPE_ARRAY: &str = "array";
 pub const TYPE_ERROR: &str = "error";
@@ -29,8 +46,12 @@ pub const TYPE_NATIVE_LIB: &str = "nativeLibrary";
 pub const TYPE_FUNCTION: &str = "function";
 pub const TYPE_UNIT: &str = "unit";
 pub const TYPE_STRUCT: &str = "struct";
+pub const TYPE_DICT: &str = "dict";
+pub const TYPE_SET: &str = "set";
+pub const TYPE_ENUM: &str = "enum";
 pub const TYPE_NO_RETURN: &str = "!";
 pub const TYPE_LIB_DATA: &str = "libdata";
+pub const TYPE_ITERATOR: &str = "iterator";
 
 #[derive(Debug)]
 pub struct NativeLibrary {
@@ -94,6 +115,25 @@ impl NativeLibrary {
         Err(anyhow::format_err!("cannot use lib loading in wasm context!"))
     }
 
+    /// number of arguments a function takes, when the library declares it
+    /// with a `<key>_arity` function. Other functions take any number of
+    /// arguments
+    /// # Safety
+    /// trust me bro
+    #[cfg(not(target_arch = "wasm32"))]
+    pub unsafe fn arity(&self, key: &str) -> Option<usize> {
+        unsafe {
+            let arity: libloading::Symbol<fn() -> usize> =
+                self.lib.get(format!("{key}_arity").as_bytes()).ok()?;
+            Some(arity())
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn arity(&self, _key: &str) -> Option<usize> {
+        None
+    }
+
     /// # Safety
     /// trust me bro
     #[cfg(not(target_arch = "wasm32"))]
@@ -126,21 +166,45 @@ pub enum Primitive {
     U8(u8),
     I8(i8),
     Int(i128),
+    /// an int that doesn't fit in an `i128`
+    BigInt(BigInt),
     Bool(bool),
     Ref(RefPrimitive),
     Null,
     Double(f64),
+    /// an exact base 10 number, written `0.1d`
+    Decimal(Decimal),
+    /// an exact fraction, written `3r` so that `1/3r` is a third
+    Rational(Rational),
+    /// a number with a unit, written `5kb` or `20 °C`
+    Quantity(Quantity),
     String(String),
     Array(Vec<Primitive>),
-    Struct(BTreeMap<String, Primitive>),
-    Error(String),
+    /// fields keep their insertion order
+    Struct(IndexMap<String, Primitive>),
+    Dict(BTreeMap<DictKey, Primitive>),
+    Set(BTreeSet<DictKey>),
+    Enum(Box<EnumType>),
+    Variant(Box<EnumVariant>),
+    Error(Box<ScriptError>),
     Function {
         parameters: Vec<Value>,
         exprs: Vec<Value>,
+        /// variables of the defining scope used by the function
+        #[serde(default)]
+        captured: Captured,
+        #[serde(skip)]
+        body: CompiledBody,
     },
     Unit,
     NoReturn,
     EarlyReturn(Box<Primitive>),
+    /// the value of a variable declared with `const`, with its name, as held
+    /// by the variable. Reading the variable gives the value itself
+    Const(String, Box<Primitive>),
+    // before the variants that are never stored, which would shift its
+    // index when loaded
+    Iterator(LazyIterator),
     #[serde(skip_serializing, skip_deserializing)]
     NativeLibrary(Arc<NativeLibrary>),
     #[serde(skip_serializing, skip_deserializing)]
@@ -154,8 +218,328 @@ pub struct LibData {
     pub data: Arc<Box<dyn Send + Any + Sync>>,
 }
 
+type Elements = Box<dyn Iterator<Item = Primitive> + Send>;
+
+/// A sequence computed as it is consumed, e.g. a range iterated by a loop
+/// or a generator. When its source can be read again, e.g. a range or an
+/// array, it starts over each time it is consumed. Otherwise, e.g. for a
+/// generator, clones share the same position
+#[derive(Clone)]
+pub struct LazyIterator {
+    /// made on the first value asked for, when there is a source
+    iterator: Arc<Mutex<Option<Elements>>>,
+    source: Option<Arc<dyn Fn() -> Elements + Send + Sync>>,
+}
+
+impl LazyIterator {
+    /// a sequence that can only be consumed once
+    pub fn new(
+        iterator: impl Iterator<Item = Primitive> + Send + 'static,
+    ) -> LazyIterator {
+        LazyIterator {
+            iterator: Arc::new(Mutex::new(Some(Box::new(iterator)))),
+            source: None,
+        }
+    }
+
+    /// a sequence made again by `source` each time it is consumed
+    pub fn restartable<I>(
+        source: impl Fn() -> I + Send + Sync + 'static,
+    ) -> LazyIterator
+    where
+        I: Iterator<Item = Primitive> + Send + 'static,
+    {
+        let source: Arc<dyn Fn() -> Elements + Send + Sync> =
+            Arc::new(move || Box::new(source()));
+        LazyIterator {
+            iterator: Arc::new(Mutex::new(None)),
+            source: Some(source),
+        }
+    }
+
+    pub fn is_restartable(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// the sequence from its start, or what is left of it when it can't be
+    /// restarted
+    pub fn restart(&self) -> LazyIterator {
+        match &self.source {
+            Some(source) => LazyIterator {
+                iterator: Arc::new(Mutex::new(None)),
+                source: Some(source.clone()),
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// a sequence made from this one, e.g. its first values, which can be
+    /// restarted when this one can
+    pub fn then<I>(
+        self,
+        f: impl Fn(LazyIterator) -> I + Send + Sync + 'static,
+    ) -> LazyIterator
+    where
+        I: Iterator<Item = Primitive> + Send + 'static,
+    {
+        if self.is_restartable() {
+            LazyIterator::restartable(move || f(self.restart()))
+        } else {
+            LazyIterator::new(f(self))
+        }
+    }
+}
+
+impl Iterator for LazyIterator {
+    type Item = Primitive;
+
+    fn next(&mut self) -> Option<Primitive> {
+        let mut iterator = self.iterator.lock().ok()?;
+        match (&mut *iterator, &self.source) {
+            (Some(iterator), _) => iterator.next(),
+            (None, Some(source)) => iterator.insert(source()).next(),
+            (None, None) => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for LazyIterator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LazyIterator")
+    }
+}
+
+impl PartialEq for LazyIterator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.iterator, &other.iterator)
+    }
+}
+
+/// A restartable sequence, e.g. a range, is stored as its values and loads
+/// back as a sequence over them. One that can only be consumed once, e.g. a
+/// generator, can't be stored
+impl Serialize for LazyIterator {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if !self.is_restartable() {
+            return Err(serde::ser::Error::custom(
+                "cannot store an iterator that can only be consumed once",
+            ));
+        }
+        serializer.collect_seq(self.restart().collect::<Vec<_>>())
+    }
+}
+
+impl<'de> Deserialize<'de> for LazyIterator {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let values: Arc<[Primitive]> =
+            Vec::<Primitive>::deserialize(deserializer)?.into();
+        Ok(LazyIterator::restartable(move || {
+            let values = values.clone();
+            (0..values.len()).map(move |i| values[i].clone())
+        }))
+    }
+}
+
 pub type RefPrimitive = Arc<RwLock<Primitive>>;
 
+/// The value of a locked cell, seen through the wrapper of a constant
+pub struct CellValue<'a>(RwLockReadGuard<'a, Primitive>);
+
+impl Deref for CellValue<'_> {
+    type Target = Primitive;
+
+    fn deref(&self) -> &Primitive {
+        match &*self.0 {
+            Primitive::Const(_, v) => v,
+            v => v,
+        }
+    }
+}
+
+pub trait ReadValue {
+    /// locks the cell to read its value, e.g. the value of a constant
+    /// rather than the constant
+    fn read_value(&self) -> LockResult<CellValue<'_>>;
+}
+
+impl ReadValue for RwLock<Primitive> {
+    fn read_value(&self) -> LockResult<CellValue<'_>> {
+        self.read()
+            .map(CellValue)
+            .map_err(|e| PoisonError::new(CellValue(e.into_inner())))
+    }
+}
+
+/// The variables of its defining scope used by a function. A method can
+/// capture the struct holding it, so only their names are printed. For the
+/// same reason, a variable met again while it is being serialized, e.g. the
+/// struct within its own method, is left out, and the copy loaded back does
+/// not capture it
+#[derive(Clone, Default)]
+pub struct Captured(pub BTreeMap<String, RefPrimitive>);
+
+thread_local! {
+    /// the captured variables being serialized
+    static SERIALIZING: std::cell::RefCell<Vec<*const RwLock<Primitive>>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+struct CapturedCell<'a>(&'a RefPrimitive);
+
+impl Serialize for CapturedCell<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let value = self.0.read().map_err(|e| {
+            serde::ser::Error::custom(format!("could not acquire lock {e}"))
+        })?;
+        SERIALIZING.with_borrow_mut(|cells| cells.push(Arc::as_ptr(self.0)));
+        let res = value.serialize(serializer);
+        SERIALIZING.with_borrow_mut(|cells| cells.pop());
+        res
+    }
+}
+
+impl Serialize for Captured {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let entries = SERIALIZING.with_borrow(|cells| {
+            self.0
+                .iter()
+                .filter(|(_, cell)| !cells.contains(&Arc::as_ptr(cell)))
+                .map(|(name, cell)| (name, CapturedCell(cell)))
+                .collect::<Vec<_>>()
+        });
+        serializer.collect_map(entries)
+    }
+}
+
+impl<'de> Deserialize<'de> for Captured {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let captured =
+            BTreeMap::<String, Primitive>::deserialize(deserializer)?;
+        Ok(Captured(
+            captured.into_iter().map(|(k, v)| (k, v.ref_prim())).collect(),
+        ))
+    }
+}
+
+impl Deref for Captured {
+    type Target = BTreeMap<String, RefPrimitive>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Captured {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl std::fmt::Debug for Captured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// The body of a function once compiled. It is shared by the copies of the
+/// function, e.g. a method read from its struct at each call, so the body
+/// is only compiled once. The compiler is not part of this crate, so the
+/// program it makes is opaque here
+#[derive(Clone, Default)]
+pub struct CompiledBody(Arc<OnceLock<Arc<dyn std::any::Any + Send + Sync>>>);
+
+impl CompiledBody {
+    pub fn get_or_init<T: std::any::Any + Send + Sync>(
+        &self,
+        compile: impl FnOnce() -> T,
+    ) -> Arc<T> {
+        self.0
+            .get_or_init(|| Arc::new(compile()))
+            .clone()
+            .downcast()
+            .unwrap_or_else(|_| {
+                panic!("function body compiled as another type")
+            })
+    }
+}
+
+impl std::fmt::Debug for CompiledBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CompiledBody")
+    }
+}
+
+/// A key of a dict, or a value of a set. Integers are normalized, so `1`
+/// and `1` as an u8 are the same key
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum DictKey {
+    Bool(bool),
+    Int(i128),
+    String(String),
+    Array(Vec<DictKey>),
+}
+
+impl TryFrom<&Primitive> for DictKey {
+    type Error = Primitive;
+
+    fn try_from(p: &Primitive) -> Result<Self, Self::Error> {
+        match p {
+            Primitive::Ref(r) => {
+                let r = r.read_value().expect("DICT KEY ERROR: could not acquire lock!");
+                DictKey::try_from(&*r)
+            }
+            Primitive::Bool(b) => Ok(DictKey::Bool(*b)),
+            Primitive::U8(n) => Ok(DictKey::Int(*n as i128)),
+            Primitive::I8(n) => Ok(DictKey::Int(*n as i128)),
+            Primitive::Int(n) => Ok(DictKey::Int(*n)),
+            Primitive::String(s) => Ok(DictKey::String(s.clone())),
+            Primitive::Array(arr) => {
+                arr.iter().map(DictKey::try_from).collect::<Result<_, _>>().map(DictKey::Array)
+            }
+            p => Err(Primitive::Error(
+                format!("{p} cannot be a dict key or a set value. Only ints, strings, bools or arrays of those can").into(),
+            )),
+        }
+    }
+}
+
+impl From<&DictKey> for Primitive {
+    fn from(k: &DictKey) -> Self {
+        match k {
+            DictKey::Bool(b) => Primitive::Bool(*b),
+            DictKey::Int(n) => Primitive::Int(*n),
+            DictKey::String(s) => Primitive::String(s.clone()),
+            DictKey::Array(arr) => {
+                Primitive::Array(arr.iter().map(Primitive::from).collect())
+            }
+        }
+    }
+}
+
+impl Display for DictKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DictKey::String(s) => write!(f, r#""{s}""#),
+            k => write!(f, "{}", Primitive::from(k)),
+        }
+    }
+}
+
 // region: traits
 impl Primitive {
     pub fn ref_prim(self) -> RefPrimitive {
@@ -175,6 +559,39 @@ pub trait StringManipulation {
     fn to_upper(&self) -> Self;
     fn to_lower(&self) -> Self;
     fn capitalize(&self) -> Self;
+    fn split(&self, separator: &Primitive) -> Self;
+    fn join(&self, separator: &Primitive) -> Self;
+    fn trim(&self) -> Self;
+    fn trim_start(&self) -> Self;
+    fn trim_end(&self) -> Self;
+    fn substring(&self, start: &Primitive, end: &Primitive) -> Self;
+    fn contains(&self, pattern: &Primitive) -> Self;
+    fn starts_with(&self, pattern: &Primitive) -> Self;
+    fn ends_with(&self, pattern: &Primitive) -> Self;
+    fn index_of(&self, pattern: &Primitive) -> Self;
+    fn pad_left(&self, width: &Primitive, pad: &Primitive) -> Self;
+    fn pad_right(&self, width: &Primitive, pad: &Primitive) -> Self;
+    fn repeat(&self, n: &Primitive) -> Self;
+    fn lines(&self) -> Self;
+    fn chars(&self) -> Self;
+}
+
+pub trait Dictionary {
+    fn keys(&self) -> Self;
+    fn values(&self) -> Self;
+    fn entries(&self) -> Self;
+    fn has_key(&self, key: &Primitive) -> Self;
+    fn remove_key(&self, key: &Primitive) -> Self;
+    fn to_dict(&self) -> Self;
+}
+
+pub trait SetOperations {
+    fn add_value(&self, value: &Primitive) -> Self;
+    fn union(&self, other: &Primitive) -> Self;
+    fn intersection(&self, other: &Primitive) -> Self;
+    fn difference(&self, other: &Primitive) -> Self;
+    fn to_set(&self) -> Self;
+    fn to_array(&self) -> Self;
 }
 
 pub trait TypeOf {
@@ -185,6 +602,18 @@ pub trait BitShift {
     fn left_shift(&self, n: &Self) -> Self;
     fn right_shift(&self, n: &Self) -> Self;
 }
+/// `+`, `-` and `*` within the bounds of the left operand's type, e.g.
+/// `wrapping_add(255, 1)` or `checked_add(255, 1)` for a u8
+pub trait FixedWidth {
+    /// wraps around on overflow
+    fn wrapping(&self, rhs: &Self, operator: Operator) -> Self;
+    /// null on overflow
+    fn checked(&self, rhs: &Self, operator: Operator) -> Self;
+}
+/// `90min to h`, a quantity in another unit of the same dimension
+pub trait ConvertUnit {
+    fn convert(&self, unit: &Self) -> Self;
+}
 pub trait ToBool {
     fn to_bool(&self) -> Self;
 }
@@ -233,6 +662,14 @@ pub trait Array {
     fn is_empty(&self) -> Primitive;
     fn swap_mem(&mut self, rhs: &mut Self, index: &Primitive) -> Self;
     fn remove(&mut self, key: &Self) -> anyhow::Result<()>;
+    fn slice(&self, start: &Self, end: &Self, incl_both_end: bool) -> Self;
+    fn splice(
+        &mut self,
+        start: &Self,
+        end: &Self,
+        incl_both_end: bool,
+        rhs: &Self,
+    ) -> Self;
 }
 
 pub trait Cos {
@@ -287,7 +724,7 @@ impl Primitive {
             Some(Ordering::Equal) => Primitive::Bool(false),
             None => Primitive::Error(format!(
                 "call to is_greater_than() for two different types {self} => {other}"
-            )),
+            ).into()),
         }
     }
     pub fn is_greater_or_equal(&self, other: &Primitive) -> Primitive {
@@ -298,7 +735,7 @@ impl Primitive {
             Some(Ordering::Less) => Primitive::Bool(false),
             None => Primitive::Error(format!(
                 "call to is_greater_or_equal() for two different types {self} => {other}"
-            )),
+            ).into()),
         }
     }
     pub fn is_less_than(&self, other: &Primitive) -> Primitive {
@@ -308,7 +745,7 @@ impl Primitive {
             Some(Ordering::Equal) => Primitive::Bool(false),
             None => Primitive::Error(format!(
                 "call to is_less_than() for two different types {self} => {other}"
-            )),
+            ).into()),
         }
     }
     pub fn is_less_or_equal(&self, other: &Primitive) -> Primitive {
@@ -319,7 +756,7 @@ impl Primitive {
             Some(Ordering::Greater) => Primitive::Bool(false),
             None => Primitive::Error(format!(
                 "call to is_less_or_equal() for two different types {self} => {other}"
-            )),
+            ).into()),
         }
     }
     pub fn is_equal(&self, other: &Primitive) -> Primitive {
@@ -332,15 +769,45 @@ impl Primitive {
                 (Primitive::Null, _) | (_, Primitive::Null) => {
                     Primitive::Bool(false)
                 }
-                (Primitive::Struct(_), Primitive::Struct(_)) => {
+                (Primitive::Struct(_), Primitive::Struct(_))
+                | (Primitive::Dict(_), Primitive::Dict(_))
+                | (Primitive::Set(_), Primitive::Set(_))
+                | (Primitive::Enum(_), Primitive::Enum(_))
+                | (Primitive::Variant(_), Primitive::Variant(_)) => {
                     Primitive::Bool(false)
                 }
                 _ => Primitive::Error(format!(
                     "call to is_equal() for two different types {self} => {other}"
-                )),
+                ).into()),
             },
         }
     }
+    /// `state is Status.Failed`
+    pub fn is_variant(&self, other: &Primitive) -> Primitive {
+        match (self, other) {
+            (Primitive::Ref(l), r) => {
+                let l =
+                    l.read_value().expect("IS ERROR: could not acquire lock!");
+                l.is_variant(r)
+            }
+            (l, Primitive::Ref(r)) => {
+                let r =
+                    r.read_value().expect("IS ERROR: could not acquire lock!");
+                l.is_variant(&r)
+            }
+            (Primitive::Variant(l), Primitive::Variant(r)) => {
+                Primitive::Bool(l.is_same_variant(r))
+            }
+            (e @ Primitive::Error(_), _) | (_, e @ Primitive::Error(_)) => {
+                e.clone()
+            }
+            (_, Primitive::Variant(_)) => Primitive::Bool(false),
+            (_, r) => Primitive::Error(
+                format!("is expects an enum variant on its right, got {r}")
+                    .into(),
+            ),
+        }
+    }
     pub fn as_ref_ok(&self) -> Result<&Primitive> {
         match self {
             Primitive::Error(msg) => Err(anyhow::Error::msg(msg.to_string())),
@@ -354,13 +821,18 @@ impl Display for Primitive {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Primitive::Ref(s) => {
-                let lock = s.read().expect("FMT ERROR: could not acquire lock");
-                write!(f, "{lock}")
+                let lock =
+                    s.read_value().expect("FMT ERROR: could not acquire lock");
+                write!(f, "{}", *lock)
             }
             Primitive::U8(u) => write!(f, "{u}"),
             Primitive::I8(u) => write!(f, "{u}"),
             Primitive::Int(i) => write!(f, "{i}"),
+            Primitive::BigInt(i) => write!(f, "{i}"),
             Primitive::Double(d) => write!(f, "{d}"),
+            Primitive::Decimal(d) => write!(f, "{d}"),
+            Primitive::Rational(r) => write!(f, "{r}"),
+            Primitive::Quantity(q) => write!(f, "{q}"),
             Primitive::Bool(b) => write!(f, "{b}"),
             Primitive::Error(e) => write!(f, "Err: {e}"),
             Primitive::String(s) => {
@@ -393,12 +865,41 @@ impl Display for Primitive {
                     .collect::<Vec<_>>();
                 write!(f, "struct {{\n{}\n}}", joined_arr[..].join(", \n"))
             }
-            Primitive::Function { parameters, exprs: _ } => {
+            Primitive::Dict(dict) => {
+                let joined_arr = dict
+                    .iter()
+                    .map(|(k, p)| {
+                        format!(
+                            "\t{k}: {}",
+                            if let Primitive::String(s) = p {
+                                format!(r#""{s}""#)
+                            } else {
+                                p.to_string()
+                            }
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "dict {{\n{}\n}}", joined_arr[..].join(", \n"))
+            }
+            Primitive::Set(set) => {
+                let joined_arr =
+                    set.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                write!(f, "set {{{}}}", joined_arr[..].join(", "))
+            }
+            Primitive::Enum(e) => write!(f, "{e}"),
+            Primitive::Variant(v) => write!(f, "{v}"),
+            Primitive::Function { parameters, .. } => {
                 let mut parameters_formatted = String::new();
                 let len = parameters.len();
                 for (idx, p) in parameters.iter().enumerate() {
                     match p {
                         Value::VariableUnused => parameters_formatted.push('_'),
+                        Value::Rest(_) => {
+                            parameters_formatted.push_str("...p");
+                            parameters_formatted.push(
+                                char::from_digit(idx as u32, 10).unwrap_or('0'),
+                            );
+                        }
                         _ => {
                             parameters_formatted.push('p');
                             parameters_formatted.push(
@@ -415,11 +916,13 @@ impl Display for Primitive {
             Primitive::NoReturn => write!(f, "!"),
             Primitive::Null => write!(f, "{NULL}"),
             Primitive::EarlyReturn(p) => write!(f, "{p}"),
+            Primitive::Const(_, p) => write!(f, "{p}"),
             Primitive::NativeLibrary { .. } => write!(f, "__native_lib__"),
             Primitive::NativeFunction(key, _) => {
                 write!(f, "__native_fn__{key}")
             }
             Primitive::LibData(_) => write!(f, "__lib_data"),
+            Primitive::Iterator(_) => write!(f, "{TYPE_ITERATOR}"),
         }
     }
 }
@@ -429,16 +932,22 @@ impl Sin for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("SIN ERORR: could not acquire lock!");
+                    s.read_value().expect("SIN ERORR: could not acquire lock!");
                 lock.sin()
             }
             Primitive::U8(i) => Primitive::Double((*i as f64).sin()),
             Primitive::I8(i) => Primitive::Double((*i as f64).sin()),
             Primitive::Int(i) => Primitive::Double((*i as f64).sin()),
+            Primitive::BigInt(i) => Primitive::Double(i.to_f64().sin()),
+            p @ (Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                p.to_double().sin()
+            }
             Primitive::Double(d) => Primitive::Double(d.sin()),
 
             Primitive::Error(e) => panic!("call to sin() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to sin() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to sin() => {self}").into(),
+            ),
         }
     }
 }
@@ -448,15 +957,21 @@ impl Cos for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("COS ERORR: could not acquire lock!");
+                    s.read_value().expect("COS ERORR: could not acquire lock!");
                 lock.cos()
             }
             Primitive::Int(i) => Primitive::Double((*i as f64).cos()),
+            Primitive::BigInt(i) => Primitive::Double(i.to_f64().cos()),
+            p @ (Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                p.to_double().cos()
+            }
             Primitive::U8(i) => Primitive::Double((*i as f64).cos()),
             Primitive::I8(i) => Primitive::Double((*i as f64).cos()),
             Primitive::Double(d) => Primitive::Double(d.cos()),
             Primitive::Error(e) => panic!("call to cos() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to cos() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to cos() => {self}").into(),
+            ),
         }
     }
 }
@@ -466,16 +981,22 @@ impl Tan for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("TAN ERORR: could not acquire lock!");
+                    s.read_value().expect("TAN ERORR: could not acquire lock!");
                 lock.tan()
             }
 
             Primitive::U8(i) => Primitive::Double((*i as f64).tan()),
             Primitive::I8(i) => Primitive::Double((*i as f64).tan()),
             Primitive::Int(i) => Primitive::Double((*i as f64).tan()),
+            Primitive::BigInt(i) => Primitive::Double(i.to_f64().tan()),
+            p @ (Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                p.to_double().tan()
+            }
             Primitive::Double(d) => Primitive::Double(d.tan()),
             Primitive::Error(e) => panic!("call to tan() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to tan() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to tan() => {self}").into(),
+            ),
         }
     }
 }
@@ -485,31 +1006,44 @@ impl Logarithm for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("LOG ERORR: could not acquire lock!");
+                    s.read_value().expect("LOG ERORR: could not acquire lock!");
                 lock.log()
             }
 
             Primitive::U8(i) => Primitive::Double((*i as f64).log10()),
             Primitive::I8(i) => Primitive::Double((*i as f64).log10()),
             Primitive::Int(i) => Primitive::Double((*i as f64).log10()),
+            Primitive::BigInt(i) => Primitive::Double(i.to_f64().log10()),
+            p @ (Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                p.to_double().log()
+            }
             Primitive::Double(d) => Primitive::Double(d.log10()),
             Primitive::Error(e) => panic!("call to log() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to log() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to log() => {self}").into(),
+            ),
         }
     }
     fn ln(&self) -> Self {
         match self {
             Primitive::Ref(s) => {
-                let lock = s.read().expect("LN ERORR: could not acquire lock!");
+                let lock =
+                    s.read_value().expect("LN ERORR: could not acquire lock!");
                 lock.ln()
             }
 
             Primitive::U8(i) => Primitive::Double((*i as f64).ln()),
             Primitive::I8(i) => Primitive::Double((*i as f64).ln()),
             Primitive::Int(i) => Primitive::Double((*i as f64).ln()),
+            Primitive::BigInt(i) => Primitive::Double(i.to_f64().ln()),
+            p @ (Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                p.to_double().ln()
+            }
             Primitive::Double(d) => Primitive::Double(d.ln()),
             Primitive::Error(e) => panic!("call to ln() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to ln() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to ln() => {self}").into(),
+            ),
         }
     }
 }
@@ -518,16 +1052,23 @@ impl Sqrt for Primitive {
     fn sqrt(&self) -> Self {
         match self {
             Primitive::Ref(s) => {
-                let lock =
-                    s.read().expect("SQRT ERORR: could not acquire lock!");
+                let lock = s
+                    .read_value()
+                    .expect("SQRT ERORR: could not acquire lock!");
                 lock.sqrt()
             }
             Primitive::U8(i) => Primitive::Double((*i as f64).sqrt()),
             Primitive::I8(i) => Primitive::Double((*i as f64).sqrt()),
             Primitive::Int(i) => Primitive::Double((*i as f64).sqrt()),
+            Primitive::BigInt(i) => Primitive::Double(i.to_f64().sqrt()),
+            p @ (Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                p.to_double().sqrt()
+            }
             Primitive::Double(d) => Primitive::Double(d.sqrt()),
             Primitive::Error(e) => panic!("call to sqrt() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to sqrt() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to sqrt() => {self}").into(),
+            ),
         }
     }
 }
@@ -536,43 +1077,77 @@ impl Abs for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("ABS ERORR: could not acquire lock!");
+                    s.read_value().expect("ABS ERORR: could not acquire lock!");
                 lock.abs()
             }
             Primitive::U8(i) => Primitive::U8(*i),
-            Primitive::I8(i) => Primitive::I8(i.abs()),
-            Primitive::Int(i) => Primitive::Int(i.abs()),
+            Primitive::I8(i) => match i.checked_abs() {
+                Some(i) => Primitive::I8(i),
+                None => Primitive::Int(i.unsigned_abs() as i128),
+            },
+            Primitive::Int(i) => match i.checked_abs() {
+                Some(i) => Primitive::Int(i),
+                None => BigInt::from(*i).abs().into(),
+            },
+            Primitive::BigInt(i) => i.abs().into(),
+            Primitive::Decimal(d) => Primitive::Decimal(d.abs()),
+            Primitive::Rational(r) => Primitive::Rational(r.abs()),
+            Primitive::Quantity(q) => {
+                Primitive::Quantity(Quantity::new(q.value.abs(), q.unit))
+            }
             Primitive::Double(d) => Primitive::Double(d.abs()),
             Primitive::Error(e) => panic!("call to abs() on an error. {e}"),
-            _ => Primitive::Error(format!("illegal call to abs() => {self}")),
+            _ => Primitive::Error(
+                format!("illegal call to abs() => {self}").into(),
+            ),
         }
     }
 }
 
 impl Pow for Primitive {
     fn pow(&self, rhs: &Self) -> Self {
+        if let Some(res) = quantity_arithmetic(self, rhs, Operator::Pow) {
+            return res;
+        }
+        if let Some(res) = exact_arithmetic(self, rhs, Operator::Pow) {
+            return res;
+        }
+        if let Some((l, r)) = bigint_operands(self, rhs) {
+            return match r.to_i128().and_then(|r| u32::try_from(r).ok()) {
+                Some(exp) => pow_big(&l, exp),
+                None => Primitive::Double(l.to_f64().powf(r.to_f64())),
+            };
+        }
         match (self, rhs) {
             (Primitive::Ref(l), Primitive::Ref(r)) => {
-                let l = l.read().expect("POW L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("POW L ERORR: could not acquire lock!");
 
-                let r = r.read().expect("POW R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("POW R ERORR: could not acquire lock!");
                 l.pow(&r)
             }
             (Primitive::Ref(l), r) => {
-                let l = l.read().expect("POW L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("POW L ERORR: could not acquire lock!");
 
                 l.pow(r)
             }
             (l, Primitive::Ref(r)) => {
-                let r = r.read().expect("POW R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("POW R ERORR: could not acquire lock!");
 
                 l.pow(&r)
             }
             (Primitive::U8(l), Primitive::U8(r)) => {
-                Primitive::Int((*l as i128).pow(*r as u32))
+                pow_int(*l as i128, *r as u32)
             }
             (Primitive::U8(l), Primitive::I8(r)) if r > &0 => {
-                Primitive::Int((*l as i128).pow(*r as u32))
+                pow_int(*l as i128, *r as u32)
             }
             (Primitive::U8(l), Primitive::I8(r)) => {
                 Primitive::Double((*l as f64).powf(*r as f64))
@@ -581,24 +1156,24 @@ impl Pow for Primitive {
             (Primitive::U8(l), Primitive::Int(r))
                 if r >= &0 && r <= &MAX_U32_AS_I128 =>
             {
-                Primitive::Int((*l as i128).pow(*r as u32))
+                pow_int(*l as i128, *r as u32)
             }
 
             (Primitive::I8(l), Primitive::I8(r)) if r > &0 => {
-                Primitive::Int((*l as i128).pow(*r as u32))
+                pow_int(*l as i128, *r as u32)
             }
             (Primitive::I8(l), Primitive::I8(r)) => {
                 Primitive::Double((*l as f64).powf(*r as f64))
             }
 
             (Primitive::I8(l), Primitive::U8(r)) => {
-                Primitive::Int((*l as i128).pow(*r as u32))
+                pow_int(*l as i128, *r as u32)
             }
             #[allow(clippy::manual_range_contains)]
             (Primitive::I8(l), Primitive::Int(r))
                 if r >= &0 && r <= &MAX_U32_AS_I128 =>
             {
-                Primitive::Int((*l as i128).pow(*r as u32))
+                pow_int(*l as i128, *r as u32)
             }
 
             (Primitive::U8(l), Primitive::Int(r)) => {
@@ -618,14 +1193,12 @@ impl Pow for Primitive {
             (Primitive::Int(l), Primitive::Int(r))
                 if r >= &0 && r <= &MAX_U32_AS_I128 =>
             {
-                Primitive::Int(l.pow(*r as u32))
+                pow_int(*l, *r as u32)
             }
 
-            (Primitive::Int(l), Primitive::U8(r)) => {
-                Primitive::Int(l.pow(*r as u32))
-            }
+            (Primitive::Int(l), Primitive::U8(r)) => pow_int(*l, *r as u32),
             (Primitive::Int(l), Primitive::I8(r)) if r >= &0 => {
-                Primitive::Int(l.pow(*r as u32))
+                pow_int(*l, *r as u32)
             }
             (Primitive::Int(l), Primitive::I8(r)) => {
                 Primitive::Double((*l as f64).powf(*r as f64))
@@ -648,41 +1221,62 @@ impl Pow for Primitive {
             (Primitive::Double(l), Primitive::Double(r)) => {
                 Primitive::Double(l.powf(*r))
             }
-            (l, r) => Primitive::Error(format!(
-                "illegal call to pow() => left: {l} right: {r}"
-            )),
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                Primitive::Double(l.to_f64().powf(*r))
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                Primitive::Double(l.powf(r.to_f64()))
+            }
+            (l, r) => Primitive::Error(
+                format!("illegal call to pow() => left: {l} right: {r}").into(),
+            ),
         }
     }
 }
 
 impl Add for Primitive {
     fn add(&self, rhs: &Self) -> Self {
+        if let Some(res) = quantity_arithmetic(self, rhs, Operator::Add) {
+            return res;
+        }
+        if let Some(res) = exact_arithmetic(self, rhs, Operator::Add) {
+            return res;
+        }
+        if let Some((l, r)) = bigint_operands(self, rhs) {
+            return l.add(&r).into();
+        }
         match (self.clone(), rhs.clone()) {
             (Primitive::Ref(l), Primitive::Ref(r)) => {
-                let l = l.read().expect("ADD L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("ADD L ERORR: could not acquire lock!");
 
-                let r = r.read().expect("ADD R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("ADD R ERORR: could not acquire lock!");
                 l.add(&r)
             }
             (Primitive::Ref(l), r) => {
-                let l = l.read().expect("ADD L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("ADD L ERORR: could not acquire lock!");
 
                 l.add(&r)
             }
             (l, Primitive::Ref(r)) => {
-                let r = r.read().expect("ADD R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("ADD R ERORR: could not acquire lock!");
 
                 l.add(&r)
             }
             (Primitive::U8(l), Primitive::U8(r)) => {
-                Primitive::Int(l as i128 + r as i128)
+                add_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::I8(r)) => {
-                Primitive::Int(l as i128 + r as i128)
-            }
-            (Primitive::U8(l), Primitive::Int(r)) => {
-                Primitive::Int(l as i128 + r)
+                add_int(l as i128, r as i128)
             }
+            (Primitive::U8(l), Primitive::Int(r)) => add_int(l as i128, r),
             (Primitive::U8(l), Primitive::String(s)) => {
                 Primitive::String(format!("{l}{s}"))
             }
@@ -691,14 +1285,12 @@ impl Add for Primitive {
             }
 
             (Primitive::I8(l), Primitive::I8(r)) => {
-                Primitive::Int(l as i128 + r as i128)
+                add_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::U8(r)) => {
-                Primitive::Int(l as i128 + r as i128)
-            }
-            (Primitive::I8(l), Primitive::Int(r)) => {
-                Primitive::Int(l as i128 + r)
+                add_int(l as i128, r as i128)
             }
+            (Primitive::I8(l), Primitive::Int(r)) => add_int(l as i128, r),
             (Primitive::I8(l), Primitive::String(s)) => {
                 Primitive::String(format!("{l}{s}"))
             }
@@ -706,13 +1298,9 @@ impl Add for Primitive {
                 Primitive::Double(l as f64 + r)
             }
 
-            (Primitive::Int(l), Primitive::Int(r)) => Primitive::Int(l + r),
-            (Primitive::Int(l), Primitive::U8(r)) => {
-                Primitive::Int(l + r as i128)
-            }
-            (Primitive::Int(l), Primitive::I8(r)) => {
-                Primitive::Int(l + r as i128)
-            }
+            (Primitive::Int(l), Primitive::Int(r)) => add_int(l, r),
+            (Primitive::Int(l), Primitive::U8(r)) => add_int(l, r as i128),
+            (Primitive::Int(l), Primitive::I8(r)) => add_int(l, r as i128),
             (Primitive::Int(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 + r)
             }
@@ -733,6 +1321,13 @@ impl Add for Primitive {
                 Primitive::Double(l + r)
             }
 
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                Primitive::Double(l.to_f64() + r)
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                Primitive::Double(l + r.to_f64())
+            }
+
             (Primitive::Array(mut l), Primitive::Array(mut r)) => {
                 l.append(&mut r);
                 Primitive::Array(l)
@@ -750,77 +1345,78 @@ impl Add for Primitive {
             (l, Primitive::String(s)) => Primitive::String(format!("{l}{s}")),
 
             (Primitive::String(s), r) => Primitive::String(format!("{s}{r}")),
-            (l, r) => Primitive::Error(format!(
-                "illegal call to add() => left: {l} right: {r}"
-            )),
+            (l, r) => Primitive::Error(
+                format!("illegal call to add() => left: {l} right: {r}").into(),
+            ),
         }
     }
 }
 
 impl Sub for Primitive {
     fn sub(&self, rhs: &Self) -> Self {
+        if let Some(res) = quantity_arithmetic(self, rhs, Operator::Subtr) {
+            return res;
+        }
+        if let Some(res) = exact_arithmetic(self, rhs, Operator::Subtr) {
+            return res;
+        }
+        if let Some((l, r)) = bigint_operands(self, rhs) {
+            return l.sub(&r).into();
+        }
         match (self.clone(), rhs.clone()) {
             (Primitive::Ref(l), Primitive::Ref(r)) => {
-                let l = l.read().expect("SUB L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("SUB L ERORR: could not acquire lock!");
 
-                let r = r.read().expect("SUB R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("SUB R ERORR: could not acquire lock!");
                 l.sub(&r)
             }
             (Primitive::Ref(l), r) => {
-                let l = l.read().expect("SUB L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("SUB L ERORR: could not acquire lock!");
 
                 l.sub(&r)
             }
             (l, Primitive::Ref(r)) => {
-                let r = r.read().expect("SUB R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("SUB R ERORR: could not acquire lock!");
 
                 l.sub(&r)
             }
 
             (Primitive::U8(l), Primitive::U8(r)) => {
-                if let Some(v) = l.checked_sub(r) {
-                    Primitive::U8(v)
-                } else {
-                    Primitive::Int(l as i128 - r as i128)
-                }
+                sub_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::I8(r)) => {
-                Primitive::Int(l as i128 - r as i128)
-            }
-            (Primitive::U8(l), Primitive::Int(r)) => {
-                Primitive::Int(l as i128 - r)
+                sub_int(l as i128, r as i128)
             }
+            (Primitive::U8(l), Primitive::Int(r)) => sub_int(l as i128, r),
             (Primitive::U8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 - r)
             }
 
             (Primitive::I8(l), Primitive::U8(r)) => {
-                Primitive::Int(l as i128 - r as i128)
+                sub_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::I8(r)) => {
-                if let Some(v) = l.checked_sub(r) {
-                    Primitive::I8(v)
-                } else {
-                    Primitive::Int(l as i128 - r as i128)
-                }
-            }
-            (Primitive::I8(l), Primitive::Int(r)) => {
-                Primitive::Int(l as i128 - r)
+                sub_int(l as i128, r as i128)
             }
+            (Primitive::I8(l), Primitive::Int(r)) => sub_int(l as i128, r),
             (Primitive::I8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 - r)
             }
 
-            (Primitive::Int(l), Primitive::Int(r)) => Primitive::Int(l - r),
+            (Primitive::Int(l), Primitive::Int(r)) => sub_int(l, r),
             (Primitive::Int(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 - r)
             }
-            (Primitive::Int(l), Primitive::U8(r)) => {
-                Primitive::Int(l - r as i128)
-            }
-            (Primitive::Int(l), Primitive::I8(r)) => {
-                Primitive::Int(l - r as i128)
-            }
+            (Primitive::Int(l), Primitive::U8(r)) => sub_int(l, r as i128),
+            (Primitive::Int(l), Primitive::I8(r)) => sub_int(l, r as i128),
 
             (Primitive::Double(l), Primitive::Int(r)) => {
                 Primitive::Double(l - r as f64)
@@ -835,40 +1431,67 @@ impl Sub for Primitive {
                 Primitive::Double(l - r as f64)
             }
 
-            (l, r) => Primitive::Error(format!(
-                "illegal call to sub() => left: {l} right: {r}"
-            )),
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                Primitive::Double(l.to_f64() - r)
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                Primitive::Double(l - r.to_f64())
+            }
+
+            (l, r) => Primitive::Error(
+                format!("illegal call to sub() => left: {l} right: {r}").into(),
+            ),
         }
     }
 }
 impl Rem for Primitive {
     fn rem(&self, rhs: &Self) -> Self {
+        if let Some(res) = quantity_arithmetic(self, rhs, Operator::Mod) {
+            return res;
+        }
+        if let Some(res) = exact_arithmetic(self, rhs, Operator::Mod) {
+            return res;
+        }
+        if let Some((l, r)) = bigint_operands(self, rhs) {
+            return match l.div_rem(&r) {
+                Some((_, remainder)) => remainder.into(),
+                None => Primitive::Double(f64::NAN),
+            };
+        }
         match (self.clone(), rhs.clone()) {
             (Primitive::Ref(l), Primitive::Ref(r)) => {
-                let l = l.read().expect("REM L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("REM L ERORR: could not acquire lock!");
 
-                let r = r.read().expect("REM R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("REM R ERORR: could not acquire lock!");
                 l.rem(&r)
             }
             (Primitive::Ref(l), r) => {
-                let l = l.read().expect("REM L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("REM L ERORR: could not acquire lock!");
 
                 l.rem(&r)
             }
             (l, Primitive::Ref(r)) => {
-                let r = r.read().expect("REM R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("REM R ERORR: could not acquire lock!");
 
                 l.rem(&r)
             }
 
             (Primitive::U8(l), Primitive::U8(r)) if r != 0 => {
-                Primitive::U8(l % r)
+                rem_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::I8(r)) if r != 0 => {
-                Primitive::Int(l as i128 % r as i128)
+                rem_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::Int(r)) if r != 0 => {
-                Primitive::Int(l as i128 % r)
+                rem_int(l as i128, r)
             }
             (Primitive::U8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 % r)
@@ -876,27 +1499,25 @@ impl Rem for Primitive {
             (Primitive::U8(_), _) => Primitive::Double(f64::NAN),
 
             (Primitive::I8(l), Primitive::I8(r)) if r != 0 => {
-                Primitive::I8(l % r)
+                rem_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::U8(r)) if r != 0 => {
-                Primitive::Int(l as i128 % r as i128)
+                rem_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::Int(r)) if r != 0 => {
-                Primitive::Int(l as i128 % r)
+                rem_int(l as i128, r)
             }
             (Primitive::I8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 % r)
             }
             (Primitive::I8(_), _) => Primitive::Double(f64::NAN),
 
-            (Primitive::Int(l), Primitive::Int(r)) if r != 0 => {
-                Primitive::Int(l % r)
-            }
+            (Primitive::Int(l), Primitive::Int(r)) if r != 0 => rem_int(l, r),
             (Primitive::Int(l), Primitive::U8(r)) if r != 0 => {
-                Primitive::Int(l % r as i128)
+                rem_int(l, r as i128)
             }
             (Primitive::Int(l), Primitive::I8(r)) if r != 0 => {
-                Primitive::Int(l % r as i128)
+                rem_int(l, r as i128)
             }
             (Primitive::Int(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 % r)
@@ -916,46 +1537,68 @@ impl Rem for Primitive {
                 Primitive::Double(l % r)
             }
 
-            (l, r) => Primitive::Error(format!(
-                "illegal call to rem() => left: {l} right: {r}"
-            )),
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                Primitive::Double(l.to_f64() % r)
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                Primitive::Double(l % r.to_f64())
+            }
+
+            (l, r) => Primitive::Error(
+                format!("illegal call to rem() => left: {l} right: {r}").into(),
+            ),
         }
     }
 }
 
 impl Mul for Primitive {
     fn mul(&self, rhs: &Self) -> Self {
+        if let Some(res) = quantity_arithmetic(self, rhs, Operator::Mult) {
+            return res;
+        }
+        if let Some(res) = exact_arithmetic(self, rhs, Operator::Mult) {
+            return res;
+        }
         fn multiply_array(arr: Vec<Primitive>, n: i128) -> Vec<Primitive> {
             let arr_size = arr.len();
             arr.into_iter().cycle().take(n as usize * arr_size).collect()
         }
+        if let Some((l, r)) = bigint_operands(self, rhs) {
+            return l.mul(&r).into();
+        }
         match (self.clone(), rhs.clone()) {
             (Primitive::Ref(l), Primitive::Ref(r)) => {
-                let l = l.read().expect("MUL L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("MUL L ERORR: could not acquire lock!");
 
-                let r = r.read().expect("MUL R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("MUL R ERORR: could not acquire lock!");
                 l.mul(&r)
             }
             (Primitive::Ref(l), r) => {
-                let l = l.read().expect("MUL L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("MUL L ERORR: could not acquire lock!");
 
                 l.mul(&r)
             }
             (l, Primitive::Ref(r)) => {
-                let r = r.read().expect("MUL R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("MUL R ERORR: could not acquire lock!");
 
                 l.mul(&r)
             }
 
             (Primitive::U8(l), Primitive::U8(r)) => {
-                Primitive::Int(l as i128 * r as i128)
+                mul_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::I8(r)) => {
-                Primitive::Int(l as i128 * r as i128)
-            }
-            (Primitive::U8(l), Primitive::Int(r)) => {
-                Primitive::Int(l as i128 * r)
+                mul_int(l as i128, r as i128)
             }
+            (Primitive::U8(l), Primitive::Int(r)) => mul_int(l as i128, r),
             (Primitive::U8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 * r)
             }
@@ -964,14 +1607,12 @@ impl Mul for Primitive {
             }
 
             (Primitive::I8(l), Primitive::U8(r)) => {
-                Primitive::Int(l as i128 * r as i128)
+                mul_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::I8(r)) => {
-                Primitive::Int(l as i128 * r as i128)
-            }
-            (Primitive::I8(l), Primitive::Int(r)) => {
-                Primitive::Int(l as i128 * r)
+                mul_int(l as i128, r as i128)
             }
+            (Primitive::I8(l), Primitive::Int(r)) => mul_int(l as i128, r),
             (Primitive::I8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 * r)
             }
@@ -979,17 +1620,11 @@ impl Mul for Primitive {
                 Primitive::Array(multiply_array(r, l as i128))
             }
 
-            (Primitive::Int(l), Primitive::Int(r)) => {
-                Primitive::Int(l.wrapping_mul(r))
-            }
-            (Primitive::Int(l), Primitive::U8(r)) => {
-                Primitive::Int(l * r as i128)
-            }
-            (Primitive::Int(l), Primitive::I8(r)) => {
-                Primitive::Int(l * r as i128)
-            }
-            (Primitive::Int(l), Primitive::Double(r)) => {
-                Primitive::Double(l as f64 * r)
+            (Primitive::Int(l), Primitive::Int(r)) => mul_int(l, r),
+            (Primitive::Int(l), Primitive::U8(r)) => mul_int(l, r as i128),
+            (Primitive::Int(l), Primitive::I8(r)) => mul_int(l, r as i128),
+            (Primitive::Int(l), Primitive::Double(r)) => {
+                Primitive::Double(l as f64 * r)
             }
             (Primitive::Int(l), Primitive::Array(r)) if l >= 0 => {
                 Primitive::Array(multiply_array(r, l))
@@ -1008,6 +1643,13 @@ impl Mul for Primitive {
                 Primitive::Double(l * r)
             }
 
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                Primitive::Double(l.to_f64() * r)
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                Primitive::Double(l * r.to_f64())
+            }
+
             (Primitive::String(l), Primitive::Int(r)) if r >= 0 => {
                 Primitive::String(l.repeat(r as usize))
             }
@@ -1028,40 +1670,63 @@ impl Mul for Primitive {
                 Primitive::Array(multiply_array(l, n as i128))
             }
 
-            (l, r) => Primitive::Error(format!(
-                "illegal call to mul() => left: {l} right: {r}"
-            )),
+            (l, r) => Primitive::Error(
+                format!("illegal call to mul() => left: {l} right: {r}").into(),
+            ),
         }
     }
 }
 impl Div for Primitive {
     fn div(&self, rhs: &Self) -> Self {
+        if let Some(res) = quantity_arithmetic(self, rhs, Operator::Div) {
+            return res;
+        }
+        if let Some(res) = exact_arithmetic(self, rhs, Operator::Div) {
+            return res;
+        }
+        if let Some((l, r)) = bigint_operands(self, rhs) {
+            return match l.div_rem(&r) {
+                Some((quotient, _)) => quotient.into(),
+                None if l.is_negative() || l.is_zero() => {
+                    Primitive::Double(f64::NAN)
+                }
+                None => Primitive::Double(f64::INFINITY),
+            };
+        }
         match (self.clone(), rhs.clone()) {
             (Primitive::Ref(l), Primitive::Ref(r)) => {
-                let l = l.read().expect("DIV L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("DIV L ERORR: could not acquire lock!");
 
-                let r = r.read().expect("DIV R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("DIV R ERORR: could not acquire lock!");
                 l.div(&r)
             }
             (Primitive::Ref(l), r) => {
-                let l = l.read().expect("DIV L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("DIV L ERORR: could not acquire lock!");
 
                 l.div(&r)
             }
             (l, Primitive::Ref(r)) => {
-                let r = r.read().expect("DIV R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("DIV R ERORR: could not acquire lock!");
 
                 l.div(&r)
             }
 
             (Primitive::U8(l), Primitive::U8(r)) if r != 0 => {
-                Primitive::Int(l as i128 / r as i128)
+                div_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::I8(r)) if r != 0 => {
-                Primitive::Int(l as i128 / r as i128)
+                div_int(l as i128, r as i128)
             }
             (Primitive::U8(l), Primitive::Int(r)) if r != 0 => {
-                Primitive::Int(l as i128 / r)
+                div_int(l as i128, r)
             }
             (Primitive::U8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 / r)
@@ -1078,13 +1743,13 @@ impl Div for Primitive {
             (Primitive::U8(_), _) => Primitive::Double(f64::NAN),
 
             (Primitive::I8(l), Primitive::U8(r)) if r != 0 => {
-                Primitive::Int(l as i128 / r as i128)
+                div_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::I8(r)) if r != 0 => {
-                Primitive::Int(l as i128 / r as i128)
+                div_int(l as i128, r as i128)
             }
             (Primitive::I8(l), Primitive::Int(r)) if r != 0 => {
-                Primitive::Int(l as i128 / r)
+                div_int(l as i128, r)
             }
             (Primitive::I8(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 / r)
@@ -1100,14 +1765,12 @@ impl Div for Primitive {
             }
             (Primitive::I8(_), _) => Primitive::Double(f64::NAN),
 
-            (Primitive::Int(l), Primitive::Int(r)) if r != 0 => {
-                Primitive::Int(l / r)
-            }
+            (Primitive::Int(l), Primitive::Int(r)) if r != 0 => div_int(l, r),
             (Primitive::Int(l), Primitive::U8(r)) if r != 0 => {
-                Primitive::Int(l / r as i128)
+                div_int(l, r as i128)
             }
             (Primitive::Int(l), Primitive::I8(r)) if r != 0 => {
-                Primitive::Int(l / r as i128)
+                div_int(l, r as i128)
             }
             (Primitive::Int(l), Primitive::Double(r)) => {
                 Primitive::Double(l as f64 / r)
@@ -1136,9 +1799,16 @@ impl Div for Primitive {
                 Primitive::Double(l / r)
             }
 
-            (l, r) => Primitive::Error(format!(
-                "illegal call to div() => left: {l} right: {r}"
-            )),
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                Primitive::Double(l.to_f64() / r)
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                Primitive::Double(l / r.to_f64())
+            }
+
+            (l, r) => Primitive::Error(
+                format!("illegal call to div() => left: {l} right: {r}").into(),
+            ),
         }
     }
 }
@@ -1148,18 +1818,29 @@ impl Neg for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("NEG ERORR: could not acquire lock!");
+                    s.read_value().expect("NEG ERORR: could not acquire lock!");
                 lock.neg()
             }
             Primitive::U8(n) if *n > i8::MAX as u8 => {
                 Primitive::Int(-(*n as i128))
             }
             Primitive::U8(n) => Primitive::I8(-(*n as i8)),
-            Primitive::I8(n) => Primitive::I8(-*n),
+            Primitive::I8(n) => match n.checked_neg() {
+                Some(n) => Primitive::I8(n),
+                None => Primitive::Int(-(*n as i128)),
+            },
 
-            Primitive::Int(n) => Primitive::Int(-n),
+            Primitive::Int(n) => sub_int(0, *n),
+            Primitive::BigInt(n) => n.neg().into(),
             Primitive::Double(n) => Primitive::Double(-n),
-            _ => Primitive::Error(format!("invalid call to neg() {self}")),
+            Primitive::Decimal(d) => Primitive::Decimal(d.neg()),
+            Primitive::Rational(r) => Primitive::Rational(r.neg()),
+            Primitive::Quantity(q) => {
+                Primitive::Quantity(Quantity::new(-q.value, q.unit))
+            }
+            _ => {
+                Primitive::Error(format!("invalid call to neg() {self}").into())
+            }
         }
     }
 }
@@ -1169,18 +1850,20 @@ impl Not for Primitive {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("NOT ERORR: could not acquire lock!");
+                    s.read_value().expect("NOT ERORR: could not acquire lock!");
                 lock.not()
             }
             Primitive::Bool(b) => Primitive::Bool(!b),
-            _ => Primitive::Error(format!("invalid call to not() {self}")),
+            _ => {
+                Primitive::Error(format!("invalid call to not() {self}").into())
+            }
         }
     }
     fn bitwise_not(&self) -> Self {
         match self {
             Primitive::Ref(s) => {
                 let lock =
-                    s.read().expect("NOT ERORR: could not acquire lock!");
+                    s.read_value().expect("NOT ERORR: could not acquire lock!");
                 lock.not()
             }
             Primitive::U8(b) if *b < i8::MAX as u8 => {
@@ -1189,9 +1872,29 @@ impl Not for Primitive {
             Primitive::U8(b) => Primitive::Int(!(*b as i128)),
             Primitive::I8(b) => Primitive::I8(!b),
             Primitive::Int(b) => Primitive::Int(!b),
-            _ => Primitive::Error(format!(
-                "invalid call to bitwise_not() {self}"
-            )),
+            _ => Primitive::Error(
+                format!("invalid call to bitwise_not() {self}").into(),
+            ),
+        }
+    }
+}
+
+impl ConvertUnit for Primitive {
+    fn convert(&self, unit: &Self) -> Self {
+        match (self, unit) {
+            (Primitive::Ref(l), unit) => {
+                let l = l
+                    .read_value()
+                    .expect("CONVERT L ERORR: could not acquire lock!");
+                l.convert(unit)
+            }
+            (l, Primitive::Ref(unit)) => {
+                let unit = unit
+                    .read_value()
+                    .expect("CONVERT R ERORR: could not acquire lock!");
+                l.convert(&unit)
+            }
+            (l, unit) => convert(l, unit),
         }
     }
 }
@@ -1200,8 +1903,9 @@ impl ToBool for Primitive {
     fn to_bool(&self) -> Self {
         match self {
             Primitive::Ref(s) => {
-                let lock =
-                    s.read().expect("TO_BOOL ERORR: could not acquire lock!");
+                let lock = s
+                    .read_value()
+                    .expect("TO_BOOL ERORR: could not acquire lock!");
                 lock.to_bool()
             }
             v @ Primitive::Bool(_) => v.clone(),
@@ -1209,15 +1913,24 @@ impl ToBool for Primitive {
             Primitive::U8(n) => Primitive::Bool(n > &0),
             Primitive::I8(n) => Primitive::Bool(n > &0),
             Primitive::Int(n) => Primitive::Bool(n > &0),
+            Primitive::BigInt(n) => Primitive::Bool(!n.is_negative()),
+            Primitive::Decimal(d) => {
+                Primitive::Bool(!d.is_negative() && !d.is_zero())
+            }
+            Primitive::Rational(r) => {
+                Primitive::Bool(!r.is_negative() && !r.is_zero())
+            }
             Primitive::Null => Primitive::Bool(false),
             Primitive::Array(a) => Primitive::Bool(!a.is_empty()),
             Primitive::String(s) => match s.parse::<bool>() {
                 Ok(b) => Primitive::Bool(b),
-                Err(e) => Primitive::Error(format!(
-                    "invalid cast to bool: {self}, {e}"
-                )),
+                Err(e) => Primitive::Error(
+                    format!("invalid cast to bool: {self}, {e}").into(),
+                ),
             },
-            _ => Primitive::Error(format!("invalide cast too bool: {self}")),
+            _ => Primitive::Error(
+                format!("invalide cast too bool: {self}").into(),
+            ),
         }
     }
 }
@@ -1226,44 +1939,61 @@ impl ToNumber for Primitive {
     fn to_int(&self) -> Self {
         match self {
             Primitive::Ref(s) => {
-                let lock =
-                    s.read().expect("TO_INT ERORR: could not acquire lock!");
+                let lock = s
+                    .read_value()
+                    .expect("TO_INT ERORR: could not acquire lock!");
                 lock.to_int()
             }
             v @ Primitive::Int(_) => v.clone(),
+            v @ Primitive::BigInt(_) => v.clone(),
             v @ Primitive::U8(_) => v.clone(),
             v @ Primitive::I8(_) => v.clone(),
             Primitive::Bool(false) => Primitive::Int(0),
             Primitive::Bool(true) => Primitive::Int(1),
             Primitive::Double(d) => Primitive::Int(*d as i128),
+            Primitive::Decimal(d) => d.trunc().into(),
+            Primitive::Rational(r) => r.trunc().into(),
+            Primitive::Quantity(q) => Primitive::Int(q.value as i128),
             Primitive::String(s) => match s.parse::<i128>() {
                 Ok(number) => Primitive::Int(number),
-                Err(e) => Primitive::Error(format!(
-                    "invalid cast to int: {self}, {e}"
-                )),
+                Err(e) => match s.parse::<BigInt>() {
+                    Ok(number) => number.into(),
+                    Err(_) => Primitive::Error(
+                        format!("invalid cast to int: {self}, {e}").into(),
+                    ),
+                },
             },
-            _ => Primitive::Error(format!("invalid cast to int: {self}")),
+            _ => {
+                Primitive::Error(format!("invalid cast to int: {self}").into())
+            }
         }
     }
 
     fn to_double(&self) -> Self {
         match self {
             Primitive::Ref(s) => {
-                let lock =
-                    s.read().expect("TO_DOUBLE ERORR: could not acquire lock!");
+                let lock = s
+                    .read_value()
+                    .expect("TO_DOUBLE ERORR: could not acquire lock!");
                 lock.to_double()
             }
             Primitive::U8(d) => Primitive::Double(*d as f64),
             Primitive::I8(d) => Primitive::Double(*d as f64),
             Primitive::Int(d) => Primitive::Double(*d as f64),
+            Primitive::BigInt(d) => Primitive::Double(d.to_f64()),
+            Primitive::Decimal(d) => Primitive::Double(d.to_f64()),
+            Primitive::Rational(r) => Primitive::Double(r.to_f64()),
+            Primitive::Quantity(q) => Primitive::Double(q.value),
             v @ Primitive::Double(_) => v.clone(),
             Primitive::String(s) => match s.parse::<f64>() {
                 Ok(number) => Primitive::Double(number),
-                Err(e) => Primitive::Error(format!(
-                    "invalid cast to double: {self}, {e}"
-                )),
+                Err(e) => Primitive::Error(
+                    format!("invalid cast to double: {self}, {e}").into(),
+                ),
             },
-            _ => Primitive::Error(format!("invalid cast to double: {self}")),
+            _ => Primitive::Error(
+                format!("invalid cast to double: {self}").into(),
+            ),
         }
     }
 }
@@ -1271,157 +2001,236 @@ impl ToNumber for Primitive {
 impl BitShift for Primitive {
     fn right_shift(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l =
-                l.read().expect("B_R_SHIFT L ERROR: could not acquire lock!");
-            let r =
-                r.read().expect("B_R_SHIFT R ERROR: could not acquire lock!");
+            let l = l
+                .read_value()
+                .expect("B_R_SHIFT L ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_R_SHIFT R ERROR: could not acquire lock!");
             return l.right_shift(&r);
         } else if let &Primitive::Ref(l) = &self {
             let l = l
-                .read()
+                .read_value()
                 .expect("B_R_SHIFT SELF ERROR: could not acquire lock!");
             return l.right_shift(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r =
-                r.read().expect("B_R_SHIFT RHS ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_R_SHIFT RHS ERROR: could not acquire lock!");
             return self.right_shift(&r);
         }
 
+        if let (Some(l), Some(r)) = (as_bigint(self), as_bigint(rhs)) {
+            return shift_right(&l, &r);
+        }
+
         match (self, rhs) {
-            (Primitive::U8(l), Primitive::U8(r)) => {
-                Primitive::Int(*l as i128 >> *r as i128)
-            }
             (Primitive::U8(l), Primitive::Bool(r)) => {
                 Primitive::U8(l >> if r == &true { 1 } else { 0 })
             }
-            (Primitive::U8(l), Primitive::Int(r)) => {
-                Primitive::Int(*l as i128 >> r)
-            }
-            (Primitive::U8(l), Primitive::I8(r)) => {
-                Primitive::Int(*l as i128 >> *r as i128)
-            }
-            (Primitive::I8(l), Primitive::I8(r)) => {
-                Primitive::Int(*l as i128 >> *r as i128)
-            }
-            (Primitive::I8(l), Primitive::Int(r)) => {
-                Primitive::Int(*l as i128 >> r)
-            }
-            (Primitive::I8(l), Primitive::U8(r)) => {
-                Primitive::Int(*l as i128 >> *r as i128)
-            }
             (Primitive::I8(l), Primitive::Bool(r)) => {
                 Primitive::I8(l >> if r == &true { 1 } else { 0 })
             }
-            (Primitive::Int(l), Primitive::U8(r)) => {
-                Primitive::Int(l >> *r as i128)
-            }
-            (Primitive::Int(l), Primitive::I8(r)) => {
-                Primitive::Int(l >> *r as i128)
-            }
-            (Primitive::Int(l), Primitive::Int(r)) => Primitive::Int(l >> *r),
             (Primitive::Int(l), Primitive::Bool(r)) => {
                 Primitive::Int(l >> if r == &true { 1 } else { 0 })
             }
-            _ => Primitive::Error(format!(
-                "illegal call to 'r_shift' => left: {self} right: {rhs}"
-            )),
+            _ => Primitive::Error(
+                format!(
+                    "illegal call to 'r_shift' => left: {self} right: {rhs}"
+                )
+                .into(),
+            ),
         }
     }
 
     fn left_shift(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l =
-                l.read().expect("B_L_SHIFT L ERROR: could not acquire lock!");
-            let r =
-                r.read().expect("B_L_SHIFT R ERROR: could not acquire lock!");
+            let l = l
+                .read_value()
+                .expect("B_L_SHIFT L ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_L_SHIFT R ERROR: could not acquire lock!");
             return l.left_shift(&r);
         } else if let &Primitive::Ref(l) = &self {
             let l = l
-                .read()
+                .read_value()
                 .expect("B_L_SHIFT SELF ERROR: could not acquire lock!");
             return l.left_shift(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r =
-                r.read().expect("B_L_SHIFT RHS ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_L_SHIFT RHS ERROR: could not acquire lock!");
             return self.left_shift(&r);
         }
 
+        if let (Some(l), Some(r)) = (as_bigint(self), as_bigint(rhs)) {
+            return shift_left(&l, &r);
+        }
+
         match (self, rhs) {
-            (Primitive::U8(l), Primitive::U8(r)) => {
-                Primitive::Int((*l as i128) << *r as i128)
-            }
             (Primitive::U8(l), Primitive::Bool(r)) => {
                 Primitive::U8(l << if r == &true { 1 } else { 0 })
             }
-            (Primitive::U8(l), Primitive::Int(r)) => {
-                Primitive::Int((*l as i128) << r)
-            }
-            (Primitive::U8(l), Primitive::I8(r)) => {
-                Primitive::Int((*l as i128) << r)
-            }
-            (Primitive::I8(l), Primitive::I8(r)) => {
-                Primitive::Int((*l as i128) << (*r as i128))
-            }
-            (Primitive::I8(l), Primitive::Int(r)) => {
-                Primitive::Int((*l as i128) << r)
-            }
-            (Primitive::I8(l), Primitive::U8(r)) => {
-                Primitive::Int((*l as i128) << *r as i128)
-            }
             (Primitive::I8(l), Primitive::Bool(r)) => {
                 Primitive::I8(l << if r == &true { 1 } else { 0 })
             }
-            (Primitive::Int(l), Primitive::U8(r)) => {
-                Primitive::Int(l << *r as i128)
-            }
-            (Primitive::Int(l), Primitive::I8(r)) => {
-                Primitive::Int(l << *r as i128)
-            }
-            (Primitive::Int(l), Primitive::Int(r)) => Primitive::Int(l << *r),
             (Primitive::Int(l), Primitive::Bool(r)) => {
                 Primitive::Int(l << if r == &true { 1 } else { 0 })
             }
-            _ => Primitive::Error(format!(
-                "illegal call to 'l_shift' => left: {self} right: {rhs}"
-            )),
+            _ => Primitive::Error(
+                format!(
+                    "illegal call to 'l_shift' => left: {self} right: {rhs}"
+                )
+                .into(),
+            ),
+        }
+    }
+}
+
+macro_rules! fixed_width {
+    ($variant:path, $l:expr, $r:expr, $operator:expr, $checked:expr) => {
+        if $checked {
+            match $operator {
+                Operator::Add => $l.checked_add($r),
+                Operator::Subtr => $l.checked_sub($r),
+                _ => $l.checked_mul($r),
+            }
+            .map_or(Primitive::Null, $variant)
+        } else {
+            $variant(match $operator {
+                Operator::Add => $l.wrapping_add($r),
+                Operator::Subtr => $l.wrapping_sub($r),
+                _ => $l.wrapping_mul($r),
+            })
         }
+    };
+}
+
+impl Primitive {
+    fn fixed_width(
+        &self,
+        rhs: &Primitive,
+        operator: Operator,
+        checked: bool,
+    ) -> Primitive {
+        match (self, rhs) {
+            (Primitive::Ref(l), r) => {
+                let l = l
+                    .read_value()
+                    .expect("FIXED_WIDTH L ERROR: could not acquire lock!");
+                return l.fixed_width(r, operator, checked);
+            }
+            (l, Primitive::Ref(r)) => {
+                let r = r
+                    .read_value()
+                    .expect("FIXED_WIDTH R ERROR: could not acquire lock!");
+                return l.fixed_width(&r, operator, checked);
+            }
+            _ => {}
+        }
+        let fn_name = format!(
+            "{}_{}",
+            if checked { "checked" } else { "wrapping" },
+            match operator {
+                Operator::Add => "add",
+                Operator::Subtr => "sub",
+                _ => "mul",
+            }
+        );
+        let r = match rhs {
+            Primitive::U8(r) => *r as i128,
+            Primitive::I8(r) => *r as i128,
+            Primitive::Int(r) => *r,
+            _ => {
+                return Primitive::Error(
+                    format!("{fn_name}: expected an int, got {rhs}").into(),
+                );
+            }
+        };
+        let out_of_range = || {
+            Primitive::Error(
+                format!(
+                    "{fn_name}: {r} is out of range for {}",
+                    self.type_of_str()
+                )
+                .into(),
+            )
+        };
+        match self {
+            Primitive::U8(l) => match u8::try_from(r) {
+                Ok(r) => fixed_width!(Primitive::U8, l, r, operator, checked),
+                Err(_) => out_of_range(),
+            },
+            Primitive::I8(l) => match i8::try_from(r) {
+                Ok(r) => fixed_width!(Primitive::I8, l, r, operator, checked),
+                Err(_) => out_of_range(),
+            },
+            Primitive::Int(l) => {
+                fixed_width!(Primitive::Int, l, r, operator, checked)
+            }
+            l => Primitive::Error(
+                format!("{fn_name}: expected a u8, i8 or int, got {l}").into(),
+            ),
+        }
+    }
+}
+
+impl FixedWidth for Primitive {
+    fn wrapping(&self, rhs: &Self, operator: Operator) -> Self {
+        self.fixed_width(rhs, operator, false)
+    }
+
+    fn checked(&self, rhs: &Self, operator: Operator) -> Self {
+        self.fixed_width(rhs, operator, true)
     }
 }
 
 impl Or for Primitive {
     fn or(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l = l.read().expect("OR L ERROR: could not acquire lock!");
-            let r = r.read().expect("OR R ERROR: could not acquire lock!");
+            let l =
+                l.read_value().expect("OR L ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("OR R ERROR: could not acquire lock!");
             return l.or(&r);
         } else if let &Primitive::Ref(l) = &self {
-            let l = l.read().expect("OR SELF ERROR: could not acquire lock!");
+            let l =
+                l.read_value().expect("OR SELF ERROR: could not acquire lock!");
             return l.or(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r = r.read().expect("OR RHS ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("OR RHS ERROR: could not acquire lock!");
             return self.or(&r);
         }
         if let &Primitive::Bool(true) = &self {
             return Primitive::Bool(true);
         }
         if !matches!((self, &rhs), (Primitive::Bool(_), Primitive::Bool(_))) {
-            Primitive::Error(format!(
-                "illegal call to 'or' => left: {self} right: {rhs}"
-            ));
+            return Primitive::Error(
+                format!("illegal call to 'or' => left: {self} right: {rhs}")
+                    .into(),
+            );
         }
         rhs.clone()
     }
 
     fn bitwise_or(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l = l.read().expect("B_OR L ERROR: could not acquire lock!");
-            let r = r.read().expect("B_OR R ERROR: could not acquire lock!");
+            let l =
+                l.read_value().expect("B_OR L ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("B_OR R ERROR: could not acquire lock!");
             return l.bitwise_or(&r);
         } else if let &Primitive::Ref(l) = &self {
-            let l = l.read().expect("B_OR SELF ERROR: could not acquire lock!");
+            let l = l
+                .read_value()
+                .expect("B_OR SELF ERROR: could not acquire lock!");
             return l.bitwise_or(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r = r.read().expect("B_OR RHS ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_OR RHS ERROR: could not acquire lock!");
             return self.bitwise_or(&r);
         }
 
@@ -1456,23 +2265,31 @@ impl Or for Primitive {
             (Primitive::Int(l), Primitive::Bool(r)) => {
                 Primitive::Int(l | if r == &true { 1 } else { 0 })
             }
-            _ => Primitive::Error(format!(
-                "illegal call to 'bitwise_or' => left: {self} right: {rhs}"
-            )),
+            _ => Primitive::Error(
+                format!(
+                    "illegal call to 'bitwise_or' => left: {self} right: {rhs}"
+                )
+                .into(),
+            ),
         }
     }
 
     fn bitwise_xor(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l = l.read().expect("B_XOR L ERROR: could not acquire lock!");
-            let r = r.read().expect("B_XOR R ERROR: could not acquire lock!");
+            let l =
+                l.read_value().expect("B_XOR L ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("B_XOR R ERROR: could not acquire lock!");
             return l.bitwise_xor(&r);
         } else if let &Primitive::Ref(l) = &self {
-            let l =
-                l.read().expect("B_XOR SELF ERROR: could not acquire lock!");
+            let l = l
+                .read_value()
+                .expect("B_XOR SELF ERROR: could not acquire lock!");
             return l.bitwise_xor(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r = r.read().expect("B_XOR RHS ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_XOR RHS ERROR: could not acquire lock!");
             return self.bitwise_xor(&r);
         }
 
@@ -1507,23 +2324,31 @@ impl Or for Primitive {
             (Primitive::Int(l), Primitive::Bool(r)) => {
                 Primitive::Int(l ^ if r == &true { 1 } else { 0 })
             }
-            _ => Primitive::Error(format!(
-                "illegal call to 'bitwise_xor' => left: {self} right: {rhs}"
-            )),
+            _ => Primitive::Error(
+                format!(
+                    "illegal call to 'bitwise_xor' => left: {self} right: {rhs}"
+                )
+                .into(),
+            ),
         }
     }
 }
 impl And for Primitive {
     fn and(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l = l.read().expect("AND L ERROR: could not acquire lock!");
-            let r = r.read().expect("AND R ERROR: could not acquire lock!");
+            let l =
+                l.read_value().expect("AND L ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("AND R ERROR: could not acquire lock!");
             return l.and(&r);
         } else if let &Primitive::Ref(l) = &self {
-            let l = l.read().expect("AND SELF ERROR: could not acquire lock!");
+            let l = l
+                .read_value()
+                .expect("AND SELF ERROR: could not acquire lock!");
             return l.and(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r = r.read().expect("AND RHS ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("AND RHS ERROR: could not acquire lock!");
             return self.and(&r);
         }
         if let &Primitive::Bool(false) = &self {
@@ -1531,24 +2356,30 @@ impl And for Primitive {
         }
 
         if !matches!((self, &rhs), (Primitive::Bool(_), Primitive::Bool(_))) {
-            return Primitive::Error(format!(
-                "illegal call to 'and' => left: {self} right: {rhs}"
-            ));
+            return Primitive::Error(
+                format!("illegal call to 'and' => left: {self} right: {rhs}")
+                    .into(),
+            );
         }
 
         rhs.clone()
     }
     fn bitwise_and(&self, rhs: &Self) -> Self {
         if let (&Primitive::Ref(l), &Primitive::Ref(r)) = (&self, &rhs) {
-            let l = l.read().expect("B_AND L ERROR: could not acquire lock!");
-            let r = r.read().expect("B_AND R ERROR: could not acquire lock!");
+            let l =
+                l.read_value().expect("B_AND L ERROR: could not acquire lock!");
+            let r =
+                r.read_value().expect("B_AND R ERROR: could not acquire lock!");
             return l.bitwise_and(&r);
         } else if let &Primitive::Ref(l) = &self {
-            let l =
-                l.read().expect("B_AND SELF ERROR: could not acquire lock!");
+            let l = l
+                .read_value()
+                .expect("B_AND SELF ERROR: could not acquire lock!");
             return l.bitwise_and(rhs);
         } else if let &Primitive::Ref(r) = &rhs {
-            let r = r.read().expect("B_AND RHS ERROR: could not acquire lock!");
+            let r = r
+                .read_value()
+                .expect("B_AND RHS ERROR: could not acquire lock!");
             return self.bitwise_and(&r);
         }
 
@@ -1583,9 +2414,12 @@ impl And for Primitive {
             (Primitive::Int(l), Primitive::Bool(r)) => {
                 Primitive::Int(l & if r == &true { 1 } else { 0 })
             }
-            _ => Primitive::Error(format!(
-                "illegal call to 'bitwise_and' => left: {self} right: {rhs}"
-            )),
+            _ => Primitive::Error(
+                format!(
+                    "illegal call to 'bitwise_and' => left: {self} right: {rhs}"
+                )
+                .into(),
+            ),
         }
     }
 }
@@ -1598,24 +2432,24 @@ impl PartialOrd for Primitive {
                     return Some(Ordering::Equal);
                 }
                 let l = l
-                    .read()
+                    .read_value()
                     .expect("PARTIAL_CMP L ERORR: could not acquire lock!");
 
                 let r = r
-                    .read()
+                    .read_value()
                     .expect("PARTIAL_CMP R ERORR: could not acquire lock!");
                 l.partial_cmp(&r)
             }
             (Primitive::Ref(l), r) => {
                 let l = l
-                    .read()
+                    .read_value()
                     .expect("PARTIAL_CMP L ERORR: could not acquire lock!");
 
                 l.partial_cmp(r)
             }
             (l, Primitive::Ref(r)) => {
                 let r = r
-                    .read()
+                    .read_value()
                     .expect("PARTIAL_CMP R ERORR: could not acquire lock!");
 
                 l.partial_cmp(&r)
@@ -1661,6 +2495,24 @@ impl PartialOrd for Primitive {
             }
             (Primitive::Double(l), Primitive::Double(r)) => l.partial_cmp(r),
 
+            (Primitive::Quantity(l), Primitive::Quantity(r)) => {
+                quantity_cmp(l, r)
+            }
+            (Primitive::Quantity(_), _) | (_, Primitive::Quantity(_)) => None,
+            (Primitive::Decimal(_) | Primitive::Rational(_), _)
+            | (_, Primitive::Decimal(_) | Primitive::Rational(_)) => {
+                exact_cmp(self, other)
+            }
+            (Primitive::BigInt(l), Primitive::Double(r)) => {
+                l.to_f64().partial_cmp(r)
+            }
+            (Primitive::Double(l), Primitive::BigInt(r)) => {
+                l.partial_cmp(&r.to_f64())
+            }
+            (Primitive::BigInt(_), _) | (_, Primitive::BigInt(_)) => {
+                bigint_operands(self, other).map(|(l, r)| l.cmp(&r))
+            }
+
             (Primitive::Bool(a), Primitive::Bool(b)) => a.partial_cmp(b),
             (l @ Primitive::Bool(_), r) => l.partial_cmp(&(r.to_bool())),
 
@@ -1668,8 +2520,8 @@ impl PartialOrd for Primitive {
             (Primitive::Unit, Primitive::Unit) => Some(Ordering::Equal),
             (Primitive::Array(l), Primitive::Array(r)) => l.partial_cmp(r),
             (
-                Primitive::Function { parameters: pl, exprs: el },
-                Primitive::Function { parameters: pr, exprs: er },
+                Primitive::Function { parameters: pl, exprs: el, .. },
+                Primitive::Function { parameters: pr, exprs: er, .. },
             ) => {
                 if pl.eq(pr)
                     && el.iter().zip(er.iter()).filter(|&(a, b)| a != b).count()
@@ -1687,6 +2539,8 @@ impl PartialOrd for Primitive {
             }
             (Primitive::EarlyReturn(l), a) => l.as_ref().partial_cmp(a),
             (l, Primitive::EarlyReturn(r)) => l.partial_cmp(r),
+            (Primitive::Const(_, l), r) => l.as_ref().partial_cmp(r),
+            (l, Primitive::Const(_, r)) => l.partial_cmp(r),
             (Primitive::Struct(l), Primitive::Struct(r)) => {
                 if l.eq(r) {
                     Some(Ordering::Equal)
@@ -1694,11 +2548,43 @@ impl PartialOrd for Primitive {
                     None
                 }
             }
+            (Primitive::Dict(l), Primitive::Dict(r)) => {
+                if l.eq(r) {
+                    Some(Ordering::Equal)
+                } else {
+                    None
+                }
+            }
+            (Primitive::Set(l), Primitive::Set(r)) => {
+                if l.eq(r) {
+                    Some(Ordering::Equal)
+                } else {
+                    None
+                }
+            }
+            (Primitive::Enum(l), Primitive::Enum(r)) => {
+                if l.eq(r) {
+                    Some(Ordering::Equal)
+                } else {
+                    None
+                }
+            }
+            (Primitive::Variant(l), Primitive::Variant(r)) => {
+                if l.eq(r) {
+                    Some(Ordering::Equal)
+                } else {
+                    None
+                }
+            }
             (Primitive::NativeLibrary { .. }, _)
             | (_, Primitive::NativeLibrary { .. }) => None,
             (Primitive::NativeFunction(_, _), _)
             | (_, Primitive::NativeFunction(_, _)) => None,
             (Primitive::Struct(_), _) => None,
+            (Primitive::Dict(_), _) => None,
+            (Primitive::Set(_), _) => None,
+            (Primitive::Enum(_), _) => None,
+            (Primitive::Variant(_), _) => None,
             (Primitive::Int(_), _) => None,
             (Primitive::U8(_), _) => None,
             (Primitive::I8(_), _) => None,
@@ -1709,8 +2595,9 @@ impl PartialOrd for Primitive {
             (Primitive::Array(_), _) => None,
             (Primitive::Error(_), _) => None,
             (Primitive::Unit, _) => None,
-            (Primitive::Function { parameters: _, exprs: _ }, _) => None,
+            (Primitive::Function { .. }, _) => None,
             (Primitive::LibData(_), _) => None,
+            (Primitive::Iterator(_), _) => None,
         }
     }
 }
@@ -1719,44 +2606,64 @@ impl Round for Primitive {
     fn floor(&self) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l = l.read().expect("FLOOR ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("FLOOR ERROR: could not acquire lock!");
                 l.floor()
             }
 
             Primitive::Int(i) => Primitive::Int(*i),
+            Primitive::BigInt(i) => Primitive::BigInt(i.clone()),
             Primitive::I8(i) => Primitive::I8(*i),
             Primitive::U8(i) => Primitive::U8(*i),
             Primitive::Double(d) => Primitive::Double(d.floor()),
+            Primitive::Decimal(d) => Primitive::Decimal(d.floor()),
+            Primitive::Rational(r) => Primitive::Rational(r.floor()),
+            Primitive::Quantity(q) => {
+                Primitive::Quantity(Quantity::new(q.value.floor(), q.unit))
+            }
 
-            r => Primitive::Error(format!("illegal call to floor!! {r}")),
+            r => {
+                Primitive::Error(format!("illegal call to floor!! {r}").into())
+            }
         }
     }
 
     fn ceil(&self) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l = l.read().expect("CEIL ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("CEIL ERROR: could not acquire lock!");
                 l.floor()
             }
 
             Primitive::Int(i) => Primitive::Int(*i),
+            Primitive::BigInt(i) => Primitive::BigInt(i.clone()),
             Primitive::I8(i) => Primitive::I8(*i),
             Primitive::U8(i) => Primitive::U8(*i),
             Primitive::Double(d) => Primitive::Double(d.ceil()),
+            Primitive::Decimal(d) => Primitive::Decimal(d.ceil()),
+            Primitive::Rational(r) => Primitive::Rational(r.ceil()),
+            Primitive::Quantity(q) => {
+                Primitive::Quantity(Quantity::new(q.value.ceil(), q.unit))
+            }
 
-            r => Primitive::Error(format!("illegal call to ceil!! {r}")),
+            r => Primitive::Error(format!("illegal call to ceil!! {r}").into()),
         }
     }
 
     fn round(&self, decimals: &Self) -> Self {
         match (self, decimals) {
             (Primitive::Ref(s), decimals) => {
-                let l = s.read().expect("ROUND ERROR: could not acquire lock!");
+                let l = s
+                    .read_value()
+                    .expect("ROUND ERROR: could not acquire lock!");
                 l.round(decimals)
             }
             (l, Primitive::Ref(decimals)) => {
                 let decimals = decimals
-                    .read()
+                    .read_value()
                     .expect("ROUND ERROR: could not acquire lock!");
                 l.round(&decimals)
             }
@@ -1768,17 +2675,29 @@ impl Round for Primitive {
             }
             (Primitive::Double(x), Primitive::Int(u)) => {
                 if u < &1 || u > &(u32::MAX as i128) {
-                    return Primitive::Error(format!(
-                        "illegal call to round!!  {u}"
-                    ));
+                    return Primitive::Error(
+                        format!("illegal call to round!!  {u}").into(),
+                    );
                 }
                 let decimals = *u as u32;
                 let y = 10i32.pow(decimals) as f64;
                 Primitive::Double((x * y).round() / y)
             }
-            (p, r) => {
-                Primitive::Error(format!("illegal call to round!!  {p} {r}"))
+            (
+                l @ (Primitive::Decimal(_) | Primitive::Rational(_)),
+                decimals,
+            ) => exact_round(l, decimals),
+            (Primitive::Quantity(q), decimals) => {
+                match Primitive::Double(q.value).round(decimals) {
+                    Primitive::Double(value) => {
+                        Primitive::Quantity(Quantity::new(value, q.unit))
+                    }
+                    e => e,
+                }
             }
+            (p, r) => Primitive::Error(
+                format!("illegal call to round!!  {p} {r}").into(),
+            ),
         }
     }
 }
@@ -1786,13 +2705,16 @@ impl StringManipulation for Primitive {
     fn match_regex(&self, regex: &Primitive) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l = l.read().expect("MATCH ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("MATCH ERROR: could not acquire lock!");
                 l.match_regex(regex)
             }
             v @ Primitive::String(s) => match regex {
                 Primitive::Ref(r) => {
-                    let r =
-                        r.read().expect("MATCH ERROR: could not acquire lock!");
+                    let r = r
+                        .read_value()
+                        .expect("MATCH ERROR: could not acquire lock!");
                     v.match_regex(&r)
                 }
                 Primitive::String(r) => match regex::Regex::new(r) {
@@ -1826,53 +2748,64 @@ impl StringManipulation for Primitive {
                         }
                         Primitive::Array(captures)
                     }
-                    Err(e) => Primitive::Error(format!("regex error: {e}")),
+                    Err(e) => {
+                        Primitive::Error(format!("regex error: {e}").into())
+                    }
                 },
-                r => Primitive::Error(format!("bad regex!  {r}")),
+                r => Primitive::Error(format!("bad regex!  {r}").into()),
             },
-            p => Primitive::Error(format!("illegal call to match!!  {p}")),
+            p => {
+                Primitive::Error(format!("illegal call to match!!  {p}").into())
+            }
         }
     }
 
     fn is_match(&self, regex: &Primitive) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l =
-                    l.read().expect("TYPE_OF ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("TYPE_OF ERROR: could not acquire lock!");
                 l.is_match(regex)
             }
             v @ Primitive::String(s) => match regex {
                 Primitive::Ref(r) => {
-                    let r =
-                        r.read().expect("MATCH ERROR: could not acquire lock!");
+                    let r = r
+                        .read_value()
+                        .expect("MATCH ERROR: could not acquire lock!");
                     v.is_match(&r)
                 }
                 Primitive::String(r) => match regex::Regex::new(r) {
                     Ok(re) => Primitive::Bool(re.is_match(s)),
-                    Err(e) => Primitive::Error(format!("regex error: {e}")),
+                    Err(e) => {
+                        Primitive::Error(format!("regex error: {e}").into())
+                    }
                 },
-                r => Primitive::Error(format!("bad regex!  {r}")),
+                r => Primitive::Error(format!("bad regex!  {r}").into()),
             },
-            p => Primitive::Error(format!("illegal call to is_match!!  {p}")),
+            p => Primitive::Error(
+                format!("illegal call to is_match!!  {p}").into(),
+            ),
         }
     }
     fn replace(&self, regex: &Primitive, new_value: &Primitive) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l =
-                    l.read().expect("REPLACE ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("REPLACE ERROR: could not acquire lock!");
                 l.match_regex(regex)
             }
             v @ Primitive::String(s) => match (regex, new_value) {
                 (Primitive::Ref(regex), new_value) => {
                     let r = regex
-                        .read()
+                        .read_value()
                         .expect("REPLACE ERROR: could not acquire lock!");
                     v.replace(&r, new_value)
                 }
                 (regex, Primitive::Ref(new_value)) => {
                     let r = new_value
-                        .read()
+                        .read_value()
                         .expect("REPLACE ERROR: could not acquire lock!");
                     v.replace(regex, &r)
                 }
@@ -1881,36 +2814,38 @@ impl StringManipulation for Primitive {
                         Ok(re) => Primitive::String(
                             re.replace(s, new_value).to_string(),
                         ),
-                        Err(e) => {
-                            Primitive::Error(format!("replace error: {e}"))
-                        }
+                        Err(e) => Primitive::Error(
+                            format!("replace error: {e}").into(),
+                        ),
                     }
                 }
-                (r, l) => {
-                    Primitive::Error(format!("bad call to replace!  {r} {l}"))
-                }
+                (r, l) => Primitive::Error(
+                    format!("bad call to replace!  {r} {l}").into(),
+                ),
             },
-            p => Primitive::Error(format!("illegal call to replace!!  {p}")),
+            p => Primitive::Error(
+                format!("illegal call to replace!!  {p}").into(),
+            ),
         }
     }
     fn replace_all(&self, regex: &Primitive, new_value: &Primitive) -> Self {
         match self {
             Primitive::Ref(l) => {
                 let l = l
-                    .read()
+                    .read_value()
                     .expect("REPLACE_ALL ERROR: could not acquire lock!");
                 l.match_regex(regex)
             }
             v @ Primitive::String(s) => match (regex, new_value) {
                 (Primitive::Ref(regex), new_value) => {
                     let r = regex
-                        .read()
+                        .read_value()
                         .expect("REPLACE_ALL ERROR: could not acquire lock!");
                     v.replace(&r, new_value)
                 }
                 (regex, Primitive::Ref(new_value)) => {
                     let r = new_value
-                        .read()
+                        .read_value()
                         .expect("REPLACE_ALL ERROR: could not acquire lock!");
                     v.replace(regex, &r)
                 }
@@ -1919,41 +2854,47 @@ impl StringManipulation for Primitive {
                         Ok(re) => Primitive::String(
                             re.replace_all(s, new_value).to_string(),
                         ),
-                        Err(e) => {
-                            Primitive::Error(format!("replace_all error: {e}"))
-                        }
+                        Err(e) => Primitive::Error(
+                            format!("replace_all error: {e}").into(),
+                        ),
                     }
                 }
-                (r, l) => Primitive::Error(format!(
-                    "bad call to replace_all!  {r} {l}"
-                )),
+                (r, l) => Primitive::Error(
+                    format!("bad call to replace_all!  {r} {l}").into(),
+                ),
             },
-            p => {
-                Primitive::Error(format!("illegal call to replace_all!!  {p}"))
-            }
+            p => Primitive::Error(
+                format!("illegal call to replace_all!!  {p}").into(),
+            ),
         }
     }
     fn to_upper(&self) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l =
-                    l.read().expect("TO_UPPER ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("TO_UPPER ERROR: could not acquire lock!");
                 l.to_upper()
             }
             Primitive::String(s) => Primitive::String(s.to_uppercase()),
-            p => Primitive::Error(format!("illegal call to to_upper!!  {p}")),
+            p => Primitive::Error(
+                format!("illegal call to to_upper!!  {p}").into(),
+            ),
         }
     }
 
     fn to_lower(&self) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l =
-                    l.read().expect("TO_LOWER ERROR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("TO_LOWER ERROR: could not acquire lock!");
                 l.to_upper()
             }
             Primitive::String(s) => Primitive::String(s.to_lowercase()),
-            p => Primitive::Error(format!("illegal call to to_lower!!  {p}")),
+            p => Primitive::Error(
+                format!("illegal call to to_lower!!  {p}").into(),
+            ),
         }
     }
 
@@ -1961,7 +2902,7 @@ impl StringManipulation for Primitive {
         match self {
             Primitive::Ref(l) => {
                 let l = l
-                    .read()
+                    .read_value()
                     .expect("CAPITALIZE ERROR: could not acquire lock!");
                 l.to_upper()
             }
@@ -1973,121 +2914,771 @@ impl StringManipulation for Primitive {
                 };
                 Primitive::String(new_s)
             }
-            p => Primitive::Error(format!("illegal call to capitalize!!  {p}")),
+            p => Primitive::Error(
+                format!("illegal call to capitalize!!  {p}").into(),
+            ),
         }
     }
-}
 
-impl TypeOf for Primitive {
-    fn type_of_str(&self) -> &'static str {
+    fn split(&self, separator: &Primitive) -> Self {
         match self {
             Primitive::Ref(l) => {
-                let l =
-                    l.read().expect("TYPE_OF ERROR: could not acquire lock!");
-                l.type_of_str()
-            }
-            Primitive::U8(_) => TYPE_U8,
-            Primitive::I8(_) => TYPE_I8,
-            Primitive::Int(_) => TYPE_INT,
-            Primitive::Bool(_) => TYPE_BOOL,
-            Primitive::Null => TYPE_NULL,
-            Primitive::Double(_) => TYPE_DOUBLE,
-            Primitive::String(_) => TYPE_STRING,
-            Primitive::Array(_) => TYPE_ARRAY,
-            Primitive::Error(_) => TYPE_ERROR,
-            Primitive::NativeLibrary(_) => TYPE_NATIVE_LIB,
-            Primitive::Function { .. } | Primitive::NativeFunction(_, _) => {
-                TYPE_FUNCTION
+                let l = l
+                    .read_value()
+                    .expect("SPLIT ERROR: could not acquire lock!");
+                l.split(separator)
+            }
+            Primitive::String(s) => match string_arg("split", separator) {
+                Ok(sep) if sep.is_empty() => self.chars(),
+                Ok(sep) => Primitive::Array(
+                    s.split(sep.as_str())
+                        .map(|p| Primitive::String(p.to_string()))
+                        .collect(),
+                ),
+                Err(e) => e,
+            },
+            p => {
+                Primitive::Error(format!("illegal call to split!!  {p}").into())
             }
-            Primitive::Struct(_) => TYPE_STRUCT,
-            Primitive::Unit => TYPE_UNIT,
-            Primitive::NoReturn => TYPE_NO_RETURN,
-            Primitive::EarlyReturn(v) => v.type_of_str(),
-            Primitive::LibData(_) => TYPE_LIB_DATA,
         }
     }
 
-    fn type_of(&self) -> Self {
-        Primitive::String(self.type_of_str().to_string())
+    fn join(&self, separator: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("JOIN ERROR: could not acquire lock!");
+                l.join(separator)
+            }
+            Primitive::Array(arr) => match string_arg("join", separator) {
+                Ok(sep) => Primitive::String(
+                    arr.iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<_>>()
+                        .join(&sep),
+                ),
+                Err(e) => e,
+            },
+            p => {
+                Primitive::Error(format!("illegal call to join!!  {p}").into())
+            }
+        }
     }
-}
 
-impl Array for Primitive {
-    fn index_at(&self, rhs: &Primitive) -> Primitive {
-        match (self, rhs) {
-            (Primitive::Ref(l), Primitive::Ref(r)) => {
+    fn trim(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
                 let l = l
-                    .read()
-                    .expect("INDEX_AT L ERORR: could not acquire lock!");
-
-                let r = r
-                    .read()
-                    .expect("INDEX_AT R ERORR: could not acquire lock!");
-                l.index_at(&r)
+                    .read_value()
+                    .expect("TRIM ERROR: could not acquire lock!");
+                l.trim()
             }
-            (Primitive::Ref(l), r) => {
-                let l = l
-                    .read()
-                    .expect("INDEX_AT L ERORR: could not acquire lock!");
-
-                l.index_at(r)
+            Primitive::String(s) => Primitive::String(s.trim().to_string()),
+            p => {
+                Primitive::Error(format!("illegal call to trim!!  {p}").into())
             }
-            (l, Primitive::Ref(r)) => {
-                let r = r
-                    .read()
-                    .expect("INDEX_AT R ERORR: could not acquire lock!");
+        }
+    }
 
-                l.index_at(&r)
+    fn trim_start(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TRIM_START ERROR: could not acquire lock!");
+                l.trim_start()
             }
-            (Primitive::Array(arr), Primitive::Int(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < arr.len() {
-                    arr[idx].clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            Primitive::String(s) => {
+                Primitive::String(s.trim_start().to_string())
+            }
+            p => Primitive::Error(
+                format!("illegal call to trim_start!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn trim_end(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TRIM_END ERROR: could not acquire lock!");
+                l.trim_end()
+            }
+            Primitive::String(s) => Primitive::String(s.trim_end().to_string()),
+            p => Primitive::Error(
+                format!("illegal call to trim_end!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn substring(&self, start: &Primitive, end: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("SUBSTRING ERROR: could not acquire lock!");
+                l.substring(start, end)
+            }
+            Primitive::String(s) => {
+                let len = s.chars().count();
+                let start = match index_arg("substring", start) {
+                    Ok(start) => start.min(len),
+                    Err(e) => return e,
+                };
+                let end = match end {
+                    Primitive::Null => len,
+                    end => match index_arg("substring", end) {
+                        Ok(end) => end.min(len),
+                        Err(e) => return e,
+                    },
+                };
+                if start > end {
+                    return Primitive::Error(
+                        format!("substring: start {start} is after end {end}")
+                            .into(),
+                    );
                 }
+                Primitive::String(
+                    s.chars().skip(start).take(end - start).collect(),
+                )
             }
-            (Primitive::Array(arr), Primitive::U8(idx)) => {
-                let idx = *idx as usize;
-                if idx < arr.len() {
-                    arr[idx].clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            p => Primitive::Error(
+                format!("illegal call to substring!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn contains(&self, pattern: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("CONTAINS ERROR: could not acquire lock!");
+                l.contains(pattern)
+            }
+            Primitive::String(s) => match string_arg("contains", pattern) {
+                Ok(pattern) => Primitive::Bool(s.contains(pattern.as_str())),
+                Err(e) => e,
+            },
+            Primitive::Set(s) => match DictKey::try_from(pattern) {
+                Ok(value) => Primitive::Bool(s.contains(&value)),
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to contains!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn starts_with(&self, pattern: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("STARTS_WITH ERROR: could not acquire lock!");
+                l.starts_with(pattern)
+            }
+            Primitive::String(s) => match string_arg("starts_with", pattern) {
+                Ok(pattern) => Primitive::Bool(s.starts_with(pattern.as_str())),
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to starts_with!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn ends_with(&self, pattern: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("ENDS_WITH ERROR: could not acquire lock!");
+                l.ends_with(pattern)
+            }
+            Primitive::String(s) => match string_arg("ends_with", pattern) {
+                Ok(pattern) => Primitive::Bool(s.ends_with(pattern.as_str())),
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to ends_with!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn index_of(&self, pattern: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("INDEX_OF ERROR: could not acquire lock!");
+                l.index_of(pattern)
+            }
+            Primitive::String(s) => match string_arg("index_of", pattern) {
+                Ok(pattern) => match s.find(pattern.as_str()) {
+                    Some(idx) => {
+                        Primitive::Int(s[..idx].chars().count() as i128)
+                    }
+                    None => Primitive::Null,
+                },
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to index_of!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn pad_left(&self, width: &Primitive, pad: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("PAD_LEFT ERROR: could not acquire lock!");
+                l.pad_left(width, pad)
+            }
+            Primitive::String(s) => match padding("pad_left", s, width, pad) {
+                Ok(padding) => Primitive::String(padding + s),
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to pad_left!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn pad_right(&self, width: &Primitive, pad: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("PAD_RIGHT ERROR: could not acquire lock!");
+                l.pad_right(width, pad)
+            }
+            Primitive::String(s) => match padding("pad_right", s, width, pad) {
+                Ok(padding) => Primitive::String(s.to_string() + &padding),
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to pad_right!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn repeat(&self, n: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("REPEAT ERROR: could not acquire lock!");
+                l.repeat(n)
+            }
+            Primitive::String(s) => match index_arg("repeat", n) {
+                Ok(n) => Primitive::String(s.repeat(n)),
+                Err(e) => e,
+            },
+            p => Primitive::Error(
+                format!("illegal call to repeat!!  {p}").into(),
+            ),
+        }
+    }
+
+    fn lines(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("LINES ERROR: could not acquire lock!");
+                l.lines()
+            }
+            Primitive::String(s) => Primitive::Array(
+                s.lines().map(|l| Primitive::String(l.to_string())).collect(),
+            ),
+            p => {
+                Primitive::Error(format!("illegal call to lines!!  {p}").into())
+            }
+        }
+    }
+
+    fn chars(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("CHARS ERROR: could not acquire lock!");
+                l.chars()
+            }
+            Primitive::String(s) => Primitive::Array(
+                s.chars().map(|c| Primitive::String(c.to_string())).collect(),
+            ),
+            p => {
+                Primitive::Error(format!("illegal call to chars!!  {p}").into())
+            }
+        }
+    }
+}
+
+fn string_arg(fn_name: &str, arg: &Primitive) -> Result<String, Primitive> {
+    match arg {
+        Primitive::Ref(r) => {
+            let r = r
+                .read_value()
+                .expect("STRING ARG ERROR: could not acquire lock!");
+            string_arg(fn_name, &r)
+        }
+        Primitive::String(s) => Ok(s.clone()),
+        p => Err(Primitive::Error(
+            format!("{fn_name}: expected a string, got {p}").into(),
+        )),
+    }
+}
+
+fn index_arg(fn_name: &str, arg: &Primitive) -> Result<usize, Primitive> {
+    let n = match arg {
+        Primitive::Ref(r) => {
+            let r = r
+                .read_value()
+                .expect("INDEX ARG ERROR: could not acquire lock!");
+            return index_arg(fn_name, &r);
+        }
+        Primitive::U8(n) => *n as i128,
+        Primitive::I8(n) => *n as i128,
+        Primitive::Int(n) => *n,
+        p => {
+            return Err(Primitive::Error(
+                format!("{fn_name}: expected an integer, got {p}").into(),
+            ));
+        }
+    };
+    usize::try_from(n).map_err(|_| {
+        Primitive::Error(
+            format!("{fn_name}: expected a positive integer, got {n}").into(),
+        )
+    })
+}
+
+/// the padding needed for `s` to be `width` chars long, space by default
+fn padding(
+    fn_name: &str,
+    s: &str,
+    width: &Primitive,
+    pad: &Primitive,
+) -> Result<String, Primitive> {
+    let width = index_arg(fn_name, width)?;
+    let pad = match pad {
+        Primitive::Null => " ".to_string(),
+        pad => string_arg(fn_name, pad)?,
+    };
+    if pad.is_empty() {
+        return Err(Primitive::Error(
+            format!("{fn_name}: the padding cannot be empty").into(),
+        ));
+    }
+    let missing = width.saturating_sub(s.chars().count());
+    Ok(pad.chars().cycle().take(missing).collect())
+}
+
+impl Dictionary for Primitive {
+    fn keys(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("KEYS ERROR: could not acquire lock!");
+                l.keys()
+            }
+            Primitive::Dict(d) => {
+                Primitive::Array(d.keys().map(Primitive::from).collect())
+            }
+            Primitive::Struct(s) => Primitive::Array(
+                s.keys().map(|k| Primitive::String(k.clone())).collect(),
+            ),
+            _ => Primitive::Error(
+                format!("call to keys() on a non dict value => {self}").into(),
+            ),
+        }
+    }
+
+    fn values(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("VALUES ERROR: could not acquire lock!");
+                l.values()
+            }
+            Primitive::Dict(d) => {
+                Primitive::Array(d.values().cloned().collect())
+            }
+            Primitive::Struct(s) => {
+                Primitive::Array(s.values().cloned().collect())
+            }
+            _ => Primitive::Error(
+                format!("call to values() on a non dict value => {self}")
+                    .into(),
+            ),
+        }
+    }
+
+    fn entries(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("ENTRIES ERROR: could not acquire lock!");
+                l.entries()
+            }
+            Primitive::Dict(d) => Primitive::Array(
+                d.iter()
+                    .map(|(k, v)| Primitive::Array(vec![k.into(), v.clone()]))
+                    .collect(),
+            ),
+            Primitive::Struct(s) => Primitive::Array(
+                s.iter()
+                    .map(|(k, v)| {
+                        Primitive::Array(vec![
+                            Primitive::String(k.clone()),
+                            v.clone(),
+                        ])
+                    })
+                    .collect(),
+            ),
+            _ => Primitive::Error(
+                format!("call to entries() on a non dict value => {self}")
+                    .into(),
+            ),
+        }
+    }
+
+    fn has_key(&self, key: &Primitive) -> Self {
+        match (self, key) {
+            (Primitive::Ref(l), _) => {
+                let l = l
+                    .read_value()
+                    .expect("HAS_KEY ERROR: could not acquire lock!");
+                l.has_key(key)
+            }
+            (Primitive::Dict(d), key) => match DictKey::try_from(key) {
+                Ok(key) => Primitive::Bool(d.contains_key(&key)),
+                Err(e) => e,
+            },
+            (Primitive::Struct(s), key) => match DictKey::try_from(key) {
+                Ok(DictKey::String(key)) => {
+                    Primitive::Bool(s.contains_key(&key))
+                }
+                Ok(_) => Primitive::Bool(false),
+                Err(e) => e,
+            },
+            _ => Primitive::Error(
+                format!("call to has_key() on a non dict value => {self}")
+                    .into(),
+            ),
+        }
+    }
+
+    fn remove_key(&self, key: &Primitive) -> Self {
+        match (self, key) {
+            (Primitive::Ref(l), _) => {
+                let l = l
+                    .read_value()
+                    .expect("REMOVE ERROR: could not acquire lock!");
+                l.remove_key(key)
+            }
+            (Primitive::Dict(d), key) => match DictKey::try_from(key) {
+                Ok(key) => {
+                    let mut d = d.clone();
+                    d.remove(&key);
+                    Primitive::Dict(d)
+                }
+                Err(e) => e,
+            },
+            (Primitive::Set(s), value) => match DictKey::try_from(value) {
+                Ok(value) => {
+                    let mut s = s.clone();
+                    s.remove(&value);
+                    Primitive::Set(s)
+                }
+                Err(e) => e,
+            },
+            (Primitive::Struct(s), key) => match DictKey::try_from(key) {
+                Ok(key) => {
+                    let mut s = s.clone();
+                    if let DictKey::String(key) = key {
+                        s.shift_remove(&key);
+                    }
+                    Primitive::Struct(s)
+                }
+                Err(e) => e,
+            },
+            _ => Primitive::Error(
+                format!("call to remove() on a non dict value => {self}")
+                    .into(),
+            ),
+        }
+    }
+
+    fn to_dict(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TO_DICT ERROR: could not acquire lock!");
+                l.to_dict()
+            }
+            Primitive::Dict(_) => self.clone(),
+            Primitive::Struct(s) => Primitive::Dict(
+                s.iter()
+                    .map(|(k, v)| (DictKey::String(k.clone()), v.clone()))
+                    .collect(),
+            ),
+            Primitive::Array(entries) => {
+                let mut dict = BTreeMap::new();
+                for entry in entries {
+                    let Primitive::Array(pair) = entry else {
+                        return Primitive::Error(
+                            format!(
+                                "to_dict: {entry} is not a [key, value] pair"
+                            )
+                            .into(),
+                        );
+                    };
+                    let [key, value] = &pair[..] else {
+                        return Primitive::Error(
+                            format!(
+                                "to_dict: {entry} is not a [key, value] pair"
+                            )
+                            .into(),
+                        );
+                    };
+                    match DictKey::try_from(key) {
+                        Ok(key) => dict.insert(key, value.clone()),
+                        Err(e) => return e,
+                    };
                 }
+                Primitive::Dict(dict)
             }
-            (Primitive::Array(arr), Primitive::I8(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < arr.len() {
-                    arr[idx].clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            _ => Primitive::Error(
+                format!("cannot convert {self} to a dict").into(),
+            ),
+        }
+    }
+}
+
+impl SetOperations for Primitive {
+    fn add_value(&self, value: &Primitive) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l =
+                    l.read_value().expect("ADD ERROR: could not acquire lock!");
+                l.add_value(value)
+            }
+            Primitive::Set(s) => match DictKey::try_from(value) {
+                Ok(value) => {
+                    let mut s = s.clone();
+                    s.insert(value);
+                    Primitive::Set(s)
                 }
+                Err(e) => e,
+            },
+            _ => Primitive::Error(
+                format!("call to add() on a non set value => {self}").into(),
+            ),
+        }
+    }
+
+    fn union(&self, other: &Primitive) -> Self {
+        match set_operands("union", self, other) {
+            Ok((l, r)) => Primitive::Set(l.union(&r).cloned().collect()),
+            Err(e) => e,
+        }
+    }
+
+    fn intersection(&self, other: &Primitive) -> Self {
+        match set_operands("intersection", self, other) {
+            Ok((l, r)) => Primitive::Set(l.intersection(&r).cloned().collect()),
+            Err(e) => e,
+        }
+    }
+
+    fn difference(&self, other: &Primitive) -> Self {
+        match set_operands("difference", self, other) {
+            Ok((l, r)) => Primitive::Set(l.difference(&r).cloned().collect()),
+            Err(e) => e,
+        }
+    }
+
+    fn to_set(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TO_SET ERROR: could not acquire lock!");
+                l.to_set()
             }
-            (Primitive::String(s), Primitive::Int(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < s.len() {
-                    let s: String = s.chars().skip(idx).take(1).collect();
-                    Primitive::String(s)
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            Primitive::Set(_) => self.clone(),
+            Primitive::Array(arr) => {
+                match arr.iter().map(DictKey::try_from).collect() {
+                    Ok(set) => Primitive::Set(set),
+                    Err(e) => e,
                 }
             }
-            (Primitive::String(s), Primitive::U8(idx)) => {
-                let idx = *idx as usize;
-                if idx < s.len() {
-                    let s: String = s.chars().skip(idx).take(1).collect();
-                    Primitive::String(s)
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            _ => Primitive::Error(
+                format!("cannot convert {self} to a set").into(),
+            ),
+        }
+    }
+
+    fn to_array(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TO_ARRAY ERROR: could not acquire lock!");
+                l.to_array()
+            }
+            Primitive::Array(_) => self.clone(),
+            Primitive::Set(s) => {
+                Primitive::Array(s.iter().map(Primitive::from).collect())
+            }
+            Primitive::Iterator(it) => Primitive::Array(it.restart().collect()),
+            _ => Primitive::Error(
+                format!("cannot convert {self} to an array").into(),
+            ),
+        }
+    }
+}
+
+/// both operands of a set operation. The right one may also be an array
+fn set_operands(
+    fn_name: &str,
+    l: &Primitive,
+    r: &Primitive,
+) -> Result<(BTreeSet<DictKey>, BTreeSet<DictKey>), Primitive> {
+    let operand = |p: &Primitive| match p.to_set() {
+        Primitive::Set(s) => Ok(s),
+        e => Err(e),
+    };
+    match l {
+        Primitive::Ref(l) => {
+            let l = l.read_value().expect("SET ERROR: could not acquire lock!");
+            set_operands(fn_name, &l, r)
+        }
+        Primitive::Set(l) => Ok((l.clone(), operand(r)?)),
+        l => Err(Primitive::Error(
+            format!("{fn_name}: expected a set, got {l}").into(),
+        )),
+    }
+}
+
+impl TypeOf for Primitive {
+    fn type_of_str(&self) -> &'static str {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TYPE_OF ERROR: could not acquire lock!");
+                l.type_of_str()
+            }
+            Primitive::U8(_) => TYPE_U8,
+            Primitive::I8(_) => TYPE_I8,
+            Primitive::Int(_) | Primitive::BigInt(_) => TYPE_INT,
+            Primitive::Bool(_) => TYPE_BOOL,
+            Primitive::Null => TYPE_NULL,
+            Primitive::Double(_) => TYPE_DOUBLE,
+            Primitive::Decimal(_) => TYPE_DECIMAL,
+            Primitive::Rational(_) => TYPE_RATIONAL,
+            Primitive::Quantity(_) => TYPE_QUANTITY,
+            Primitive::String(_) => TYPE_STRING,
+            Primitive::Array(_) => TYPE_ARRAY,
+            Primitive::Error(_) => TYPE_ERROR,
+            Primitive::NativeLibrary(_) => TYPE_NATIVE_LIB,
+            Primitive::Function { .. } | Primitive::NativeFunction(_, _) => {
+                TYPE_FUNCTION
+            }
+            Primitive::Struct(_) => TYPE_STRUCT,
+            Primitive::Dict(_) => TYPE_DICT,
+            Primitive::Set(_) => TYPE_SET,
+            Primitive::Enum(_) | Primitive::Variant(_) => TYPE_ENUM,
+            Primitive::Unit => TYPE_UNIT,
+            Primitive::NoReturn => TYPE_NO_RETURN,
+            Primitive::EarlyReturn(v) | Primitive::Const(_, v) => {
+                v.type_of_str()
+            }
+            Primitive::LibData(_) => TYPE_LIB_DATA,
+            Primitive::Iterator(_) => TYPE_ITERATOR,
+        }
+    }
+
+    fn type_of(&self) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("TYPE_OF ERROR: could not acquire lock!");
+                l.type_of()
+            }
+            Primitive::Variant(v) => Primitive::String(v.enum_name.clone()),
+            _ => Primitive::String(self.type_of_str().to_string()),
+        }
+    }
+}
+
+impl Array for Primitive {
+    fn index_at(&self, rhs: &Primitive) -> Primitive {
+        match (self, rhs) {
+            (Primitive::Ref(l), Primitive::Ref(r)) => {
+                let l = l
+                    .read_value()
+                    .expect("INDEX_AT L ERORR: could not acquire lock!");
+
+                let r = r
+                    .read_value()
+                    .expect("INDEX_AT R ERORR: could not acquire lock!");
+                l.index_at(&r)
+            }
+            (Primitive::Ref(l), r) => {
+                let l = l
+                    .read_value()
+                    .expect("INDEX_AT L ERORR: could not acquire lock!");
+
+                l.index_at(r)
+            }
+            (l, Primitive::Ref(r)) => {
+                let r = r
+                    .read_value()
+                    .expect("INDEX_AT R ERORR: could not acquire lock!");
+
+                l.index_at(&r)
+            }
+            (Primitive::Array(arr), idx) if is_index(idx) => {
+                match resolve_index(idx, arr.len()) {
+                    Some(idx) => arr[idx].clone(),
+                    None => Primitive::Error("index out of range".into()),
                 }
             }
-            (Primitive::String(s), Primitive::I8(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < s.len() {
-                    let s: String = s.chars().skip(idx).take(1).collect();
-                    Primitive::String(s)
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            (Primitive::Iterator(it), idx) if is_index(idx) => {
+                // only a negative index needs the length
+                let negative = matches!(
+                    idx,
+                    Primitive::I8(i) if *i < 0
+                ) || matches!(idx, Primitive::Int(i) if *i < 0);
+                let len =
+                    if negative { it.restart().count() } else { usize::MAX };
+                match resolve_index(idx, len)
+                    .and_then(|idx| it.restart().nth(idx))
+                {
+                    Some(p) => p,
+                    None => Primitive::Error("index out of range".into()),
+                }
+            }
+            (Primitive::String(s), idx) if is_index(idx) => {
+                match resolve_index(idx, s.chars().count()) {
+                    Some(idx) => {
+                        Primitive::String(s.chars().skip(idx).take(1).collect())
+                    }
+                    None => Primitive::Error("index out of range".into()),
                 }
             }
             (Primitive::Struct(struc), Primitive::String(key)) => {
@@ -2097,24 +3688,39 @@ impl Array for Primitive {
                     Primitive::Null
                 }
             }
-            (key, value) => Primitive::Error(format!(
-                "illegal access to array!!!  {key} => {value}"
-            )),
+            (Primitive::Dict(dict), key) => match DictKey::try_from(key) {
+                Ok(key) => dict.get(&key).cloned().unwrap_or(Primitive::Null),
+                Err(e) => e,
+            },
+            (Primitive::Error(e), Primitive::String(key)) => e.get(key),
+            (Primitive::Variant(v), Primitive::String(key)) => {
+                v.fields.get(key).cloned().unwrap_or(Primitive::Null)
+            }
+            (Primitive::Enum(e), Primitive::String(key)) => e.variant(key),
+            (key, value) => Primitive::Error(
+                format!("illegal access to array!!!  {key} => {value}").into(),
+            ),
         }
     }
 
     fn len(&self) -> Primitive {
         match self {
             Primitive::Ref(l) => {
-                let l = l.read().expect("LEN ERROR: could not acquire lock!");
+                let l =
+                    l.read_value().expect("LEN ERROR: could not acquire lock!");
                 l.len()
             }
             Primitive::String(s) => Primitive::Int(s.len() as i128),
             Primitive::Array(a) => Primitive::Int(a.len() as i128),
             Primitive::Struct(s) => Primitive::Int(s.len() as i128),
-            _ => Primitive::Error(format!(
-                "call to len() on a non array value => {self}"
-            )),
+            Primitive::Dict(d) => Primitive::Int(d.len() as i128),
+            Primitive::Set(s) => Primitive::Int(s.len() as i128),
+            Primitive::Iterator(it) => {
+                Primitive::Int(it.restart().count() as i128)
+            }
+            _ => Primitive::Error(
+                format!("call to len() on a non array value => {self}").into(),
+            ),
         }
     }
 
@@ -2133,7 +3739,7 @@ impl Array for Primitive {
                     .expect("SWAP_MEM L ERORR: could not acquire lock!");
 
                 let r = r
-                    .read()
+                    .read_value()
                     .expect("SWAP_MEM R ERORR: could not acquire lock!");
                 l.swap_mem(rhs, &r)
             }
@@ -2146,43 +3752,23 @@ impl Array for Primitive {
             }
             (l, Primitive::Ref(index)) => {
                 let index = index
-                    .read()
+                    .read_value()
                     .expect("SWAP_MEM R ERORR: could not acquire lock!");
 
                 l.swap_mem(rhs, &index)
             }
-            (Primitive::Array(arr), Primitive::Int(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if !matches!(rhs, Primitive::Error(_) | Primitive::Unit)
-                    && idx < arr.len()
-                {
-                    std::mem::swap(&mut arr[idx], rhs);
-                    arr[idx].clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
-                }
-            }
-
-            (Primitive::Array(arr), Primitive::U8(idx)) => {
-                let idx = *idx as usize;
-                if !matches!(rhs, Primitive::Error(_) | Primitive::Unit)
-                    && idx < arr.len()
-                {
-                    std::mem::swap(&mut arr[idx], rhs);
-                    arr[idx].clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
-                }
-            }
-            (Primitive::Array(arr), Primitive::I8(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if !matches!(rhs, Primitive::Error(_) | Primitive::Unit)
-                    && idx < arr.len()
-                {
-                    std::mem::swap(&mut arr[idx], rhs);
-                    arr[idx].clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            (Primitive::Array(arr), idx) if is_index(idx) => {
+                match resolve_index(idx, arr.len()) {
+                    Some(idx)
+                        if !matches!(
+                            rhs,
+                            Primitive::Error(_) | Primitive::Unit
+                        ) =>
+                    {
+                        std::mem::swap(&mut arr[idx], rhs);
+                        arr[idx].clone()
+                    }
+                    _ => Primitive::Error("index out of range".into()),
                 }
             }
             (Primitive::Struct(s), Primitive::String(k)) => {
@@ -2193,43 +3779,31 @@ impl Array for Primitive {
                 }
                 s[k].clone()
             }
-            (Primitive::String(s), Primitive::Int(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if !matches!(rhs, Primitive::Error(_) | Primitive::Unit)
-                    && idx < s.len()
-                {
-                    s.remove(idx);
-                    s.insert_str(idx, &rhs.to_string());
-                    rhs.clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
-                }
-            }
-            (Primitive::String(s), Primitive::I8(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if !matches!(rhs, Primitive::Error(_) | Primitive::Unit)
-                    && idx < s.len()
-                {
-                    s.remove(idx);
-                    s.insert_str(idx, &rhs.to_string());
-                    rhs.clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
+            (Primitive::Dict(d), key) => match DictKey::try_from(key) {
+                Ok(key) => {
+                    let value = d.entry(key).or_insert(Primitive::Null);
+                    std::mem::swap(value, rhs);
+                    value.clone()
                 }
-            }
-            (Primitive::String(s), Primitive::U8(idx)) => {
-                let idx = *idx as usize;
-                if !matches!(rhs, Primitive::Error(_) | Primitive::Unit)
-                    && idx < s.len()
-                {
-                    s.remove(idx);
-                    s.insert_str(idx, &rhs.to_string());
-                    rhs.clone()
-                } else {
-                    Primitive::Error("index out of range".to_string())
+                Err(e) => e,
+            },
+            (Primitive::String(s), idx) if is_index(idx) => {
+                match resolve_index(idx, s.chars().count()) {
+                    Some(idx)
+                        if !matches!(
+                            rhs,
+                            Primitive::Error(_) | Primitive::Unit
+                        ) =>
+                    {
+                        let at = char_offset(s, idx);
+                        s.remove(at);
+                        s.insert_str(at, &rhs.to_string());
+                        rhs.clone()
+                    }
+                    _ => Primitive::Error("index out of range".into()),
                 }
             }
-            _ => Primitive::Error("invalid call to swap_mem()".to_string()),
+            _ => Primitive::Error("invalid call to swap_mem()".into()),
         }
     }
 
@@ -2242,8 +3816,9 @@ impl Array for Primitive {
                 let mut l =
                     l.write().expect("REMOVE L ERORR: could not acquire lock!");
 
-                let r =
-                    r.read().expect("REMOVE R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("REMOVE R ERORR: could not acquire lock!");
                 l.remove(&r)
             }
             (Primitive::Ref(l), _) => {
@@ -2254,68 +3829,39 @@ impl Array for Primitive {
             }
             (l, Primitive::Ref(index)) => {
                 let index = index
-                    .read()
+                    .read_value()
                     .expect("REMOVE R ERORR: could not acquire lock!");
 
                 l.remove(&index)
             }
-            (Primitive::Array(arr), Primitive::Int(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < arr.len() {
-                    arr.remove(idx);
-                    Ok(())
-                } else {
-                    Err(anyhow::Error::msg("index out of range"))
-                }
-            }
-            (Primitive::Array(arr), Primitive::U8(idx)) => {
-                let idx = *idx as usize;
-                if idx < arr.len() {
-                    arr.remove(idx);
-                    Ok(())
-                } else {
-                    Err(anyhow::Error::msg("index out of range"))
-                }
-            }
-            (Primitive::Array(arr), Primitive::I8(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < arr.len() {
-                    arr.remove(idx);
-                    Ok(())
-                } else {
-                    Err(anyhow::Error::msg("index out of range"))
-                }
-            }
-
-            (Primitive::String(s), Primitive::U8(idx)) => {
-                let idx = *idx as usize;
-                if idx < s.len() {
-                    s.remove(idx);
-                    Ok(())
-                } else {
-                    Err(anyhow::Error::msg("index out of range"))
+            (Primitive::Array(arr), idx) if is_index(idx) => {
+                match resolve_index(idx, arr.len()) {
+                    Some(idx) => {
+                        arr.remove(idx);
+                        Ok(())
+                    }
+                    None => Err(anyhow::Error::msg("index out of range")),
                 }
             }
-            (Primitive::String(s), Primitive::I8(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < s.len() {
-                    s.remove(idx);
-                    Ok(())
-                } else {
-                    Err(anyhow::Error::msg("index out of range"))
+            (Primitive::String(s), idx) if is_index(idx) => {
+                match resolve_index(idx, s.chars().count()) {
+                    Some(idx) => {
+                        s.remove(char_offset(s, idx));
+                        Ok(())
+                    }
+                    None => Err(anyhow::Error::msg("index out of range")),
                 }
             }
-            (Primitive::String(s), Primitive::Int(idx)) if idx >= &0 => {
-                let idx = *idx as usize;
-                if idx < s.len() {
-                    s.remove(idx);
-                    Ok(())
-                } else {
-                    Err(anyhow::Error::msg("index out of range"))
+            (Primitive::Struct(struc), Primitive::String(key)) => {
+                match struc.shift_remove(key) {
+                    Some(_p) => Ok(()),
+                    _ => Err(anyhow::Error::msg("key doesn't exist")),
                 }
             }
-            (Primitive::Struct(struc), Primitive::String(key)) => {
-                match struc.remove(key) {
+            (Primitive::Dict(dict), key) => {
+                let key = DictKey::try_from(key)
+                    .map_err(|e| anyhow::Error::msg(e.to_string()))?;
+                match dict.remove(&key) {
                     Some(_p) => Ok(()),
                     _ => Err(anyhow::Error::msg("key doesn't exist")),
                 }
@@ -2327,10 +3873,163 @@ impl Array for Primitive {
     fn is_empty(&self) -> Primitive {
         match self.len() {
             Primitive::Int(n) => Primitive::Bool(n == 0),
-            e => Primitive::Error(format!("err: {e}")),
+            e => Primitive::Error(format!("err: {e}").into()),
+        }
+    }
+
+    fn slice(&self, start: &Self, end: &Self, incl_both_end: bool) -> Self {
+        match self {
+            Primitive::Ref(l) => {
+                let l = l
+                    .read_value()
+                    .expect("SLICE ERORR: could not acquire lock!");
+                l.slice(start, end, incl_both_end)
+            }
+            Primitive::Array(arr) => {
+                match slice_bounds(start, end, incl_both_end, arr.len()) {
+                    Ok((start, end)) => {
+                        Primitive::Array(arr[start..end].to_vec())
+                    }
+                    Err(e) => e,
+                }
+            }
+            Primitive::Iterator(it) => {
+                let len = it.restart().count();
+                match slice_bounds(start, end, incl_both_end, len) {
+                    Ok((start, end)) => Primitive::Array(
+                        it.restart().skip(start).take(end - start).collect(),
+                    ),
+                    Err(e) => e,
+                }
+            }
+            Primitive::String(s) => {
+                match slice_bounds(start, end, incl_both_end, s.chars().count())
+                {
+                    Ok((start, end)) => Primitive::String(
+                        s.chars().skip(start).take(end - start).collect(),
+                    ),
+                    Err(e) => e,
+                }
+            }
+            p => Primitive::Error(format!("cannot slice {p}").into()),
+        }
+    }
+
+    fn splice(
+        &mut self,
+        start: &Self,
+        end: &Self,
+        incl_both_end: bool,
+        rhs: &Self,
+    ) -> Self {
+        match (self, rhs) {
+            (Primitive::Ref(l), _) => {
+                let mut l =
+                    l.write().expect("SPLICE ERORR: could not acquire lock!");
+                l.splice(start, end, incl_both_end, rhs)
+            }
+            (l, Primitive::Ref(r)) => {
+                let r = r
+                    .read_value()
+                    .expect("SPLICE ERORR: could not acquire lock!");
+                l.splice(start, end, incl_both_end, &r)
+            }
+            (Primitive::Array(arr), Primitive::Array(_) | Primitive::Unit) => {
+                match slice_bounds(start, end, incl_both_end, arr.len()) {
+                    Ok((start, end)) => {
+                        let values = match rhs {
+                            Primitive::Array(values) => values.clone(),
+                            _ => vec![],
+                        };
+                        arr.splice(start..end, values);
+                        Primitive::Array(arr.clone())
+                    }
+                    Err(e) => e,
+                }
+            }
+            (Primitive::String(s), Primitive::String(_) | Primitive::Unit) => {
+                match slice_bounds(start, end, incl_both_end, s.chars().count())
+                {
+                    Ok((start, end)) => {
+                        let (start, end) =
+                            (char_offset(s, start), char_offset(s, end));
+                        let value = match rhs {
+                            Primitive::String(value) => value.as_str(),
+                            _ => "",
+                        };
+                        s.replace_range(start..end, value);
+                        Primitive::String(s.clone())
+                    }
+                    Err(e) => e,
+                }
+            }
+            (l, r) => Primitive::Error(
+                format!("cannot assign {r} to a slice of {l}").into(),
+            ),
         }
     }
 }
+
+fn is_index(idx: &Primitive) -> bool {
+    matches!(idx, Primitive::U8(_) | Primitive::I8(_) | Primitive::Int(_))
+}
+
+/// a negative index counts from the end
+fn resolve_index(idx: &Primitive, len: usize) -> Option<usize> {
+    let idx = match idx {
+        Primitive::U8(idx) => *idx as i128,
+        Primitive::I8(idx) => *idx as i128,
+        Primitive::Int(idx) => *idx,
+        _ => return None,
+    };
+    let idx = if idx < 0 { idx + len as i128 } else { idx };
+    usize::try_from(idx).ok().filter(|idx| *idx < len)
+}
+
+/// the bounds of a slice, `null` when open. Like indexes, negative bounds
+/// count from the end, and bounds out of range are clamped
+fn slice_bounds(
+    start: &Primitive,
+    end: &Primitive,
+    incl_both_end: bool,
+    len: usize,
+) -> Result<(usize, usize), Primitive> {
+    let len = len as i128;
+    let bound = |b: &Primitive,
+                 incl: bool|
+     -> Result<Option<i128>, Primitive> {
+        let b = match b {
+            Primitive::Ref(r) => r
+                .read_value()
+                .expect("SLICE ERORR: could not acquire lock!")
+                .clone(),
+            b => b.clone(),
+        };
+        let b = match b {
+            Primitive::Null => return Ok(None),
+            Primitive::U8(b) => b as i128,
+            Primitive::I8(b) => b as i128,
+            Primitive::Int(b) => b,
+            b => {
+                return Err(Primitive::Error(
+                    format!("slice bound must be an integer, got {b}").into(),
+                ));
+            }
+        };
+        let b = if b < 0 { b + len } else { b };
+        let b = if incl { b + 1 } else { b };
+        Ok(Some(b.clamp(0, len)))
+    };
+    let start = bound(start, false)?.unwrap_or(0);
+    let end = bound(end, incl_both_end)?.unwrap_or(len);
+    Ok((start as usize, end.max(start) as usize))
+}
+
+/// the byte offset of the char at `idx`
+fn char_offset(s: &str, idx: usize) -> usize {
+    s.char_indices().nth(idx).map(|(offset, _)| offset).unwrap_or(s.len())
+}
+
 impl PartialEq for Primitive {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -2339,34 +4038,56 @@ impl PartialEq for Primitive {
                     return true;
                 }
 
-                let l0 =
-                    l0.read().expect("EQ L ERORR: could not acquire lock!");
+                let l0 = l0
+                    .read_value()
+                    .expect("EQ L ERORR: could not acquire lock!");
 
-                let r = r0.read().expect("EQ R ERORR: could not acquire lock!");
+                let r = r0
+                    .read_value()
+                    .expect("EQ R ERORR: could not acquire lock!");
                 l0.eq(&r)
             }
             (Primitive::Ref(l), _) => {
-                let l = l.read().expect("EQ L ERORR: could not acquire lock!");
+                let l = l
+                    .read_value()
+                    .expect("EQ L ERORR: could not acquire lock!");
                 l.eq(other)
             }
             (_, Primitive::Ref(r)) => {
-                let r = r.read().expect("EQ R ERORR: could not acquire lock!");
+                let r = r
+                    .read_value()
+                    .expect("EQ R ERORR: could not acquire lock!");
                 self.eq(&r)
             }
+            (Primitive::Const(_, l), r) => l.as_ref().eq(r),
+            (l, Primitive::Const(_, r)) => l.eq(r),
             (Self::U8(l0), Self::U8(r0)) => l0 == r0,
             (Self::I8(l0), Self::I8(r0)) => l0 == r0,
             (Self::Int(l0), Self::Int(r0)) => l0 == r0,
+            (Self::BigInt(l0), Self::BigInt(r0)) => l0 == r0,
             (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
             (Self::Double(l0), Self::Double(r0)) => l0 == r0,
+            (Self::Decimal(l0), Self::Decimal(r0)) => l0 == r0,
+            (Self::Rational(l0), Self::Rational(r0)) => l0 == r0,
+            (Self::Quantity(l0), Self::Quantity(r0)) => l0 == r0,
             (Self::String(l0), Self::String(r0)) => l0 == r0,
             (Self::Array(l0), Self::Array(r0)) => l0 == r0,
             (Self::Struct(l0), Self::Struct(r0)) => l0 == r0,
+            (Self::Dict(l0), Self::Dict(r0)) => l0 == r0,
+            (Self::Set(l0), Self::Set(r0)) => l0 == r0,
+            (Self::Enum(l0), Self::Enum(r0)) => l0 == r0,
+            (Self::Variant(l0), Self::Variant(r0)) => l0 == r0,
             (Self::Error(l0), Self::Error(r0)) => l0 == r0,
             (
-                Self::Function { parameters: l_parameters, exprs: l_exprs },
-                Self::Function { parameters: r_parameters, exprs: r_exprs },
+                Self::Function {
+                    parameters: l_parameters, exprs: l_exprs, ..
+                },
+                Self::Function {
+                    parameters: r_parameters, exprs: r_exprs, ..
+                },
             ) => l_parameters == r_parameters && l_exprs == r_exprs,
             (Self::EarlyReturn(l0), Self::EarlyReturn(r0)) => l0 == r0,
+            (Self::Iterator(l0), Self::Iterator(r0)) => l0 == r0,
             _ => {
                 core::mem::discriminant(self) == core::mem::discriminant(other)
             }
@@ -2379,10 +4100,12 @@ impl DisplayHex for Primitive {
             Primitive::U8(u) => Primitive::String(format!("{u:#x}")),
             Primitive::I8(u) => Primitive::String(format!("{u:#x}")),
             Primitive::Int(u) => Primitive::String(format!("{u:#x}")),
+            Primitive::BigInt(u) => Primitive::String(format!("{u:#x}")),
 
             Primitive::Ref(l0) => {
-                let l0 =
-                    l0.read().expect("EQ L ERORR: could not acquire lock!");
+                let l0 = l0
+                    .read_value()
+                    .expect("EQ L ERORR: could not acquire lock!");
                 l0.to_hex()
             }
             Primitive::Double(d) => {
@@ -2391,7 +4114,9 @@ impl DisplayHex for Primitive {
                 Primitive::String(format!("{u:#x}"))
             }
 
-            e => Primitive::Error(format!("could not convert to_hex: {e}")),
+            e => Primitive::Error(
+                format!("could not convert to_hex: {e}").into(),
+            ),
         }
     }
 }
@@ -2401,11 +4126,13 @@ impl DisplayBinary for Primitive {
             Primitive::U8(u) => Primitive::String(format!("{u:#b}")),
             Primitive::I8(u) => Primitive::String(format!("{u:#b}")),
             Primitive::Int(u) => Primitive::String(format!("{u:#b}")),
+            Primitive::BigInt(u) => Primitive::String(format!("{u:#b}")),
 
             Primitive::Ref(l0) => {
-                let l0 =
-                    l0.read().expect("EQ L ERORR: could not acquire lock!");
-                l0.to_hex()
+                let l0 = l0
+                    .read_value()
+                    .expect("EQ L ERORR: could not acquire lock!");
+                l0.to_binary()
             }
             Primitive::Double(d) => {
                 let bytes = d.to_ne_bytes();
@@ -2413,7 +4140,9 @@ impl DisplayBinary for Primitive {
                 Primitive::String(format!("{u:#b}"))
             }
 
-            e => Primitive::Error(format!("could not convert to_binary: {e}")),
+            e => Primitive::Error(
+                format!("could not convert to_binary: {e}").into(),
+            ),
         }
     }
 }
@@ -2421,9 +4150,11 @@ impl DisplayBinary for Primitive {
 
 #[cfg(test)]
 mod test {
+    use indexmap::IndexMap;
+
     use super::Add;
 
-    use super::Primitive;
+    use super::{Captured, CompiledBody, Primitive};
 
     #[test]
     fn test_add_valid() {
@@ -2443,4 +4174,54 @@ mod test {
         let r = Primitive::Double(2.);
         assert_eq!(l.add(&r), Primitive::Double(3.));
     }
+
+    #[test]
+    fn test_compiled_body_shared_by_copies() {
+        let body = CompiledBody::default();
+        let copy = body.clone();
+        let compiled = body.get_or_init(|| String::from("program"));
+        let again = copy.get_or_init(|| String::from("compiled twice"));
+        assert!(std::sync::Arc::ptr_eq(&compiled, &again));
+    }
+
+    #[test]
+    fn test_self_captured_function() {
+        // `counter.get = () => { &counter }`
+        let counter = Primitive::Struct(IndexMap::from([(
+            "n".into(),
+            Primitive::Int(0),
+        )]))
+        .ref_prim();
+        let mut captured = Captured::default();
+        captured.insert("counter".into(), counter.clone());
+        let get = Primitive::Function {
+            parameters: vec![],
+            exprs: vec![],
+            captured,
+            body: CompiledBody::default(),
+        };
+        if let Ok(mut c) = counter.write() {
+            if let Primitive::Struct(s) = &mut *c {
+                s.insert("get".into(), get);
+            }
+        }
+        let counter = counter.read().unwrap().clone();
+        assert!(format!("{counter:?}").contains("captured: {\"counter\"}"));
+        let json = serde_json::to_string(&counter).unwrap();
+        let Primitive::Struct(s) = serde_json::from_str(&json).unwrap() else {
+            panic!("not a struct: {json}");
+        };
+        // the struct is captured once, without the method capturing it again
+        let Primitive::Function { captured, .. } = &s["get"] else {
+            panic!("not a function: {json}");
+        };
+        let counter = captured["counter"].read().unwrap().clone();
+        let Primitive::Struct(s) = counter else {
+            panic!("not a struct: {json}");
+        };
+        assert_eq!(Primitive::Int(0), s["n"]);
+        assert!(
+            matches!(&s["get"], Primitive::Function { captured, .. } if captured.is_empty())
+        );
+    }
 }
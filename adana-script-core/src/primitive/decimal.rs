@@ -0,0 +1,237 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::BigInt;
+
+/// fractional digits kept by a division that doesn't terminate, e.g. `1d / 3`
+pub const DIV_PRECISION: u32 = 28;
+/// a literal such as `1e100000000d` is refused, to not exhaust the memory
+const MAX_EXPONENT: i64 = 1 << 16;
+
+/// an exact base 10 number, `mantissa * 10^-scale`, written `0.1d`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Decimal {
+    mantissa: BigInt,
+    /// digits after the point, without trailing zeros
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: BigInt, scale: u32) -> Self {
+        let mut decimal = Decimal { mantissa, scale };
+        let ten = BigInt::from(10);
+        while decimal.scale > 0 {
+            match decimal.mantissa.div_rem(&ten) {
+                Some((quotient, remainder)) if remainder.is_zero() => {
+                    decimal.mantissa = quotient;
+                    decimal.scale -= 1;
+                }
+                _ => break,
+            }
+        }
+        decimal
+    }
+
+    pub fn mantissa(&self) -> &BigInt {
+        &self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// the integer part, truncated toward zero
+    pub fn trunc(&self) -> BigInt {
+        let (quotient, _) =
+            self.mantissa.div_rem(&ten_pow(self.scale)).expect("not zero");
+        quotient
+    }
+
+    pub fn neg(&self) -> Self {
+        Decimal { mantissa: self.mantissa.neg(), scale: self.scale }
+    }
+
+    pub fn abs(&self) -> Self {
+        Decimal { mantissa: self.mantissa.abs(), scale: self.scale }
+    }
+
+    pub fn add(&self, rhs: &Decimal) -> Self {
+        let (l, r, scale) = self.align(rhs);
+        Decimal::new(l.add(&r), scale)
+    }
+
+    pub fn sub(&self, rhs: &Decimal) -> Self {
+        let (l, r, scale) = self.align(rhs);
+        Decimal::new(l.sub(&r), scale)
+    }
+
+    pub fn mul(&self, rhs: &Decimal) -> Self {
+        Decimal::new(self.mantissa.mul(&rhs.mantissa), self.scale + rhs.scale)
+    }
+
+    /// rounded half away from zero to `DIV_PRECISION` fractional digits.
+    /// `None` when dividing by zero
+    pub fn div(&self, rhs: &Decimal) -> Option<Self> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let numerator = self.mantissa.mul(&ten_pow(rhs.scale + DIV_PRECISION));
+        let denominator = rhs.mantissa.mul(&ten_pow(self.scale));
+        Some(Decimal::new(div_round(&numerator, &denominator), DIV_PRECISION))
+    }
+
+    /// remainder of the division truncated toward zero, like an int.
+    /// `None` when dividing by zero
+    pub fn rem(&self, rhs: &Decimal) -> Option<Self> {
+        let (l, r, scale) = self.align(rhs);
+        let (_, remainder) = l.div_rem(&r)?;
+        Some(Decimal::new(remainder, scale))
+    }
+
+    /// `None` when raising zero to a negative power
+    pub fn pow(&self, exp: i32) -> Option<Self> {
+        let power = Decimal::new(
+            self.mantissa.pow(exp.unsigned_abs()),
+            self.scale * exp.unsigned_abs(),
+        );
+        if exp < 0 {
+            Decimal::from(BigInt::from(1)).div(&power)
+        } else {
+            Some(power)
+        }
+    }
+
+    /// rounded half away from zero to `decimals` fractional digits
+    pub fn round(&self, decimals: u32) -> Self {
+        if self.scale <= decimals {
+            return self.clone();
+        }
+        let divisor = ten_pow(self.scale - decimals);
+        Decimal::new(div_round(&self.mantissa, &divisor), decimals)
+    }
+
+    pub fn floor(&self) -> Self {
+        let trunc = self.trunc();
+        if self.is_negative() && self.scale > 0 {
+            trunc.sub(&BigInt::from(1)).into()
+        } else {
+            trunc.into()
+        }
+    }
+
+    pub fn ceil(&self) -> Self {
+        let trunc = self.trunc();
+        if !self.is_negative() && self.scale > 0 {
+            trunc.add(&BigInt::from(1)).into()
+        } else {
+            trunc.into()
+        }
+    }
+
+    /// both mantissas at the same scale
+    fn align(&self, rhs: &Decimal) -> (BigInt, BigInt, u32) {
+        let scale = self.scale.max(rhs.scale);
+        (
+            self.mantissa.mul(&ten_pow(scale - self.scale)),
+            rhs.mantissa.mul(&ten_pow(scale - rhs.scale)),
+            scale,
+        )
+    }
+}
+
+pub(super) fn ten_pow(exp: u32) -> BigInt {
+    BigInt::from(10).pow(exp)
+}
+
+/// `l / r` rounded half away from zero, `r` must not be zero
+pub(super) fn div_round(l: &BigInt, r: &BigInt) -> BigInt {
+    let (quotient, remainder) = l.div_rem(r).expect("not zero");
+    if remainder.abs().mul(&BigInt::from(2)) < r.abs() {
+        quotient
+    } else if l.is_negative() != r.is_negative() {
+        quotient.sub(&BigInt::from(1))
+    } else {
+        quotient.add(&BigInt::from(1))
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(n: BigInt) -> Self {
+        Decimal { mantissa: n, scale: 0 }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (l, r, _) = self.align(other);
+        l.cmp(&r)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.mantissa);
+        }
+        let digits = self.mantissa.abs().to_string();
+        let scale = self.scale as usize;
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        let sign = if self.is_negative() { "-" } else { "" };
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = anyhow::Error;
+
+    /// `12.5`, `-0.001` or `1.5e3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow::format_err!("invalid decimal {s}");
+        let (number, exponent) = match s.split_once(['e', 'E']) {
+            Some((number, exponent)) => {
+                (number, exponent.parse::<i64>().map_err(|_| invalid())?)
+            }
+            None => (s, 0),
+        };
+        let (integer, fraction) =
+            number.split_once('.').unwrap_or((number, ""));
+        if !fraction.bytes().all(|b| b.is_ascii_digit())
+            || integer.trim_start_matches(['-', '+']).is_empty()
+                && fraction.is_empty()
+        {
+            return Err(invalid());
+        }
+        let integer = match integer {
+            "" | "-" | "+" => format!("{integer}0"),
+            _ => integer.to_string(),
+        };
+        let mantissa: BigInt = format!("{integer}{fraction}").parse()?;
+        match fraction.len() as i64 - exponent {
+            scale if scale.abs() > MAX_EXPONENT => Err(invalid()),
+            scale if scale < 0 => {
+                Ok(Decimal::new(mantissa.mul(&ten_pow(-scale as u32)), 0))
+            }
+            scale => Ok(Decimal::new(mantissa, scale as u32)),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt::Display;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use super::{Primitive, ReadValue};
+
+/// An enum declared in a script, e.g.
+/// `enum Status { Pending, Failed { reason } }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumType {
+    pub name: String,
+    /// variants with the fields of their payload, in declaration order
+    pub variants: Vec<(String, Vec<String>)>,
+}
+
+/// A value of an enum, e.g. `Status.Failed("disk full")`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub enum_name: String,
+    pub name: String,
+    pub fields: IndexMap<String, Primitive>,
+}
+
+impl EnumType {
+    fn fields(&self, variant: &str) -> Option<&[String]> {
+        self.variants
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map(|(_, fields)| fields.as_slice())
+    }
+
+    fn no_variant(&self, variant: &str) -> Primitive {
+        Primitive::Error(
+            format!("enum {} has no variant {variant}", self.name).into(),
+        )
+    }
+
+    /// `Status.Pending`. A variant with a payload is built by calling it,
+    /// without arguments its fields are null, which is enough for `is`
+    pub fn variant(&self, name: &str) -> Primitive {
+        let Some(fields) = self.fields(name) else {
+            return self.no_variant(name);
+        };
+        Primitive::Variant(Box::new(EnumVariant {
+            enum_name: self.name.clone(),
+            name: name.to_string(),
+            fields: fields
+                .iter()
+                .map(|field| (field.clone(), Primitive::Null))
+                .collect(),
+        }))
+    }
+
+    /// `Status.Failed("disk full")`, arguments are the fields of the
+    /// payload in declaration order
+    pub fn construct(&self, name: &str, args: Vec<Primitive>) -> Primitive {
+        let Some(fields) = self.fields(name) else {
+            return self.no_variant(name);
+        };
+        if fields.len() != args.len() {
+            return Primitive::Error(
+                format!(
+                    "{}.{name} expects {} argument(s), got {}",
+                    self.name,
+                    fields.len(),
+                    args.len()
+                )
+                .into(),
+            );
+        }
+        let mut values = IndexMap::with_capacity(fields.len());
+        for (field, arg) in fields.iter().zip(args) {
+            match arg {
+                e @ Primitive::Error(_) => return e,
+                Primitive::Unit => {
+                    return Primitive::Error(
+                        "cannot push unit () to enum variant".into(),
+                    );
+                }
+                arg => {
+                    values.insert(field.clone(), arg);
+                }
+            }
+        }
+        Primitive::Variant(Box::new(EnumVariant {
+            enum_name: self.name.clone(),
+            name: name.to_string(),
+            fields: values,
+        }))
+    }
+
+    /// `Status(value)`, a variant back from its json form, `"Pending"` or
+    /// `{"Failed": {"reason": "disk full"}}`
+    pub fn from_primitive(&self, value: &Primitive) -> Primitive {
+        match value {
+            Primitive::Ref(r) => {
+                let r = r
+                    .read_value()
+                    .expect("ENUM ERROR: could not acquire lock!");
+                self.from_primitive(&r)
+            }
+            Primitive::Variant(v)
+                if v.enum_name == self.name
+                    && self.fields(&v.name).is_some() =>
+            {
+                value.clone()
+            }
+            Primitive::String(name) => match self.fields(name) {
+                Some([]) => self.variant(name),
+                Some(_) => Primitive::Error(
+                    format!("{}.{name} needs a payload", self.name).into(),
+                ),
+                None => self.no_variant(name),
+            },
+            Primitive::Struct(s) if s.len() == 1 => {
+                let Some((name, payload)) = s.first() else {
+                    unreachable!("ENUM BUG: struct has one field")
+                };
+                let Some(fields) = self.fields(name) else {
+                    return self.no_variant(name);
+                };
+                let payload = match payload {
+                    Primitive::Ref(r) => r
+                        .read_value()
+                        .expect("ENUM ERROR: could not acquire lock!")
+                        .clone(),
+                    p => p.clone(),
+                };
+                let Primitive::Struct(mut payload) = payload else {
+                    return Primitive::Error(
+                        format!(
+                            "payload of {}.{name} is not a struct",
+                            self.name
+                        )
+                        .into(),
+                    );
+                };
+                let mut args = Vec::with_capacity(fields.len());
+                for field in fields {
+                    match payload.shift_remove(field) {
+                        Some(arg) => args.push(arg),
+                        None => {
+                            return Primitive::Error(
+                                format!(
+                                    "missing field {field} for {}.{name}",
+                                    self.name
+                                )
+                                .into(),
+                            );
+                        }
+                    }
+                }
+                self.construct(name, args)
+            }
+            v => Primitive::Error(
+                format!("cannot convert {v} to {}", self.name).into(),
+            ),
+        }
+    }
+}
+
+impl EnumVariant {
+    /// `state is Status.Failed`, the payload doesn't matter
+    pub fn is_same_variant(&self, other: &EnumVariant) -> bool {
+        self.enum_name == other.enum_name && self.name == other.name
+    }
+}
+
+impl Display for EnumType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variants = self
+            .variants
+            .iter()
+            .map(|(name, fields)| match &fields[..] {
+                [] => name.clone(),
+                fields => format!("{name} {{ {} }}", fields.join(", ")),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "enum {} {{ {} }}", self.name, variants.join(", "))
+    }
+}
+
+impl Display for EnumVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.enum_name, self.name)?;
+        if self.fields.is_empty() {
+            return Ok(());
+        }
+        let args = self
+            .fields
+            .values()
+            .map(|p| match p {
+                Primitive::String(s) => format!(r#""{s}""#),
+                p => p.to_string(),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "({})", args.join(", "))
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::Display;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use super::Primitive;
+use crate::Span;
+
+pub const DEFAULT_ERROR_KIND: &str = "Error";
+/// a value that can't be read as what was asked, e.g. `to_int("a")`
+pub const PARSE_ERROR_KIND: &str = "ParseError";
+/// a file that can't be read
+pub const IO_ERROR_KIND: &str = "IoError";
+/// a value of the wrong type, e.g. `sqrt("a")`
+pub const TYPE_ERROR_KIND: &str = "TypeError";
+/// a call with the wrong number of arguments
+pub const ARGUMENT_ERROR_KIND: &str = "ArgumentError";
+
+pub const ERROR_KIND: &str = "kind";
+pub const ERROR_MESSAGE: &str = "message";
+pub const ERROR_CAUSE: &str = "cause";
+pub const ERROR_STACK: &str = "stack";
+
+/// An error value, with what caused it and where it was raised.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptError {
+    pub kind: String,
+    pub message: String,
+    pub cause: Option<Box<ScriptError>>,
+    /// extra fields, accessible like struct keys
+    pub payload: IndexMap<String, Primitive>,
+    /// innermost frame first
+    pub stack: Vec<StackFrame>,
+    /// the expression that raised the error, if known
+    #[serde(skip)]
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub function: String,
+    pub location: Option<String>,
+}
+
+impl ScriptError {
+    pub fn new(message: impl Into<String>) -> Self {
+        ScriptError {
+            kind: DEFAULT_ERROR_KIND.to_string(),
+            message: message.into(),
+            cause: None,
+            payload: IndexMap::new(),
+            stack: vec![],
+            span: None,
+        }
+    }
+
+    pub fn with_kind(kind: &str, message: impl Into<String>) -> Self {
+        ScriptError { kind: kind.to_string(), ..ScriptError::new(message) }
+    }
+
+    /// build an error from a struct, e.g. `make_err(struct { kind: "NotFound", message: "..." })`.
+    /// Fields other than kind, message and cause end up in the payload.
+    pub fn from_struct(mut fields: IndexMap<String, Primitive>) -> Self {
+        let mut take = |key: &str| fields.shift_remove(key);
+        let kind = match take(ERROR_KIND) {
+            Some(Primitive::String(kind)) => kind,
+            Some(kind) => kind.to_string(),
+            None => DEFAULT_ERROR_KIND.to_string(),
+        };
+        let message = match take(ERROR_MESSAGE) {
+            Some(Primitive::String(message)) => message,
+            Some(message) => message.to_string(),
+            None => String::new(),
+        };
+        let cause = match take(ERROR_CAUSE) {
+            Some(Primitive::Error(cause)) => Some(cause),
+            Some(Primitive::Null) | None => None,
+            Some(cause) => Some(Box::new(ScriptError::new(cause.to_string()))),
+        };
+        ScriptError {
+            kind,
+            message,
+            cause,
+            payload: fields,
+            stack: vec![],
+            span: None,
+        }
+    }
+
+    /// value of a field, as accessed from a script
+    pub fn get(&self, key: &str) -> Primitive {
+        match key {
+            ERROR_KIND => Primitive::String(self.kind.clone()),
+            ERROR_MESSAGE => Primitive::String(self.message.clone()),
+            ERROR_CAUSE => match &self.cause {
+                Some(cause) => Primitive::Error(cause.clone()),
+                None => Primitive::Null,
+            },
+            ERROR_STACK => Primitive::Array(
+                self.stack.iter().map(StackFrame::to_struct).collect(),
+            ),
+            key => self.payload.get(key).cloned().unwrap_or(Primitive::Null),
+        }
+    }
+
+    /// the error as a struct, e.g. to jsonify it
+    pub fn to_struct(&self) -> IndexMap<String, Primitive> {
+        let mut fields = IndexMap::with_capacity(self.payload.len() + 4);
+        for key in [ERROR_KIND, ERROR_MESSAGE] {
+            fields.insert(key.to_string(), self.get(key));
+        }
+        fields.extend(self.payload.clone());
+        fields.insert(ERROR_STACK.to_string(), self.get(ERROR_STACK));
+        let cause = match &self.cause {
+            Some(cause) => Primitive::Struct(cause.to_struct()),
+            None => Primitive::Null,
+        };
+        fields.insert(ERROR_CAUSE.to_string(), cause);
+        fields
+    }
+
+    /// the error with its payload, stack trace and causes
+    pub fn report(&self) -> String {
+        format!("{self}{}", self.details())
+    }
+
+    /// the payload, stack trace and causes of the error, one per line, each
+    /// line starting with a line break
+    pub fn details(&self) -> String {
+        let mut report = String::new();
+        let mut error = Some(self);
+        while let Some(e) = error {
+            if !std::ptr::eq(e, self) {
+                report.push_str(&format!("\ncaused by: {e}"));
+            }
+            for (key, value) in &e.payload {
+                report.push_str(&format!("\n    {key}: {value}"));
+            }
+            for frame in &e.stack {
+                report.push_str(&format!("\n    at {frame}"));
+            }
+            error = e.cause.as_deref();
+        }
+        report
+    }
+}
+
+impl StackFrame {
+    fn to_struct(&self) -> Primitive {
+        Primitive::Struct(IndexMap::from([
+            ("function".to_string(), Primitive::String(self.function.clone())),
+            (
+                "location".to_string(),
+                self.location
+                    .as_ref()
+                    .map(|l| Primitive::String(l.clone()))
+                    .unwrap_or(Primitive::Null),
+            ),
+        ]))
+    }
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} ({location})", self.function),
+            None => write!(f, "{}", self.function),
+        }
+    }
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.kind != DEFAULT_ERROR_KIND {
+            write!(f, "{}: ", self.kind)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// the stack trace and span don't matter when comparing errors
+impl PartialEq for ScriptError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.message == other.message
+            && self.cause == other.cause
+            && self.payload == other.payload
+    }
+}
+
+impl PartialOrd for ScriptError {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.to_string().partial_cmp(&other.to_string())
+    }
+}
+
+impl From<String> for Box<ScriptError> {
+    fn from(message: String) -> Self {
+        Box::new(ScriptError::new(message))
+    }
+}
+
+impl From<&str> for Box<ScriptError> {
+    fn from(message: &str) -> Self {
+        Box::new(ScriptError::new(message))
+    }
+}
@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+
+use crate::Operator;
+
+use super::{
+    Decimal, Primitive, Rational,
+    bigint::{MAX_POW_BITS, as_bigint},
+};
+
+/// both operands of an operation involving a decimal or a rational, in the
+/// widest of their types. A double wins, as the result can't be exact anyway
+enum Exact {
+    Decimal(Decimal, Decimal),
+    Rational(Rational, Rational),
+    Double(f64, f64),
+}
+
+fn is_exact(p: &Primitive) -> bool {
+    matches!(p, Primitive::Decimal(_) | Primitive::Rational(_))
+}
+
+fn as_decimal(p: &Primitive) -> Option<Decimal> {
+    match p {
+        Primitive::Decimal(d) => Some(d.clone()),
+        _ => as_bigint(p).map(Decimal::from),
+    }
+}
+
+fn as_rational(p: &Primitive) -> Option<Rational> {
+    match p {
+        Primitive::Rational(r) => Some(r.clone()),
+        Primitive::Decimal(d) => Some(Rational::from(d)),
+        _ => as_bigint(p).map(Rational::from),
+    }
+}
+
+pub(super) fn exact_to_f64(p: &Primitive) -> Option<f64> {
+    match p {
+        Primitive::Decimal(d) => Some(d.to_f64()),
+        Primitive::Rational(r) => Some(r.to_f64()),
+        Primitive::Double(d) => Some(*d),
+        _ => as_bigint(p).map(|n| n.to_f64()),
+    }
+}
+
+fn exact_operands(l: &Primitive, r: &Primitive) -> Option<Exact> {
+    if !is_exact(l) && !is_exact(r) {
+        return None;
+    }
+    match (l, r) {
+        (Primitive::Double(_), _) | (_, Primitive::Double(_)) => {
+            Some(Exact::Double(exact_to_f64(l)?, exact_to_f64(r)?))
+        }
+        (Primitive::Rational(_), _) | (_, Primitive::Rational(_)) => {
+            Some(Exact::Rational(as_rational(l)?, as_rational(r)?))
+        }
+        _ => Some(Exact::Decimal(as_decimal(l)?, as_decimal(r)?)),
+    }
+}
+
+/// `l operator r` when one of them is a decimal or a rational and the other
+/// one a number, `None` otherwise
+pub(super) fn exact_arithmetic(
+    l: &Primitive,
+    r: &Primitive,
+    operator: Operator,
+) -> Option<Primitive> {
+    if operator == Operator::Pow {
+        return exact_pow(l, r);
+    }
+    let division_by_zero = || {
+        let name = if operator == Operator::Div { "div" } else { "rem" };
+        Primitive::Error(
+            format!("illegal call to {name}() => left: {l} right: {r}, division by zero")
+                .into(),
+        )
+    };
+    let res = match (exact_operands(l, r)?, operator) {
+        (Exact::Decimal(l, r), Operator::Add) => Primitive::Decimal(l.add(&r)),
+        (Exact::Decimal(l, r), Operator::Subtr) => {
+            Primitive::Decimal(l.sub(&r))
+        }
+        (Exact::Decimal(l, r), Operator::Mult) => Primitive::Decimal(l.mul(&r)),
+        (Exact::Decimal(l, r), Operator::Div) => {
+            l.div(&r).map_or_else(division_by_zero, Primitive::Decimal)
+        }
+        (Exact::Decimal(l, r), Operator::Mod) => {
+            l.rem(&r).map_or_else(division_by_zero, Primitive::Decimal)
+        }
+        (Exact::Rational(l, r), Operator::Add) => {
+            Primitive::Rational(l.add(&r))
+        }
+        (Exact::Rational(l, r), Operator::Subtr) => {
+            Primitive::Rational(l.sub(&r))
+        }
+        (Exact::Rational(l, r), Operator::Mult) => {
+            Primitive::Rational(l.mul(&r))
+        }
+        (Exact::Rational(l, r), Operator::Div) => {
+            l.div(&r).map_or_else(division_by_zero, Primitive::Rational)
+        }
+        (Exact::Rational(l, r), Operator::Mod) => {
+            l.rem(&r).map_or_else(division_by_zero, Primitive::Rational)
+        }
+        (Exact::Double(l, r), Operator::Add) => Primitive::Double(l + r),
+        (Exact::Double(l, r), Operator::Subtr) => Primitive::Double(l - r),
+        (Exact::Double(l, r), Operator::Mult) => Primitive::Double(l * r),
+        (Exact::Double(l, r), Operator::Div) => Primitive::Double(l / r),
+        (Exact::Double(l, r), Operator::Mod) => Primitive::Double(l % r),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// exact when a decimal or a rational is raised to an int, a double otherwise
+fn exact_pow(l: &Primitive, r: &Primitive) -> Option<Primitive> {
+    if !is_exact(l) && !is_exact(r) {
+        return None;
+    }
+    let exp = as_bigint(r).and_then(|exp| exp.to_i128());
+    let (exp, bits) = match (l, exp) {
+        (Primitive::Decimal(d), Some(exp)) => {
+            (exp, d.mantissa().bits().max(d.scale() as u64 * 4))
+        }
+        (Primitive::Rational(q), Some(exp)) => {
+            (exp, q.numerator().bits().max(q.denominator().bits()))
+        }
+        _ => {
+            return Some(Primitive::Double(
+                exact_to_f64(l)?.powf(exact_to_f64(r)?),
+            ));
+        }
+    };
+    let too_large = || {
+        Primitive::Error(
+            format!("illegal call to pow() => {l}^{exp} is too large").into(),
+        )
+    };
+    let Ok(exp) = i32::try_from(exp) else {
+        return Some(too_large());
+    };
+    if bits.saturating_mul(exp.unsigned_abs() as u64) > MAX_POW_BITS {
+        return Some(too_large());
+    }
+    let division_by_zero = || {
+        Primitive::Error(
+            format!("illegal call to pow() => {l}^{exp}, division by zero")
+                .into(),
+        )
+    };
+    Some(match l {
+        Primitive::Decimal(d) => {
+            d.pow(exp).map_or_else(division_by_zero, Primitive::Decimal)
+        }
+        Primitive::Rational(q) => {
+            q.pow(exp).map_or_else(division_by_zero, Primitive::Rational)
+        }
+        _ => unreachable!("EXACT BUG: not a decimal or a rational"),
+    })
+}
+
+pub(super) fn exact_cmp(l: &Primitive, r: &Primitive) -> Option<Ordering> {
+    match exact_operands(l, r)? {
+        Exact::Decimal(l, r) => Some(l.cmp(&r)),
+        Exact::Rational(l, r) => Some(l.cmp(&r)),
+        Exact::Double(l, r) => l.partial_cmp(&r),
+    }
+}
+
+/// rounded half away from zero, keeping the type of `l`
+pub(super) fn exact_round(l: &Primitive, decimals: &Primitive) -> Primitive {
+    let n = as_bigint(decimals)
+        .and_then(|n| n.to_i128())
+        .and_then(|n| u32::try_from(n).ok())
+        .filter(|n| (*n as u64) * 4 <= MAX_POW_BITS);
+    match (l, n) {
+        (Primitive::Decimal(d), Some(n)) => Primitive::Decimal(d.round(n)),
+        (Primitive::Rational(r), Some(n)) => Primitive::Rational(r.round(n)),
+        (l, _) => Primitive::Error(
+            format!("illegal call to round!!  {l} {decimals}").into(),
+        ),
+    }
+}
@@ -1,9 +1,8 @@
-use std::collections::BTreeMap;
-
 use anyhow::anyhow;
+use indexmap::IndexMap;
 use serde_json::{Value, json};
 
-use super::Primitive;
+use super::{DictKey, Primitive, ReadValue};
 
 pub trait Json {
     fn from_json(s: &str) -> anyhow::Result<Self>
@@ -31,7 +30,7 @@ fn json_to_primitive(value: Value) -> anyhow::Result<Primitive> {
             Ok(Primitive::Array(prim_array))
         }
         Value::Object(o) => {
-            let mut struct_array = BTreeMap::new();
+            let mut struct_array = IndexMap::with_capacity(o.len());
             for (k, v) in o {
                 struct_array.insert(k, json_to_primitive(v)?);
             }
@@ -42,13 +41,19 @@ fn json_to_primitive(value: Value) -> anyhow::Result<Primitive> {
 fn primitive_to_value(p: &Primitive) -> anyhow::Result<Value> {
     match p {
         Primitive::Ref(r) => {
-            let r =
-                r.read().map_err(|e| anyhow!("could not acquire lock! {e}"))?;
+            let r = r
+                .read_value()
+                .map_err(|e| anyhow!("could not acquire lock! {e}"))?;
             primitive_to_value(&r)
         }
         Primitive::U8(u) => Ok(json!(u)),
         Primitive::I8(u) => Ok(json!(u)),
         Primitive::Int(u) => Ok(json!(u)),
+        // json numbers can't hold it without losing precision
+        Primitive::BigInt(u) => Ok(Value::String(u.to_string())),
+        Primitive::Decimal(d) => Ok(Value::String(d.to_string())),
+        Primitive::Rational(r) => Ok(Value::String(r.to_string())),
+        Primitive::Quantity(q) => Ok(Value::String(q.to_string())),
         Primitive::Double(u) => Ok(json!(u)),
         Primitive::Bool(b) => Ok(json!(b)),
         Primitive::Null => Ok(Value::Null),
@@ -67,6 +72,38 @@ fn primitive_to_value(p: &Primitive) -> anyhow::Result<Value> {
             }
             Ok(Value::Object(o))
         }
+        Primitive::Dict(d) => {
+            let mut o = serde_json::Map::with_capacity(d.len());
+            for (k, v) in d {
+                let k = match k {
+                    DictKey::String(k) => k.to_string(),
+                    k => k.to_string(),
+                };
+                o.insert(k, primitive_to_value(v)?);
+            }
+            Ok(Value::Object(o))
+        }
+        Primitive::Set(set) => {
+            let mut json_arr = Vec::with_capacity(set.len());
+            for v in set {
+                json_arr.push(primitive_to_value(&v.into())?);
+            }
+            Ok(Value::Array(json_arr))
+        }
+        Primitive::Error(e) => {
+            primitive_to_value(&Primitive::Struct(e.to_struct()))
+        }
+        // `"Pending"` or `{"Failed": {"reason": "disk full"}}`
+        Primitive::Variant(v) if v.fields.is_empty() => {
+            Ok(Value::String(v.name.clone()))
+        }
+        Primitive::Variant(v) => {
+            let payload =
+                primitive_to_value(&Primitive::Struct(v.fields.clone()))?;
+            let mut o = serde_json::Map::with_capacity(1);
+            o.insert(v.name.clone(), payload);
+            Ok(Value::Object(o))
+        }
         v => Ok(json!(v.to_string())),
     }
 }
@@ -1,5 +1,18 @@
+mod bigint;
 mod core_primitive;
+mod decimal;
+mod enums;
+mod error;
+mod exact;
 mod json;
+mod quantity;
+mod rational;
+pub use bigint::BigInt;
 pub use core_primitive::*;
+pub use decimal::{DIV_PRECISION, Decimal};
+pub use enums::*;
+pub use error::*;
 pub use json::*;
+pub use quantity::{Dimension, Quantity, Unit};
+pub use rational::Rational;
 // pub use json::*;
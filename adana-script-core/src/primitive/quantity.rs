@@ -0,0 +1,409 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Operator;
+
+use super::{Primitive, exact::exact_to_f64};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    DataSize,
+    Time,
+    Length,
+    Mass,
+    Temperature,
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Dimension::DataSize => "data size",
+            Dimension::Time => "time",
+            Dimension::Length => "length",
+            Dimension::Mass => "mass",
+            Dimension::Temperature => "temperature",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// a unit a number can be suffixed with, e.g. `5kb` or `20 °C`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    Byte,
+    Kilobyte,
+    Megabyte,
+    Gigabyte,
+    Terabyte,
+    Kibibyte,
+    Mebibyte,
+    Gibibyte,
+    Tebibyte,
+    Millisecond,
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Millimetre,
+    Centimetre,
+    Metre,
+    Kilometre,
+    Inch,
+    Foot,
+    Yard,
+    Mile,
+    Milligram,
+    Gram,
+    Kilogram,
+    Tonne,
+    Ounce,
+    Pound,
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl Unit {
+    pub const ALL: [Unit; 32] = [
+        Unit::Byte,
+        Unit::Kilobyte,
+        Unit::Megabyte,
+        Unit::Gigabyte,
+        Unit::Terabyte,
+        Unit::Kibibyte,
+        Unit::Mebibyte,
+        Unit::Gibibyte,
+        Unit::Tebibyte,
+        Unit::Millisecond,
+        Unit::Second,
+        Unit::Minute,
+        Unit::Hour,
+        Unit::Day,
+        Unit::Week,
+        Unit::Millimetre,
+        Unit::Centimetre,
+        Unit::Metre,
+        Unit::Kilometre,
+        Unit::Inch,
+        Unit::Foot,
+        Unit::Yard,
+        Unit::Mile,
+        Unit::Milligram,
+        Unit::Gram,
+        Unit::Kilogram,
+        Unit::Tonne,
+        Unit::Ounce,
+        Unit::Pound,
+        Unit::Celsius,
+        Unit::Fahrenheit,
+        Unit::Kelvin,
+    ];
+
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Byte => "b",
+            Unit::Kilobyte => "kb",
+            Unit::Megabyte => "mb",
+            Unit::Gigabyte => "gb",
+            Unit::Terabyte => "tb",
+            Unit::Kibibyte => "kib",
+            Unit::Mebibyte => "mib",
+            Unit::Gibibyte => "gib",
+            Unit::Tebibyte => "tib",
+            Unit::Millisecond => "ms",
+            Unit::Second => "s",
+            Unit::Minute => "min",
+            Unit::Hour => "h",
+            Unit::Day => "day",
+            Unit::Week => "week",
+            Unit::Millimetre => "mm",
+            Unit::Centimetre => "cm",
+            Unit::Metre => "m",
+            Unit::Kilometre => "km",
+            Unit::Inch => "inch",
+            Unit::Foot => "ft",
+            Unit::Yard => "yd",
+            Unit::Mile => "mi",
+            Unit::Milligram => "mg",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Tonne => "t",
+            Unit::Ounce => "oz",
+            Unit::Pound => "lb",
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Kelvin => "K",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Unit> {
+        Unit::ALL.into_iter().find(|u| u.symbol() == symbol)
+    }
+
+    pub const fn dimension(&self) -> Dimension {
+        match self {
+            Unit::Byte
+            | Unit::Kilobyte
+            | Unit::Megabyte
+            | Unit::Gigabyte
+            | Unit::Terabyte
+            | Unit::Kibibyte
+            | Unit::Mebibyte
+            | Unit::Gibibyte
+            | Unit::Tebibyte => Dimension::DataSize,
+            Unit::Millisecond
+            | Unit::Second
+            | Unit::Minute
+            | Unit::Hour
+            | Unit::Day
+            | Unit::Week => Dimension::Time,
+            Unit::Millimetre
+            | Unit::Centimetre
+            | Unit::Metre
+            | Unit::Kilometre
+            | Unit::Inch
+            | Unit::Foot
+            | Unit::Yard
+            | Unit::Mile => Dimension::Length,
+            Unit::Milligram
+            | Unit::Gram
+            | Unit::Kilogram
+            | Unit::Tonne
+            | Unit::Ounce
+            | Unit::Pound => Dimension::Mass,
+            Unit::Celsius | Unit::Fahrenheit | Unit::Kelvin => {
+                Dimension::Temperature
+            }
+        }
+    }
+
+    /// the unit in the smallest unit of its dimension (bytes, milliseconds,
+    /// micrometres and nanograms), so that a conversion only divides once.
+    /// Temperatures are not proportional and are converted on their own
+    const fn factor(&self) -> f64 {
+        match self {
+            Unit::Byte => 1.,
+            Unit::Kilobyte => 1e3,
+            Unit::Megabyte => 1e6,
+            Unit::Gigabyte => 1e9,
+            Unit::Terabyte => 1e12,
+            Unit::Kibibyte => 1024.,
+            Unit::Mebibyte => 1_048_576.,
+            Unit::Gibibyte => 1_073_741_824.,
+            Unit::Tebibyte => 1_099_511_627_776.,
+            Unit::Millisecond => 1.,
+            Unit::Second => 1e3,
+            Unit::Minute => 6e4,
+            Unit::Hour => 3.6e6,
+            Unit::Day => 8.64e7,
+            Unit::Week => 6.048e8,
+            Unit::Millimetre => 1e3,
+            Unit::Centimetre => 1e4,
+            Unit::Metre => 1e6,
+            Unit::Kilometre => 1e9,
+            Unit::Inch => 25_400.,
+            Unit::Foot => 304_800.,
+            Unit::Yard => 914_400.,
+            Unit::Mile => 1_609_344_000.,
+            Unit::Milligram => 1e6,
+            Unit::Gram => 1e9,
+            Unit::Kilogram => 1e12,
+            Unit::Tonne => 1e15,
+            Unit::Ounce => 28_349_523_125.,
+            Unit::Pound => 453_592_370_000.,
+            Unit::Celsius | Unit::Fahrenheit | Unit::Kelvin => 1.,
+        }
+    }
+}
+
+/// a number with a unit, e.g. `5kb`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Quantity { value, unit }
+    }
+
+    /// `None` when the units are not of the same dimension
+    pub fn convert(&self, unit: Unit) -> Option<Quantity> {
+        if self.unit.dimension() != unit.dimension() {
+            return None;
+        }
+        let value = match (self.unit, unit) {
+            (from, to) if from == to => self.value,
+            (Unit::Celsius, Unit::Fahrenheit) => self.value * 9. / 5. + 32.,
+            (Unit::Fahrenheit, Unit::Celsius) => (self.value - 32.) * 5. / 9.,
+            // hundredths of a degree, so that 273.15 is exact
+            (Unit::Celsius, Unit::Kelvin) => {
+                (self.value * 100. + 27_315.) / 100.
+            }
+            (Unit::Kelvin, Unit::Celsius) => {
+                (self.value * 100. - 27_315.) / 100.
+            }
+            (Unit::Fahrenheit, Unit::Kelvin) => {
+                ((self.value - 32.) * 500. / 9. + 27_315.) / 100.
+            }
+            (Unit::Kelvin, Unit::Fahrenheit) => {
+                (self.value * 100. - 27_315.) * 9. / 500. + 32.
+            }
+            (from, to) => self.value * from.factor() / to.factor(),
+        };
+        Some(Quantity::new(value, unit))
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.value, self.unit.symbol())
+    }
+}
+
+/// `r` in the unit of `l`, or an error when they can't be mixed
+fn same_unit(
+    l: &Quantity,
+    r: &Quantity,
+    operator: Operator,
+) -> Result<f64, Primitive> {
+    let error = |reason: String| {
+        Err(Primitive::Error(
+            format!(
+                "illegal call to {} => left: {l} right: {r}, {reason}",
+                operation_name(operator)
+            )
+            .into(),
+        ))
+    };
+    let (l_dim, r_dim) = (l.unit.dimension(), r.unit.dimension());
+    if l_dim != r_dim {
+        return error(format!("cannot mix {l_dim} and {r_dim}"));
+    }
+    // 20 °C + 10 °F can't tell a temperature from a difference of them
+    if l_dim == Dimension::Temperature
+        && l.unit != r.unit
+        && matches!(operator, Operator::Add | Operator::Subtr)
+    {
+        return error(format!(
+            "convert {} to {} first",
+            r.unit.symbol(),
+            l.unit.symbol()
+        ));
+    }
+    match r.convert(l.unit) {
+        Some(r) => Ok(r.value),
+        None => error(format!("cannot mix {l_dim} and {r_dim}")),
+    }
+}
+
+fn operation_name(operator: Operator) -> &'static str {
+    match operator {
+        Operator::Add => "add()",
+        Operator::Subtr => "sub()",
+        Operator::Mult => "mul()",
+        Operator::Div => "div()",
+        Operator::Mod => "rem()",
+        Operator::Pow => "pow()",
+        _ => "convert()",
+    }
+}
+
+/// `l operator r` when one of them is a quantity and the other one a
+/// quantity or a number, `None` otherwise
+pub(super) fn quantity_arithmetic(
+    l: &Primitive,
+    r: &Primitive,
+    operator: Operator,
+) -> Option<Primitive> {
+    let error = |reason: &str| {
+        Primitive::Error(
+            format!(
+                "illegal call to {} => left: {l} right: {r}, {reason}",
+                operation_name(operator)
+            )
+            .into(),
+        )
+    };
+    let res = match (l, r, operator) {
+        (Primitive::Quantity(l), Primitive::Quantity(r), _) => {
+            match (same_unit(l, r, operator), operator) {
+                (Err(e), _) => e,
+                (Ok(r), Operator::Add) => {
+                    Primitive::Quantity(Quantity::new(l.value + r, l.unit))
+                }
+                (Ok(r), Operator::Subtr) => {
+                    Primitive::Quantity(Quantity::new(l.value - r, l.unit))
+                }
+                (Ok(r), Operator::Div) => Primitive::Double(l.value / r),
+                (Ok(r), Operator::Mod) => {
+                    Primitive::Quantity(Quantity::new(l.value % r, l.unit))
+                }
+                _ => error("a quantity can't have a compound unit"),
+            }
+        }
+        (Primitive::Quantity(q), n, _) => {
+            let n = exact_to_f64(n)?;
+            match operator {
+                Operator::Mult => {
+                    Primitive::Quantity(Quantity::new(q.value * n, q.unit))
+                }
+                Operator::Div => {
+                    Primitive::Quantity(Quantity::new(q.value / n, q.unit))
+                }
+                Operator::Mod => {
+                    Primitive::Quantity(Quantity::new(q.value % n, q.unit))
+                }
+                Operator::Pow => error("a quantity can't have a compound unit"),
+                _ => error("expected a quantity"),
+            }
+        }
+        (n, Primitive::Quantity(q), _) => {
+            let n = exact_to_f64(n)?;
+            match operator {
+                Operator::Mult => {
+                    Primitive::Quantity(Quantity::new(n * q.value, q.unit))
+                }
+                Operator::Div | Operator::Pow => {
+                    error("a quantity can't have a compound unit")
+                }
+                _ => error("expected a quantity"),
+            }
+        }
+        _ => return None,
+    };
+    Some(res)
+}
+
+pub(super) fn quantity_cmp(l: &Quantity, r: &Quantity) -> Option<Ordering> {
+    l.value.partial_cmp(&r.convert(l.unit)?.value)
+}
+
+/// `90min to h`, the target unit is given by its symbol
+pub(super) fn convert(l: &Primitive, unit: &Primitive) -> Primitive {
+    let target = match unit {
+        Primitive::String(symbol) => Unit::from_symbol(symbol),
+        _ => None,
+    };
+    match (l, target) {
+        (Primitive::Quantity(q), Some(target)) => match q.convert(target) {
+            Some(q) => Primitive::Quantity(q),
+            None => Primitive::Error(
+                format!(
+                    "illegal call to convert() => cannot convert {} ({}) to {} ({})",
+                    q.unit.symbol(),
+                    q.unit.dimension(),
+                    target.symbol(),
+                    target.dimension()
+                )
+                .into(),
+            ),
+        },
+        (l, _) => Primitive::Error(
+            format!("illegal call to convert() => {l} to {unit}").into(),
+        ),
+    }
+}
@@ -0,0 +1,205 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    BigInt, Decimal,
+    decimal::{div_round, ten_pow},
+};
+
+/// an exact fraction, written `3r` so that `1/3r` is a third
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rational {
+    numerator: BigInt,
+    /// always positive, the fraction is kept reduced
+    denominator: BigInt,
+}
+
+impl Rational {
+    /// `None` when the denominator is zero
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+        let divisor = gcd(&numerator, &denominator);
+        let (mut numerator, _) = numerator.div_rem(&divisor)?;
+        let (mut denominator, _) = denominator.div_rem(&divisor)?;
+        if denominator.is_negative() {
+            numerator = numerator.neg();
+            denominator = denominator.neg();
+        }
+        Some(Rational { numerator, denominator })
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        &self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numerator.is_negative()
+    }
+
+    pub fn to_decimal(&self) -> Decimal {
+        Decimal::from(self.numerator.clone())
+            .div(&Decimal::from(self.denominator.clone()))
+            .expect("not zero")
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_decimal().to_f64()
+    }
+
+    /// the integer part, truncated toward zero
+    pub fn trunc(&self) -> BigInt {
+        let (quotient, _) =
+            self.numerator.div_rem(&self.denominator).expect("not zero");
+        quotient
+    }
+
+    pub fn neg(&self) -> Self {
+        Rational {
+            numerator: self.numerator.neg(),
+            denominator: self.denominator.clone(),
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        Rational {
+            numerator: self.numerator.abs(),
+            denominator: self.denominator.clone(),
+        }
+    }
+
+    pub fn add(&self, rhs: &Rational) -> Self {
+        Rational::new(
+            self.numerator
+                .mul(&rhs.denominator)
+                .add(&rhs.numerator.mul(&self.denominator)),
+            self.denominator.mul(&rhs.denominator),
+        )
+        .expect("not zero")
+    }
+
+    pub fn sub(&self, rhs: &Rational) -> Self {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &Rational) -> Self {
+        Rational::new(
+            self.numerator.mul(&rhs.numerator),
+            self.denominator.mul(&rhs.denominator),
+        )
+        .expect("not zero")
+    }
+
+    /// `None` when dividing by zero
+    pub fn div(&self, rhs: &Rational) -> Option<Self> {
+        Rational::new(
+            self.numerator.mul(&rhs.denominator),
+            self.denominator.mul(&rhs.numerator),
+        )
+    }
+
+    /// remainder of the division truncated toward zero, like an int.
+    /// `None` when dividing by zero
+    pub fn rem(&self, rhs: &Rational) -> Option<Self> {
+        let quotient = Rational::from(self.div(rhs)?.trunc());
+        Some(self.sub(&rhs.mul(&quotient)))
+    }
+
+    /// `None` when raising zero to a negative power
+    pub fn pow(&self, exp: i32) -> Option<Self> {
+        let numerator = self.numerator.pow(exp.unsigned_abs());
+        let denominator = self.denominator.pow(exp.unsigned_abs());
+        if exp < 0 {
+            Rational::new(denominator, numerator)
+        } else {
+            Some(Rational { numerator, denominator })
+        }
+    }
+
+    /// rounded half away from zero to `decimals` fractional digits
+    pub fn round(&self, decimals: u32) -> Self {
+        let scale = ten_pow(decimals);
+        Rational::new(
+            div_round(&self.numerator.mul(&scale), &self.denominator),
+            scale,
+        )
+        .expect("not zero")
+    }
+
+    pub fn floor(&self) -> Self {
+        let trunc = self.trunc();
+        if self.is_negative() && !self.is_integer() {
+            trunc.sub(&BigInt::from(1)).into()
+        } else {
+            trunc.into()
+        }
+    }
+
+    pub fn ceil(&self) -> Self {
+        let trunc = self.trunc();
+        if !self.is_negative() && !self.is_integer() {
+            trunc.add(&BigInt::from(1)).into()
+        } else {
+            trunc.into()
+        }
+    }
+
+    fn is_integer(&self) -> bool {
+        self.denominator == BigInt::from(1)
+    }
+}
+
+fn gcd(l: &BigInt, r: &BigInt) -> BigInt {
+    let (mut l, mut r) = (l.abs(), r.abs());
+    while let Some((_, remainder)) = l.div_rem(&r) {
+        (l, r) = (r, remainder);
+    }
+    l
+}
+
+impl From<BigInt> for Rational {
+    fn from(n: BigInt) -> Self {
+        Rational { numerator: n, denominator: BigInt::from(1) }
+    }
+}
+
+impl From<&Decimal> for Rational {
+    fn from(d: &Decimal) -> Self {
+        Rational::new(d.mantissa().clone(), ten_pow(d.scale()))
+            .expect("not zero")
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numerator
+            .mul(&other.denominator)
+            .cmp(&other.numerator.mul(&self.denominator))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
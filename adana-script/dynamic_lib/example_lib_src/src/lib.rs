@@ -18,6 +18,12 @@ pub fn hello(
     }
     Ok(Primitive::String(s))
 }
+/// `callback` takes a single function
+#[no_mangle]
+pub fn callback_arity() -> usize {
+    1
+}
+
 #[no_mangle]
 pub fn callback(
     mut params: Vec<Primitive>,
@@ -0,0 +1,264 @@
+use std::{fmt::Debug, ops::Range, sync::Arc};
+
+use adana_script_core::{
+    primitive::Primitive, BuiltInFunctionType, KeyAccess, Operator, Span, Value,
+};
+
+/// index of a variable name in `Program::names`
+pub(crate) type Slot = usize;
+
+/// A compiled script, or the body of a function.
+#[derive(Debug)]
+pub(crate) struct Program {
+    /// variable names, resolved against the context when first used
+    pub names: Vec<String>,
+    pub code: Code,
+    /// the code yields, calling the function returns an iterator
+    pub generator: bool,
+}
+
+/// A compiled function: its body, and the default value of each of its
+/// parameters.
+#[derive(Debug)]
+pub(crate) struct Function {
+    pub body: Arc<Program>,
+    /// one per parameter, `None` when it has no default value
+    pub defaults: Vec<Option<Program>>,
+}
+
+#[derive(Default)]
+pub(crate) struct Code {
+    pub ops: Vec<Op>,
+    /// source location of the instructions the ops were compiled from
+    pub spans: Vec<(Range<usize>, Span)>,
+}
+
+impl Code {
+    /// innermost span covering the op at `pc`
+    pub fn span_at(&self, pc: usize) -> Option<&Span> {
+        self.spans
+            .iter()
+            .filter(|(range, _)| range.contains(&pc))
+            .min_by_key(|(range, _)| range.len())
+            .map(|(_, span)| span)
+    }
+}
+
+impl Debug for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.ops.iter().enumerate()).finish()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Op {
+    // region: expressions
+    Const(Primitive),
+    Load(Slot),
+    LoadNeg(Slot),
+    LoadRef(Slot),
+    /// the variable, or the primitive when it is not in scope
+    LoadOr(Slot, Primitive),
+    /// `!`, `~` and negation
+    Unary(Operator),
+    Binary(Operator),
+    /// a range is an iterator, collected into an array only on demand
+    Range {
+        incl_both_end: bool,
+    },
+    FString(Box<(String, Vec<(String, Code)>)>),
+    Array(Box<[Code]>),
+    Struct(Box<[(String, Code)]>),
+    Dict(Box<[(Code, Code)]>),
+    Set(Box<[Code]>),
+    BuiltIn(BuiltInFunctionType),
+    /// call the variable named after a builtin when it is in scope, run the
+    /// builtin otherwise
+    Shadowable(Slot, Box<(Code, Code)>),
+    /// `a ?? b`, b when a is null or fails
+    Coalesce(Box<(Code, Code)>),
+    /// `make_err(struct {..})`, whose fields may hold errors (e.g. the cause)
+    MakeError(Box<[(String, Code)]>),
+    Access(Box<Access>),
+    Call(Box<Call>),
+    /// a function capturing variables of the current scope
+    Closure(Box<Closure>),
+    Try(Box<Try>),
+    /// match the value on top of the stack against the arms
+    Match(Box<[Arm]>),
+    /// throw the value on top of the stack as an error
+    Throw,
+    /// hand the value on top of the stack to the consumer of the generator
+    Yield,
+    /// leave the innermost loop, or the one with the label, with the value
+    /// on top of the stack if any
+    Break {
+        label: Option<String>,
+        with_value: bool,
+    },
+    /// go to the next iteration of the innermost loop, or of the one with
+    /// the label
+    Continue(Option<String>),
+    Assign(Slot),
+    /// `import "./utils.adana" as utils`, push the exports of the module
+    Import(String),
+    /// `const x = 1` or `let x = 1`, bind the value on top of the stack to
+    /// a new variable
+    Declare {
+        slot: Slot,
+        constant: bool,
+    },
+    AssignAt(Box<AssignAt>),
+    /// bind the variables of the pattern to the value on top of the stack,
+    /// e.g. `[a, b] = [b, a]`
+    Destructure(Box<Pattern>),
+    Drop(Box<[DropTarget]>),
+    /// unwrap an early return, as done at the end of a block
+    Unwrap,
+    Return,
+    /// a nested block of instructions
+    Block(Code),
+    /// a nested block of instructions with its own scope
+    Scoped(Code),
+    Fail(String),
+    // endregion: expressions
+
+    // region: instructions
+    Jump(usize),
+    JumpUnlessTrue(usize),
+    /// leave the block if the condition on top of the stack is an error
+    ExitIfError,
+    /// leave the block with the value on top of the stack
+    Exit,
+    /// store the instruction result, leaving the block on return or error
+    Store,
+    /// store the result of an instruction nested in an `if`
+    StoreInBlock,
+    /// store the result of an instruction nested in a loop, jumping out of
+    /// it on `break` or back to `start` on `continue`
+    StoreInLoop {
+        label: Option<String>,
+        start: usize,
+        end: usize,
+    },
+    EnterScope,
+    ExitScope,
+    IterStart,
+    IterNext {
+        var: Option<Slot>,
+        index_var: Option<Slot>,
+        done: usize,
+    },
+    IterEnd,
+    // endregion: instructions
+}
+
+#[derive(Debug)]
+pub(crate) enum Key {
+    /// `KeyAccess::Index` or `KeyAccess::Key`
+    Const(KeyAccess),
+    Variable(Code),
+    /// `[start..end]`, an open bound evaluates to null
+    Slice {
+        start: Code,
+        end: Code,
+        incl_both_end: bool,
+    },
+    Call {
+        key: Box<Key>,
+        args: Args,
+        site: Option<Span>,
+    },
+    /// `?.key`, the rest of the access is skipped when the value is null
+    Optional(Box<Key>),
+}
+
+#[derive(Debug)]
+pub(crate) struct Access {
+    pub keys: Vec<Key>,
+    /// keys as parsed, used for error messages
+    pub next_keys: Vec<KeyAccess>,
+}
+
+#[derive(Debug)]
+pub(crate) struct AssignAt {
+    /// `None` when the root isn't a variable
+    pub root: Option<Slot>,
+    pub keys: Vec<Key>,
+    pub next_keys: Vec<KeyAccess>,
+}
+
+#[derive(Debug)]
+pub(crate) enum DropTarget {
+    Variable(Slot),
+    At(AssignAt),
+}
+
+#[derive(Debug)]
+pub(crate) enum Callee {
+    Variable(Slot),
+    Block(Code),
+}
+
+#[derive(Debug)]
+pub(crate) enum Args {
+    /// arguments with whether they were written as a plain variable, then
+    /// the named arguments, e.g. `f(1, b = 2)`
+    List(Vec<(Code, bool)>, Vec<(String, Code)>),
+    Invalid(Value),
+}
+
+#[derive(Debug)]
+pub(crate) struct Call {
+    pub function: Callee,
+    pub args: Args,
+    /// the statement making the call, for stack traces
+    pub site: Option<Span>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Closure {
+    pub function: Primitive,
+    /// captured variables, and whether they are captured by reference
+    pub captures: Vec<(Slot, bool)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Try {
+    pub body: Code,
+    /// variable holding the caught error, and the catch block
+    pub catch: Option<(Option<Slot>, Code)>,
+    pub finally: Option<Code>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Arm {
+    pub pattern: Pattern,
+    pub guard: Option<Code>,
+    pub body: Code,
+}
+
+#[derive(Debug)]
+pub(crate) enum Pattern {
+    /// a wildcard, or a binding
+    Any(Option<Slot>),
+    Literal(Primitive),
+    Range {
+        start: Code,
+        end: Code,
+        incl_both_end: bool,
+    },
+    Type {
+        type_name: String,
+        var: Option<Slot>,
+    },
+    Array {
+        head: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+        tail: Vec<Pattern>,
+    },
+    Struct {
+        fields: Vec<(String, Pattern)>,
+        rest: Option<Box<Pattern>>,
+    },
+}
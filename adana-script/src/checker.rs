@@ -0,0 +1,956 @@
+use std::{collections::BTreeSet, fmt::Display, sync::Arc};
+
+use adana_script_core::{
+    constants::SELF,
+    primitive::{
+        BigInt, Decimal, Neg, Not, Primitive, Quantity, Rational, TypeOf, Unit,
+        TYPE_ARRAY, TYPE_BOOL, TYPE_DECIMAL, TYPE_DICT, TYPE_DOUBLE,
+        TYPE_ERROR, TYPE_FUNCTION, TYPE_I8, TYPE_INT, TYPE_ITERATOR,
+        TYPE_LIB_DATA, TYPE_NATIVE_LIB, TYPE_NO_RETURN, TYPE_NULL,
+        TYPE_QUANTITY, TYPE_RATIONAL, TYPE_SET, TYPE_STRING, TYPE_STRUCT,
+        TYPE_U8, TYPE_UNIT,
+    },
+    BuiltInFunctionType, KeyAccess, Operator, Pattern, Source, Span, Value,
+};
+
+use crate::{
+    compiler::next_operator_position, compute::parse, diagnostic::Diagnostic,
+    parser::PATTERN_TYPES, prelude::BTreeMap,
+};
+
+/// check a script without running it: values not matching their type
+/// annotation, unknown variables and calls with the wrong number of
+/// arguments. Returns the problems found
+pub fn check(s: &str, name: Option<&str>) -> anyhow::Result<Vec<Diagnostic>> {
+    let source =
+        Arc::new(Source { name: name.map(String::from), text: s.to_string() });
+    let instructions = parse(&source)?;
+    let mut checker = Checker {
+        source,
+        bound: BTreeSet::from([SELF.to_string()]),
+        opaque: false,
+        variables: BTreeMap::new(),
+        returns: vec![],
+        yields: false,
+        span: None,
+        diagnostics: vec![],
+    };
+    // the first pass finds the variables bound anywhere in the script, e.g.
+    // a global defined after the function using it
+    checker.statements(&instructions);
+    checker.variables.clear();
+    checker.diagnostics.clear();
+    checker.statements(&instructions);
+    Ok(checker.diagnostics)
+}
+
+/// What is known about a value
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    /// could be anything, nothing is reported about it
+    Unknown,
+    /// an u8, i8 or int. Arithmetic changes the size of an integer
+    Integer,
+    Is(&'static str),
+}
+
+impl Type {
+    fn annotation(type_name: &str) -> Type {
+        PATTERN_TYPES
+            .iter()
+            .find(|t| **t == type_name)
+            .map_or(Type::Unknown, |t| Type::Is(t))
+    }
+
+    fn of(primitive: &Primitive) -> Type {
+        match primitive.type_of_str() {
+            TYPE_U8 | TYPE_I8 | TYPE_INT => Type::Integer,
+            TYPE_ERROR | TYPE_NATIVE_LIB | TYPE_LIB_DATA | TYPE_NO_RETURN => {
+                Type::Unknown
+            }
+            t => Type::Is(t),
+        }
+    }
+
+    /// whether a value of type `actual` can be given where `self` is
+    /// expected. An int accepts any integer
+    fn accepts(&self, actual: &Type) -> bool {
+        match (self, actual) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Is(TYPE_INT), Type::Is(TYPE_U8 | TYPE_I8))
+            | (Type::Is(TYPE_U8 | TYPE_I8 | TYPE_INT), Type::Integer) => true,
+            // a range is an iterator, which the array functions take too
+            (Type::Is(TYPE_ARRAY), Type::Is(TYPE_ITERATOR)) => true,
+            (expected, actual) => expected == actual,
+        }
+    }
+
+    /// a value of this type, to find out what an operator does with it
+    fn sample(&self) -> Option<Primitive> {
+        let sample = match self {
+            Type::Unknown => return None,
+            Type::Integer => Primitive::Int(2),
+            Type::Is(TYPE_U8) => Primitive::U8(2),
+            Type::Is(TYPE_I8) => Primitive::I8(2),
+            Type::Is(TYPE_INT) => Primitive::Int(2),
+            Type::Is(TYPE_DOUBLE) => Primitive::Double(2.),
+            Type::Is(TYPE_DECIMAL) => {
+                Primitive::Decimal(Decimal::from(BigInt::from(2)))
+            }
+            Type::Is(TYPE_RATIONAL) => {
+                Primitive::Rational(Rational::from(BigInt::from(2)))
+            }
+            Type::Is(TYPE_QUANTITY) => {
+                Primitive::Quantity(Quantity::new(2., Unit::Byte))
+            }
+            Type::Is(TYPE_BOOL) => Primitive::Bool(true),
+            Type::Is(TYPE_NULL) => Primitive::Null,
+            Type::Is(TYPE_STRING) => Primitive::String("a".into()),
+            Type::Is(TYPE_ARRAY) => Primitive::Array(vec![]),
+            Type::Is(TYPE_STRUCT) => Primitive::Struct(Default::default()),
+            Type::Is(TYPE_DICT) => Primitive::Dict(BTreeMap::new()),
+            Type::Is(TYPE_SET) => Primitive::Set(BTreeSet::new()),
+            Type::Is(_) => return None,
+        };
+        Some(sample)
+    }
+
+    fn is_callable(&self) -> bool {
+        !matches!(
+            self,
+            Type::Integer
+                | Type::Is(
+                    TYPE_U8
+                        | TYPE_I8
+                        | TYPE_INT
+                        | TYPE_DOUBLE
+                        | TYPE_DECIMAL
+                        | TYPE_RATIONAL
+                        | TYPE_QUANTITY
+                        | TYPE_BOOL
+                        | TYPE_NULL
+                        | TYPE_STRING
+                        | TYPE_ARRAY
+                        | TYPE_DICT
+                        | TYPE_SET
+                        | TYPE_ITERATOR
+                )
+        )
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Unknown => write!(f, "unknown"),
+            Type::Integer => write!(f, "{TYPE_INT}"),
+            Type::Is(t) => write!(f, "{t}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Parameter {
+    name: String,
+    ty: Type,
+    default: bool,
+    rest: bool,
+}
+
+/// the parameters of a function and the type it returns, to check the calls
+#[derive(Debug, Clone, PartialEq)]
+struct Signature {
+    parameters: Vec<Parameter>,
+    returns: Type,
+}
+
+#[derive(Debug, Clone)]
+struct Variable {
+    ty: Type,
+    /// `let x: int`, every value given to the variable must be an int
+    declared: Option<Type>,
+    constant: bool,
+    signature: Option<Signature>,
+}
+
+impl Variable {
+    fn of(ty: Type) -> Variable {
+        Variable { ty, declared: None, constant: false, signature: None }
+    }
+}
+
+struct Checker {
+    source: Arc<Source>,
+    /// every variable bound somewhere in the script
+    bound: BTreeSet<String>,
+    /// `include` and `eval` bind variables the checker doesn't see
+    opaque: bool,
+    variables: BTreeMap<String, Variable>,
+    /// the functions being checked and the type they return, innermost last
+    returns: Vec<(String, Type)>,
+    /// the function being checked yields, it returns an iterator
+    yields: bool,
+    span: Option<Span>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn report(&mut self, message: String) {
+        let diagnostic = match &self.span {
+            Some(span) => Diagnostic::from_span(message.clone(), span),
+            None => None,
+        };
+        let diagnostic = diagnostic
+            .unwrap_or_else(|| Diagnostic::new(message, &self.source, 0, 0));
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn bind(&mut self, name: &str, variable: Variable) {
+        self.bound.insert(name.to_string());
+        self.variables.insert(name.to_string(), variable);
+    }
+
+    fn variable(&mut self, name: &str) -> Type {
+        if let Some(variable) = self.variables.get(name) {
+            return variable.ty.clone();
+        }
+        if !self.opaque && !self.bound.contains(name) {
+            self.report(format!("unknown variable {name}"));
+        }
+        Type::Unknown
+    }
+
+    /// the type of the last statement
+    fn statements(&mut self, exprs: &[Value]) -> Type {
+        let mut ty = Type::Unknown;
+        for expr in exprs {
+            ty = self.value(expr);
+        }
+        ty
+    }
+
+    /// a block that may not run, e.g. a branch of an `if`
+    fn block(&mut self, exprs: &[Value]) -> Type {
+        let before = self.variables.clone();
+        let ty = self.statements(exprs);
+        self.merge(&before);
+        ty
+    }
+
+    /// after a block that may not run, or run many times, a variable it
+    /// changed holds either its old value or the new one. Variables bound in
+    /// the block are gone
+    fn merge(&mut self, before: &BTreeMap<String, Variable>) {
+        self.variables.retain(|name, variable| {
+            let Some(previous) = before.get(name) else {
+                return false;
+            };
+            if previous.ty != variable.ty
+                || previous.signature != variable.signature
+            {
+                variable.ty =
+                    variable.declared.clone().unwrap_or(Type::Unknown);
+                variable.signature = None;
+            }
+            true
+        });
+    }
+
+    /// the body of a loop, variables it changes can hold anything when it
+    /// starts again. It is checked a first time only to find them
+    fn repeated(&mut self, check: impl Fn(&mut Self)) {
+        let before = self.variables.clone();
+        let reported = self.diagnostics.len();
+        check(self);
+        self.diagnostics.truncate(reported);
+        self.merge(&before);
+        check(self);
+        self.merge(&before);
+    }
+
+    fn value(&mut self, value: &Value) -> Type {
+        match value {
+            Value::Spanned { span, value } => {
+                let outer = self.span.replace(span.clone());
+                let ty = self.value(value);
+                self.span = outer;
+                ty
+            }
+            Value::Primitive(p) => Type::of(p),
+            Value::Null => Type::Is(TYPE_NULL),
+            Value::Decimal(_) | Value::Const(_) => Type::Is(TYPE_DOUBLE),
+            Value::U8(_) => Type::Is(TYPE_U8),
+            Value::I8(_) => Type::Is(TYPE_I8),
+            Value::Integer(_) => Type::Is(TYPE_INT),
+            Value::Bool(_) => Type::Is(TYPE_BOOL),
+            Value::String(_) => Type::Is(TYPE_STRING),
+            Value::FString(_, parameters) => {
+                for (_, parameter) in parameters {
+                    self.value(parameter);
+                }
+                Type::Is(TYPE_STRING)
+            }
+            Value::Range { start, end, .. } => {
+                self.value(start);
+                self.value(end);
+                Type::Is(TYPE_ITERATOR)
+            }
+            Value::Array(values) => {
+                self.statements(values);
+                Type::Is(TYPE_ARRAY)
+            }
+            Value::Set(values) => {
+                self.statements(values);
+                Type::Is(TYPE_SET)
+            }
+            Value::Dict(entries) => {
+                for (key, value) in entries {
+                    self.value(key);
+                    self.value(value);
+                }
+                Type::Is(TYPE_DICT)
+            }
+            Value::Struct(fields) => {
+                for value in fields.values() {
+                    self.value(value);
+                }
+                Type::Is(TYPE_STRUCT)
+            }
+            Value::Expression(values) | Value::BlockParen(values) => {
+                self.operations(values)
+            }
+            Value::ImplicitMultiply(value) => {
+                self.value(value);
+                Type::Unknown
+            }
+            Value::UnitSuffix { number, unit } => {
+                self.value(number);
+                let name = unit.symbol();
+                if self.variables.contains_key(name)
+                    || self.bound.contains(name)
+                {
+                    Type::Unknown
+                } else {
+                    Type::Is(TYPE_QUANTITY)
+                }
+            }
+            Value::Variable(name) | Value::VariableRef(name) => {
+                self.variable(name)
+            }
+            Value::VariableNegate(name) => {
+                let ty = self.variable(name);
+                self.unary(Operator::Subtr, ty)
+            }
+            Value::Declaration { constant, name, type_name, expr } => {
+                let declared = type_name.as_deref().map(Type::annotation);
+                self.assign(name, expr, Some((*constant, declared)))
+            }
+            Value::VariableExpr { name, expr } => match name.unspanned() {
+                Value::Variable(name) => self.assign(name, expr, None),
+                Value::Pattern(pattern) => {
+                    let ty = self.value(expr);
+                    self.bind_pattern(pattern, Type::Unknown);
+                    ty
+                }
+                name => {
+                    self.value(name);
+                    self.value(expr)
+                }
+            },
+            Value::Import { alias, .. } => {
+                self.bind(alias, Variable::of(Type::Is(TYPE_STRUCT)));
+                Type::Is(TYPE_STRUCT)
+            }
+            Value::Export(value) => self.value(value),
+            Value::Function { parameters, exprs } => {
+                self.lambda(parameters, exprs, Type::Unknown)
+            }
+            Value::Typed { value, type_name } => match value.unspanned() {
+                Value::Function { parameters, exprs } => {
+                    self.lambda(parameters, exprs, Type::annotation(type_name))
+                }
+                value => self.value(value),
+            },
+            Value::FunctionCall { parameters, function } => {
+                self.call(function, parameters)
+            }
+            Value::BuiltInFunction { fn_type, expr } => {
+                self.builtin(*fn_type, expr)
+            }
+            Value::IfExpr { cond, exprs, else_expr } => {
+                self.value(cond);
+                self.block(exprs);
+                if let Some(else_expr) = else_expr {
+                    self.block(else_expr);
+                }
+                Type::Unknown
+            }
+            Value::WhileExpr { cond, exprs } => {
+                self.repeated(|checker| {
+                    checker.value(cond);
+                    checker.statements(exprs);
+                });
+                Type::Unknown
+            }
+            Value::ForeachExpr { var, index_var, iterator, exprs } => {
+                self.value(iterator);
+                self.repeated(|checker| {
+                    checker.bind(var, Variable::of(Type::Unknown));
+                    if let Some(index_var) = index_var {
+                        checker.bind(index_var, Variable::of(Type::Unknown));
+                    }
+                    checker.statements(exprs);
+                });
+                Type::Unknown
+            }
+            Value::TryExpr { exprs, catch_var, catch_expr, finally_expr } => {
+                self.block(exprs);
+                if let Some(catch_expr) = catch_expr {
+                    let before = self.variables.clone();
+                    if let Some(catch_var) = catch_var {
+                        self.bind(catch_var, Variable::of(Type::Unknown));
+                    }
+                    self.statements(catch_expr);
+                    self.merge(&before);
+                }
+                if let Some(finally_expr) = finally_expr {
+                    self.block(finally_expr);
+                }
+                Type::Unknown
+            }
+            Value::MatchExpr { value, arms } => {
+                let ty = self.value(value);
+                for arm in arms {
+                    let before = self.variables.clone();
+                    self.bind_pattern(&arm.pattern, ty.clone());
+                    if let Some(guard) = &arm.guard {
+                        self.value(guard);
+                    }
+                    self.statements(&arm.exprs);
+                    self.merge(&before);
+                }
+                Type::Unknown
+            }
+            Value::EarlyReturn(value) => {
+                if let Some(value) = value.as_ref() {
+                    let ty = self.value(value);
+                    self.returned(ty);
+                }
+                Type::Unknown
+            }
+            Value::BreakWith { value, .. } => {
+                if let Some(value) = value {
+                    self.value(value);
+                }
+                Type::Unknown
+            }
+            Value::Labeled { value, .. } => self.value(value),
+            Value::Yield(value) => {
+                self.value(value);
+                self.yields = true;
+                Type::Unknown
+            }
+            Value::Throw(value) | Value::Drop(value) => {
+                self.value(value);
+                Type::Unknown
+            }
+            Value::MultiDepthAccess { root, next_keys } => {
+                self.value(root);
+                for key in next_keys {
+                    self.key(key);
+                }
+                Type::Unknown
+            }
+            Value::Break
+            | Value::Continue(_)
+            | Value::NoOp
+            | Value::Operation(_)
+            | Value::VariableUnused
+            | Value::Rest(_)
+            | Value::Pattern(_) => Type::Unknown,
+        }
+    }
+
+    fn key(&mut self, key: &KeyAccess) {
+        match key {
+            KeyAccess::Variable(value) => {
+                self.value(value);
+            }
+            KeyAccess::FunctionCall { key, parameters } => {
+                self.key(key);
+                self.arguments(parameters);
+            }
+            KeyAccess::Optional(key) => self.key(key),
+            KeyAccess::Index(_) | KeyAccess::Key(_) => {}
+        }
+    }
+
+    /// `name = expr`, or `let name: type = expr` when `declaration` is given
+    fn assign(
+        &mut self,
+        name: &str,
+        expr: &Value,
+        declaration: Option<(bool, Option<Type>)>,
+    ) -> Type {
+        let previous = self.variables.get(name).cloned();
+        let (constant, declared) = match declaration {
+            Some(declaration) => declaration,
+            None => {
+                if previous.as_ref().is_some_and(|v| v.constant) {
+                    self.report(format!("cannot assign to constant {name}"));
+                }
+                (false, previous.and_then(|v| v.declared))
+            }
+        };
+        let (ty, signature) = match function_parts(expr) {
+            Some((parameters, exprs, returns)) => {
+                let signature = signature(parameters, returns);
+                // a function can call itself
+                self.bind(
+                    name,
+                    Variable {
+                        ty: Type::Is(TYPE_FUNCTION),
+                        declared: declared.clone(),
+                        constant,
+                        signature: Some(signature.clone()),
+                    },
+                );
+                self.function(name, parameters, exprs, &signature);
+                (Type::Is(TYPE_FUNCTION), Some(signature))
+            }
+            None => (self.value(expr), None),
+        };
+        if let Some(declared) = &declared {
+            if !declared.accepts(&ty) {
+                self.report(format!(
+                    "{name} is declared as {declared}, got {ty}"
+                ));
+            }
+        }
+        self.bind(
+            name,
+            Variable {
+                ty: declared.clone().unwrap_or_else(|| ty.clone()),
+                declared,
+                constant,
+                signature,
+            },
+        );
+        ty
+    }
+
+    /// bind the variables of a pattern, `ty` being the type of the whole
+    /// value
+    fn bind_pattern(&mut self, pattern: &Pattern, ty: Type) {
+        match pattern {
+            Pattern::Binding(name) => self.bind(name, Variable::of(ty)),
+            Pattern::Type { binding: Some(name), .. } => {
+                self.bind(name, Variable::of(Type::Unknown))
+            }
+            Pattern::Array { head, rest, tail } => {
+                for pattern in head.iter().chain(tail) {
+                    self.bind_pattern(pattern, Type::Unknown);
+                }
+                if let Some(rest) = rest {
+                    self.bind_pattern(rest, Type::Is(TYPE_ARRAY));
+                }
+            }
+            Pattern::Struct { fields, rest } => {
+                for (_, pattern) in fields {
+                    self.bind_pattern(pattern, Type::Unknown);
+                }
+                if let Some(rest) = rest {
+                    self.bind_pattern(rest, Type::Is(TYPE_STRUCT));
+                }
+            }
+            Pattern::Range(range) => {
+                self.value(range);
+            }
+            Pattern::Wildcard
+            | Pattern::Literal(_)
+            | Pattern::Type { binding: None, .. } => {}
+        }
+    }
+
+    fn lambda(
+        &mut self,
+        parameters: &Value,
+        exprs: &[Value],
+        returns: Type,
+    ) -> Type {
+        let signature = signature(parameters, returns);
+        self.function(TYPE_FUNCTION, parameters, exprs, &signature);
+        Type::Is(TYPE_FUNCTION)
+    }
+
+    /// check the body of a function, with its parameters bound
+    fn function(
+        &mut self,
+        name: &str,
+        parameters: &Value,
+        exprs: &[Value],
+        signature: &Signature,
+    ) {
+        let outer = self.variables.clone();
+        if let Value::BlockParen(values) = parameters {
+            for (value, parameter) in values.iter().zip(&signature.parameters) {
+                if let (_, Some(default)) = parameter_of(value) {
+                    let ty = self.value(default);
+                    self.argument(name, parameter, &ty);
+                }
+                let declared = Some(parameter.ty.clone())
+                    .filter(|ty| ty != &Type::Unknown);
+                self.bind(
+                    &parameter.name,
+                    Variable {
+                        ty: parameter.ty.clone(),
+                        declared,
+                        constant: false,
+                        signature: None,
+                    },
+                );
+            }
+        }
+        self.returns.push((name.to_string(), signature.returns.clone()));
+        let outer_yields = std::mem::take(&mut self.yields);
+        let last = self.statements(exprs);
+        if self.yields {
+            self.returned(Type::Is(TYPE_ITERATOR));
+        } else {
+            self.returned(last);
+        }
+        self.yields = outer_yields;
+        self.returns.pop();
+        self.variables = outer;
+    }
+
+    /// a value returned by the function being checked
+    fn returned(&mut self, ty: Type) {
+        let Some((name, returns)) = self.returns.last() else {
+            return;
+        };
+        if !returns.accepts(&ty) {
+            let message = format!("{name} returns {returns}, got {ty}");
+            self.report(message);
+        }
+    }
+
+    fn argument(&mut self, name: &str, parameter: &Parameter, ty: &Type) {
+        if !parameter.ty.accepts(ty) {
+            self.report(format!(
+                "{name} expects {} for parameter {}, got {ty}",
+                parameter.ty, parameter.name
+            ));
+        }
+    }
+
+    /// the types of the positional and the named arguments of a call
+    fn arguments(
+        &mut self,
+        parameters: &Value,
+    ) -> (Vec<Type>, Vec<(String, Type)>) {
+        let Value::BlockParen(values) = parameters else {
+            self.value(parameters);
+            return (vec![], vec![]);
+        };
+        let mut positional = vec![];
+        let mut named = vec![];
+        for value in values {
+            match value {
+                Value::VariableExpr { name, expr } => match name.as_ref() {
+                    Value::Variable(name) => {
+                        named.push((name.clone(), self.value(expr)))
+                    }
+                    _ => positional.push(self.value(value)),
+                },
+                value => positional.push(self.value(value)),
+            }
+        }
+        (positional, named)
+    }
+
+    fn call(&mut self, function: &Value, parameters: &Value) -> Type {
+        let (positional, named) = self.arguments(parameters);
+        let Value::Variable(name) = function.unspanned() else {
+            self.value(function);
+            return Type::Unknown;
+        };
+        let ty = self.variable(name);
+        let Some(variable) = self.variables.get(name).cloned() else {
+            return Type::Unknown;
+        };
+        match variable.signature {
+            Some(signature) => {
+                self.bind_arguments(name, &signature, positional, named);
+                signature.returns
+            }
+            None => {
+                if !ty.is_callable() {
+                    self.report(format!("{name} is not a function, got {ty}"));
+                }
+                Type::Unknown
+            }
+        }
+    }
+
+    /// the same checks as when the arguments of a call are bound to the
+    /// parameters of the function, with the same messages
+    fn bind_arguments(
+        &mut self,
+        name: &str,
+        signature: &Signature,
+        positional: Vec<Type>,
+        mut named: Vec<(String, Type)>,
+    ) {
+        let given = positional.len();
+        let mut values = positional.into_iter();
+        for parameter in &signature.parameters {
+            if parameter.rest {
+                values.by_ref().for_each(drop);
+                continue;
+            }
+            let named_arg = named
+                .iter()
+                .position(|(n, _)| n == &parameter.name)
+                .map(|idx| named.remove(idx).1);
+            match (values.next(), named_arg) {
+                (Some(_), Some(_)) => {
+                    return self.report(format!(
+                        "{name} got two values for parameter {}",
+                        parameter.name
+                    ));
+                }
+                (Some(ty), None) | (None, Some(ty)) => {
+                    self.argument(name, parameter, &ty)
+                }
+                (None, None) if parameter.default => {}
+                (None, None) => {
+                    return self.report(format!(
+                        "{name} is missing parameter {}",
+                        parameter.name
+                    ));
+                }
+            }
+        }
+        if values.next().is_some() {
+            let expected = signature.parameters.len();
+            return self.report(format!(
+                "{name} takes {expected} argument(s), got {given}"
+            ));
+        }
+        if let Some((key, _)) = named.first() {
+            let message = format!("{name} has no parameter {key}");
+            self.report(message);
+        }
+    }
+
+    fn builtin(&mut self, fn_type: BuiltInFunctionType, expr: &Value) -> Type {
+        if matches!(
+            fn_type,
+            BuiltInFunctionType::Include | BuiltInFunctionType::Eval
+        ) {
+            self.opaque = true;
+        }
+        let name = fn_type.as_str();
+        if fn_type.is_shadowable()
+            && (self.opaque
+                || self.variables.contains_key(name)
+                || self.bound.contains(name))
+        {
+            // may call a variable named after the builtin
+            self.value(expr);
+            return Type::Unknown;
+        }
+        if let Value::Array(args) = expr {
+            let (least, most) = fn_type.arity();
+            if !(least..=most).contains(&args.len()) {
+                let expected = if least == most {
+                    format!("{least} argument(s)")
+                } else {
+                    format!("{least} to {most} arguments")
+                };
+                self.report(format!(
+                    "{name} takes {expected}, got {}",
+                    args.len()
+                ));
+            }
+        }
+        self.value(expr);
+        builtin_type(fn_type)
+    }
+
+    fn operations(&mut self, operations: &[Value]) -> Type {
+        match operations {
+            [] => return Type::Unknown,
+            [value] => return self.value(value),
+            _ => {}
+        }
+        let implicit = operations.iter().any(|o| {
+            matches!(
+                o,
+                Value::ImplicitMultiply(_)
+                    | Value::Operation(Operator::Pow2 | Operator::Pow3)
+            )
+        });
+        let position = next_operator_position(operations).filter(|_| !implicit);
+        let Some(position) = position else {
+            return self.unchecked(operations);
+        };
+        let Value::Operation(operator) = operations[position] else {
+            unreachable!("CHECKER BUG: not an operator")
+        };
+        let (left, right) =
+            (&operations[..position], &operations[position + 1..]);
+        // a negation, e.g. `2 * -x`
+        if matches!(left.last(), Some(Value::Operation(_))) {
+            return self.unchecked(operations);
+        }
+        if left.is_empty() {
+            let ty = self.operations(right);
+            return self.unary(operator, ty);
+        }
+        let left = self.operations(left);
+        let right = self.operations(right);
+        self.binary(operator, left, right)
+    }
+
+    /// the values of an expression the checker doesn't evaluate
+    fn unchecked(&mut self, operations: &[Value]) -> Type {
+        for operation in operations {
+            self.value(operation);
+        }
+        Type::Unknown
+    }
+
+    fn unary(&mut self, operator: Operator, ty: Type) -> Type {
+        let Some(sample) = ty.sample() else {
+            return Type::Unknown;
+        };
+        let result = match operator {
+            Operator::Not => sample.not(),
+            Operator::BitwiseNot => sample.bitwise_not(),
+            Operator::Subtr => sample.neg(),
+            _ => return Type::Unknown,
+        };
+        if let Primitive::Error(_) = result {
+            self.report(format!("cannot apply {} to {ty}", operator.as_str()));
+            return Type::Unknown;
+        }
+        Type::of(&result)
+    }
+
+    fn binary(&mut self, operator: Operator, left: Type, right: Type) -> Type {
+        let comparison = matches!(
+            operator,
+            Operator::Equal
+                | Operator::NotEqual
+                | Operator::Less
+                | Operator::Greater
+                | Operator::LessOrEqual
+                | Operator::GreaterOrEqual
+                | Operator::Is
+        );
+        if operator == Operator::NullCoalesce {
+            return if left == right { left } else { Type::Unknown };
+        }
+        // the sample of a quantity has a unit the target may not fit
+        if operator == Operator::Convert {
+            return Type::Is(TYPE_QUANTITY);
+        }
+        let (Some(l), Some(r)) = (left.sample(), right.sample()) else {
+            return if comparison {
+                Type::Is(TYPE_BOOL)
+            } else {
+                Type::Unknown
+            };
+        };
+        match crate::vm::binary(operator, &l, &r) {
+            Primitive::Error(_) => {
+                self.report(format!(
+                    "cannot apply {} to {left} and {right}",
+                    operator.as_str()
+                ));
+                Type::Unknown
+            }
+            // a negative exponent gives a double
+            _ if operator == Operator::Pow => Type::Unknown,
+            result => Type::of(&result),
+        }
+    }
+}
+
+/// the parameters and body of a function, with the type it returns
+fn function_parts(value: &Value) -> Option<(&Value, &[Value], Type)> {
+    match value.unspanned() {
+        Value::Function { parameters, exprs } => {
+            Some((parameters, exprs, Type::Unknown))
+        }
+        Value::Typed { value, type_name } => match value.unspanned() {
+            Value::Function { parameters, exprs } => {
+                Some((parameters, exprs, Type::annotation(type_name)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn signature(parameters: &Value, returns: Type) -> Signature {
+    let parameters = match parameters {
+        Value::BlockParen(values) => {
+            values.iter().map(|value| parameter_of(value).0).collect()
+        }
+        _ => vec![],
+    };
+    Signature { parameters, returns }
+}
+
+/// a parameter of a function, and its default value
+fn parameter_of(value: &Value) -> (Parameter, Option<&Value>) {
+    let parameter = |name: &str, rest| Parameter {
+        name: name.to_string(),
+        ty: Type::Unknown,
+        default: false,
+        rest,
+    };
+    match value {
+        Value::Typed { value, type_name } => {
+            let (mut parameter, default) = parameter_of(value);
+            parameter.ty = Type::annotation(type_name);
+            (parameter, default)
+        }
+        Value::VariableExpr { name, expr } => {
+            let (mut parameter, _) = parameter_of(name);
+            parameter.default = true;
+            (parameter, Some(expr))
+        }
+        Value::Variable(name) => (parameter(name, false), None),
+        Value::Rest(name) => (parameter(name, true), None),
+        _ => (parameter("_", false), None),
+    }
+}
+
+/// the type returned by a builtin, when it doesn't depend on its arguments
+fn builtin_type(fn_type: BuiltInFunctionType) -> Type {
+    match fn_type {
+        BuiltInFunctionType::IsError
+        | BuiltInFunctionType::IsU8
+        | BuiltInFunctionType::IsI8
+        | BuiltInFunctionType::IsStruct
+        | BuiltInFunctionType::IsBool
+        | BuiltInFunctionType::IsInt
+        | BuiltInFunctionType::IsDouble
+        | BuiltInFunctionType::IsFunction
+        | BuiltInFunctionType::IsArray
+        | BuiltInFunctionType::IsDict
+        | BuiltInFunctionType::IsSet => Type::Is(TYPE_BOOL),
+        BuiltInFunctionType::TypeOf
+        | BuiltInFunctionType::ToString
+        | BuiltInFunctionType::Jsonify => Type::Is(TYPE_STRING),
+        BuiltInFunctionType::Length => Type::Integer,
+        BuiltInFunctionType::Println | BuiltInFunctionType::Print => {
+            Type::Is(TYPE_UNIT)
+        }
+        _ => Type::Unknown,
+    }
+}
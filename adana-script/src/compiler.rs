@@ -0,0 +1,1429 @@
+use std::collections::{BTreeMap, HashMap};
+
+use adana_script_core::{
+    constants::{BREAK, CONTINUE},
+    primitive::{Captured, CompiledBody, Primitive, Quantity},
+    BuiltInFunctionType, KeyAccess, MatchArm, MathConstants, Operator, Pattern,
+    Span, Value,
+};
+use indexmap::IndexMap;
+
+use crate::bytecode::{
+    self, Access, Args, Arm, AssignAt, Call, Callee, Closure, Code, DropTarget,
+    Key, Op, Program, Slot, Try,
+};
+
+/// compile a block of instructions
+pub(crate) fn compile(instructions: Vec<Value>) -> Program {
+    let mut compiler = Compiler::default();
+    compiler.instructions(instructions);
+    compiler.finish()
+}
+
+/// compile a single expression
+pub(crate) fn compile_expression(value: Value) -> Program {
+    let mut compiler = Compiler::default();
+    compiler.root(value);
+    compiler.finish()
+}
+
+#[derive(Default)]
+struct Compiler {
+    names: Vec<String>,
+    slots: HashMap<String, Slot>,
+    code: Code,
+    /// span of the statement being compiled
+    span: Option<Span>,
+    /// labels of the loops being compiled, innermost last
+    loops: Vec<Option<String>>,
+    /// label of the next loop
+    label: Option<String>,
+    /// set once the code yields
+    generator: bool,
+    /// set while compiling code a generator can't pause in, e.g. the
+    /// arguments of a call
+    in_expression: bool,
+}
+
+impl Compiler {
+    fn finish(self) -> Program {
+        Program {
+            names: self.names,
+            code: self.code,
+            generator: self.generator,
+        }
+    }
+
+    fn slot(&mut self, name: &str) -> Slot {
+        if let Some(slot) = self.slots.get(name) {
+            return *slot;
+        }
+        let slot = self.names.len();
+        self.names.push(name.to_string());
+        self.slots.insert(name.to_string(), slot);
+        slot
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        self.code.ops.push(op);
+        self.code.ops.len() - 1
+    }
+
+    fn here(&self) -> usize {
+        self.code.ops.len()
+    }
+
+    fn patch(&mut self, at: usize) {
+        let here = self.here();
+        match &mut self.code.ops[at] {
+            Op::Jump(to)
+            | Op::JumpUnlessTrue(to)
+            | Op::StoreInLoop { end: to, .. }
+            | Op::IterNext { done: to, .. } => *to = here,
+            op => unreachable!("COMPILER BUG: cannot patch {op:?}"),
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.code.ops.truncate(len);
+        self.code.spans.retain(|(range, _)| range.start < len);
+    }
+
+    /// compile into a separate chunk of code
+    fn nested(&mut self, f: impl FnOnce(&mut Self)) -> Code {
+        let outer = std::mem::replace(&mut self.in_expression, true);
+        let code = self.resumable(f);
+        self.in_expression = outer;
+        code
+    }
+
+    /// compile into a separate chunk of code a generator can pause in, e.g.
+    /// the body of an `if`
+    fn resumable(&mut self, f: impl FnOnce(&mut Self)) -> Code {
+        let outer = std::mem::take(&mut self.code);
+        f(self);
+        std::mem::replace(&mut self.code, outer)
+    }
+
+    fn spanned(&mut self, span: Option<Span>, start: usize) {
+        if let Some(span) = span {
+            let end = self.here();
+            self.code.spans.push((start..end, span));
+        }
+    }
+
+    // region: instructions
+    fn instructions(&mut self, instructions: Vec<Value>) {
+        for instruction in instructions {
+            let start = self.here();
+            let (span, instruction) = unspan(instruction);
+            let site = span.clone().or_else(|| self.span.clone());
+            let outer = std::mem::replace(&mut self.span, site);
+            self.instruction(instruction);
+            self.span = outer;
+            self.spanned(span, start);
+        }
+    }
+
+    fn instruction(&mut self, instruction: Value) {
+        match instruction {
+            Value::EarlyReturn(v) => {
+                match *v {
+                    Some(v) => self.block(v),
+                    None => {
+                        self.emit(Op::Const(Primitive::Null));
+                    }
+                }
+                self.emit(Op::Exit);
+            }
+            Value::IfExpr { cond, exprs, else_expr } => {
+                self.root(*cond);
+                self.emit(Op::ExitIfError);
+                let jump_else = self.emit(Op::JumpUnlessTrue(0));
+                self.scoped_block(exprs);
+                if let Some(else_expr) = else_expr {
+                    let jump_end = self.emit(Op::Jump(0));
+                    self.patch(jump_else);
+                    self.scoped_block(else_expr);
+                    self.patch(jump_end);
+                } else {
+                    self.patch(jump_else);
+                }
+            }
+            Value::Labeled { label, value } => {
+                self.label = Some(label);
+                self.instruction(*value);
+            }
+            Value::WhileExpr { cond, exprs } => {
+                let label = self.label.take();
+                self.emit(Op::EnterScope);
+                let start = self.here();
+                self.root(*cond);
+                let jump_end = self.emit(Op::JumpUnlessTrue(0));
+                let breaks = self.loop_body(exprs, label, start);
+                self.emit(Op::Jump(start));
+                self.patch(jump_end);
+                for b in breaks {
+                    self.patch(b);
+                }
+                self.emit(Op::ExitScope);
+            }
+            Value::ForeachExpr { var, index_var, iterator, exprs } => {
+                let label = self.label.take();
+                self.root(*iterator);
+                self.emit(Op::EnterScope);
+                self.emit(Op::IterStart);
+                let var = (!var.starts_with('_')).then(|| self.slot(&var));
+                let index_var = index_var
+                    .filter(|i| !i.starts_with('_'))
+                    .map(|i| self.slot(&i));
+                let start = self.emit(Op::IterNext { var, index_var, done: 0 });
+                let breaks = self.loop_body(exprs, label, start);
+                self.emit(Op::Jump(start));
+                for b in breaks {
+                    self.patch(b);
+                }
+                self.emit(Op::IterEnd);
+                self.patch(start);
+                self.emit(Op::ExitScope);
+            }
+            Value::TryExpr { exprs, catch_var, catch_expr, finally_expr } => {
+                let body = self.resumable(|c| c.scoped_block(exprs));
+                let catch = catch_expr.map(|exprs| {
+                    let var = catch_var
+                        .filter(|v| !v.starts_with('_'))
+                        .map(|v| self.slot(&v));
+                    (var, self.resumable(|c| c.scoped_block(exprs)))
+                });
+                let finally = finally_expr
+                    .map(|exprs| self.resumable(|c| c.scoped_block(exprs)));
+                self.emit(Op::Try(Box::new(Try { body, catch, finally })));
+                self.emit(Op::StoreInBlock);
+            }
+            instruction => {
+                self.root(instruction);
+                self.emit(Op::Store);
+            }
+        }
+    }
+
+    fn scoped_block(&mut self, exprs: Vec<Value>) {
+        self.emit(Op::EnterScope);
+        for expr in exprs {
+            self.root(expr);
+            self.emit(Op::StoreInBlock);
+        }
+        self.emit(Op::ExitScope);
+    }
+
+    /// returns the jumps to patch with the end of the loop. `continue`
+    /// jumps back to `start`
+    fn loop_body(
+        &mut self,
+        exprs: Vec<Value>,
+        label: Option<String>,
+        start: usize,
+    ) -> Vec<usize> {
+        self.loops.push(label.clone());
+        let breaks = exprs
+            .into_iter()
+            .map(|expr| {
+                self.root(expr);
+                self.emit(Op::StoreInLoop {
+                    label: label.clone(),
+                    start,
+                    end: 0,
+                })
+            })
+            .collect();
+        self.loops.pop();
+        breaks
+    }
+
+    /// make sure a `break` or `continue` has a loop to jump out of
+    fn loop_jump(
+        &self,
+        keyword: &str,
+        label: &Option<String>,
+    ) -> anyhow::Result<()> {
+        match label {
+            _ if self.loops.is_empty() => {
+                Err(anyhow::format_err!("{keyword} outside of a loop"))
+            }
+            Some(label) if !self.loops.contains(&Some(label.clone())) => {
+                Err(anyhow::format_err!("{keyword}: no loop labeled '{label}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// a block made of a single instruction
+    fn block(&mut self, value: Value) {
+        if matches!(
+            value.unspanned(),
+            Value::IfExpr { .. }
+                | Value::WhileExpr { .. }
+                | Value::ForeachExpr { .. }
+                | Value::Labeled { .. }
+                | Value::TryExpr { .. }
+                | Value::EarlyReturn(_)
+        ) {
+            let code = self.resumable(|c| c.instructions(vec![value]));
+            self.emit(Op::Block(code));
+        } else {
+            self.root(value);
+            self.emit(Op::Unwrap);
+        }
+    }
+
+    fn block_code(&mut self, value: Value) -> Code {
+        self.nested(|c| c.block(value))
+    }
+
+    fn root_code(&mut self, value: Value) -> Code {
+        self.nested(|c| c.root(value))
+    }
+    // endregion: instructions
+
+    // region: expressions
+
+    /// compile an expression evaluated on its own. An invalid expression
+    /// fails when evaluated
+    fn root(&mut self, value: Value) {
+        let start = self.here();
+        match self.expr(value, true) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.emit(Op::Fail("Invalid expression!".into()));
+            }
+            Err(e) => {
+                self.truncate(start);
+                self.emit(Op::Fail(e.to_string()));
+            }
+        }
+    }
+
+    /// returns false when the expression is empty
+    fn expr(&mut self, value: Value, root: bool) -> anyhow::Result<bool> {
+        match value {
+            Value::Spanned { span, value } => {
+                let start = self.here();
+                let outer = self.span.replace(span.clone());
+                let emitted = self.expr(*value, root);
+                self.span = outer;
+                let emitted = emitted?;
+                self.spanned(Some(span), start);
+                Ok(emitted)
+            }
+            Value::Expression(operations) | Value::BlockParen(operations) => {
+                self.operations(operations, root)
+            }
+            Value::Operation(operator) => {
+                self.operator(operator, self.here(), 0);
+                Ok(true)
+            }
+            Value::NoOp => self.constant(Primitive::Unit),
+            Value::Primitive(p) => self.constant(p),
+            Value::UnitSuffix { number, unit } => {
+                self.expr(*number, false)?;
+                let slot = self.slot(unit.symbol());
+                let unit = Primitive::Quantity(Quantity::new(1., unit));
+                self.emit(Op::LoadOr(slot, unit));
+                self.emit(Op::Binary(Operator::Mult));
+                Ok(true)
+            }
+            Value::ImplicitMultiply(value) => Err(anyhow::Error::msg(format!(
+                "AST BUG: invalid implicit multiplier, unreachable branch: {value:?}",
+            ))),
+            Value::Decimal(num) => self.constant(Primitive::Double(num)),
+            Value::Integer(num) => self.constant(Primitive::Int(num)),
+            Value::U8(num) => self.constant(Primitive::U8(num)),
+            Value::I8(num) => self.constant(Primitive::I8(num)),
+            Value::Bool(b) => self.constant(Primitive::Bool(b)),
+            Value::String(s) => self.constant(Primitive::String(s)),
+            Value::FString(s, parameters) => {
+                let parameters = parameters
+                    .into_iter()
+                    .map(|(key, param)| (key, self.root_code(param)))
+                    .collect();
+                self.emit(Op::FString(Box::new((s, parameters))));
+                Ok(true)
+            }
+            Value::Range { start, end, incl_both_end } => {
+                self.range_bound(*start)?;
+                self.range_bound(*end)?;
+                self.emit(Op::Range { incl_both_end });
+                Ok(true)
+            }
+            Value::Variable(name) => {
+                let slot = self.slot(&name);
+                self.emit(Op::Load(slot));
+                Ok(true)
+            }
+            Value::VariableRef(name) => {
+                let slot = self.slot(&name);
+                self.emit(Op::LoadRef(slot));
+                Ok(true)
+            }
+            Value::VariableNegate(name) => {
+                let slot = self.slot(&name);
+                self.emit(Op::LoadNeg(slot));
+                Ok(true)
+            }
+            Value::VariableUnused => {
+                self.emit(Op::Fail("forbidden usage of VariableUnused".into()));
+                Ok(true)
+            }
+            Value::Rest(name) => {
+                self.emit(Op::Fail(
+                    format!("...{name} is only allowed as a parameter"),
+                ));
+                Ok(true)
+            }
+            Value::Pattern(pattern) => {
+                self.emit(Op::Fail(format!(
+                    "{pattern:?} is only allowed on the left of an assignment"
+                )));
+                Ok(true)
+            }
+            Value::Typed { value, .. } => self.expr(*value, root),
+            Value::Import { path, alias } => {
+                anyhow::ensure!(
+                    root,
+                    "invalid import, only allowed at the top level"
+                );
+                let slot = self.slot(&alias);
+                self.emit(Op::Import(path));
+                self.emit(Op::Assign(slot));
+                Ok(true)
+            }
+            Value::Export(value) => {
+                anyhow::ensure!(
+                    root,
+                    "invalid export, only allowed at the top level"
+                );
+                self.expr(*value, root)
+            }
+            Value::MultiDepthAccess { root, next_keys } => {
+                match *root {
+                    Value::String(s) => {
+                        self.emit(Op::Const(Primitive::String(s)));
+                    }
+                    v @ Value::FString(_, _)
+                    | v @ Value::Variable(_)
+                    | v @ Value::Array(_)
+                    | v @ Value::Struct(_)
+                    | v @ Value::Dict(_)
+                    | v @ Value::Set(_)
+                    | v @ Value::BuiltInFunction { .. }
+                    | v @ Value::FunctionCall { .. }
+                    | v @ Value::VariableRef(_) => self.root(v),
+                    v => {
+                        self.emit(Op::Fail(format!(
+                            "illegal multidepth access {v:?}"
+                        )));
+                        return Ok(true);
+                    }
+                }
+                let keys = self.keys(&next_keys);
+                self.emit(Op::Access(Box::new(Access { keys, next_keys })));
+                Ok(true)
+            }
+            Value::VariableExpr { name, expr } => {
+                anyhow::ensure!(
+                    root,
+                    "invalid variable assignment, tree root is not none"
+                );
+                let assign = match *name {
+                    Value::Variable(n) => Some(Op::Assign(self.slot(&n))),
+                    Value::Pattern(pattern) => {
+                        Some(Op::Destructure(Box::new(self.pattern(*pattern)?)))
+                    }
+                    Value::VariableUnused => None,
+                    Value::MultiDepthAccess { root, next_keys } => {
+                        Some(Op::AssignAt(Box::new(
+                            self.assign_at(*root, next_keys),
+                        )))
+                    }
+                    name => {
+                        return Err(anyhow::Error::msg(format!(
+                        "AST ERROR: invalid variable expression {name:?} => {expr:?}",
+                    )))
+                    }
+                };
+                let assigned = match (&assign, untyped(*expr)) {
+                    // a function doesn't capture the variable it is assigned
+                    // to, so it can call itself
+                    (
+                        Some(Op::Assign(slot)),
+                        Value::Function { parameters, exprs },
+                    ) => {
+                        let name = self.names[*slot].clone();
+                        self.closure(*parameters, exprs, Some(&name))
+                    }
+                    (_, expr) => self.expr(expr, false)?,
+                };
+                anyhow::ensure!(
+                    assigned,
+                    "invalid variable expr: nothing to assign"
+                );
+                if let Some(assign) = assign {
+                    self.emit(assign);
+                }
+                Ok(true)
+            }
+            Value::Declaration { constant, name, expr, .. } => {
+                anyhow::ensure!(
+                    root,
+                    "invalid declaration, tree root is not none"
+                );
+                let slot = self.slot(&name);
+                let assigned = match untyped(*expr) {
+                    Value::Function { parameters, exprs } => {
+                        self.closure(*parameters, exprs, Some(&name))
+                    }
+                    expr => self.expr(expr, false)?,
+                };
+                anyhow::ensure!(
+                    assigned,
+                    "invalid declaration: nothing to assign"
+                );
+                self.emit(Op::Declare { slot, constant });
+                Ok(true)
+            }
+            Value::Const(c) => match c {
+                c if c == MathConstants::Pi.get_symbol() => {
+                    self.constant(Primitive::Double(std::f64::consts::PI))
+                }
+                c if c == MathConstants::EulerNumber.get_symbol() => {
+                    self.constant(Primitive::Double(std::f64::consts::E))
+                }
+                c if c == MathConstants::Tau.get_symbol() => {
+                    self.constant(Primitive::Double(std::f64::consts::TAU))
+                }
+                c => Err(anyhow::format_err!("unknown constant {c}")),
+            },
+            Value::BuiltInFunction { fn_type, expr } if fn_type.is_shadowable() => {
+                // a variable named after the builtin, e.g. one defined by an
+                // include, is called instead of the builtin
+                let builtin = self.nested(|c| {
+                    if let Err(e) = c.builtin(fn_type, (*expr).clone()) {
+                        c.truncate(0);
+                        c.emit(Op::Fail(e.to_string()));
+                    }
+                });
+                let args = match *expr {
+                    Value::Array(args) => args,
+                    expr => vec![expr],
+                };
+                let variable = self.root_code(Value::FunctionCall {
+                    parameters: Box::new(Value::BlockParen(args)),
+                    function: Box::new(Value::Variable(
+                        fn_type.as_str().to_string(),
+                    )),
+                });
+                let slot = self.slot(fn_type.as_str());
+                self.emit(Op::Shadowable(slot, Box::new((builtin, variable))));
+                Ok(true)
+            }
+            Value::BuiltInFunction { fn_type, expr } => {
+                self.builtin(fn_type, *expr)
+            }
+            v @ Value::IfExpr { .. }
+            | v @ Value::WhileExpr { .. }
+            | v @ Value::ForeachExpr { .. }
+            | v @ Value::Labeled { .. }
+            | v @ Value::TryExpr { .. } => {
+                let code = self.resumable(|c| c.instructions(vec![v]));
+                self.emit(Op::Scoped(code));
+                Ok(true)
+            }
+            Value::Array(arr) => {
+                let arr = arr.into_iter().map(|v| self.block_code(v)).collect();
+                self.emit(Op::Array(arr));
+                Ok(true)
+            }
+            Value::Struct(struc) => {
+                let struc = self.struct_fields(struc);
+                self.emit(Op::Struct(struc));
+                Ok(true)
+            }
+            Value::Dict(dict) => {
+                let dict = dict
+                    .into_iter()
+                    .map(|(k, v)| (self.block_code(k), self.block_code(v)))
+                    .collect();
+                self.emit(Op::Dict(dict));
+                Ok(true)
+            }
+            Value::Set(set) => {
+                let set = set.into_iter().map(|v| self.block_code(v)).collect();
+                self.emit(Op::Set(set));
+                Ok(true)
+            }
+            Value::Function { parameters, exprs } => {
+                Ok(self.closure(*parameters, exprs, None))
+            }
+            Value::FunctionCall { parameters, function } => {
+                let function = match function.unspanned() {
+                    Value::Variable(name) => Callee::Variable(self.slot(name)),
+                    _ => Callee::Block(self.block_code(*function)),
+                };
+                let args = self.args(*parameters);
+                let site = self.span.clone();
+                self.emit(Op::Call(Box::new(Call { function, args, site })));
+                Ok(true)
+            }
+            Value::MatchExpr { value, arms } => {
+                self.root(*value);
+                let arms = arms
+                    .into_iter()
+                    .map(|arm| self.arm(arm))
+                    .collect::<anyhow::Result<_>>()?;
+                self.emit(Op::Match(arms));
+                Ok(true)
+            }
+            Value::Throw(v) => {
+                self.root(*v);
+                self.emit(Op::Throw);
+                Ok(true)
+            }
+            Value::Yield(v) => {
+                self.generator = true;
+                if self.in_expression {
+                    return Err(anyhow::format_err!(
+                        "yield cannot be used inside an expression"
+                    ));
+                }
+                self.root(*v);
+                self.emit(Op::Yield);
+                Ok(true)
+            }
+            Value::Break => {
+                self.loop_jump(BREAK, &None)?;
+                self.emit(Op::Break { label: None, with_value: false });
+                Ok(true)
+            }
+            Value::BreakWith { label, value } => {
+                self.loop_jump(BREAK, &label)?;
+                let with_value = value.is_some();
+                if let Some(value) = value {
+                    self.root(*value);
+                }
+                self.emit(Op::Break { label, with_value });
+                Ok(true)
+            }
+            Value::Continue(label) => {
+                self.loop_jump(CONTINUE, &label)?;
+                self.emit(Op::Continue(label));
+                Ok(true)
+            }
+            Value::Null => self.constant(Primitive::Null),
+            Value::Drop(v) => {
+                let Value::BlockParen(variables) = *v else {
+                    return Err(anyhow::Error::msg(format!(
+                        "drop must be in paren: {v:?}"
+                    )));
+                };
+                let mut targets = Vec::with_capacity(variables.len());
+                for variable in variables {
+                    match variable {
+                        Value::Variable(name) => targets
+                            .push(DropTarget::Variable(self.slot(&name))),
+                        Value::MultiDepthAccess { root, next_keys } => {
+                            targets.push(DropTarget::At(
+                                self.assign_at(*root, next_keys),
+                            ))
+                        }
+                        _ => {
+                            return Err(anyhow::Error::msg(format!(
+                                "not a variable: {variable:?}"
+                            )));
+                        }
+                    }
+                }
+                self.emit(Op::Drop(targets.into()));
+                Ok(true)
+            }
+            Value::EarlyReturn(v) => {
+                match *v {
+                    Some(v) => self.block(v),
+                    None => {
+                        self.emit(Op::Const(Primitive::Null));
+                    }
+                }
+                self.emit(Op::Return);
+                Ok(true)
+            }
+        }
+    }
+
+    fn builtin(
+        &mut self,
+        fn_type: BuiltInFunctionType,
+        expr: Value,
+    ) -> anyhow::Result<bool> {
+        if let (false, Value::Array(args)) = (fn_type.takes_many_args(), &expr)
+        {
+            return Err(anyhow::format_err!(
+                "{} takes 1 argument(s), got {}",
+                fn_type.as_str(),
+                args.len()
+            ));
+        }
+        match fn_type {
+            BuiltInFunctionType::MakeError => match struct_literal(expr) {
+                Ok(struc) => {
+                    let struc = self.struct_fields(struc);
+                    self.emit(Op::MakeError(struc));
+                }
+                Err(expr) => {
+                    self.root(expr);
+                    self.emit(Op::BuiltIn(BuiltInFunctionType::MakeError));
+                }
+            },
+            fn_type => {
+                self.root(expr);
+                self.emit(Op::BuiltIn(fn_type));
+            }
+        }
+        Ok(true)
+    }
+
+    fn constant(&mut self, p: Primitive) -> anyhow::Result<bool> {
+        self.emit(Op::Const(p));
+        Ok(true)
+    }
+
+    fn closure(
+        &mut self,
+        parameters: Value,
+        exprs: Vec<Value>,
+        name: Option<&str>,
+    ) -> bool {
+        let function = function(parameters, exprs);
+        let Primitive::Function { parameters, exprs, .. } = &function else {
+            self.emit(Op::Const(function));
+            return true;
+        };
+        let mut variables = BTreeMap::new();
+        for expr in exprs {
+            used_variables(expr, &mut variables);
+        }
+        used_in_parameters(parameters, &mut variables);
+        if let Some(name) = name {
+            variables.remove(name);
+        }
+        if variables.is_empty() {
+            self.emit(Op::Const(function));
+            return true;
+        }
+        let captures = variables
+            .into_iter()
+            .map(|(name, by_ref)| (self.slot(&name), by_ref))
+            .collect();
+        self.emit(Op::Closure(Box::new(Closure { function, captures })));
+        true
+    }
+
+    fn arm(&mut self, arm: MatchArm) -> anyhow::Result<Arm> {
+        let MatchArm { pattern, guard, exprs } = arm;
+        Ok(Arm {
+            pattern: self.pattern(pattern)?,
+            guard: guard.map(|guard| self.root_code(guard)),
+            body: self.resumable(|c| c.scoped_block(exprs)),
+        })
+    }
+
+    fn pattern(
+        &mut self,
+        pattern: Pattern,
+    ) -> anyhow::Result<bytecode::Pattern> {
+        let pattern = match pattern {
+            Pattern::Wildcard => bytecode::Pattern::Any(None),
+            Pattern::Binding(name) => {
+                bytecode::Pattern::Any(Some(self.slot(&name)))
+            }
+            Pattern::Literal(value) => {
+                bytecode::Pattern::Literal(literal(value)?)
+            }
+            Pattern::Range(range) => match *range {
+                Value::Range { start, end, incl_both_end } => {
+                    bytecode::Pattern::Range {
+                        start: self.root_code(*start),
+                        end: self.root_code(*end),
+                        incl_both_end,
+                    }
+                }
+                range => {
+                    return Err(anyhow::format_err!(
+                        "AST BUG: invalid range pattern {range:?}"
+                    ))
+                }
+            },
+            Pattern::Type { binding, type_name } => bytecode::Pattern::Type {
+                type_name,
+                var: binding.map(|name| self.slot(&name)),
+            },
+            Pattern::Array { head, rest, tail } => bytecode::Pattern::Array {
+                head: self.patterns(head)?,
+                rest: self.rest_pattern(rest)?,
+                tail: self.patterns(tail)?,
+            },
+            Pattern::Struct { fields, rest } => bytecode::Pattern::Struct {
+                fields: fields
+                    .into_iter()
+                    .map(|(key, p)| Ok((key, self.pattern(p)?)))
+                    .collect::<anyhow::Result<_>>()?,
+                rest: self.rest_pattern(rest)?,
+            },
+        };
+        Ok(pattern)
+    }
+
+    fn patterns(
+        &mut self,
+        patterns: Vec<Pattern>,
+    ) -> anyhow::Result<Vec<bytecode::Pattern>> {
+        patterns.into_iter().map(|p| self.pattern(p)).collect()
+    }
+
+    fn rest_pattern(
+        &mut self,
+        rest: Option<Box<Pattern>>,
+    ) -> anyhow::Result<Option<Box<bytecode::Pattern>>> {
+        rest.map(|rest| self.pattern(*rest).map(Box::new)).transpose()
+    }
+
+    fn range_bound(&mut self, bound: Value) -> anyhow::Result<()> {
+        match bound {
+            Value::Variable(name) | Value::VariableRef(name) => {
+                let slot = self.slot(&name);
+                self.emit(Op::Load(slot));
+            }
+            Value::Integer(num) => {
+                self.emit(Op::Const(Primitive::Int(num)));
+            }
+            Value::U8(num) => {
+                self.emit(Op::Const(Primitive::Int(num as i128)));
+            }
+            Value::I8(num) => {
+                self.emit(Op::Const(Primitive::Int(num as i128)));
+            }
+            _ => {
+                return Err(anyhow::format_err!(
+                    "range error: {bound:?} is not an integer"
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn operations(
+        &mut self,
+        mut operations: Vec<Value>,
+        root: bool,
+    ) -> anyhow::Result<bool> {
+        if operations.is_empty() {
+            return Ok(false);
+        }
+        if operations.len() == 1 {
+            return self.expr(operations.remove(0), root);
+        }
+
+        // handle implicit multiply and pow2,pow3. e.g 2x²
+        while let Some(pos) = operations.iter().position(|o| {
+            matches!(
+                o,
+                Value::ImplicitMultiply(_)
+                    | Value::Operation(Operator::Pow2)
+                    | Value::Operation(Operator::Pow3)
+            )
+        }) {
+            let operation = operations.remove(pos);
+            match operation {
+                Value::ImplicitMultiply(v)
+                    if matches!(
+                        *v,
+                        Value::Integer(_)
+                            | Value::Decimal(_)
+                            | Value::U8(_)
+                            | Value::I8(_)
+                    ) =>
+                {
+                    operations.insert(pos, *v);
+                    operations
+                        .insert(pos + 1, Value::Operation(Operator::Mult));
+                }
+                Value::Operation(Operator::Pow2) => {
+                    operations.insert(pos, Value::Operation(Operator::Pow));
+                    operations.insert(pos + 1, Value::U8(2));
+                }
+                Value::Operation(Operator::Pow3) => {
+                    operations.insert(pos, Value::Operation(Operator::Pow));
+                    operations.insert(pos + 1, Value::U8(3));
+                }
+                _ => {
+                    return Err(anyhow::format_err!(
+                    "AST ERROR: unreachable implicit parameter {operation:?}"
+                ))
+                }
+            }
+        }
+
+        let Some(op_pos) = next_operator_position(&operations) else {
+            return Err(anyhow::Error::msg(
+                "AST ERROR: invalid expression! None",
+            ));
+        };
+        let mut left: Vec<Value> = operations.drain(0..op_pos).collect();
+        let Value::Operation(operator) = operations.remove(0) else {
+            unreachable!("COMPILER BUG: not an operator")
+        };
+
+        // handle negation
+        if operator == Operator::Subtr
+            && matches!(
+                left.last(),
+                Some(Value::Operation(
+                    Operator::Subtr
+                        | Operator::Mult
+                        | Operator::Pow
+                        | Operator::BitwiseNot
+                        | Operator::BitwiseAnd
+                        | Operator::BitwiseLShift
+                        | Operator::BitwiseRShift
+                        | Operator::BitwiseOr
+                        | Operator::BitwiseXor
+                        | Operator::Add
+                        | Operator::Mod
+                        | Operator::Div
+                ))
+            )
+        {
+            let negated = match operations.first() {
+                Some(Value::Decimal(d)) => Some(Value::Decimal(-d)),
+                Some(Value::Integer(d)) => Some(Value::Integer(-d)),
+                Some(Value::U8(d)) => (*d as i8)
+                    .checked_neg()
+                    .map(Value::I8)
+                    .or_else(|| Some(Value::Integer(-(*d as i128)))),
+                Some(Value::I8(d)) => d
+                    .checked_neg()
+                    .map(Value::I8)
+                    .or_else(|| Some(Value::Integer(-(*d as i128)))),
+                Some(Value::Variable(d)) => {
+                    Some(Value::VariableNegate(d.to_string()))
+                }
+                _ => None,
+            };
+            if let Some(negated) = negated {
+                // override one of the negate operator by the negated value
+                operations[0] = negated;
+                left.append(&mut operations);
+                return self.operations(left, root);
+            }
+        }
+
+        // `??` only evaluates its right operand when needed
+        if operator == Operator::NullCoalesce && !left.is_empty() {
+            let left = self.root_code(Value::BlockParen(left));
+            let right = self.root_code(Value::BlockParen(operations));
+            self.emit(Op::Coalesce(Box::new((left, right))));
+            return Ok(true);
+        }
+
+        let start = self.here();
+        let mut children = 0;
+        if self.expr(Value::BlockParen(left), false)? {
+            children += 1;
+        }
+        let right = if operations.len() == 1 {
+            operations.remove(0)
+        } else {
+            Value::BlockParen(operations)
+        };
+        if self.expr(right, false)? {
+            children += 1;
+        }
+        self.operator(operator, start, children);
+        Ok(true)
+    }
+
+    /// emit an operator applied to the `children` values compiled since
+    /// `start`
+    fn operator(&mut self, operator: Operator, start: usize, children: usize) {
+        let fail = |c: &mut Self, msg: &str| {
+            c.truncate(start);
+            c.emit(Op::Fail(msg.to_string()));
+        };
+        match (operator, children) {
+            (Operator::Pow2, _) => {
+                fail(self, "BUG: unreacheable pow2 in compute!")
+            }
+            (Operator::Pow3, _) => {
+                fail(self, "BUG: unreacheable pow3 in compute!")
+            }
+            (Operator::Not, 1) | (Operator::BitwiseNot, 1) => {
+                self.emit(Op::Unary(operator));
+            }
+            (Operator::Not, _) => {
+                fail(self, "only one value allowed, no '!' possible")
+            }
+            (Operator::BitwiseNot, _) => {
+                fail(self, "only one value allowed, no '~' possible")
+            }
+            (Operator::Subtr, 1) => {
+                self.emit(Op::Unary(Operator::Subtr));
+            }
+            (
+                Operator::Add
+                | Operator::Mult
+                | Operator::Mod
+                | Operator::Pow
+                | Operator::Div,
+                1,
+            ) => {}
+            (_, 1) => fail(self, single_operand_error(operator)),
+            (_, children) => {
+                for _ in children..2 {
+                    self.emit(Op::Const(Primitive::Unit));
+                }
+                self.emit(Op::Binary(operator));
+            }
+        }
+    }
+
+    fn keys(&mut self, keys: &[KeyAccess]) -> Vec<Key> {
+        keys.iter().map(|k| self.key(k)).collect()
+    }
+
+    fn key(&mut self, key: &KeyAccess) -> Key {
+        match key {
+            k @ KeyAccess::Index(_) | k @ KeyAccess::Key(_) => {
+                Key::Const(k.clone())
+            }
+            KeyAccess::Variable(Value::Range { start, end, incl_both_end }) => {
+                Key::Slice {
+                    start: self.root_code(*start.clone()),
+                    end: self.root_code(*end.clone()),
+                    incl_both_end: *incl_both_end,
+                }
+            }
+            KeyAccess::Variable(v) => Key::Variable(self.root_code(v.clone())),
+            KeyAccess::FunctionCall { key, parameters } => Key::Call {
+                key: Box::new(self.key(key)),
+                args: self.args(parameters.clone()),
+                site: self.span.clone(),
+            },
+            KeyAccess::Optional(key) => Key::Optional(Box::new(self.key(key))),
+        }
+    }
+
+    fn assign_at(
+        &mut self,
+        root: Value,
+        next_keys: Vec<KeyAccess>,
+    ) -> AssignAt {
+        let root = match root {
+            Value::Variable(name) | Value::VariableRef(name) => {
+                Some(self.slot(&name))
+            }
+            _ => None,
+        };
+        let keys = self.keys(&next_keys);
+        AssignAt { root, keys, next_keys }
+    }
+
+    fn struct_fields(
+        &mut self,
+        struc: IndexMap<String, Value>,
+    ) -> Box<[(String, Code)]> {
+        struc
+            .into_iter()
+            .filter(|(k, _)| !k.starts_with('_'))
+            .map(|(k, v)| (k, self.block_code(v)))
+            .collect()
+    }
+
+    fn args(&mut self, parameters: Value) -> Args {
+        match parameters {
+            Value::BlockParen(params) => {
+                let mut args = Vec::with_capacity(params.len());
+                let mut named = vec![];
+                for p in params {
+                    match p {
+                        Value::VariableExpr { name, expr } => match *name {
+                            Value::Variable(name) => {
+                                named.push((name, self.root_code(*expr)))
+                            }
+                            name => args.push((
+                                self.root_code(Value::VariableExpr {
+                                    name: Box::new(name),
+                                    expr,
+                                }),
+                                false,
+                            )),
+                        },
+                        p => {
+                            let is_variable = matches!(p, Value::Variable(_));
+                            args.push((self.root_code(p), is_variable));
+                        }
+                    }
+                }
+                Args::List(args, named)
+            }
+            parameters => Args::Invalid(parameters),
+        }
+    }
+    // endregion: expressions
+}
+
+fn unspan(value: Value) -> (Option<Span>, Value) {
+    match value {
+        Value::Spanned { span, value } => (Some(span), *value),
+        value => (None, value),
+    }
+}
+
+/// the fields of a struct literal, possibly in parens, or the value itself
+fn struct_literal(value: Value) -> Result<IndexMap<String, Value>, Value> {
+    match value {
+        Value::Struct(struc) => Ok(struc),
+        Value::Spanned { span, value } => struct_literal(*value)
+            .map_err(|value| Value::Spanned { span, value: Box::new(value) }),
+        Value::BlockParen(mut values) if values.len() == 1 => {
+            struct_literal(values.remove(0))
+                .map_err(|value| Value::BlockParen(vec![value]))
+        }
+        value => Err(value),
+    }
+}
+
+/// types are only used by `adana check`
+fn untyped(value: Value) -> Value {
+    match value {
+        Value::Typed { value, .. } => *value,
+        Value::VariableExpr { name, expr } => {
+            Value::VariableExpr { name: Box::new(untyped(*name)), expr }
+        }
+        value => value,
+    }
+}
+
+fn function(parameters: Value, exprs: Vec<Value>) -> Primitive {
+    let Value::BlockParen(parameters) = parameters else {
+        return Primitive::Error(
+            format!("not a valid function: {parameters:?}, {exprs:?}").into(),
+        );
+    };
+    let parameters: Vec<_> = parameters.into_iter().map(untyped).collect();
+    let valid = parameters.iter().enumerate().all(|(idx, v)| match v {
+        Value::Variable(_) | Value::VariableUnused => true,
+        Value::VariableExpr { name, .. } => {
+            matches!(name.as_ref(), Value::Variable(_))
+        }
+        // the rest parameter comes last
+        Value::Rest(_) => idx == parameters.len() - 1,
+        _ => false,
+    });
+    if !valid {
+        return Primitive::Error(
+            format!("not a valid parameter: {parameters:?}").into(),
+        );
+    }
+    Primitive::Function {
+        parameters,
+        exprs,
+        captured: Captured::default(),
+        body: CompiledBody::default(),
+    }
+}
+
+fn literal(value: Value) -> anyhow::Result<Primitive> {
+    match value {
+        Value::U8(num) => Ok(Primitive::U8(num)),
+        Value::I8(num) => Ok(Primitive::I8(num)),
+        Value::Integer(num) => Ok(Primitive::Int(num)),
+        Value::Decimal(num) => Ok(Primitive::Double(num)),
+        Value::Bool(b) => Ok(Primitive::Bool(b)),
+        Value::String(s) => Ok(Primitive::String(s)),
+        Value::Null => Ok(Primitive::Null),
+        v => Err(anyhow::format_err!("not a literal pattern: {v:?}")),
+    }
+}
+
+/// variables used by an expression, and whether they are used as a reference
+fn used_variables(value: &Value, variables: &mut BTreeMap<String, bool>) {
+    let mut used = |name: &str, by_ref: bool| {
+        *variables.entry(name.to_string()).or_default() |= by_ref;
+    };
+    match value {
+        Value::Variable(name) | Value::VariableNegate(name) => {
+            used(name, false)
+        }
+        Value::VariableRef(name) => used(name, true),
+        Value::Pattern(pattern) => used_in_pattern(pattern, variables),
+        Value::ForeachExpr { var, index_var, iterator, exprs } => {
+            used(var, false);
+            if let Some(index_var) = index_var {
+                used(index_var, false);
+            }
+            used_variables(iterator, variables);
+            exprs.iter().for_each(|v| used_variables(v, variables));
+        }
+        Value::Function { parameters, exprs } => {
+            let mut inner = BTreeMap::new();
+            exprs.iter().for_each(|v| used_variables(v, &mut inner));
+            if let Value::BlockParen(parameters) = parameters.as_ref() {
+                used_in_parameters(parameters, &mut inner);
+            }
+            for (name, by_ref) in inner {
+                *variables.entry(name).or_default() |= by_ref;
+            }
+        }
+        Value::Spanned { value, .. }
+        | Value::Labeled { value, .. }
+        | Value::Drop(value)
+        | Value::Throw(value)
+        | Value::Yield(value)
+        | Value::ImplicitMultiply(value)
+        | Value::BuiltInFunction { expr: value, .. } => {
+            used_variables(value, variables)
+        }
+        Value::UnitSuffix { number, unit } => {
+            used_variables(number, variables);
+            variables.entry(unit.symbol().to_string()).or_default();
+        }
+        Value::BreakWith { value, .. } => {
+            if let Some(value) = value {
+                used_variables(value, variables)
+            }
+        }
+        Value::EarlyReturn(value) => {
+            if let Some(value) = value.as_ref() {
+                used_variables(value, variables)
+            }
+        }
+        Value::Expression(values)
+        | Value::BlockParen(values)
+        | Value::Array(values)
+        | Value::Set(values) => {
+            values.iter().for_each(|v| used_variables(v, variables))
+        }
+        Value::FunctionCall { parameters, function } => {
+            used_in_args(parameters, variables);
+            used_variables(function, variables);
+        }
+        Value::Range { start, end, .. } => {
+            used_variables(start, variables);
+            used_variables(end, variables);
+        }
+        Value::FString(_, parameters) => {
+            parameters.iter().for_each(|(_, v)| used_variables(v, variables))
+        }
+        Value::VariableExpr { name, expr } => {
+            used_variables(name, variables);
+            used_variables(expr, variables);
+        }
+        Value::Declaration { name, expr, .. } => {
+            used(name, false);
+            used_variables(expr, variables);
+        }
+        Value::Typed { value, .. } => used_variables(value, variables),
+        Value::Import { alias, .. } => used(alias, false),
+        Value::Export(value) => used_variables(value, variables),
+        Value::IfExpr { cond, exprs, else_expr } => {
+            used_variables(cond, variables);
+            exprs
+                .iter()
+                .chain(else_expr.iter().flatten())
+                .for_each(|v| used_variables(v, variables));
+        }
+        Value::WhileExpr { cond, exprs } => {
+            used_variables(cond, variables);
+            exprs.iter().for_each(|v| used_variables(v, variables));
+        }
+        Value::TryExpr { exprs, catch_var, catch_expr, finally_expr } => {
+            if let Some(catch_var) = catch_var {
+                used(catch_var, false);
+            }
+            exprs
+                .iter()
+                .chain(catch_expr.iter().flatten())
+                .chain(finally_expr.iter().flatten())
+                .for_each(|v| used_variables(v, variables));
+        }
+        Value::MatchExpr { value, arms } => {
+            used_variables(value, variables);
+            for MatchArm { pattern, guard, exprs } in arms {
+                used_in_pattern(pattern, variables);
+                guard
+                    .iter()
+                    .chain(exprs)
+                    .for_each(|v| used_variables(v, variables));
+            }
+        }
+        Value::Struct(struc) => {
+            struc.values().for_each(|v| used_variables(v, variables))
+        }
+        Value::Dict(dict) => dict.iter().for_each(|(k, v)| {
+            used_variables(k, variables);
+            used_variables(v, variables);
+        }),
+        Value::MultiDepthAccess { root, next_keys } => {
+            used_variables(root, variables);
+            next_keys.iter().for_each(|k| used_in_key(k, variables));
+        }
+        Value::Break
+        | Value::Continue(_)
+        | Value::Primitive(_)
+        | Value::Operation(_)
+        | Value::Null
+        | Value::Decimal(_)
+        | Value::U8(_)
+        | Value::I8(_)
+        | Value::Integer(_)
+        | Value::Bool(_)
+        | Value::NoOp
+        | Value::String(_)
+        | Value::VariableUnused
+        | Value::Rest(_)
+        | Value::Const(_) => {}
+    }
+}
+
+/// parameters are not used from the outer scope, but their default values
+/// are evaluated when the function is called
+fn used_in_parameters(
+    parameters: &[Value],
+    variables: &mut BTreeMap<String, bool>,
+) {
+    for parameter in parameters {
+        if let Value::VariableExpr { expr, .. } = parameter {
+            used_variables(expr, variables);
+        }
+    }
+    fn remove(parameter: &Value, variables: &mut BTreeMap<String, bool>) {
+        match parameter {
+            Value::Variable(name) | Value::Rest(name) => {
+                variables.remove(name);
+            }
+            Value::VariableExpr { name: value, .. }
+            | Value::Typed { value, .. } => remove(value, variables),
+            _ => {}
+        }
+    }
+    for parameter in parameters {
+        remove(parameter, variables);
+    }
+}
+
+/// the name of a named argument, `f(b = 2)`, is not a variable
+fn used_in_args(parameters: &Value, variables: &mut BTreeMap<String, bool>) {
+    match parameters {
+        Value::BlockParen(args) => {
+            for arg in args {
+                match arg {
+                    Value::VariableExpr { expr, .. } => {
+                        used_variables(expr, variables)
+                    }
+                    arg => used_variables(arg, variables),
+                }
+            }
+        }
+        parameters => used_variables(parameters, variables),
+    }
+}
+
+fn used_in_key(key: &KeyAccess, variables: &mut BTreeMap<String, bool>) {
+    match key {
+        KeyAccess::Variable(value) => used_variables(value, variables),
+        KeyAccess::FunctionCall { key, parameters } => {
+            used_in_key(key, variables);
+            used_in_args(parameters, variables);
+        }
+        KeyAccess::Optional(key) => used_in_key(key, variables),
+        KeyAccess::Index(_) | KeyAccess::Key(_) => {}
+    }
+}
+
+fn used_in_pattern(pattern: &Pattern, variables: &mut BTreeMap<String, bool>) {
+    match pattern {
+        Pattern::Binding(name) | Pattern::Type { binding: Some(name), .. } => {
+            variables.entry(name.clone()).or_default();
+        }
+        Pattern::Range(range) => used_variables(range, variables),
+        Pattern::Array { head, rest, tail } => head
+            .iter()
+            .chain(rest.as_deref())
+            .chain(tail)
+            .for_each(|p| used_in_pattern(p, variables)),
+        Pattern::Struct { fields, rest } => fields
+            .iter()
+            .map(|(_, p)| p)
+            .chain(rest.as_deref())
+            .for_each(|p| used_in_pattern(p, variables)),
+        Pattern::Wildcard
+        | Pattern::Literal(_)
+        | Pattern::Type { binding: None, .. } => {}
+    }
+}
+
+/// position of the operator with the lowest precedence
+pub(crate) fn next_operator_position(operations: &[Value]) -> Option<usize> {
+    const PRECEDENCE: [Operator; 24] = [
+        Operator::Convert,
+        Operator::NullCoalesce,
+        Operator::Or,
+        Operator::And,
+        Operator::BitwiseOr,
+        Operator::BitwiseXor,
+        Operator::BitwiseAnd,
+        Operator::GreaterOrEqual,
+        Operator::LessOrEqual,
+        Operator::Greater,
+        Operator::Less,
+        Operator::Equal,
+        Operator::NotEqual,
+        Operator::Is,
+        Operator::BitwiseLShift,
+        Operator::BitwiseRShift,
+        Operator::Add,
+        Operator::Subtr,
+        Operator::Mult,
+        Operator::Mod,
+        Operator::Div,
+        Operator::Pow,
+        Operator::BitwiseNot,
+        Operator::Not,
+    ];
+    PRECEDENCE.iter().find_map(|op| {
+        operations
+            .iter()
+            .rposition(|c| matches!(c, Value::Operation(o) if o == op))
+    })
+}
+
+fn single_operand_error(operator: Operator) -> &'static str {
+    match operator {
+        Operator::And => "only one value, no '&&' comparison possible",
+        Operator::Or => "only one value, no '||' comparison possible",
+        Operator::NullCoalesce => "only one value, no '??' possible",
+        Operator::BitwiseAnd => "only one value, no 'AND' comparison possible",
+        Operator::BitwiseOr => "only one value, no '|' comparison possible",
+        Operator::BitwiseXor => "only one value, no 'XOR' comparison possible",
+        Operator::BitwiseLShift => "only one value for '<<' ",
+        Operator::BitwiseRShift => "only one value, for '>>'",
+        Operator::Equal => "only one value, no '==' comparison possible",
+        Operator::NotEqual => "only one value, no '!=' comparison possible",
+        Operator::Is => "only one value, no 'is' comparison possible",
+        Operator::Less => "only one value, no '<' comparison possible",
+        Operator::Greater => "only one value, no '>' comparison possible",
+        Operator::GreaterOrEqual => {
+            "only one value, no '>=' comparison possible"
+        }
+        Operator::LessOrEqual => "only one value, no '<=' comparison possible",
+        _ => unreachable!("COMPILER BUG: {operator:?} takes one operand"),
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Display;
+
+use adana_script_core::{
+    primitive::{Primitive, ScriptError, StackFrame},
+    Source, Span,
+};
+
+/// An error tied to a location in a script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub file: Option<String>,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, counted in chars
+    pub column: usize,
+    pub snippet: String,
+    /// number of chars to underline, starting at `column`
+    pub underline: usize,
+    /// the payload, stack trace and causes of a runtime error, printed
+    /// after the snippet
+    pub details: String,
+}
+
+impl Diagnostic {
+    pub fn new(
+        message: impl Into<String>,
+        source: &Source,
+        start: usize,
+        end: usize,
+    ) -> Diagnostic {
+        let text = source.text.as_str();
+        let start = floor_char_boundary(text, start.min(text.len()));
+        let end = floor_char_boundary(text, end.clamp(start, text.len()));
+
+        let line_start = text[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end =
+            text[start..].find('\n').map(|i| i + start).unwrap_or(text.len());
+        let snippet = text[line_start..line_end].trim_end_matches('\r');
+
+        let line = text[..start].matches('\n').count() + 1;
+        let column = text[line_start..start].chars().count() + 1;
+        let underline =
+            text[start..end.min(line_end)].trim_end().chars().count().max(1);
+
+        Diagnostic {
+            message: message.into(),
+            file: source.name.clone(),
+            line,
+            column,
+            snippet: snippet.to_string(),
+            underline,
+            details: String::new(),
+        }
+    }
+
+    pub fn from_span(message: impl Into<String>, span: &Span) -> Option<Self> {
+        let source = span.source.as_ref()?;
+        Some(Diagnostic::new(message, source, span.start, span.end))
+    }
+
+    /// the location of an error returned as a value, e.g. by a function
+    /// that failed, if it knows where it was raised
+    pub fn from_error(error: &ScriptError) -> Option<Self> {
+        let mut diagnostic =
+            Diagnostic::from_span(error.to_string(), error.span.as_ref()?)?;
+        diagnostic.details = error.details();
+        Some(diagnostic)
+    }
+}
+
+/// where a span starts, as `file:line:column` or `line:column`
+pub(crate) fn location(span: &Span) -> Option<String> {
+    let Diagnostic { file, line, column, .. } =
+        Diagnostic::from_span("", span)?;
+    match file {
+        Some(file) => Some(format!("{file}:{line}:{column}")),
+        None => Some(format!("{line}:{column}")),
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Diagnostic {
+            message,
+            file,
+            line,
+            column,
+            snippet,
+            underline,
+            details,
+        } = self;
+        let gutter = " ".repeat(line.to_string().len());
+        // keep tabs so the caret stays aligned with the snippet
+        let padding: String = snippet
+            .chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(f, "{message}")?;
+        match file {
+            Some(file) => writeln!(f, "{gutter}--> {file}:{line}:{column}")?,
+            None => writeln!(f, "{gutter}--> {line}:{column}")?,
+        }
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {snippet}")?;
+        write!(f, "{gutter} | {padding}{}", "^".repeat(*underline))?;
+        write!(f, "{details}")
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// An error thrown by a script with `throw`, until it is caught.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Thrown(pub Primitive);
+
+impl Thrown {
+    /// the thrown value, without its stack trace
+    fn message(&self) -> String {
+        match &self.0 {
+            Primitive::Error(e) => e.to_string(),
+            p => p.to_string(),
+        }
+    }
+
+    fn details(&self) -> String {
+        match &self.0 {
+            Primitive::Error(e) => e.details(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl Display for Thrown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Primitive::Error(e) => write!(f, "{}", e.report()),
+            p => write!(f, "{p}"),
+        }
+    }
+}
+
+impl std::error::Error for Thrown {}
+
+/// A generator pausing at a `yield`, unwinding the blocks it runs until the
+/// next value is asked for. It is not an error of the script, so it can't
+/// be caught.
+#[derive(Debug)]
+pub(crate) struct Suspended;
+
+impl Display for Suspended {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "generator suspended")
+    }
+}
+
+impl std::error::Error for Suspended {}
+
+/// attach the location of the instruction that failed, unless the error
+/// already knows where it comes from (e.g. from an included file).
+/// Runtime errors become structured errors, with the call stack at that
+/// point, so they can be caught like thrown ones.
+pub(crate) fn locate(
+    err: anyhow::Error,
+    span: &Span,
+    stack: impl FnOnce() -> Vec<StackFrame>,
+) -> anyhow::Error {
+    if err.downcast_ref::<Diagnostic>().is_some() || err.is::<Suspended>() {
+        return err;
+    }
+    let message = match err.downcast_ref::<Thrown>() {
+        Some(thrown) => thrown.message(),
+        None => format!("{err:#}"),
+    };
+    let Some(mut diagnostic) = Diagnostic::from_span(message, span) else {
+        return err;
+    };
+    // the diagnostic prints the whole error: the thrown value is kept
+    // under it only so it can still be caught
+    let thrown = match err.downcast::<Thrown>() {
+        // an error raised by a builtin, which doesn't know the call stack
+        Ok(Thrown(Primitive::Error(mut e))) if e.stack.is_empty() => {
+            e.stack = stack();
+            e.span = Some(span.clone());
+            Thrown(Primitive::Error(e))
+        }
+        Ok(thrown) => thrown,
+        Err(_) => Thrown(Primitive::Error(Box::new(ScriptError {
+            stack: stack(),
+            span: Some(span.clone()),
+            ..ScriptError::new(diagnostic.message.clone())
+        }))),
+    };
+    diagnostic.details = thrown.details();
+    anyhow::Error::new(thrown).context(diagnostic)
+}
+
+/// the error caught by a `catch` block
+pub(crate) fn caught(err: anyhow::Error) -> Primitive {
+    if let Some(Thrown(p)) = err.downcast_ref::<Thrown>() {
+        return p.clone();
+    }
+    match err.downcast::<Diagnostic>() {
+        Ok(diagnostic) => Primitive::Error(diagnostic.message.into()),
+        Err(err) => Primitive::Error(format!("{err:#}").into()),
+    }
+}
+
+fn floor_char_boundary(text: &str, mut idx: usize) -> usize {
+    while !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
@@ -1,17 +1,18 @@
-mod ast;
+mod bytecode;
+mod checker;
+mod compiler;
 mod compute;
+mod diagnostic;
 mod parser;
 mod prelude;
 mod require_dynamic_lib;
 mod string_parser;
-use std::collections::BTreeMap;
+mod vm;
+pub use checker::check;
+pub use compute::{compute, compute_named};
+pub use diagnostic::Diagnostic;
 
-use adana_script_core::TreeNodeValue;
-pub use compute::compute;
-
-use slab_tree::Tree;
-
-use crate::ast::to_ast;
+use crate::compiler::compile;
 use crate::parser::parse_instructions;
 
 pub fn print_ast(script: &str) -> anyhow::Result<()> {
@@ -28,18 +29,12 @@ pub fn print_ast(script: &str) -> anyhow::Result<()> {
         )
     );
 
-    let mut dummy_ctx = BTreeMap::new();
     for instruction in instructions {
-        let mut tree: Tree<TreeNodeValue> = Tree::new();
-
         println!("==================INSTRUCTION================");
         println!("{instruction:#?}");
-        to_ast(&mut dummy_ctx, instruction, &mut tree, &None)?;
-
-        let mut tree_fmt = String::new();
-        tree.write_formatted(&mut tree_fmt)?;
-        println!("===================AST TREE==================");
-        print!("{tree_fmt}");
+        let program = compile(vec![instruction]);
+        println!("==================BYTECODE===================");
+        println!("{program:#?}");
     }
     Ok(())
 }
@@ -1,5 +1,7 @@
 use nom::{
-    combinator::recognize,
+    bytes::complete::{take_till1, take_while},
+    character::complete::{multispace1, satisfy, space0, space1},
+    combinator::{consumed, map_opt, not, recognize, value},
     error::{Error, ErrorKind},
 };
 
@@ -13,12 +15,97 @@ use crate::prelude::{
 use super::string_parser::parse_escaped_string;
 use adana_script_core::{
     constants::{
-        BREAK, DROP, ELSE, FOR, IF, IN, MULTILINE, NULL, RETURN, STRUCT, WHILE,
+        AS, BREAK, CATCH, CONST, CONTINUE, DICT, DROP, ELSE, ENUM, EXPORT,
+        FINALLY, FOR, IF, IMPORT, IN, LET, MATCH, MULTILINE, NULL, RETURN, SET,
+        STRUCT, THROW, TRY, WHILE, YIELD,
     },
-    primitive::Primitive,
-    KeyAccess, FORBIDDEN_VARIABLE_NAME,
+    primitive::{
+        BigInt, Decimal, EnumType, Primitive, Rational, Unit, TYPE_ARRAY,
+        TYPE_BOOL, TYPE_DECIMAL, TYPE_DICT, TYPE_DOUBLE, TYPE_ERROR,
+        TYPE_FUNCTION, TYPE_I8, TYPE_INT, TYPE_ITERATOR, TYPE_NULL,
+        TYPE_QUANTITY, TYPE_RATIONAL, TYPE_SET, TYPE_STRING, TYPE_STRUCT,
+        TYPE_U8, TYPE_UNIT,
+    },
+    KeyAccess, MatchArm, Pattern, Source, Span, FORBIDDEN_VARIABLE_NAME,
 };
 use adana_script_core::{BuiltInFunctionType, MathConstants, Operator, Value};
+use std::{cell::RefCell, sync::Arc};
+
+// region: source tracking
+// nom only hands us sub slices of the script, so the location of an
+// instruction is computed from its distance to the start of the text being
+// parsed. The text is registered here for the duration of the parsing.
+struct CurrentSource {
+    base: usize,
+    len: usize,
+    source: Option<Arc<Source>>,
+}
+
+thread_local! {
+    static CURRENT_SOURCE: RefCell<Option<CurrentSource>> =
+        const { RefCell::new(None) };
+}
+
+struct SourceGuard(Option<CurrentSource>);
+
+impl SourceGuard {
+    fn enter(text: &str, source: Option<Arc<Source>>) -> SourceGuard {
+        let current = CurrentSource {
+            base: text.as_ptr() as usize,
+            len: text.len(),
+            source,
+        };
+        SourceGuard(CURRENT_SOURCE.with_borrow_mut(|c| c.replace(current)))
+    }
+}
+
+impl Drop for SourceGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_SOURCE.with_borrow_mut(|c| *c = previous);
+    }
+}
+
+/// offset of `part` in the source being parsed, if it comes from it
+pub(super) fn source_offset(part: &str) -> Option<usize> {
+    CURRENT_SOURCE.with_borrow(|c| {
+        let c = c.as_ref()?;
+        let ptr = part.as_ptr() as usize;
+        (ptr >= c.base && ptr + part.len() <= c.base + c.len)
+            .then(|| ptr - c.base)
+    })
+}
+
+fn spanned<'a, F>(mut parser: F) -> impl FnMut(&'a str) -> Res<'a, Value>
+where
+    F: FnMut(&'a str) -> Res<'a, Value>,
+{
+    move |s| {
+        let (rest, value) = parser(s)?;
+        let Some(offset) = source_offset(s) else {
+            return Ok((rest, value));
+        };
+        let consumed = &s[..s.len() - rest.len()];
+        let start = offset + consumed.len() - consumed.trim_start().len();
+        let end = offset + consumed.trim_end().len();
+        let source = CURRENT_SOURCE
+            .with_borrow(|c| c.as_ref().and_then(|c| c.source.clone()));
+        Ok((
+            rest,
+            Value::Spanned {
+                span: Span { start, end, source },
+                value: Box::new(value),
+            },
+        ))
+    }
+}
+
+/// parse a whole script, tying the spans of its instructions to `source`
+pub(super) fn parse_source(source: &Arc<Source>) -> Res<Vec<Value>> {
+    let _guard = SourceGuard::enter(&source.text, Some(source.clone()));
+    parse_instructions(&source.text)
+}
+// endregion: source tracking
 
 pub(super) fn comments(s: &str) -> Res<Vec<&str>> {
     terminated(
@@ -87,38 +174,119 @@ fn parse_i128_hex(s: &str) -> Res<Value> {
     })?;
     Ok(("", Value::Integer(r)))
 }
+/// an integer literal too large for an `i128`
+fn parse_big_integer(s: &str) -> Res<Value> {
+    let r = s.parse::<BigInt>().map_err(|_e| {
+        nom::Err::Error(nom::error::make_error::<&str, Error<&str>>(
+            "could not parse big integer",
+            ErrorKind::Digit,
+        ))
+    })?;
+    Ok(("", Value::Primitive(Primitive::BigInt(r))))
+}
+/// `0.1d` is an exact decimal, `3r` a rational so that `1/3r` is a third
+fn parse_exact_number(s: &str) -> Res<Value> {
+    let (rest, (number, suffix)) = pair(
+        recognize_float,
+        terminated(
+            one_of("dr"),
+            not(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+        ),
+    )(s)?;
+    let d = number.parse::<Decimal>().map_err(|_e| {
+        nom::Err::Error(nom::error::make_error::<&str, Error<&str>>(
+            "could not parse decimal",
+            ErrorKind::Float,
+        ))
+    })?;
+    let p = match suffix {
+        'd' => Primitive::Decimal(d),
+        _ => Primitive::Rational(Rational::from(&d)),
+    };
+    Ok((rest, Value::Primitive(p)))
+}
+/// a unit symbol, e.g. `kb` or `°C`
+fn parse_unit(s: &str) -> Res<Unit> {
+    map_opt(
+        take_while1(|c: char| c.is_alphanumeric() || c == '°'),
+        Unit::from_symbol,
+    )(s)
+}
+/// `5kb`, `90min` or `20 °C`. Whether there is a space or not, the suffix
+/// may be a variable multiplied implicitly, which is only known at runtime.
+fn parse_quantity(s: &str) -> Res<Value> {
+    map(pair(terminated(parse_number, space0), parse_unit), |(number, unit)| {
+        Value::UnitSuffix { number: Box::new(number), unit }
+    })(s)
+}
+/// `to h` in `90min to h`, or `in cm` in `3 ft in cm`
+fn parse_conversion(s: &str) -> Res<Unit> {
+    // the space before is already taken by the value converted
+    preceded(delimited(space0, alt((tag("to"), tag("in"))), space1), parse_unit)(
+        s,
+    )
+}
+/// values optionally followed by a conversion, e.g. `5kb + 3mb to kb`
+fn converted_values<'a, F>(
+    value: F,
+) -> impl FnMut(&'a str) -> Res<'a, Vec<Value>>
+where
+    F: FnMut(&'a str) -> Res<'a, Value>,
+{
+    map(
+        pair(
+            many1(preceded(not(parse_conversion), value)),
+            opt(parse_conversion),
+        ),
+        |(values, unit)| match unit {
+            Some(unit) => vec![
+                Value::BlockParen(values),
+                Value::Operation(Operator::Convert),
+                Value::String(unit.symbol().to_string()),
+            ],
+            None => values,
+        },
+    )
+}
 fn parse_number(s: &str) -> Res<Value> {
     alt((
         map_parser(recognize_binary, parse_u8_binary),
         map_parser(recognize_binary, parse_i128_binary),
         map_parser(recognize_hexadecimal, parse_u8_hex),
         map_parser(recognize_hexadecimal, parse_i128_hex),
+        parse_exact_number,
         map_parser(
             recognize_float,
             alt((
                 map(all_consuming(U8), Value::U8),
                 map(all_consuming(I8), Value::I8),
                 map(all_consuming(I128), Value::Integer),
+                parse_big_integer,
                 map(all_consuming(double), Value::Decimal),
             )),
         ),
     ))(s)
 }
 
+fn parse_range_start(s: &str) -> Res<Value> {
+    preceded(
+        multispace0,
+        map_parser(
+            take_until(".."),
+            all_consuming(alt((parse_variable, parse_number))),
+        ),
+    )(s)
+}
+
+fn parse_range_end(s: &str) -> Res<Value> {
+    alt((parse_variable, parse_number))(s)
+}
+
 fn parse_range(s: &str) -> Res<Value> {
     map(
         pair(
-            preceded(
-                multispace0,
-                terminated(
-                    map_parser(
-                        take_until(".."),
-                        all_consuming(alt((parse_variable, parse_number))),
-                    ),
-                    tag(".."),
-                ),
-            ),
-            pair(opt(tag("=")), alt((parse_variable, parse_number))),
+            terminated(parse_range_start, tag("..")),
+            pair(opt(tag("=")), parse_range_end),
         ),
         |(start, (incl_both_end, end))| Value::Range {
             start: Box::new(start),
@@ -228,11 +396,11 @@ fn parse_constant(s: &str) -> Res<Value> {
 }
 
 fn parse_block_paren(s: &str) -> Res<Value> {
-    let parser = |p| many1(parse_value)(p);
+    let parser = |p| converted_values(parse_value)(p);
     parse_paren(parser, false)(s)
 }
 fn parse_block_paren_opt(s: &str) -> Res<Value> {
-    let parser = |p| many1(parse_value)(p);
+    let parser = |p| converted_values(parse_value)(p);
     parse_paren(parser, true)(s)
 }
 
@@ -259,12 +427,29 @@ where
 }
 
 fn parse_fn(s: &str) -> Res<Value> {
-    let parser = |p| separated_list0(tag_no_space(","), parse_value)(p);
+    let parser = |p| {
+        separated_list0(
+            tag_no_space(","),
+            alt((
+                map(parse_destructure_pattern, |(source, pattern)| {
+                    (source, Value::Pattern(Box::new(pattern)))
+                }),
+                consumed(alt((
+                    parse_typed_param,
+                    parse_rest,
+                    parse_named_arg,
+                    parse_value,
+                ))),
+            )),
+        )(p)
+    };
     let parse_expr = |p| {
         preceded(
             tag_no_space("{"),
             terminated(
-                map(map(many1(parse_value), Value::BlockParen), |v| vec![v]),
+                map(converted_values(parse_value), |v| {
+                    vec![Value::BlockParen(v)]
+                }),
                 tag_no_space("}"),
             ),
         )(p)
@@ -283,7 +468,13 @@ fn parse_fn(s: &str) -> Res<Value> {
     };
     map(
         separated_pair(
-            map_parser(take_until("=>"), parse_paren(parser, false)),
+            map_parser(
+                take_until("=>"),
+                pair(
+                    delimited(tag_no_space("("), parser, tag_no_space(")")),
+                    terminated(opt(parse_type_annotation), multispace0),
+                ),
+            ),
             tag("=>"),
             alt((
                 parse_expr,
@@ -292,29 +483,110 @@ fn parse_fn(s: &str) -> Res<Value> {
                 map_parser(take_until("\n"), parse_expr),
             )),
         ),
-        |(parameters, exprs)| Value::Function {
-            parameters: Box::new(parameters),
-            exprs,
+        |((parameters, return_type), mut exprs)| {
+            let mut destructured = vec![];
+            let parameters = parameters
+                .into_iter()
+                .map(|(source, parameter)| match parameter {
+                    Value::Pattern(pattern) => {
+                        let (variable, assign) = destructure(source, *pattern);
+                        destructured.push(assign);
+                        Value::Variable(variable)
+                    }
+                    parameter => parameter,
+                })
+                .collect();
+            exprs.splice(0..0, destructured);
+            let function = Value::Function {
+                parameters: Box::new(Value::BlockParen(parameters)),
+                exprs,
+            };
+            match return_type {
+                Some(type_name) => Value::Typed {
+                    value: Box::new(function),
+                    type_name: type_name.to_string(),
+                },
+                None => function,
+            }
         },
     )(s)
 }
 
+fn parse_fn_arg(s: &str) -> Res<Value> {
+    alt((
+        parse_fn,
+        map(converted_values(preceded(multispace0, parse_value)), |mut v| {
+            if v.len() == 1 {
+                v.remove(0)
+            } else {
+                Value::Expression(v)
+            }
+        }),
+    ))(s)
+}
+
+/// `b = 2`, a default value in a function definition or a named argument
+/// in a function call
+fn parse_named_arg(s: &str) -> Res<Value> {
+    map(
+        separated_pair(
+            parse_variable_str,
+            terminated(tag_no_space("="), not(tag("="))),
+            parse_fn_arg,
+        ),
+        |(name, value)| Value::VariableExpr {
+            name: Box::new(Value::Variable(name.to_string())),
+            expr: Box::new(value),
+        },
+    )(s)
+}
+
+/// `a: int`, `a: int = 1` or `...args: array`
+fn parse_typed_param(s: &str) -> Res<Value> {
+    map(
+        tuple((
+            alt((parse_rest, parse_variable)),
+            parse_type_annotation,
+            opt(preceded(
+                terminated(tag_no_space("="), not(tag("="))),
+                parse_fn_arg,
+            )),
+        )),
+        |(parameter, type_name, default)| {
+            let typed = Value::Typed {
+                value: Box::new(parameter),
+                type_name: type_name.to_string(),
+            };
+            match default {
+                Some(expr) => Value::VariableExpr {
+                    name: Box::new(typed),
+                    expr: Box::new(expr),
+                },
+                None => typed,
+            }
+        },
+    )(s)
+}
+
+/// `: int`, one of the types returned by `type_of`
+fn parse_type_annotation(s: &str) -> Res<&str> {
+    preceded(tag_no_space(":"), parse_pattern_type)(s)
+}
+
+/// `...args`
+fn parse_rest(s: &str) -> Res<Value> {
+    map(preceded(tag_no_space("..."), parse_variable_str), |name| {
+        Value::Rest(name.to_string())
+    })(s)
+}
+
 fn parse_fn_args(s: &str) -> Res<Vec<Value>> {
     preceded(
         tag_no_space("("),
         terminated(
             separated_list0(
                 tag_no_space(","),
-                alt((
-                    parse_fn,
-                    map(many1(preceded(multispace0, parse_value)), |mut v| {
-                        if v.len() == 1 {
-                            v.remove(0)
-                        } else {
-                            Value::Expression(v)
-                        }
-                    }),
-                )),
+                alt((parse_named_arg, parse_fn_arg)),
             ),
             tag_no_space(")"),
         ),
@@ -323,7 +595,14 @@ fn parse_fn_args(s: &str) -> Res<Vec<Value>> {
 fn parse_fn_call(s: &str) -> Res<Value> {
     map(
         pair(
-            alt((parse_fn, parse_variable)),
+            alt((
+                parse_fn,
+                // a variable shadowing a builtin is resolved when compiling
+                verify(parse_variable, |v| {
+                    !matches!(v, Value::Variable(name)
+                        if BuiltInFunctionType::from_name(name).is_some())
+                }),
+            )),
             map(parse_fn_args, Value::BlockParen),
         ),
         |(function, parameters)| Value::FunctionCall {
@@ -338,12 +617,16 @@ fn parse_foreach(s: &str) -> Res<Value> {
         separated_pair(
             pair(
                 opt(terminated(parse_variable_str, tag_no_space(","))),
-                parse_variable_str,
+                alt((
+                    map(parse_destructure_pattern, |(v, p)| (v, Some(p))),
+                    map(parse_variable_str, |v| (v, None)),
+                )),
             ),
             tag_no_space(IN),
             alt((
                 parse_range,
                 parse_multidepth_access,
+                parse_builtin_fn,
                 parse_fn_call,
                 parse_array,
                 parse_fstring,
@@ -363,11 +646,21 @@ fn parse_foreach(s: &str) -> Res<Value> {
                 parse_block(parse_instructions),
             ),
         ),
-        |(((idx, v), arr), exprs)| Value::ForeachExpr {
-            var: v.to_string(),
-            index_var: idx.map(String::from),
-            iterator: Box::new(arr),
-            exprs,
+        |(((idx, (v, pattern)), arr), mut exprs)| {
+            let var = match pattern {
+                Some(pattern) => {
+                    let (variable, assign) = destructure(v, pattern);
+                    exprs.insert(0, assign);
+                    variable
+                }
+                None => v.to_string(),
+            };
+            Value::ForeachExpr {
+                var,
+                index_var: idx.map(String::from),
+                iterator: Box::new(arr),
+                exprs,
+            }
         },
     )(s)
 }
@@ -390,23 +683,30 @@ fn parse_builtin_fn(s: &str) -> Res<Value> {
         fn_type: BuiltInFunctionType,
     ) -> impl Fn(&'a str) -> Res<'a, Value> {
         move |s: &str| {
-            map(
-                preceded(tag_no_space(fn_type.as_str()), parse_block_paren),
-                |expr| Value::BuiltInFunction { fn_type, expr: Box::new(expr) },
-            )(s)
-        }
-    }
-    fn parse_builtin_many_args<'a>(
-        fn_type: BuiltInFunctionType,
-    ) -> impl Fn(&'a str) -> Res<'a, Value> {
-        move |s: &str| {
-            map(
-                preceded(tag_no_space(fn_type.as_str()), parse_fn_args),
-                |expr| Value::BuiltInFunction {
-                    fn_type,
-                    expr: Box::new(Value::Array(expr)),
-                },
+            if fn_type.takes_many_args() {
+                return map(
+                    preceded(tag_no_space(fn_type.as_str()), parse_fn_args),
+                    |expr| Value::BuiltInFunction {
+                        fn_type,
+                        expr: Box::new(Value::Array(expr)),
+                    },
+                )(s);
+            }
+            preceded(
+                tag_no_space(fn_type.as_str()),
+                alt((
+                    parse_block_paren,
+                    // keep calls with the wrong number of arguments, so they
+                    // can be reported instead of silently dropped
+                    map(
+                        verify(parse_fn_args, |args: &[Value]| args.len() != 1),
+                        Value::Array,
+                    ),
+                )),
             )(s)
+            .map(|(rest, expr)| {
+                (rest, Value::BuiltInFunction { fn_type, expr: Box::new(expr) })
+            })
         }
     }
     alt((
@@ -427,6 +727,8 @@ fn parse_builtin_fn(s: &str) -> Res<Value> {
             parse_builtin(BuiltInFunctionType::IsFunction),
             parse_builtin(BuiltInFunctionType::IsBool),
             parse_builtin(BuiltInFunctionType::IsArray),
+            parse_builtin(BuiltInFunctionType::IsDict),
+            parse_builtin(BuiltInFunctionType::IsSet),
         )),
         alt((
             parse_builtin(BuiltInFunctionType::ToHex),
@@ -456,12 +758,74 @@ fn parse_builtin_fn(s: &str) -> Res<Value> {
             parse_builtin(BuiltInFunctionType::ParseJson),
         )),
         alt((
-            parse_builtin_many_args(BuiltInFunctionType::IsMatch),
-            parse_builtin_many_args(BuiltInFunctionType::Match),
-            parse_builtin_many_args(BuiltInFunctionType::Replace),
-            parse_builtin_many_args(BuiltInFunctionType::Round),
-            parse_builtin_many_args(BuiltInFunctionType::ReplaceAll),
-        )), // parse_builtin(BuiltInFunctionType::ReadLines),
+            parse_builtin(BuiltInFunctionType::IsMatch),
+            parse_builtin(BuiltInFunctionType::Match),
+            parse_builtin(BuiltInFunctionType::Replace),
+            parse_builtin(BuiltInFunctionType::Round),
+            parse_builtin(BuiltInFunctionType::ReplaceAll),
+        )),
+        alt((
+            parse_builtin(BuiltInFunctionType::Map),
+            parse_builtin(BuiltInFunctionType::Filter),
+            parse_builtin(BuiltInFunctionType::Reduce),
+            parse_builtin(BuiltInFunctionType::SortBy),
+            parse_builtin(BuiltInFunctionType::Sort),
+            parse_builtin(BuiltInFunctionType::Find),
+            parse_builtin(BuiltInFunctionType::Any),
+            parse_builtin(BuiltInFunctionType::All),
+            parse_builtin(BuiltInFunctionType::Zip),
+            parse_builtin(BuiltInFunctionType::Flatten),
+            parse_builtin(BuiltInFunctionType::Reverse),
+            parse_builtin(BuiltInFunctionType::Unique),
+            parse_builtin(BuiltInFunctionType::GroupBy),
+        )),
+        alt((
+            parse_builtin(BuiltInFunctionType::Split),
+            parse_builtin(BuiltInFunctionType::Join),
+            parse_builtin(BuiltInFunctionType::TrimStart),
+            parse_builtin(BuiltInFunctionType::TrimEnd),
+            parse_builtin(BuiltInFunctionType::Trim),
+            parse_builtin(BuiltInFunctionType::Substring),
+            parse_builtin(BuiltInFunctionType::Contains),
+            parse_builtin(BuiltInFunctionType::StartsWith),
+            parse_builtin(BuiltInFunctionType::EndsWith),
+            parse_builtin(BuiltInFunctionType::IndexOf),
+            parse_builtin(BuiltInFunctionType::PadLeft),
+            parse_builtin(BuiltInFunctionType::PadRight),
+            parse_builtin(BuiltInFunctionType::Repeat),
+            parse_builtin(BuiltInFunctionType::Lines),
+            parse_builtin(BuiltInFunctionType::Chars),
+        )),
+        alt((
+            parse_builtin(BuiltInFunctionType::ToDict),
+            parse_builtin(BuiltInFunctionType::Keys),
+            parse_builtin(BuiltInFunctionType::Values),
+            parse_builtin(BuiltInFunctionType::Entries),
+            parse_builtin(BuiltInFunctionType::HasKey),
+            parse_builtin(BuiltInFunctionType::Remove),
+            parse_builtin(BuiltInFunctionType::ToSet),
+            parse_builtin(BuiltInFunctionType::ToArray),
+            parse_builtin(BuiltInFunctionType::Add),
+            parse_builtin(BuiltInFunctionType::Union),
+            parse_builtin(BuiltInFunctionType::Intersection),
+            parse_builtin(BuiltInFunctionType::Difference),
+        )),
+        alt((
+            parse_builtin(BuiltInFunctionType::Iter),
+            parse_builtin(BuiltInFunctionType::Take),
+            parse_builtin(BuiltInFunctionType::Skip),
+            parse_builtin(BuiltInFunctionType::Enumerate),
+            parse_builtin(BuiltInFunctionType::Chain),
+            parse_builtin(BuiltInFunctionType::ReadLines),
+        )),
+        alt((
+            parse_builtin(BuiltInFunctionType::WrappingAdd),
+            parse_builtin(BuiltInFunctionType::WrappingSub),
+            parse_builtin(BuiltInFunctionType::WrappingMul),
+            parse_builtin(BuiltInFunctionType::CheckedAdd),
+            parse_builtin(BuiltInFunctionType::CheckedSub),
+            parse_builtin(BuiltInFunctionType::CheckedMul),
+        )),
     ))(s)
 }
 
@@ -470,7 +834,7 @@ fn parse_struct_expr(s: &str) -> Res<Value> {
     alt((
         parse_fn_call,
         parse_fn,
-        map(many1(parse_value), |mut expr| {
+        map(converted_values(parse_value), |mut expr| {
             if expr.len() == 1 {
                 expr.remove(0)
             } else {
@@ -518,6 +882,107 @@ pub(super) fn parse_struct(s: &str) -> Res<Value> {
         ),
     )(s)
 }
+/// `dict { 1: "one", "two words": 2 }`. Unlike struct fields, keys are
+/// expressions
+fn parse_dict(s: &str) -> Res<Value> {
+    let pair_key_value = |p| {
+        separated_pair(
+            preceded(opt(comments), parse_struct_expr),
+            tag_no_space(":"),
+            preceded(opt(comments), parse_struct_expr),
+        )(p)
+    };
+    preceded(
+        tag_no_space(DICT),
+        map(
+            delimited(
+                tag_no_space("{"),
+                many0(terminated(pair_key_value, opt(tag_no_space(",")))),
+                preceded(opt(comments), tag_no_space("}")),
+            ),
+            Value::Dict,
+        ),
+    )(s)
+}
+/// `enum Status { Pending, Failed { reason, code } }`, binds the enum to
+/// its name
+fn parse_enum(s: &str) -> Res<Value> {
+    let parse_fields = |s| {
+        delimited(
+            tag_no_space("{"),
+            separated_list0(tag_no_space(","), parse_field_name),
+            preceded(opt(tag_no_space(",")), tag_no_space("}")),
+        )(s)
+    };
+    let parse_variant = move |s| {
+        preceded(
+            opt(comments),
+            terminated(
+                pair(
+                    preceded(multispace0, parse_field_name),
+                    opt(parse_fields),
+                ),
+                opt(tag_no_space(",")),
+            ),
+        )(s)
+    };
+    map(
+        preceded(
+            tag_no_space(ENUM),
+            pair(
+                parse_variable_str,
+                delimited(
+                    tag_no_space("{"),
+                    verify(many0(parse_variant), |variants: &[_]| {
+                        variants.iter().enumerate().all(|(i, (name, _))| {
+                            variants[..i].iter().all(|(n, _)| n != name)
+                        })
+                    }),
+                    preceded(opt(comments), tag_no_space("}")),
+                ),
+            ),
+        ),
+        |(name, variants)| Value::VariableExpr {
+            name: Box::new(Value::Variable(name.to_string())),
+            expr: Box::new(Value::Primitive(Primitive::Enum(Box::new(
+                EnumType {
+                    name: name.to_string(),
+                    variants: variants
+                        .into_iter()
+                        .map(|(variant, fields)| {
+                            (
+                                variant.to_string(),
+                                fields
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(String::from)
+                                    .collect(),
+                            )
+                        })
+                        .collect(),
+                },
+            )))),
+        },
+    )(s)
+}
+
+/// `set { 1, 2, 3 }`
+fn parse_set(s: &str) -> Res<Value> {
+    preceded(
+        tag_no_space(SET),
+        map(
+            delimited(
+                tag_no_space("{"),
+                many0(preceded(
+                    opt(comments),
+                    terminated(parse_struct_expr, opt(tag_no_space(","))),
+                )),
+                preceded(opt(comments), tag_no_space("}")),
+            ),
+            Value::Set,
+        ),
+    )(s)
+}
 fn parse_array(s: &str) -> Res<Value> {
     map(
         preceded(
@@ -525,7 +990,7 @@ fn parse_array(s: &str) -> Res<Value> {
             terminated(
                 separated_list0(
                     tag_no_space(","),
-                    alt((parse_fn, parse_range, parse_number, parse_value)),
+                    alt((parse_range, parse_fn_arg)),
                 ),
                 preceded(multispace0, tag_no_space("]")),
             ),
@@ -622,10 +1087,44 @@ fn parse_index_brackets(s: &str) -> Res<KeyAccess> {
         },
     )(s)
 }
+/// `[1..3]`, `[..=2]` or `[2..]`. An open bound is null
+fn parse_slice_brackets(s: &str) -> Res<KeyAccess> {
+    let parse_open_range = |s| {
+        map(
+            tuple((
+                opt(parse_range_start),
+                preceded(multispace0, tag("..")),
+                opt(tag("=")),
+                opt(parse_range_end),
+            )),
+            |(start, _, incl_both_end, end)| Value::Range {
+                start: Box::new(start.unwrap_or(Value::Null)),
+                end: Box::new(end.unwrap_or(Value::Null)),
+                incl_both_end: incl_both_end.is_some(),
+            },
+        )(s)
+    };
+    map(
+        preceded(
+            tag_no_space("["),
+            terminated(alt((parse_range, parse_open_range)), tag_no_space("]")),
+        ),
+        KeyAccess::Variable,
+    )(s)
+}
+
+/// a field accessed with a dot. Unlike variables, fields can be named after
+/// a reserved keyword, e.g. `person.values`
+fn parse_field_name(s: &str) -> Res<&str> {
+    verify(take_while1(|c: char| c.is_alphanumeric() || c == '_'), |s: &str| {
+        s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+    })(s)
+}
+
 fn parse_key_dots(s: &str) -> Res<KeyAccess> {
     map(
         pair(
-            map(preceded(tag("."), parse_variable_str), |k| {
+            map(preceded(tag("."), parse_field_name), |k| {
                 KeyAccess::Key(Primitive::String(k.to_string()))
             }),
             opt(parse_fn_args),
@@ -640,48 +1139,103 @@ fn parse_key_dots(s: &str) -> Res<KeyAccess> {
     )(s)
 }
 
+/// `println(a)` followed by `[a, b] = [b, a]` on the next line are two
+/// instructions, only a `.` can continue an access on a new line
+fn continues_access(parsed: &str, key: &str) -> bool {
+    let before = &parsed[parsed.trim_end().len()..];
+    let key_start = key.trim_start();
+    let between = &key[..key.len() - key_start.len()];
+    key_start.starts_with('.')
+        || key_start.starts_with("?.")
+        || !(before.contains('\n') || between.contains('\n'))
+}
+
+/// `?.key` or `?[index]`, the key is wrapped in `KeyAccess::Optional`
+fn optional<'a>(
+    key: impl FnMut(&'a str) -> Res<'a, KeyAccess>,
+) -> impl FnMut(&'a str) -> Res<'a, KeyAccess> {
+    map(pair(opt(tag("?")), key), |(optional, key)| match optional {
+        Some(_) => KeyAccess::Optional(Box::new(key)),
+        None => key,
+    })
+}
+
+fn access_start<'a>(
+    root: impl FnMut(&'a str) -> Res<'a, Value>,
+    key: impl FnMut(&'a str) -> Res<'a, KeyAccess>,
+) -> impl FnMut(&'a str) -> Res<'a, (Value, KeyAccess)> {
+    map(
+        verify(
+            pair(consumed(root), consumed(optional(key))),
+            |((root, _), (key, _)): &((&str, Value), (&str, KeyAccess))| {
+                continues_access(root, key)
+            },
+        ),
+        |((_, root), (_, key))| (root, key),
+    )
+}
+
 fn parse_multidepth_access(s: &str) -> Res<Value> {
     let (res, (root, mut next_keys)) = map(
         alt((
-            pair(
+            access_start(
                 parse_builtin_fn,
                 alt((
                     parse_key_brackets,
+                    parse_slice_brackets,
                     parse_index_brackets,
                     parse_variable_brackets,
                     parse_key_dots,
                 )),
             ),
-            pair(
+            access_start(
                 parse_fn_call,
                 alt((
                     parse_key_brackets,
+                    parse_slice_brackets,
                     parse_index_brackets,
                     parse_variable_brackets,
                     parse_key_dots,
                 )),
             ),
-            pair(
+            access_start(
                 parse_variable,
                 alt((
                     parse_key_brackets,
+                    parse_slice_brackets,
                     parse_index_brackets,
                     parse_variable_brackets,
                     parse_key_dots,
                 )),
             ),
-            pair(
+            access_start(
                 parse_struct,
                 alt((
                     parse_key_brackets,
+                    parse_slice_brackets,
                     parse_index_brackets,
                     parse_variable_brackets,
                     parse_key_dots,
                 )),
             ),
-            pair(
+            access_start(
+                parse_dict,
+                alt((
+                    parse_key_brackets,
+                    parse_index_brackets,
+                    parse_variable_brackets,
+                    parse_key_dots,
+                )),
+            ),
+            access_start(parse_set, parse_key_dots),
+            access_start(
                 alt((parse_array, parse_fstring, parse_string)),
-                alt((parse_variable_brackets, parse_index_brackets)),
+                alt((
+                    parse_variable_brackets,
+                    parse_slice_brackets,
+                    parse_index_brackets,
+                    parse_key_dots,
+                )),
             ),
         )),
         |(s, key)| (Box::new(s), vec![key]),
@@ -689,13 +1243,17 @@ fn parse_multidepth_access(s: &str) -> Res<Value> {
 
     let mut new_rest = res;
 
-    while let Ok((rest, key)) = alt((
+    while let Ok((rest, (source, key))) = consumed(optional(alt((
         parse_key_brackets,
+        parse_slice_brackets,
         parse_index_brackets,
         parse_variable_brackets,
         parse_key_dots,
-    ))(new_rest)
+    ))))(new_rest)
     {
+        if !continues_access(&s[..s.len() - new_rest.len()], source) {
+            break;
+        }
         next_keys.push(key);
         new_rest = rest;
     }
@@ -716,11 +1274,14 @@ fn parse_value(s: &str) -> Res<Value> {
             alt((
                 parse_block_paren,
                 parse_operation,
+                parse_quantity,
                 parse_implicit_multiply,
-                parse_multidepth_access,
+                spanned(parse_multidepth_access),
                 parse_struct,
-                parse_builtin_fn,
-                parse_fn_call,
+                parse_dict,
+                parse_set,
+                spanned(parse_builtin_fn),
+                spanned(parse_fn_call),
                 parse_array,
                 parse_range,
                 parse_number,
@@ -767,51 +1328,137 @@ fn parse_operation(s: &str) -> Res<Value> {
         parse_op(Operator::Or),
         parse_op(Operator::Or),
         alt((
+            parse_op(Operator::NullCoalesce),
             parse_op(Operator::BitwiseNot),
             parse_op(Operator::BitwiseAnd),
             parse_op(Operator::BitwiseOr),
             parse_op(Operator::BitwiseXor),
+            // a word, e.g. `is_set(x)` is not an operation
+            map(
+                delimited(
+                    multispace0,
+                    terminated(
+                        tag(Operator::Is.as_str()),
+                        not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+                    ),
+                    multispace0,
+                ),
+                |_| Value::Operation(Operator::Is),
+            ),
         )),
     ))(s)
 }
 
 fn parse_expression(s: &str) -> Res<Value> {
-    map_parser(
+    spanned(map_parser(
         parse_multiline, // todo this is probably the source of all issues
-        map(many1(preceded(opt(comments), parse_value)), |mut v| {
+        map(converted_values(preceded(opt(comments), parse_value)), |mut v| {
             if v.len() == 1 {
                 v.remove(0)
             } else {
                 Value::Expression(v)
             }
         }),
-    )(s)
+    ))(s)
 }
 
-fn parse_simple_instruction(s: &str) -> Res<Value> {
-    alt((
-        map(
+/// `const x = 1` or `let x = 1`
+fn parse_declaration(s: &str) -> Res<Value> {
+    let keyword =
+        |keyword| preceded(multispace0, terminated(tag(keyword), multispace1));
+    map(
+        pair(
+            alt((value(true, keyword(CONST)), value(false, keyword(LET)))),
             separated_pair(
                 pair(
-                    alt((parse_multidepth_access, parse_variable)),
-                    opt(parse_operation),
+                    verify(parse_variable_str, |s: &str| {
+                        !s.starts_with(['&', '_'])
+                    }),
+                    opt(parse_type_annotation),
                 ),
-                tag_no_space("="),
+                terminated(tag_no_space("="), not(tag("="))),
                 parse_complex_expression,
             ),
-            |((variable, mut operator), expr)| match operator.take() {
-                Some(operator) => Value::VariableExpr {
-                    name: Box::new(variable.clone()),
-                    expr: Box::new(Value::Expression(vec![
-                        variable, operator, expr,
-                    ])),
-                },
-                _ => Value::VariableExpr {
-                    name: Box::new(variable),
-                    expr: Box::new(expr),
-                },
-            },
         ),
+        |(constant, ((name, type_name), expr))| Value::Declaration {
+            constant,
+            name: name.to_string(),
+            type_name: type_name.map(String::from),
+            expr: Box::new(expr),
+        },
+    )(s)
+}
+
+/// `import "./utils.adana" as utils`
+fn parse_import(s: &str) -> Res<Value> {
+    map(
+        preceded(
+            preceded(multispace0, terminated(tag(IMPORT), multispace1)),
+            separated_pair(
+                delimited(tag("\""), parse_escaped_string, tag("\"")),
+                delimited(multispace1, tag(AS), multispace1),
+                verify(parse_variable_str, |s: &str| {
+                    !s.starts_with(['&', '_'])
+                }),
+            ),
+        ),
+        |(path, alias)| Value::Import {
+            path: path.into_owned(),
+            alias: alias.to_string(),
+        },
+    )(s)
+}
+
+/// `export x = 1`, `export const x = 1` or `export enum X { .. }`
+fn parse_export(s: &str) -> Res<Value> {
+    map(
+        preceded(
+            preceded(multispace0, terminated(tag(EXPORT), multispace1)),
+            alt((
+                parse_declaration,
+                parse_enum,
+                verify(parse_assignment, |v| match v {
+                    Value::VariableExpr { name, .. } => {
+                        matches!(name.as_ref(), Value::Variable(_))
+                    }
+                    _ => false,
+                }),
+            )),
+        ),
+        |v| Value::Export(Box::new(v)),
+    )(s)
+}
+
+fn parse_assignment(s: &str) -> Res<Value> {
+    map(
+        separated_pair(
+            pair(
+                alt((parse_multidepth_access, parse_variable)),
+                opt(parse_operation),
+            ),
+            tag_no_space("="),
+            parse_complex_expression,
+        ),
+        |((variable, mut operator), expr)| match operator.take() {
+            Some(operator) => Value::VariableExpr {
+                name: Box::new(variable.clone()),
+                expr: Box::new(Value::Expression(vec![
+                    variable, operator, expr,
+                ])),
+            },
+            _ => Value::VariableExpr {
+                name: Box::new(variable),
+                expr: Box::new(expr),
+            },
+        },
+    )(s)
+}
+
+fn parse_simple_instruction(s: &str) -> Res<Value> {
+    alt((
+        parse_declaration,
+        parse_destructure,
+        parse_assignment,
         parse_complex_expression,
     ))(s)
 }
@@ -839,6 +1486,347 @@ fn parse_if_statement(s: &str) -> Res<Value> {
         },
     )(s)
 }
+fn parse_try_statement(s: &str) -> Res<Value> {
+    let parse_catch = |s| {
+        preceded(
+            tag_no_space(CATCH),
+            pair(
+                opt(preceded(
+                    opt(tag_no_space("(")),
+                    terminated(parse_variable_str, opt(tag_no_space(")"))),
+                )),
+                parse_block(parse_instructions),
+            ),
+        )(s)
+    };
+    map(
+        verify(
+            preceded(
+                tag_no_space(TRY),
+                tuple((
+                    parse_block(parse_instructions),
+                    opt(parse_catch),
+                    opt(preceded(
+                        tag_no_space(FINALLY),
+                        parse_block(parse_instructions),
+                    )),
+                )),
+            ),
+            |(_, catch, finally)| catch.is_some() || finally.is_some(),
+        ),
+        |(exprs, catch, finally_expr)| {
+            let (catch_var, catch_expr) = match catch {
+                Some((var, exprs)) => (var.map(String::from), Some(exprs)),
+                None => (None, None),
+            };
+            Value::TryExpr { exprs, catch_var, catch_expr, finally_expr }
+        },
+    )(s)
+}
+
+/// an expression ending with its line, or with a `,` or closing bracket that
+/// isn't nested in it, e.g. `x => "x",` in a match
+fn take_inline_expression(s: &str) -> Res<&str> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut end = s.len();
+    for (i, c) in s.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            ',' | '\n' | ')' | ']' | '}' => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let (expr, rest) = s.split_at(end);
+    if expr.trim().is_empty() {
+        return Err(nom::Err::Error(nom::error::make_error(
+            s,
+            ErrorKind::TakeUntil,
+        )));
+    }
+    Ok((rest, expr))
+}
+
+// region: match
+/// types a pattern can test, as returned by `type_of`. Also the types of
+/// annotations
+pub(crate) const PATTERN_TYPES: [&str; 18] = [
+    TYPE_U8,
+    TYPE_I8,
+    TYPE_INT,
+    TYPE_DOUBLE,
+    TYPE_DECIMAL,
+    TYPE_RATIONAL,
+    TYPE_QUANTITY,
+    TYPE_BOOL,
+    TYPE_NULL,
+    TYPE_STRING,
+    TYPE_ARRAY,
+    TYPE_STRUCT,
+    TYPE_DICT,
+    TYPE_SET,
+    TYPE_FUNCTION,
+    TYPE_ERROR,
+    TYPE_UNIT,
+    TYPE_ITERATOR,
+];
+
+fn parse_pattern_name(s: &str) -> Res<&str> {
+    verify(
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        |name: &str| name.starts_with(|c: char| c.is_alphabetic() || c == '_'),
+    )(s)
+}
+
+fn parse_pattern_type(s: &str) -> Res<&str> {
+    verify(parse_pattern_name, |name| PATTERN_TYPES.contains(name))(s)
+}
+
+fn binding(name: &str) -> Option<String> {
+    (!name.starts_with('_')).then(|| name.to_string())
+}
+
+/// `_`, `true`, `null`, `int`, `x` or `x: int`
+fn parse_name_pattern(s: &str) -> Res<Pattern> {
+    let (rest, name) = parse_pattern_name(s)?;
+    match name {
+        "true" => Ok((rest, Pattern::Literal(Value::Bool(true)))),
+        "false" => Ok((rest, Pattern::Literal(Value::Bool(false)))),
+        NULL => Ok((rest, Pattern::Literal(Value::Null))),
+        name if PATTERN_TYPES.contains(&name) => Ok((
+            rest,
+            Pattern::Type { binding: None, type_name: name.to_string() },
+        )),
+        name if !name.starts_with('_')
+            && default_check_reserved_keywords(&[name]) =>
+        {
+            Err(nom::Err::Error(nom::error::make_error(s, ErrorKind::Verify)))
+        }
+        name => map(
+            opt(preceded(tag_no_space(":"), parse_pattern_type)),
+            |type_name| match type_name {
+                Some(type_name) => Pattern::Type {
+                    binding: binding(name),
+                    type_name: type_name.to_string(),
+                },
+                None => binding(name)
+                    .map(Pattern::Binding)
+                    .unwrap_or(Pattern::Wildcard),
+            },
+        )(rest),
+    }
+}
+
+/// `..`, `..rest` or `...rest`
+fn parse_rest_pattern(s: &str) -> Res<Pattern> {
+    let dots = alt((tag_no_space("..."), tag_no_space("..")));
+    map(preceded(dots, opt(parse_pattern_name)), |name| {
+        name.and_then(binding)
+            .map(Pattern::Binding)
+            .unwrap_or(Pattern::Wildcard)
+    })(s)
+}
+
+fn parse_array_pattern(s: &str) -> Res<Pattern> {
+    map(
+        delimited(
+            tag_no_space("["),
+            pair(
+                separated_list0(tag_no_space(","), parse_pattern),
+                opt(preceded(
+                    opt(tag_no_space(",")),
+                    pair(
+                        parse_rest_pattern,
+                        many0(preceded(tag_no_space(","), parse_pattern)),
+                    ),
+                )),
+            ),
+            preceded(opt(tag_no_space(",")), tag_no_space("]")),
+        ),
+        |(head, rest)| match rest {
+            Some((rest, tail)) => {
+                Pattern::Array { head, rest: Some(Box::new(rest)), tail }
+            }
+            None => Pattern::Array { head, rest: None, tail: vec![] },
+        },
+    )(s)
+}
+
+fn parse_struct_pattern(s: &str) -> Res<Pattern> {
+    let parse_field = |s| {
+        map(
+            pair(
+                preceded(multispace0, parse_key_struct),
+                opt(preceded(tag_no_space(":"), parse_pattern)),
+            ),
+            |(key, pattern): (&str, _)| {
+                let pattern = pattern.unwrap_or_else(|| {
+                    binding(key)
+                        .map(Pattern::Binding)
+                        .unwrap_or(Pattern::Wildcard)
+                });
+                (key.to_string(), pattern)
+            },
+        )(s)
+    };
+    let parse_fields = move |s| {
+        delimited(
+            tag_no_space("{"),
+            pair(
+                separated_list0(tag_no_space(","), parse_field),
+                opt(preceded(opt(tag_no_space(",")), parse_rest_pattern)),
+            ),
+            preceded(opt(tag_no_space(",")), tag_no_space("}")),
+        )(s)
+    };
+    alt((
+        map(preceded(tag_no_space(STRUCT), parse_fields), |(fields, rest)| {
+            Pattern::Struct { fields, rest: rest.map(Box::new) }
+        }),
+        // `{ name, age }`, other fields are ignored
+        map(parse_fields, |(fields, rest)| Pattern::Struct {
+            fields,
+            rest: Some(Box::new(rest.unwrap_or(Pattern::Wildcard))),
+        }),
+    ))(s)
+}
+
+/// `[a, b, ...rest]` or `{ name, age }`, with the source of the pattern
+fn parse_destructure_pattern(s: &str) -> Res<(&str, Pattern)> {
+    preceded(
+        multispace0,
+        consumed(alt((parse_array_pattern, parse_struct_pattern))),
+    )(s)
+}
+
+/// `[a, b] = [b, a]`
+fn parse_destructure(s: &str) -> Res<Value> {
+    map(
+        separated_pair(
+            parse_destructure_pattern,
+            terminated(tag_no_space("="), not(tag("="))),
+            parse_complex_expression,
+        ),
+        |((_, pattern), expr)| Value::VariableExpr {
+            name: Box::new(Value::Pattern(Box::new(pattern))),
+            expr: Box::new(expr),
+        },
+    )(s)
+}
+
+/// a pattern in a loop or a parameter is bound to a variable named after
+/// its source, destructured at the start of the body
+fn destructure(source: &str, pattern: Pattern) -> (String, Value) {
+    let variable = source.trim().to_string();
+    let assign = Value::VariableExpr {
+        name: Box::new(Value::Pattern(Box::new(pattern))),
+        expr: Box::new(Value::Variable(variable.clone())),
+    };
+    (variable, assign)
+}
+
+fn parse_pattern(s: &str) -> Res<Pattern> {
+    preceded(
+        multispace0,
+        terminated(
+            alt((
+                parse_array_pattern,
+                parse_struct_pattern,
+                map(parse_range, |r| Pattern::Range(Box::new(r))),
+                map(alt((parse_number, parse_string)), Pattern::Literal),
+                parse_name_pattern,
+            )),
+            multispace0,
+        ),
+    )(s)
+}
+
+fn parse_match_arm(s: &str) -> Res<MatchArm> {
+    map(
+        tuple((
+            parse_pattern,
+            opt(preceded(
+                tag_no_space(IF),
+                map_parser(
+                    take_until("=>"),
+                    all_consuming(parse_complex_expression),
+                ),
+            )),
+            preceded(
+                tag_no_space("=>"),
+                alt((
+                    parse_block(parse_instructions),
+                    map(
+                        map_parser(
+                            take_inline_expression,
+                            all_consuming(spanned(alt((
+                                parse_throw,
+                                parse_early_return,
+                                parse_complex_expression,
+                                parse_break,
+                                parse_continue,
+                            )))),
+                        ),
+                        |expr| vec![expr],
+                    ),
+                )),
+            ),
+        )),
+        |(pattern, guard, exprs)| MatchArm { pattern, guard, exprs },
+    )(s)
+}
+
+fn parse_match_statement(s: &str) -> Res<Value> {
+    map(
+        preceded(
+            tag_no_space(MATCH),
+            pair(
+                map_parser(
+                    take_till1(|c| c == '{' || c == '\n'),
+                    all_consuming(parse_complex_expression),
+                ),
+                delimited(
+                    tag_no_space("{"),
+                    many0(preceded(
+                        opt(comments),
+                        terminated(parse_match_arm, opt(tag_no_space(","))),
+                    )),
+                    preceded(opt(comments), tag_no_space("}")),
+                ),
+            ),
+        ),
+        |(value, arms)| Value::MatchExpr { value: Box::new(value), arms },
+    )(s)
+}
+// endregion: match
+
+fn parse_throw(s: &str) -> Res<Value> {
+    map(preceded(tag_no_space(THROW), parse_complex_expression), |v| {
+        Value::Throw(Box::new(v))
+    })(s)
+}
+
+fn parse_yield(s: &str) -> Res<Value> {
+    map(preceded(tag_no_space(YIELD), parse_complex_expression), |v| {
+        Value::Yield(Box::new(v))
+    })(s)
+}
+
 fn parse_multiline(s: &str) -> Res<&str> {
     alt((
         preceded(
@@ -881,29 +1869,91 @@ where
     }
 }
 
+fn parse_loop_label(s: &str) -> Res<String> {
+    map(preceded(tag("'"), parse_variable_str), String::from)(s)
+}
+
+fn parse_labeled_loop(s: &str) -> Res<Value> {
+    map(
+        pair(
+            preceded(
+                multispace0,
+                terminated(parse_loop_label, tag_no_space(":")),
+            ),
+            alt((parse_foreach, parse_while_statement)),
+        ),
+        |(label, value)| Value::Labeled { label, value: Box::new(value) },
+    )(s)
+}
+
+/// the label and value of a `break`, on the same line
 fn parse_break(s: &str) -> Res<Value> {
-    map(tag_no_space(BREAK), |_| Value::Break)(s)
+    let space = |s| take_while(|c: char| c == ' ' || c == '\t')(s);
+    map(
+        preceded(
+            preceded(multispace0, tag(BREAK)),
+            pair(
+                opt(preceded(space, parse_loop_label)),
+                opt(preceded(
+                    space,
+                    map_parser(
+                        take_inline_expression,
+                        all_consuming(parse_complex_expression),
+                    ),
+                )),
+            ),
+        ),
+        |(label, value)| match (label, value) {
+            (None, None) => Value::Break,
+            (label, value) => {
+                Value::BreakWith { label, value: value.map(Box::new) }
+            }
+        },
+    )(s)
+}
+
+fn parse_continue(s: &str) -> Res<Value> {
+    map(
+        preceded(
+            preceded(multispace0, tag(CONTINUE)),
+            opt(preceded(
+                take_while(|c: char| c == ' ' || c == '\t'),
+                parse_loop_label,
+            )),
+        ),
+        Value::Continue,
+    )(s)
 }
 // CONTEXT: this should maybe replace parse_expression
 fn parse_complex_expression(s: &str) -> Res<Value> {
+    // `[3, 1].sort()` is an access, left to parse_expression
+    let literal = |parser: fn(&str) -> Res<Value>| {
+        terminated(parser, not(tag_no_space(".")))
+    };
     alt((
-        all_consuming(parse_multidepth_access),
-        all_consuming(parse_builtin_fn),
-        all_consuming(parse_fn_call),
+        parse_match_statement,
+        parse_labeled_loop,
+        parse_foreach,
+        parse_while_statement,
+        all_consuming(spanned(parse_multidepth_access)),
+        all_consuming(spanned(parse_builtin_fn)),
+        all_consuming(spanned(parse_fn_call)),
         // TODO maybe with tuple() this giant mess can be simplified e.g
         // tuple(alt(parser1, parser2,...))
-        map(
+        spanned(map(
             tuple((
-                alt((parse_multidepth_access, parse_fn_call)),
+                alt((spanned(parse_multidepth_access), spanned(parse_fn_call))),
                 parse_operation,
                 parse_expression,
             )),
             |(k, o, v)| Value::Expression(vec![k, o, v]),
-        ),
+        )),
         parse_fn,
-        parse_struct,
-        parse_fstring,
-        parse_array,
+        literal(parse_struct),
+        literal(parse_dict),
+        literal(parse_set),
+        literal(parse_fstring),
+        literal(parse_array),
         parse_expression,
     ))(s)
 }
@@ -915,6 +1965,20 @@ fn parse_early_return(s: &str) -> Res<Value> {
 }
 
 pub fn parse_instructions(instructions: &str) -> Res<Vec<Value>> {
+    // nested blocks are parsed within the source of their parent
+    let _guard = source_offset(instructions)
+        .is_none()
+        .then(|| SourceGuard::enter(instructions, None));
+    // imports and exports are only allowed at the top level of a file
+    let top_level = source_offset(instructions) == Some(0);
+    let parse_module = move |s| {
+        if top_level {
+            alt((parse_import, parse_export))(s)
+        } else {
+            Err(nom::Err::Error(Error::new(s, ErrorKind::Verify)))
+        }
+    };
+
     let (instructions, _) = opt(comments)(instructions)?;
 
     let instructions = instructions.trim();
@@ -925,16 +1989,24 @@ pub fn parse_instructions(instructions: &str) -> Res<Vec<Value>> {
     terminated(
         many1(preceded(
             opt(comments),
-            alt((
+            spanned(alt((
                 all_consuming(parse_value),
+                parse_labeled_loop,
                 parse_foreach,
                 parse_while_statement,
                 parse_if_statement,
+                parse_try_statement,
+                parse_match_statement,
+                parse_enum,
+                parse_module,
                 parse_simple_instruction,
                 parse_drop,
+                parse_throw,
+                parse_yield,
                 parse_early_return,
                 parse_break,
-            )),
+                parse_continue,
+            ))),
         )),
         opt(comments),
     )(instructions)
@@ -11,8 +11,6 @@ pub use nom::{
     IResult,
 };
 
-pub use anyhow::Context;
-
 pub type Res<'a, T> = IResult<&'a str, T>;
 
 pub use std::collections::BTreeMap;
@@ -100,21 +100,21 @@ fn test_file_array() {
     assert_eq!(ctx["copy"].read().unwrap().clone(), copy);
 
     let fancy_list = Array(vec![
-        Primitive::U8(1),
+        Primitive::Int(1),
         Array(vec![
-            Primitive::U8(2),
+            Primitive::Int(2),
             Array(vec![
-                Primitive::U8(3),
+                Primitive::Int(3),
                 Array(vec![
-                    Primitive::U8(4),
+                    Primitive::Int(4),
                     Array(vec![
-                        Primitive::U8(5),
+                        Primitive::Int(5),
                         Array(vec![
-                            Primitive::U8(6),
+                            Primitive::Int(6),
                             Array(vec![
-                                Primitive::U8(7),
+                                Primitive::Int(7),
                                 Array(vec![
-                                    Primitive::U8(8),
+                                    Primitive::Int(8),
                                     Array(vec![
                                         Primitive::U8(9),
                                         Array(vec![]),
@@ -1,7 +1,9 @@
 use std::collections::BTreeMap;
 
+use indexmap::IndexMap;
+
 use crate::compute;
-use adana_script_core::primitive::Primitive;
+use adana_script_core::primitive::{Captured, CompiledBody, Primitive};
 #[test]
 fn test_builtin_to_int() {
     let mut ctx = BTreeMap::new();
@@ -92,7 +94,7 @@ fn test_parse_json() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
         r,
-        Primitive::Struct(BTreeMap::from([(
+        Primitive::Struct(IndexMap::from([(
             "a".to_string(),
             Primitive::Int(9)
         )]))
@@ -163,9 +165,9 @@ fn test_jsonify() {
     assert_eq!(
         Primitive::String(
             r#"{
-  "age": 36,
   "firstName": "Nordine",
   "lastName": "Bittich",
+  "age": 36,
   "notes": [
     1,
     2,
@@ -347,9 +349,15 @@ fn test_type_of() {
     ctx.insert("y".to_string(), Primitive::Double(3.).ref_prim());
     ctx.insert(
         "z".to_string(),
-        Primitive::Function { parameters: vec![], exprs: vec![] }.ref_prim(),
+        Primitive::Function {
+            parameters: vec![],
+            exprs: vec![],
+            captured: Captured::default(),
+            body: CompiledBody::default(),
+        }
+        .ref_prim(),
     );
-    ctx.insert("a".to_string(), Primitive::Error("err".to_string()).ref_prim());
+    ctx.insert("a".to_string(), Primitive::Error("err".into()).ref_prim());
     ctx.insert("b".to_string(), Primitive::Array(vec![]).ref_prim());
     ctx.insert("c".to_string(), Primitive::Bool(true).ref_prim());
     ctx.insert("d".to_string(), Primitive::String("a".to_string()).ref_prim());
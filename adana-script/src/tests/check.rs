@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::{check, compute};
+
+fn problems(program: &str) -> Vec<(String, usize)> {
+    check(program, None)
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.message, d.line))
+        .collect()
+}
+
+#[test]
+fn test_annotations_ignored_at_runtime() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        let total: int = 1
+        const name: string = "adana"
+        sum = (a: int, b: int = 2, ...rest: array): int => {
+            a + b + length(rest)
+        }
+        res = sum(total, 3, 4, 5)
+        default = sum(1)
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(6), ctx["res"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(3), ctx["default"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("adana".into()),
+        ctx["name"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_check_no_problem() {
+    let program = r#"
+        let count: int = 0
+        fact = (n: int): int => {
+            if n <= 1 {
+                return 1
+            }
+            n * fact(n - 1)
+        }
+        for i in 1..5 {
+            count = count + fact(i)
+        }
+        point = struct {
+            x: 1,
+            move: (dx: int) => {
+                self.x = self.x + dx
+            },
+        }
+        x = 1
+        if count > 10 {
+            x = "big"
+        }
+        y = x + 1
+        try {
+            z = later(count)
+        } catch e {
+            println(e.message)
+        }
+        later = (v) => { v }
+        padded = pad_left("1", 3, "0")
+        rounded = round(1.234)
+    "#;
+    assert_eq!(Vec::<(String, usize)>::new(), problems(program));
+}
+
+#[test]
+fn test_check_problems() {
+    let program = r#"
+        let count: int = 0
+        count = "many"
+        sum = (a: int, b: int): int => {
+            a + b
+        }
+        r = sum(1, "x")
+        r = sum(1)
+        r = sum(1, 2, 3)
+        r = sum(1, 2, c = 3)
+        r = sum(1, 2, a = 3)
+        s = "a" - 1
+        println(unknown)
+        r = replace("abc", "a")
+        greet = (name: string): string => {
+            return 5
+        }
+        const k = 1
+        k = 2
+        n = 3
+        n()
+        if true {
+            block_local = 1
+        }
+    "#;
+    assert_eq!(
+        vec![
+            ("count is declared as int, got string".to_string(), 3),
+            ("sum expects int for parameter b, got string".to_string(), 7),
+            ("sum is missing parameter b".to_string(), 8),
+            ("sum takes 2 argument(s), got 3".to_string(), 9),
+            ("sum has no parameter c".to_string(), 10),
+            ("sum got two values for parameter a".to_string(), 11),
+            ("cannot apply - to string and u8".to_string(), 12),
+            ("unknown variable unknown".to_string(), 13),
+            ("replace takes 3 argument(s), got 2".to_string(), 14),
+            ("greet returns string, got u8".to_string(), 16),
+            ("cannot assign to constant k".to_string(), 19),
+            ("n is not a function, got u8".to_string(), 21),
+        ],
+        problems(program)
+    );
+}
+
+#[test]
+fn test_check_builtin_arity() {
+    let program = r#"
+        y = length("a", "b")
+        z = to_upper()
+        w = length("abc")
+        r = round(1.25, 1, 2)
+    "#;
+    assert_eq!(
+        vec![
+            ("length takes 1 argument(s), got 2".to_string(), 2),
+            ("to_upper takes 1 argument(s), got 0".to_string(), 3),
+            ("round takes 1 to 2 arguments, got 3".to_string(), 5),
+        ],
+        problems(program)
+    );
+    let mut ctx = BTreeMap::new();
+    let err = compute(r#"length("a", "b")"#, &mut ctx, "N/A").unwrap_err();
+    assert!(err.to_string().contains("length takes 1 argument(s), got 2"));
+}
+
+#[test]
+fn test_check_does_not_run() {
+    let program = r#"
+        println("side effect")
+        x = 1 / 0
+        include("does_not_exist.adana")
+        y = defined_by_include
+    "#;
+    assert!(problems(program).is_empty());
+    assert!(check("if true {\n    x = 1\n", None).is_err());
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{Primitive, RefPrimitive};
+
+use crate::compute;
+
+#[test]
+fn test_closure_counter() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        make_counter = () => {
+            count = 0
+            () => {
+                count = count + 1
+                count
+            }
+        }
+        counter = make_counter()
+        other = make_counter()
+        counter()
+        counter()
+        other()
+        c = counter()
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(3), res);
+    assert!(!ctx.contains_key("count"));
+    assert_eq!(Primitive::Int(3), compute("c", &mut ctx, "N/A").unwrap());
+}
+
+#[test]
+fn test_closure_partial_application() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        adder = (a) => {
+            (b) => { a + b }
+        }
+        add_five = adder(5)
+        add_one = adder(1)
+        x = add_five(3)
+        y = add_one(2)
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(8), ctx["x"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(3), ctx["y"].read().unwrap().clone());
+}
+
+#[test]
+fn test_closure_captures_by_value() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        x = 1
+        get_x = () => { x }
+        x = 2
+        a = get_x()
+        set_x = () => { x = 100 }
+        set_x()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["a"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(2), ctx["x"].read().unwrap().clone());
+}
+
+#[test]
+fn test_closure_captures_by_reference() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        total = 0
+        for_each = (arr, f) => {
+            for v in arr {
+                f(v)
+            }
+        }
+        for_each([1, 2, 3], (v) => {
+            t = &total
+            total = total + v
+        })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(6), ctx["total"].read().unwrap().clone());
+}
+
+#[test]
+fn test_closure_recursive() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        fact = 3
+        fact = (n) => {
+            if (n > 1) {
+                n * fact(n - 1)
+            } else {
+                1
+            }
+        }
+        fact(5)
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(120), res);
+}
+
+#[test]
+fn test_method_capturing_its_struct() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        counter = struct { n: 0 }
+        counter.get = () => { &counter }
+        counter.n = 5
+        n = counter.get().n
+        counter.get.n.m
+    "#;
+    let res = compute(program, &mut ctx, "N/A");
+    let err = res.unwrap_err();
+    assert!(format!("{err:?}").contains("illegal usage of multidepth access"));
+    assert_eq!(Primitive::U8(5), ctx["n"].read().unwrap().clone());
+    assert!(format!("{:?}", ctx["counter"]).contains("captured: {\"counter\"}"));
+}
+
+#[test]
+fn test_closure_stored_and_restored() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        add = (a) => {
+            (b) => {
+                a + b
+            }
+        }
+        add5 = add(5)
+        counter = struct {
+            n: 0
+        }
+        counter.get = () => {
+            &counter
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    // the way the cache stores a context
+    let stored = bincode::serialize(&ctx).unwrap();
+    drop(ctx);
+    let mut ctx: BTreeMap<String, RefPrimitive> =
+        bincode::deserialize(&stored).unwrap();
+    assert_eq!(Primitive::Int(6), compute("add5(1)", &mut ctx, "N/A").unwrap());
+    assert_eq!(
+        Primitive::U8(0),
+        compute("counter.get().n", &mut ctx, "N/A").unwrap()
+    );
+}
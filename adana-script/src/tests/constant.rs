@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{Primitive, RefPrimitive};
+
+use crate::compute;
+
+#[test]
+fn test_const_and_let() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        const x = [1, 2]
+        let y = 3
+        y = y + 1
+        const double = (n) => { n * 2 }
+        doubled = double(x[1])
+        shadowed = 0
+        if true {
+            let y = 10
+            const z = 5
+            shadowed = y + z
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![Primitive::U8(1), Primitive::U8(2)]),
+        ctx["x"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(4), ctx["y"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(4), ctx["doubled"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(15), ctx["shadowed"].read().unwrap().clone());
+    assert!(!ctx.contains_key("z"));
+    // `z = 1` is not rejected once the block declaring it is left
+    let _ = compute("z = 1", &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["z"].read().unwrap().clone());
+}
+
+#[test]
+fn test_const_cannot_change() {
+    for program in [
+        "x = 3",
+        "x += 1",
+        "x[0] = 3",
+        "[x, y] = [1, 2]",
+        "drop(x)",
+        "drop(x[0])",
+        "const x = 3",
+        "let x = 3",
+        "for x in [1] { x }",
+        "r = &x\nr[0] = 3",
+        "point = struct { v: &x, set: () => { self.v[0] = 3 } }\npoint.set()",
+    ] {
+        let mut ctx = BTreeMap::new();
+        let _ = compute("const x = [1, 2]", &mut ctx, "N/A").unwrap();
+        let res = compute(program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+        assert_eq!(
+            Primitive::Array(vec![Primitive::U8(1), Primitive::U8(2)]),
+            ctx["x"].read().unwrap().clone(),
+            "{program}"
+        );
+    }
+}
+
+#[test]
+fn test_const_errors() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        const sum = (a, b) => { a + b }
+        const counter = struct {
+            count: 0,
+            increment: () => {
+                self.count = self.count + 1
+            },
+        }
+        errors = []
+        try {
+            sum = 1
+        } catch e {
+            errors = errors + e.message
+        }
+        try {
+            drop(sum)
+        } catch e {
+            errors = errors + e.message
+        }
+        try {
+            &counter.increment()
+        } catch e {
+            errors = errors + e.message
+        }
+        try {
+            let counter = 1
+        } catch e {
+            errors = errors + e.message
+        }
+        copy = counter
+        copy.count = 5
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(
+            [
+                "cannot assign to constant sum",
+                "cannot drop constant sum",
+                "cannot mutate constant counter",
+                "constant counter is already declared",
+            ]
+            .iter()
+            .map(|e| Primitive::String(e.to_string()))
+            .collect()
+        ),
+        ctx["errors"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::U8(5),
+        compute("copy.count", &mut ctx, "N/A").unwrap()
+    );
+    assert_eq!(
+        Primitive::U8(0),
+        compute("counter.count", &mut ctx, "N/A").unwrap()
+    );
+}
+
+#[test]
+fn test_const_stored_context() {
+    let mut ctx = BTreeMap::new();
+    let _ = compute("const x = 1\ny = 2", &mut ctx, "N/A").unwrap();
+    assert_eq!(vec!["x", "y"], ctx.keys().collect::<Vec<_>>());
+    // the way the cache stores a context
+    let stored = bincode::serialize(&ctx).unwrap();
+    drop(ctx);
+    let mut restored: BTreeMap<String, RefPrimitive> =
+        bincode::deserialize(&stored).unwrap();
+    assert!(compute("x = 3", &mut restored, "N/A").is_err());
+    let _ = compute("y = 3", &mut restored, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), compute("x", &mut restored, "N/A").unwrap());
+    assert_eq!(Primitive::U8(3), compute("y", &mut restored, "N/A").unwrap());
+}
+
+#[test]
+fn test_const_in_cloned_context_on_another_thread() {
+    let mut ctx = BTreeMap::new();
+    let _ = compute("const x = 1", &mut ctx, "N/A").unwrap();
+    let mut copy = ctx.clone();
+    let res = std::thread::spawn(move || compute("x = 5", &mut copy, "N/A"))
+        .join()
+        .unwrap();
+    assert!(res.is_err());
+    assert_eq!(Primitive::U8(1), compute("x", &mut ctx, "N/A").unwrap());
+}
+
+#[test]
+fn test_const_captured_by_value() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        const k = 5
+        f = () => {
+            k = 3
+            k
+        }
+        message = null
+        try {
+            f()
+        } catch e {
+            message = e.message
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("cannot assign to constant k".into()),
+        ctx["message"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::U8(5), ctx["k"].read().unwrap().clone());
+}
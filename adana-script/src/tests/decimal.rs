@@ -0,0 +1,115 @@
+use adana_script_core::primitive::{Decimal, Primitive, Rational};
+
+use super::eval;
+
+fn decimal(n: &str) -> Primitive {
+    Primitive::Decimal(n.parse().unwrap())
+}
+
+fn rational(numerator: i128, denominator: i128) -> Primitive {
+    Primitive::Rational(
+        Rational::new(numerator.into(), denominator.into()).unwrap(),
+    )
+}
+
+#[test]
+fn test_decimal() {
+    assert_eq!(Primitive::Double(0.30000000000000004), eval("0.1 + 0.2"));
+    assert_eq!(decimal("0.3"), eval("0.1d + 0.2d"));
+    assert_eq!(Primitive::Bool(true), eval("0.1d + 0.2d == 0.3d"));
+    assert_eq!(decimal("-0.15"), eval("-0.05d * 3"));
+    assert_eq!(decimal("1.1"), eval("1.2d - 0.1d"));
+    assert_eq!(decimal("1500"), eval("1.5e3d"));
+    assert_eq!(decimal("0.3333333333333333333333333333"), eval("1d / 3"));
+    assert_eq!(decimal("0.6666666666666666666666666667"), eval("2d / 3"));
+    assert_eq!(decimal("0.25"), eval("2d ^ -2"));
+    assert_eq!(decimal("1.21"), eval("1.1d ^ 2"));
+    assert_eq!(decimal("1"), eval("10d % 3"));
+    assert_eq!(decimal("0.75"), eval("x = 0.5d\nx + 0.25d"));
+    assert_eq!(Primitive::Double(0.75), eval("0.5d + 0.25"));
+    assert_eq!(Primitive::Bool(true), eval("0.1d < 0.2 && 3 > 2.5d"));
+    assert_eq!(
+        Primitive::String("0.1".into()),
+        eval(
+            r#"x = 0.1d
+                "" + x"#
+        )
+    );
+}
+
+#[test]
+fn test_rational() {
+    assert_eq!(rational(1, 3), eval("1/3r"));
+    assert_eq!(rational(1, 2), eval("1/3r + 1/6r"));
+    assert_eq!(rational(1, 1), eval("1/3r * 3"));
+    assert_eq!(rational(-1, 6), eval("1/3r - 1/2r"));
+    assert_eq!(rational(8, 27), eval("(2/3r) ^ 3"));
+    assert_eq!(rational(9, 4), eval("(2/3r) ^ -2"));
+    assert_eq!(rational(1, 2), eval("0.5r"));
+    assert_eq!(rational(3, 4), eval("0.5d + 1/4r"));
+    assert_eq!(rational(1, 6), eval("(1/2r) % (1/3r)"));
+    assert_eq!(Primitive::Bool(true), eval("0.1d < 1/3r"));
+    assert_eq!(Primitive::Bool(true), eval("2/4r == 1/2r"));
+    assert_eq!(Primitive::String("1/3".into()), eval(r#""" + 1/3r"#));
+}
+
+#[test]
+fn test_exact_round_and_conversions() {
+    assert_eq!(decimal("2.35"), eval("round(2.345d, 2)"));
+    assert_eq!(decimal("-2.35"), eval("round(-2.345d, 2)"));
+    assert_eq!(decimal("2"), eval("round(1.5d, 0)"));
+    assert_eq!(rational(333, 1000), eval("round(1/3r, 3)"));
+    assert_eq!(decimal("-3"), eval("floor(-2.5d)"));
+    assert_eq!(decimal("-2"), eval("ceil(-2.5d)"));
+    assert_eq!(rational(4, 1), eval("ceil(7/2r)"));
+    assert_eq!(rational(-4, 1), eval("floor(-7/2r)"));
+    assert_eq!(decimal("2.5"), eval("abs(-2.5d)"));
+
+    assert_eq!(Primitive::Double(1. / 3.), eval("to_double(1/3r)"));
+    assert_eq!(Primitive::Double(0.1), eval("to_double(0.1d)"));
+    assert_eq!(Primitive::Int(3), eval("to_int(7/2r)"));
+    assert_eq!(Primitive::Int(-2), eval("to_int(-2.9d)"));
+    assert_eq!(Primitive::String("decimal".into()), eval("type_of(0.1d)"));
+    assert_eq!(Primitive::String("rational".into()), eval("type_of(1/3r)"));
+    assert_eq!(
+        Primitive::String("{\n  \"a\": \"0.1\",\n  \"b\": \"1/3\"\n}".into()),
+        eval("jsonify(struct { a: 0.1d, b: 1/3r })")
+    );
+    // a suffix only follows a number
+    assert_eq!(Primitive::Int(6), eval("x = 2\n3x"));
+}
+
+#[test]
+fn test_exact_errors() {
+    for (expr, message) in [
+        ("1d / 0", "illegal call to div()"),
+        ("1/3r % 0", "illegal call to rem()"),
+        ("0r ^ -1", "illegal call to pow()"),
+        ("1.5d ^ 100000000", "is too large"),
+        ("round(1.5d, -1)", "illegal call to round"),
+    ] {
+        let res = eval(expr);
+        assert!(
+            matches!(&res, Primitive::Error(e) if e.to_string().contains(message)),
+            "{expr}: {res:?}"
+        );
+    }
+}
+
+#[test]
+fn test_decimal_parse_and_display() {
+    for (n, expected) in [
+        ("0", "0"),
+        ("0.10", "0.1"),
+        ("-0.001", "-0.001"),
+        ("12.5", "12.5"),
+        ("1.5e3", "1500"),
+        ("25e-3", "0.025"),
+        ("-.5", "-0.5"),
+    ] {
+        assert_eq!(expected, n.parse::<Decimal>().unwrap().to_string());
+    }
+    for n in ["", ".", "1.2.3", "1e", "abc", "1e100000000"] {
+        assert!(n.parse::<Decimal>().is_err(), "{n}");
+    }
+}
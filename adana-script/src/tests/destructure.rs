@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::{compute, tests::u8s};
+
+#[test]
+fn test_destructure_array() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        arr = [1, 2, 3, 4]
+        [a, b, ...rest] = arr
+        [first, ...middle, last] = [1, 2, 3, 4, 5]
+        [_, second] = ["ignored", "kept"]
+        [x, y] = ["x", "y"]
+        [x, y] = [y, x]
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["a"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(2), ctx["b"].read().unwrap().clone());
+    assert_eq!(u8s(&[3, 4]), ctx["rest"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(1), ctx["first"].read().unwrap().clone());
+    assert_eq!(u8s(&[2, 3, 4]), ctx["middle"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(5), ctx["last"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("kept".into()),
+        ctx["second"].read().unwrap().clone()
+    );
+    assert!(!ctx.contains_key("_"));
+    assert_eq!(Primitive::String("y".into()), ctx["x"].read().unwrap().clone());
+    assert_eq!(Primitive::String("x".into()), ctx["y"].read().unwrap().clone());
+}
+
+#[test]
+fn test_destructure_struct() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        person = struct { name: "Ada", age: 36, city: "London" }
+        {name, age} = person
+        {city: town, ...others} = person
+        {data: {items: [head, ...tail]}} = struct {
+            data: struct { items: [9, 8, 7] }
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("Ada".into()),
+        ctx["name"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::U8(36), ctx["age"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("London".into()),
+        ctx["town"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Struct(
+            [
+                ("name".to_string(), Primitive::String("Ada".into())),
+                ("age".to_string(), Primitive::U8(36)),
+            ]
+            .into_iter()
+            .collect()
+        ),
+        ctx["others"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::U8(9), ctx["head"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::U8(8), Primitive::U8(7)]),
+        ctx["tail"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_destructure_foreach() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        pairs = [["a", 1], ["b", 2]]
+        ks = []
+        total = 0
+        for [k, v] in pairs {
+            ks = ks + k
+            total = total + v
+        }
+        people = [struct { name: "Ada", age: 36 }, struct { name: "Bob", age: 40 }]
+        names = []
+        for i, {name} in people {
+            names = names + ("""${i}:${name}""")
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("a".into()),
+            Primitive::String("b".into())
+        ]),
+        ctx["ks"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(3), ctx["total"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("0:Ada".into()),
+            Primitive::String("1:Bob".into())
+        ]),
+        ctx["names"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_destructure_parameters() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        person = struct { name: "Ada", age: 36 }
+        describe = ([x, y], {name}, suffix = "!") => {
+            """${name}: ${x},${y}${suffix}"""
+        }
+        described = describe([1, 2], person)
+        sums = map([[1, 2], [3, 4]], ([a, b]) => { a + b })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("Ada: 1,2!".into()),
+        ctx["described"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![Primitive::Int(3), Primitive::Int(7)]),
+        ctx["sums"].read().unwrap().clone()
+    );
+    let mismatch = compute("describe([1], person)", &mut ctx, "N/A");
+    assert!(matches!(mismatch, Ok(Primitive::Error(_))), "{mismatch:?}");
+    // bindings don't leak out of the function
+    assert!(!ctx.contains_key("x"));
+}
+
+#[test]
+fn test_destructure_errors() {
+    for program in [
+        "[a, b] = [1]",
+        "[a] = 1",
+        "{missing} = struct { name: 1 }",
+        r#"{name} = "name""#,
+    ] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use adana_script_core::{primitive::Primitive, Source, Value};
+use serial_test::serial;
+
+use crate::{compute, compute_named, parser::parse_source, Diagnostic};
+
+fn diagnostic(err: anyhow::Error) -> Diagnostic {
+    err.downcast::<Diagnostic>().expect("error should be located")
+}
+
+#[test]
+fn test_parser_error_location() {
+    let script = "a = 1\nif (a == 1 { 3 }\n";
+    let mut ctx = BTreeMap::new();
+    let err = compute(script, &mut ctx, "N/A").unwrap_err();
+    let err = diagnostic(err);
+    assert_eq!(err.message, "PARSER ERROR: could not parse instructions");
+    assert_eq!((err.file, err.line, err.column), (None, 2, 1));
+    assert_eq!(err.snippet, "if (a == 1 { 3 }");
+}
+
+#[test]
+fn test_runtime_error_location() {
+    let script = "a = 1\nb = 2\n\n   c = a.b.c\n";
+    let mut ctx = BTreeMap::new();
+    let err =
+        compute_named(script, Some("main.adana"), &mut ctx, "N/A").unwrap_err();
+    let err = diagnostic(err);
+    assert!(err.message.starts_with("illegal usage of multidepth access"));
+    assert_eq!(err.file.as_deref(), Some("main.adana"));
+    // the key access that failed, not the whole assignment
+    assert_eq!((err.line, err.column, err.underline), (4, 8, 5));
+    assert_eq!(
+        err.to_string().lines().skip(1).collect::<Vec<_>>(),
+        vec![
+            " --> main.adana:4:8",
+            "  |",
+            "4 |    c = a.b.c",
+            "  |        ^^^^^",
+            "    at main (main.adana:4:8)",
+        ]
+    );
+}
+
+#[test]
+fn test_thrown_error_printed_once() {
+    let script = "f = () => {
+  throw \"boom\"
+}
+f()";
+    let mut ctx = BTreeMap::new();
+    let err = compute(script, &mut ctx, "N/A").unwrap_err();
+    let text = diagnostic(err).to_string();
+    assert_eq!(
+        text.lines().collect::<Vec<_>>(),
+        vec![
+            "boom",
+            " --> 2:3",
+            "  |",
+            "2 |   throw \"boom\"",
+            "  |   ^^^^^^^^^^^^",
+            "    at f (2:3)",
+            "    at main (4:1)",
+        ]
+    );
+}
+
+#[test]
+#[serial]
+fn test_error_location_in_included_file() {
+    let script = r#"
+    x = 1
+    include("file_tests/test_error.adana")
+    "#;
+    let mut ctx = BTreeMap::new();
+    let err = compute(script, &mut ctx, "N/A").unwrap_err();
+    let err = diagnostic(err);
+    assert_eq!(err.file.as_deref(), Some("file_tests/test_error.adana"));
+    assert_eq!((err.line, err.column), (4, 5));
+    assert_eq!(err.snippet, "c = a.b.c");
+}
+
+#[test]
+fn test_error_location_in_block() {
+    let script = "x = 1\nif (x == 1) {\n\ty = x.a.b\n}";
+    let mut ctx = BTreeMap::new();
+    let err = compute(script, &mut ctx, "N/A").unwrap_err();
+    let err = diagnostic(err);
+    assert_eq!((err.line, err.column), (3, 6));
+    assert!(err
+        .to_string()
+        .ends_with("3 | \ty = x.a.b\n  | \t    ^^^^^\n    at main (3:6)"));
+}
+
+#[test]
+fn test_parsed_values_are_spanned() {
+    let source = Arc::new(Source {
+        name: None,
+        text: "a = 1\n  while(a < 3) {\n    a = a + 1\n  }".to_string(),
+    });
+    let (rest, instructions) = parse_source(&source).unwrap();
+    assert!(rest.is_empty());
+    let spans = instructions
+        .iter()
+        .map(|v| match v {
+            Value::Spanned { span, .. } => &source.text[span.start..span.end],
+            v => panic!("{v:?} is not spanned"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(spans, vec!["a = 1", "while(a < 3) {\n    a = a + 1\n  }"]);
+
+    let Value::Spanned { value, .. } = &instructions[1] else { unreachable!() };
+    let Value::WhileExpr { exprs, .. } = &**value else {
+        panic!("expected while loop, got {value:?}")
+    };
+    let Value::Spanned { span, .. } = &exprs[0] else {
+        panic!("{:?} is not spanned", exprs[0])
+    };
+    assert_eq!(&source.text[span.start..span.end], "a = a + 1");
+    assert!(span.source.as_ref().is_some_and(|s| Arc::ptr_eq(s, &source)));
+}
+
+#[test]
+fn test_parsed_expressions_are_spanned() {
+    let source = Arc::new(Source {
+        name: None,
+        text: "x = f(a.b, 2) + length(c[0])".to_string(),
+    });
+    let (rest, instructions) = parse_source(&source).unwrap();
+    assert!(rest.is_empty());
+    // every span shows up in the debug output as `Span(start..end)`
+    let debug = format!("{instructions:?}");
+    let spans = debug
+        .split("Span(")
+        .skip(1)
+        .filter_map(|s| {
+            let (start, end) = s.split_once(')')?.0.split_once("..")?;
+            Some(&source.text[start.parse().ok()?..end.parse().ok()?])
+        })
+        .collect::<Vec<_>>();
+    for expected in [
+        "x = f(a.b, 2) + length(c[0])",
+        "f(a.b, 2) + length(c[0])",
+        "f(a.b, 2)",
+        "a.b",
+        "length(c[0])",
+        "c[0]",
+    ] {
+        assert!(spans.contains(&expected), "{expected:?} not in {spans:?}");
+    }
+}
+
+#[test]
+fn test_error_value_location() {
+    let script = "x = 1\nf = () => {\n    to_int(\"a\")\n}\ny = f()\ny";
+    let mut ctx = BTreeMap::new();
+    let Primitive::Error(e) = compute(script, &mut ctx, "N/A").unwrap() else {
+        panic!("expected an error value")
+    };
+    let err = Diagnostic::from_error(&e).expect("error should be located");
+    assert_eq!((err.line, err.column, err.underline), (3, 5, 11));
+    assert_eq!(
+        err.to_string().lines().collect::<Vec<_>>(),
+        vec![
+            "ParseError: invalid cast to int: a, invalid digit found in string",
+            " --> 3:5",
+            "  |",
+            "3 |     to_int(\"a\")",
+            "  |     ^^^^^^^^^^^",
+            "    at f (3:5)",
+            "    at main (5:5)",
+        ]
+    );
+}
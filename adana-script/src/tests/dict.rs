@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{Array, DictKey, Primitive};
+
+use crate::compute;
+
+#[test]
+fn test_dict_literal() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        name = "two words"
+        pair = [1, 2]
+        d = dict {
+            1: "one",
+            name: 2,
+            "x": 3, # comment
+            true: "yes",
+            [1, 2]: "pair",
+        }
+        one = d[1]
+        two = d[name]
+        three = d.x
+        yes = d[true]
+        p = d[pair]
+        missing = d[42]
+        len = length(d)
+        t = type_of(d)
+        empty = dict {}
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Int(1), Primitive::String("one".into())),
+            (DictKey::String("two words".into()), Primitive::U8(2)),
+            (DictKey::String("x".into()), Primitive::U8(3)),
+            (DictKey::Bool(true), Primitive::String("yes".into())),
+            (
+                DictKey::Array(vec![DictKey::Int(1), DictKey::Int(2)]),
+                Primitive::String("pair".into())
+            ),
+        ])),
+        ctx["d"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("one".into()),
+        ctx["one"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::U8(2), ctx["two"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(3), ctx["three"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("yes".into()),
+        ctx["yes"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("pair".into()),
+        ctx["p"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Null, ctx["missing"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(5), ctx["len"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("dict".into()),
+        ctx["t"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Dict(BTreeMap::new()),
+        ctx["empty"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_dict_insert_and_drop() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        d = dict {}
+        d[200] = "u8"
+        d[200] = "updated"
+        key = "some key"
+        d[key] = 1
+        d[key] += 1
+        nested = struct { lookup: dict { 1: "a" } }
+        nested.lookup[2] = "b"
+        d[-1] = "negative"
+        drop(d[-1])
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Int(200), Primitive::String("updated".into())),
+            (DictKey::String("some key".into()), Primitive::Int(2)),
+        ])),
+        ctx["d"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Int(1), Primitive::String("a".into())),
+            (DictKey::Int(2), Primitive::String("b".into())),
+        ])),
+        ctx["nested"]
+            .read()
+            .unwrap()
+            .index_at(&Primitive::String("lookup".into()))
+    );
+}
+
+#[test]
+fn test_dict_builtins() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        d = dict { "b": 2, "a": 1, 3: "c" }
+        k = keys(d)
+        v = values(d)
+        e = entries(d)
+        has_a = has_key(d, "a")
+        has_z = has_key(d, "z")
+        removed = remove(d, "a")
+        len = length(d)
+        from_struct = to_dict(struct { x: 1 })
+        from_entries = to_dict([[1, "one"], [true, "yes"]])
+        is_d = is_dict(d)
+        is_s = is_dict(struct {})
+        struct_keys = keys(struct { x: 1, y: 2 })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let s = |s: &str| Primitive::String(s.into());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::Int(3), s("a"), s("b")]),
+        ctx["k"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![s("c"), Primitive::U8(1), Primitive::U8(2)]),
+        ctx["v"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Array(vec![Primitive::Int(3), s("c")]),
+            Primitive::Array(vec![s("a"), Primitive::U8(1)]),
+            Primitive::Array(vec![s("b"), Primitive::U8(2)]),
+        ]),
+        ctx["e"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Bool(true), ctx["has_a"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(false), ctx["has_z"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Int(3), s("c")),
+            (DictKey::String("b".into()), Primitive::U8(2)),
+        ])),
+        ctx["removed"].read().unwrap().clone()
+    );
+    // remove returns a new dict
+    assert_eq!(Primitive::Int(3), ctx["len"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([(
+            DictKey::String("x".into()),
+            Primitive::U8(1)
+        )])),
+        ctx["from_struct"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Bool(true), s("yes")),
+            (DictKey::Int(1), s("one")),
+        ])),
+        ctx["from_entries"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Bool(true), ctx["is_d"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(false), ctx["is_s"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![s("x"), s("y")]),
+        ctx["struct_keys"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_dict_foreach() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        scores = dict { "ada": 36, "sam": 12 }
+        names = []
+        total = 0
+        for name, score in scores {
+            names = names + name
+            total = total + score
+        }
+        vals = []
+        for score in scores {
+            vals = vals + score
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("ada".into()),
+            Primitive::String("sam".into()),
+        ]),
+        ctx["names"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(48), ctx["total"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::U8(36), Primitive::U8(12)]),
+        ctx["vals"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_dict_json() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        d = dict { 1: "one", "a b": [1, 2] }
+        json = jsonify(d)
+        back = to_dict(parse_json(json))
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String(
+            "{\n  \"1\": \"one\",\n  \"a b\": [\n    1,\n    2\n  ]\n}".into()
+        ),
+        ctx["json"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::String("1".into()), Primitive::String("one".into())),
+            (
+                DictKey::String("a b".into()),
+                Primitive::Array(vec![Primitive::Int(1), Primitive::Int(2)])
+            ),
+        ])),
+        ctx["back"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_dict_errors() {
+    for program in [
+        "dict { 1.5: 1 }",
+        "d = dict {}\nd[1.5] = 1",
+        "to_dict([1, 2])",
+        "has_key(dict {}, struct {})",
+        "keys([1])",
+    ] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+    }
+}
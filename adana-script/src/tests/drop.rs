@@ -1,6 +1,11 @@
 use std::collections::BTreeMap;
 
-use adana_script_core::{primitive::Primitive, BuiltInFunctionType, Value};
+use indexmap::IndexMap;
+
+use adana_script_core::{
+    primitive::{Captured, CompiledBody, Primitive},
+    BuiltInFunctionType, Value,
+};
 
 use crate::compute;
 
@@ -51,10 +56,12 @@ fn test_drop_struct_access() {
     let _ = compute(exp, &mut ctx, "N/A").unwrap();
     assert_eq!(
         ctx["s"].read().unwrap().clone(),
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "x".to_string(),
                 Primitive::Function {
+                    captured: Captured::default(),
+                    body: CompiledBody::default(),
                     parameters: vec![
                         Value::VariableUnused,
                         Value::Variable("n".to_string(),),
@@ -88,10 +95,12 @@ fn test_drop_struct_access_alt() {
     let _ = compute(exp, &mut ctx, "N/A").unwrap();
     assert_eq!(
         ctx["s"].read().unwrap().clone(),
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "x".to_string(),
                 Primitive::Function {
+                    captured: Captured::default(),
+                    body: CompiledBody::default(),
                     parameters: vec![
                         Value::VariableUnused,
                         Value::Variable("n".to_string(),),
@@ -100,3 +100,38 @@ fn require_direct_call_test() {
     dbg!(ctx);
     println!("{res:?}");
 }
+
+#[test]
+#[serial]
+fn named_args_dynamic_lib_test() {
+    let mut ctx = BTreeMap::new();
+
+    let q = r#"
+    lib = require("libplugin_example.so")
+    lib.hello("Nordine", greeting = "salut")
+    "#;
+    let res = compute(q, &mut ctx, "dynamic_lib").unwrap();
+
+    let Primitive::Error(e) = res else {
+        panic!("expected an error, got {res:?}");
+    };
+    assert_eq!("native function hello takes no named argument", e.message);
+}
+
+#[test]
+#[serial]
+fn arity_dynamic_lib_test() {
+    let mut ctx = BTreeMap::new();
+
+    let q = r#"
+    lib = require("libplugin_example.so")
+    lib.callback()
+    "#;
+    let res = compute(q, &mut ctx, "dynamic_lib").unwrap();
+
+    let Primitive::Error(e) = res else {
+        panic!("expected an error, got {res:?}");
+    };
+    assert_eq!("ArgumentError", e.kind);
+    assert_eq!("callback takes 1 argument(s), got 0", e.message);
+}
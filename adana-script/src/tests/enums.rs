@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{EnumType, EnumVariant, Primitive};
+use indexmap::IndexMap;
+
+use crate::compute;
+
+const STATUS: &str = r#"
+    enum Status {
+        Pending,
+        Running, # comment
+        Failed { reason, code },
+    }
+"#;
+
+fn variant(name: &str, fields: &[(&str, Primitive)]) -> Primitive {
+    Primitive::Variant(Box::new(EnumVariant {
+        enum_name: "Status".into(),
+        name: name.into(),
+        fields: fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect::<IndexMap<_, _>>(),
+    }))
+}
+
+#[test]
+fn test_enum_declaration() {
+    let mut ctx = BTreeMap::new();
+    let program = format!(
+        r#"{STATUS}
+        pending = Status.Pending
+        failed = Status.Failed("disk full", 500)
+        reason = failed.reason
+        t = type_of(failed)
+        t_enum = type_of(Status)
+        printed = to_string(failed)
+        printed_enum = to_string(Status)
+    "#
+    );
+    let _ = compute(&program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Enum(Box::new(EnumType {
+            name: "Status".into(),
+            variants: vec![
+                ("Pending".into(), vec![]),
+                ("Running".into(), vec![]),
+                ("Failed".into(), vec!["reason".into(), "code".into()]),
+            ],
+        })),
+        ctx["Status"].read().unwrap().clone()
+    );
+    assert_eq!(variant("Pending", &[]), ctx["pending"].read().unwrap().clone());
+    assert_eq!(
+        variant(
+            "Failed",
+            &[
+                ("reason", Primitive::String("disk full".into())),
+                ("code", Primitive::Int(500))
+            ]
+        ),
+        ctx["failed"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("disk full".into()),
+        ctx["reason"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("Status".into()),
+        ctx["t"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("enum".into()),
+        ctx["t_enum"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String(r#"Status.Failed("disk full", 500)"#.into()),
+        ctx["printed"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String(
+            "enum Status { Pending, Running, Failed { reason, code } }".into()
+        ),
+        ctx["printed_enum"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_enum_equality() {
+    let mut ctx = BTreeMap::new();
+    let program = format!(
+        r#"{STATUS}
+        pending = Status.Pending
+        failed = Status.Failed("disk full", 500)
+        same = pending == Status.Pending
+        different = pending == Status.Running
+        not_equal = pending != Status.Running
+        same_payload = failed == Status.Failed("disk full", 500)
+        other_payload = failed == Status.Failed("timeout", 500)
+    "#
+    );
+    let _ = compute(&program, &mut ctx, "N/A").unwrap();
+    for (name, expected) in [
+        ("same", true),
+        ("different", false),
+        ("not_equal", true),
+        ("same_payload", true),
+        ("other_payload", false),
+    ] {
+        assert_eq!(
+            Primitive::Bool(expected),
+            ctx[name].read().unwrap().clone(),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_enum_is() {
+    let mut ctx = BTreeMap::new();
+    let program = format!(
+        r#"{STATUS}
+        describe = (state) => {{
+            if state is Status.Pending {{
+                "pending"
+            }} else if state is Status.Failed && state.code >= 500 {{
+                "failed: " + state.reason
+            }} else if state is Status.Failed {{
+                "failed"
+            }} else {{
+                "running"
+            }}
+        }}
+        a = describe(Status.Pending)
+        b = describe(Status.Failed("disk full", 500))
+        c = describe(Status.Failed("retry", 1))
+        d = describe(Status.Running)
+        not_a_variant = 1 is Status.Pending
+        in_match = match Status.Running {{
+            s if s is Status.Running => "matched",
+            _ => "nope",
+        }}
+    "#
+    );
+    let _ = compute(&program, &mut ctx, "N/A").unwrap();
+    let s = |s: &str| Primitive::String(s.into());
+    assert_eq!(s("pending"), ctx["a"].read().unwrap().clone());
+    assert_eq!(s("failed: disk full"), ctx["b"].read().unwrap().clone());
+    assert_eq!(s("failed"), ctx["c"].read().unwrap().clone());
+    assert_eq!(s("running"), ctx["d"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Bool(false),
+        ctx["not_a_variant"].read().unwrap().clone()
+    );
+    assert_eq!(s("matched"), ctx["in_match"].read().unwrap().clone());
+}
+
+#[test]
+fn test_enum_json() {
+    let mut ctx = BTreeMap::new();
+    let program = format!(
+        r#"{STATUS}
+        failed = Status.Failed("disk full", 500)
+        json = jsonify(failed)
+        json_pending = jsonify(Status.Pending)
+        back = Status(parse_json(json))
+        back_pending = Status(parse_json(json_pending))
+        round_trip = back == failed
+    "#
+    );
+    let _ = compute(&program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String(
+            "{\n  \"Failed\": {\n    \"reason\": \"disk full\",\n    \"code\": 500\n  }\n}"
+                .into()
+        ),
+        ctx["json"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("\"Pending\"".into()),
+        ctx["json_pending"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Bool(true),
+        ctx["round_trip"].read().unwrap().clone()
+    );
+    assert_eq!(
+        variant("Pending", &[]),
+        ctx["back_pending"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_enum_errors() {
+    for program in [
+        "Status.Unknown",
+        r#"Status.Failed("missing code")"#,
+        r#"Status("Unknown")"#,
+        r#"Status("Failed")"#,
+        r#"Status(struct { Failed: struct { reason: "no code" } })"#,
+        "1 is 1",
+    ] {
+        let mut ctx = BTreeMap::new();
+        let program = format!("{STATUS}\n{program}");
+        let res = compute(&program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+    }
+    // variants must be unique
+    let mut ctx = BTreeMap::new();
+    assert!(compute("enum Dup { A, A }", &mut ctx, "N/A").is_err());
+}
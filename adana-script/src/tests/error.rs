@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+
+use adana_script_core::primitive::{Array, Json, Primitive, ScriptError};
+
+use crate::compute;
+
+#[test]
+fn test_make_err_struct() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        try {
+            throw make_err(struct { kind: "NotFound", message: "no such user", id: 42 })
+        } catch e {
+            [e.kind, e.message, e.id, e.whatever, to_string(e), is_error(e)]
+        }
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("NotFound".into()),
+            Primitive::String("no such user".into()),
+            Primitive::U8(42),
+            Primitive::Null,
+            Primitive::String("Err: NotFound: no such user".into()),
+            Primitive::Bool(true),
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_error_cause() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        load = () => {
+            try {
+                parse_json("{bad")
+            } catch e {
+                throw make_err(struct { kind: "ConfigError", message: "no config", cause: e })
+            }
+        }
+        try {
+            load()
+        } catch e {
+            [e.kind, e.cause.kind, e.cause.cause]
+        }
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("ConfigError".into()),
+            Primitive::String("ParseError".into()),
+            Primitive::Null,
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_builtin_error_kinds() {
+    for (expr, kind) in [
+        (r#"to_int("abc")"#, "ParseError"),
+        (r#"parse_json("{bad")"#, "ParseError"),
+        (r#"is_match("a", "(")"#, "ParseError"),
+        (r#"read_lines("/no/such/file")"#, "IoError"),
+        (r#"include("/no/such/file.adana")"#, "IoError"),
+        (r#"sqrt("a")"#, "TypeError"),
+        (r#"take(1, 2)"#, "TypeError"),
+        (r#"take([1])"#, "ArgumentError"),
+        (r#""a".length(1)"#, "ArgumentError"),
+        (r#"make_err("mine")"#, "Error"),
+    ] {
+        let mut ctx = BTreeMap::new();
+        let program = format!(
+            r#"
+            kind = null
+            try {{
+                kind = {expr}.kind
+            }} catch err {{
+                kind = err.kind
+            }}
+            kind
+            "#
+        );
+        let res = compute(&program, &mut ctx, "N/A").unwrap();
+        assert_eq!(Primitive::String(kind.into()), res, "{expr}");
+    }
+    // the error of a callback keeps its kind
+    let mut ctx = BTreeMap::new();
+    let program = r#"map([1], (x) => { make_err("mine") }).kind"#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::String("Error".into()), res);
+}
+
+#[test]
+fn test_error_stack_trace() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        inner = () => {
+            throw make_err(struct { kind: "Boom", message: "boom" })
+        }
+        outer = () => {
+            inner()
+        }
+        try {
+            outer()
+        } catch e {
+            e.stack
+        }
+    "#;
+    let Primitive::Array(stack) = compute(program, &mut ctx, "N/A").unwrap()
+    else {
+        panic!("stack should be an array");
+    };
+    let field = |frame: &Primitive, key: &str| {
+        frame.index_at(&Primitive::String(key.into()))
+    };
+    assert_eq!(
+        vec![
+            Primitive::String("inner".into()),
+            Primitive::String("outer".into()),
+            Primitive::String("main".into()),
+        ],
+        stack.iter().map(|f| field(f, "function")).collect::<Vec<_>>()
+    );
+    assert_eq!(Primitive::String("3:13".into()), field(&stack[0], "location"));
+    assert_eq!(Primitive::String("9:13".into()), field(&stack[2], "location"));
+}
+
+#[test]
+fn test_jsonify_error() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        try {
+            throw make_err(struct { kind: "NotFound", message: "nope", id: 1 })
+        } catch e {
+            jsonify(e)
+        }
+    "#;
+    let Primitive::String(json) = compute(program, &mut ctx, "N/A").unwrap()
+    else {
+        panic!("jsonify should return a string");
+    };
+    let Primitive::Struct(fields) = Primitive::from_json(&json).unwrap() else {
+        panic!("an error should be jsonified as an object");
+    };
+    assert_eq!(Some(&Primitive::String("NotFound".into())), fields.get("kind"));
+    assert_eq!(Some(&Primitive::String("nope".into())), fields.get("message"));
+    assert_eq!(Some(&Primitive::Int(1)), fields.get("id"));
+    assert_eq!(Some(&Primitive::Null), fields.get("cause"));
+}
+
+#[test]
+fn test_error_report() {
+    let error = ScriptError {
+        cause: Some(Box::new(ScriptError::new("disk full"))),
+        ..ScriptError::from_struct(IndexMap::from([
+            ("kind".to_string(), Primitive::String("IoError".into())),
+            ("message".to_string(), Primitive::String("write failed".into())),
+            ("path".to_string(), Primitive::String("/tmp/x".into())),
+        ]))
+    };
+    assert_eq!(
+        "IoError: write failed\n    path: /tmp/x\ncaused by: disk full",
+        error.report()
+    );
+}
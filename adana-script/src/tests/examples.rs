@@ -1,5 +1,7 @@
 use std::collections::BTreeMap;
 
+use indexmap::IndexMap;
+
 use crate::compute;
 use adana_script_core::primitive::Primitive::*;
 #[test]
@@ -13,17 +15,17 @@ fn test_example1() {
     assert_eq!(
         ctx["students"].read().unwrap().clone(),
         Array(vec![
-            Struct(BTreeMap::from([
+            Struct(IndexMap::from([
                 ("first_name".into(), String("John".into(),)),
                 ("last_name".into(), String("Doe".into(),)),
                 ("note".into(), U8(18,)),
             ])),
-            Struct(BTreeMap::from([
+            Struct(IndexMap::from([
                 ("first_name".into(), String("Jane".into(),)),
                 ("last_name".into(), String("Dow".into(),)),
                 ("note".into(), U8(9,)),
             ])),
-            Struct(BTreeMap::from([
+            Struct(IndexMap::from([
                 ("first_name".into(), String("Bryan".into(),)),
                 ("last_name".into(), String("Bower".into(),)),
                 ("note".into(), I8(-10,)),
@@ -33,17 +35,17 @@ fn test_example1() {
     assert_eq!(
         ctx["sorted_students"].read().unwrap().clone(),
         Array(vec![
-            Struct(BTreeMap::from([
+            Struct(IndexMap::from([
                 ("first_name".into(), String("Bryan".into(),)),
                 ("last_name".into(), String("Bower".into(),)),
                 ("note".into(), I8(-10,)),
             ])),
-            Struct(BTreeMap::from([
+            Struct(IndexMap::from([
                 ("first_name".into(), String("Jane".into(),)),
                 ("last_name".into(), String("Dow".into(),)),
                 ("note".into(), U8(9,)),
             ])),
-            Struct(BTreeMap::from([
+            Struct(IndexMap::from([
                 ("first_name".into(), String("John".into(),)),
                 ("last_name".into(), String("Doe".into(),)),
                 ("note".into(), U8(18,)),
@@ -62,6 +64,6 @@ fn test_example2() {
     assert_eq!(res, Array(vec![Int(15), Int(1), Int(11), Int(22)]));
     assert_eq!(
         compute("fun([1])", &mut ctx, "N/A",).unwrap(),
-        Array(vec![U8(0)])
+        Array(vec![Int(0)])
     );
 }
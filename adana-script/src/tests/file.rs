@@ -109,9 +109,9 @@ fn test_nested_file() {
         .collect();
     assert_eq!(
         &BTreeMap::from([
-            ("a".to_string(), Primitive::U8(0)),
+            ("a".to_string(), Primitive::Int(0)),
             ("b".to_string(), Primitive::Int(240)),
-            ("x".to_string(), Primitive::U8(50)),
+            ("x".to_string(), Primitive::Int(50)),
             ("s".to_string(), Primitive::String("mod 3".to_string())),
             ("z".to_string(), Primitive::String("mod 1".to_string())),
         ]),
@@ -172,7 +172,7 @@ fn test_includes() {
             ("b".to_string(), Primitive::Int(233,)),
             ("bfr".to_string(), Primitive::U8(2,)),
             ("c".to_string(), Primitive::Int(233,)),
-            ("n".to_string(), Primitive::U8(1,)),
+            ("n".to_string(), Primitive::Int(1,)),
             ("x".to_string(), Primitive::U8(4,))
         ]),
     );
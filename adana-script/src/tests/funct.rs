@@ -2,7 +2,10 @@ use std::collections::BTreeMap;
 
 use crate::compute;
 
-use adana_script_core::{primitive::Primitive, Operator, Value};
+use adana_script_core::{
+    primitive::{Captured, CompiledBody, Primitive},
+    Operator, Value,
+};
 #[test]
 fn test_anon_func_call() {
     let mut ctx = BTreeMap::new();
@@ -28,6 +31,8 @@ fn test_anon_func_call() {
     assert_eq!(
         *ctx["z"].read().unwrap(),
         Primitive::Function {
+            captured: Captured::default(),
+            body: CompiledBody::default(),
             parameters: vec![
                 Value::Variable("a".to_string()),
                 Value::Variable("b".to_string()),
@@ -343,3 +348,73 @@ fn test_regression_struct_with_builtin() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(r, Primitive::String("hello bro!".into()));
 }
+
+#[test]
+fn test_default_named_and_rest_params() {
+    let mut ctx = BTreeMap::new();
+    let s = r#"
+        greet = (name, greeting = "hello", punct = "!") => {
+            greeting + " " + name + punct
+        }
+        a = greet("ada")
+        b = greet("ada", "hi")
+        c = greet("ada", punct = "?")
+        d = greet(punct = ".", name = "bob")
+        sum = (first, ...rest) => {
+            reduce(rest, (acc, v) => { acc + v }, first)
+        }
+        e = sum(1)
+        f = sum(1, 2, 3)
+        rest = (...items) => { items }
+        g = rest()
+        pair = (x, y = x * 2) => { [x, y] }
+        h = pair(3)
+        offset = 10
+        shift = (x, by = offset) => { x + by }
+        i = shift(1)
+        s = struct { add: (x, y = 1) => { x + y } }
+        j = s.add(1, y = 5)
+    "#;
+    let _ = compute(s, &mut ctx, "N/A").unwrap();
+    let string = |s: &str| Primitive::String(s.into());
+    assert_eq!(string("hello ada!"), ctx["a"].read().unwrap().clone());
+    assert_eq!(string("hi ada!"), ctx["b"].read().unwrap().clone());
+    assert_eq!(string("hello ada?"), ctx["c"].read().unwrap().clone());
+    assert_eq!(string("hello bob."), ctx["d"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(1), ctx["e"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(6), ctx["f"].read().unwrap().clone());
+    assert_eq!(Primitive::Array(vec![]), ctx["g"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::U8(3), Primitive::Int(6)]),
+        ctx["h"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(11), ctx["i"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(6), ctx["j"].read().unwrap().clone());
+}
+
+#[test]
+fn test_arity_errors() {
+    let definition = r#"
+        greet = (name, greeting = "hello") => { greeting + " " + name }
+    "#;
+    for (call, expected) in [
+        ("greet()", "greet is missing parameter name"),
+        (r#"greet("a", "b", "c")"#, "greet takes 2 argument(s), got 3"),
+        (
+            r#"greet("a", name = "b")"#,
+            "greet got two values for parameter name",
+        ),
+        (r#"greet("a", nope = 1)"#, "greet has no parameter nope"),
+    ] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(&format!("{definition}\n{call}"), &mut ctx, "N/A");
+        let Ok(Primitive::Error(e)) = res else {
+            panic!("{call} => {res:?}");
+        };
+        assert_eq!(expected, e.message, "{call}");
+    }
+    // the rest parameter must be the last one
+    let mut ctx = BTreeMap::new();
+    let res = compute("f = (...rest, a) => { a }\nf(1)", &mut ctx, "N/A");
+    assert!(matches!(res, Err(_) | Ok(Primitive::Error(_))), "{res:?}");
+}
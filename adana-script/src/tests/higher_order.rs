@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{DictKey, Primitive};
+
+use crate::{
+    compute,
+    tests::{eval, ints, strings, u8s},
+};
+
+#[test]
+fn test_map_filter_reduce() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        arr = [1, 2, 3, 4, 5]
+        doubled = map(arr, (x) => { x * 2 })
+        evens = filter(arr, (x) => { x % 2 == 0 })
+        sum = reduce(arr, (acc, x) => { acc + x }, 0)
+        no_init = reduce(arr, (acc, x) => { acc + x })
+        single = reduce([7], (acc, x) => { acc + x })
+        square = (x) => { x * x }
+        squares = map(arr, square)
+        empty = map([], square)
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(ints(&[2, 4, 6, 8, 10]), ctx["doubled"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::U8(2), Primitive::U8(4)]),
+        ctx["evens"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(15), ctx["sum"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(15), ctx["no_init"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(7), ctx["single"].read().unwrap().clone());
+    assert!(matches!(
+        eval("reduce([], (acc, x) => { acc + x })"),
+        Primitive::Error(_)
+    ));
+    assert_eq!(
+        ints(&[1, 4, 9, 16, 25]),
+        ctx["squares"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Array(vec![]), ctx["empty"].read().unwrap().clone());
+}
+
+#[test]
+fn test_sort_and_sort_by() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        nums = sort([3, 1.5, -2, 10])
+        words = sort(["pear", "apple", "fig"])
+        people = [struct {name: "Sam", age: 40}, struct {name: "Ada", age: 36}, struct {name: "Bob", age: 40}]
+        by_age = sort_by(people, (a, b) => { a.age - b.age })
+        names = map(by_age, (p) => { p.name })
+        desc = sort_by([1, 3, 2], (a, b) => { b - a })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::I8(-2),
+            Primitive::Double(1.5),
+            Primitive::U8(3),
+            Primitive::U8(10),
+        ]),
+        ctx["nums"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("apple".into()),
+            Primitive::String("fig".into()),
+            Primitive::String("pear".into()),
+        ]),
+        ctx["words"].read().unwrap().clone()
+    );
+    // the sort is stable
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("Ada".into()),
+            Primitive::String("Sam".into()),
+            Primitive::String("Bob".into()),
+        ]),
+        ctx["names"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(3),
+            Primitive::U8(2),
+            Primitive::U8(1),
+        ]),
+        ctx["desc"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_find_any_all() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        arr = [1, 8, 3, 12]
+        big = find(arr, (x) => { x > 5 })
+        none = find(arr, (x) => { x > 50 })
+        has_even = any(arr, (x) => { x % 2 == 0 })
+        all_pos = all(arr, (x) => { x > 0 })
+        all_even = all(arr, (x) => { x % 2 == 0 })
+        any_empty = any([], (x) => { true })
+        all_empty = all([], (x) => { false })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(8), ctx["big"].read().unwrap().clone());
+    assert_eq!(Primitive::Null, ctx["none"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(true), ctx["has_even"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(true), ctx["all_pos"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(false), ctx["all_even"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Bool(false),
+        ctx["any_empty"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Bool(true), ctx["all_empty"].read().unwrap().clone());
+}
+
+#[test]
+fn test_zip_flatten_reverse_unique() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        pairs = zip([1, 2, 3], ["a", "b"])
+        flat = flatten([[1, 2], 3, [[4]]])
+        rev = reverse([1, 2, 3])
+        rev_str = reverse("adana!")
+        uniq = unique([1, 2, 1, 3.0, 3, "a", "a", true])
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Array(vec![
+                Primitive::U8(1),
+                Primitive::String("a".into())
+            ]),
+            Primitive::Array(vec![
+                Primitive::U8(2),
+                Primitive::String("b".into())
+            ]),
+        ]),
+        ctx["pairs"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(1),
+            Primitive::U8(2),
+            Primitive::U8(3),
+            Primitive::Array(vec![Primitive::U8(4)]),
+        ]),
+        ctx["flat"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(3),
+            Primitive::U8(2),
+            Primitive::U8(1),
+        ]),
+        ctx["rev"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("!anada".into()),
+        ctx["rev_str"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(1),
+            Primitive::U8(2),
+            Primitive::Double(3.0),
+            Primitive::String("a".into()),
+            Primitive::Bool(true),
+        ]),
+        ctx["uniq"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_group_by() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        parity = (x) => {
+            if x % 2 == 0 {
+                "even"
+            } else {
+                "odd"
+            }
+        }
+        groups = group_by([1, 2, 3, 4, 5], parity)
+        evens = groups.even
+        odds = groups.odd
+        by_bool = group_by([1, 2, 3, 4], (x) => { x > 2 })
+        by_len = group_by(["a", "bb", "c"], (s) => { length(s) })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    // groups are keyed by the value returned by the function
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Bool(false), u8s(&[1, 2])),
+            (DictKey::Bool(true), u8s(&[3, 4])),
+        ])),
+        ctx["by_bool"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Dict(BTreeMap::from([
+            (DictKey::Int(1), strings(&["a", "c"])),
+            (DictKey::Int(2), strings(&["bb"])),
+        ])),
+        ctx["by_len"].read().unwrap().clone()
+    );
+    assert!(matches!(
+        eval("group_by([1], (x) => { 1.5 })"),
+        Primitive::Error(_)
+    ));
+    assert_eq!(
+        Primitive::Array(vec![Primitive::U8(2), Primitive::U8(4)]),
+        ctx["evens"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(1),
+            Primitive::U8(3),
+            Primitive::U8(5),
+        ]),
+        ctx["odds"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_higher_order_errors() {
+    let program = r#"
+        check = (x) => {
+            if x > 1 {
+                make_err("too big")
+            } else {
+                x
+            }
+        }
+        map([1, 2, 3], check)
+    "#;
+    let mut ctx = BTreeMap::new();
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    let Primitive::Error(e) = res else {
+        panic!("expected an error, got {res}")
+    };
+    assert_eq!("too big", e.message);
+
+    // the first error of the comparator, the array being sorted anyway
+    let program = r#"
+        sort_by([3, 1, 2, 5, 4], (a, b) => {
+            if a == 2 || b == 2 {
+                make_err("cannot compare " + a + " and " + b)
+            } else {
+                a - b
+            }
+        })
+    "#;
+    let mut ctx = BTreeMap::new();
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    let Primitive::Error(e) = res else {
+        panic!("expected an error, got {res}")
+    };
+    assert!(e.message.starts_with("cannot compare"), "{e}");
+
+    for program in [
+        r#"sort([1, "a"])"#,
+        "map(5, (x) => { x })",
+        "map([1], 5)",
+        "filter([1])",
+        "sort_by([1, 2], (a, b) => { true })",
+    ] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(program, &mut ctx, "N/A").unwrap();
+        assert!(matches!(res, Primitive::Error(_)), "{program}");
+    }
+}
+
+#[test]
+fn test_builtin_names_can_be_shadowed() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        before = sort([3, 1, 2])
+        sort = (a) => { a }
+        after = sort([3, 1, 2])
+        map = () => { "mine" }
+        m = map()
+        keys = ["a"]
+        values = values(dict { "a": 1 })
+        inner = () => { sort([2, 1]) }
+        from_closure = inner()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(u8s(&[1, 2, 3]), ctx["before"].read().unwrap().clone());
+    assert_eq!(u8s(&[3, 1, 2]), ctx["after"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("mine".into()),
+        ctx["m"].read().unwrap().clone()
+    );
+    assert_eq!(u8s(&[1]), ctx["values"].read().unwrap().clone());
+    assert_eq!(u8s(&[2, 1]), ctx["from_closure"].read().unwrap().clone());
+    assert!(compute("length = 1", &mut ctx, "N/A").is_err());
+    assert!(compute("set = 1", &mut ctx, "N/A").is_err());
+}
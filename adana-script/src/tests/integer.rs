@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{BigInt, Primitive};
+
+use super::eval;
+use crate::compute;
+
+fn big(n: &str) -> Primitive {
+    Primitive::BigInt(n.parse().unwrap())
+}
+
+#[test]
+fn test_int_overflow_promotes() {
+    assert_eq!(
+        big("170141183460469231731687303715884105728"),
+        eval("170141183460469231731687303715884105727 + 1")
+    );
+    assert_eq!(
+        big("-170141183460469231731687303715884105729"),
+        eval("-170141183460469231731687303715884105728 - 1")
+    );
+    assert_eq!(
+        big("340282366920938463463374607431768211454"),
+        eval("170141183460469231731687303715884105727 * 2")
+    );
+    assert_eq!(
+        big("1606938044258990275541962092341162602522202993782792835301376"),
+        eval("2 ^ 200")
+    );
+    assert_eq!(
+        big("170141183460469231731687303715884105728"),
+        eval("-170141183460469231731687303715884105728 / -1")
+    );
+    assert_eq!(
+        big("1606938044258990275541962092341162602522202993782792835301376"),
+        eval("1 << 200")
+    );
+    assert_eq!(Primitive::Int(0), eval("8 >> 300"));
+
+    // back to an int as soon as the result fits
+    assert_eq!(
+        Primitive::Int(170141183460469231731687303715884105727),
+        eval("170141183460469231731687303715884105727 + 1 - 1")
+    );
+    assert_eq!(Primitive::Int(1), eval("2 ^ 200 / 2 ^ 199 - 1"));
+
+    // a negative big int is written with its sign, not in two's complement
+    assert_eq!(
+        Primitive::String("0x100000000000000000000000000000000".into()),
+        eval("to_hex(2 ^ 128)")
+    );
+    assert_eq!(
+        Primitive::String("-0x100000000000000000000000000000001".into()),
+        eval("to_hex(-(2 ^ 128) - 1)")
+    );
+    assert_eq!(
+        Primitive::String(format!("0b1{}101", "0".repeat(126))),
+        eval("to_binary(2 ^ 129 + 5)")
+    );
+}
+
+#[test]
+fn test_bigint() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        fact = (n) => {
+            acc = 1
+            for i in 1..=n {
+                acc = acc * i
+            }
+            acc
+        }
+        f = fact(40)
+        ratio = fact(40) / fact(38)
+        modulo = 123456789012345678901234567890123456789012345678901234567890 % 1000000007
+        kind = type_of(f)
+        is_integer = is_int(f)
+        greater = f > fact(39)
+        less = -f < 3.5
+        parsed = to_int("99999999999999999999999999999999999999999999")
+        text = "" + f
+        approx = to_double(2 ^ 130)
+        json = jsonify(struct { n: 2 ^ 130 })
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(
+        big("815915283247897734345611269596115894272000000000"),
+        get("f")
+    );
+    assert_eq!(Primitive::Int(1560), get("ratio"));
+    assert_eq!(Primitive::Int(47102882), get("modulo"));
+    assert_eq!(Primitive::String("int".into()), get("kind"));
+    assert_eq!(Primitive::Bool(true), get("is_integer"));
+    assert_eq!(Primitive::Bool(true), get("greater"));
+    assert_eq!(Primitive::Bool(true), get("less"));
+    assert_eq!(
+        big("99999999999999999999999999999999999999999999"),
+        get("parsed")
+    );
+    assert_eq!(
+        Primitive::String(
+            "815915283247897734345611269596115894272000000000".into()
+        ),
+        get("text")
+    );
+    assert_eq!(Primitive::Double(2f64.powi(130)), get("approx"));
+    assert_eq!(
+        Primitive::String(
+            "{\n  \"n\": \"1361129467683753853853498429727072845824\"\n}"
+                .into()
+        ),
+        get("json")
+    );
+    assert!(matches!(eval("2 ^ 100000000"), Primitive::Error(_)));
+}
+
+#[test]
+fn test_fixed_width_arithmetic() {
+    // arithmetic on u8 and i8 always gives an int, even when the result
+    // would fit
+    for expr in
+        ["2 * 3", "2 + 3", "6 / 3", "3 - 1", "3 % 2", "-3 - -1", "-3 % 2"]
+    {
+        assert!(matches!(eval(expr), Primitive::Int(_)), "{expr}");
+    }
+    assert_eq!(Primitive::Int(2), eval("3 - 1"));
+    assert_eq!(Primitive::Int(-1), eval("-3 % 2"));
+    assert_eq!(Primitive::Int(256), eval("255 + 1"));
+    assert_eq!(Primitive::Int(-1), eval("0 - 1"));
+    assert_eq!(Primitive::Int(-129), eval("-128 - 1"));
+    assert_eq!(Primitive::Int(0), eval("-128 % -1"));
+    assert_eq!(Primitive::Int(128), eval("x = -128\n-x"));
+}
+
+#[test]
+fn test_wrapping_and_checked() {
+    assert_eq!(Primitive::U8(0), eval("wrapping_add(255, 1)"));
+    assert_eq!(Primitive::U8(255), eval("wrapping_sub(0, 1)"));
+    assert_eq!(Primitive::U8(0), eval("wrapping_mul(16, 16)"));
+    assert_eq!(Primitive::I8(56), eval("wrapping_sub(-100, 100)"));
+    assert_eq!(
+        Primitive::Int(i128::MIN),
+        eval("wrapping_add(170141183460469231731687303715884105727, 1)")
+    );
+    assert_eq!(Primitive::U8(4), eval("x = 250\nx.wrapping_add(10)"));
+
+    assert_eq!(Primitive::Null, eval("checked_add(255, 1)"));
+    assert_eq!(Primitive::U8(255), eval("checked_add(200, 55)"));
+    assert_eq!(Primitive::Null, eval("checked_sub(0, 1)"));
+    assert_eq!(Primitive::I8(-128), eval("checked_sub(-100, 28)"));
+    assert_eq!(
+        Primitive::Null,
+        eval("checked_mul(170141183460469231731687303715884105727, 2)")
+    );
+
+    for (expr, message) in [
+        ("wrapping_add(1, 300)", "wrapping_add: 300 is out of range for u8"),
+        (r#"checked_mul("a", 3)"#, "checked_mul: expected a u8, i8 or int"),
+        ("wrapping_add(2 ^ 130, 1)", "wrapping_add: expected a u8, i8 or int"),
+        ("checked_add(1, 1.5)", "checked_add: expected an int, got 1.5"),
+    ] {
+        let res = eval(expr);
+        assert!(
+            matches!(&res, Primitive::Error(e) if e.to_string().contains(message)),
+            "{expr}: {res:?}"
+        );
+    }
+}
+
+#[test]
+fn test_bigint_parse_and_display() {
+    for n in [
+        "0",
+        "-1",
+        "4294967296",
+        "-18446744073709551616",
+        "1000000000000000000000000000000000000000000",
+        "-999999999999999999999999999999999999999999999999",
+    ] {
+        assert_eq!(n, n.parse::<BigInt>().unwrap().to_string());
+    }
+    assert!("12a".parse::<BigInt>().is_err());
+    assert!("-".parse::<BigInt>().is_err());
+
+    let a: BigInt = "123456789012345678901234567890123456789".parse().unwrap();
+    let b: BigInt = "-987654321098765432109876543210".parse().unwrap();
+    let (q, r) = a.div_rem(&b).unwrap();
+    assert_eq!("-124999998", q.to_string());
+    assert_eq!("850308642085030864208626543209", r.to_string());
+    assert_eq!(a, q.mul(&b).add(&r));
+    assert!(a.div_rem(&BigInt::from(0)).is_none());
+}
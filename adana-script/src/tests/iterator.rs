@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::{
+    primitive::{Array, Primitive, RefPrimitive},
+    Value,
+};
+use serial_test::serial;
+
+use crate::{
+    compiler::compile,
+    compute,
+    tests::{ints, strings, u8s},
+};
+
+#[test]
+fn test_lazy_range() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        total = 0
+        for x in 1..1000000000000 {
+            if x > 4 {
+                break
+            }
+            total = total + x
+        }
+        r = iter(1..=1000000000000)
+        kind = type_of(r)
+        first = to_array(take(r, 2))
+        again = to_array(take(r, 2))
+        arr = 1..4
+        arr_kind = type_of(arr)
+        collected = to_array(arr)
+        big = 1..1000000000000
+        big_firsts = to_array(take(big, 3))
+        big_nth = big[5]
+        [one, two] = 1..=2
+        nested = struct { r: 1..1000000000000 }
+        nested_firsts = to_array(take(nested.r, 2))
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::Int(10), get("total"));
+    assert_eq!(Primitive::String("iterator".into()), get("kind"));
+    assert_eq!(ints(&[1, 2]), get("first"));
+    // a range starts over each time it is consumed
+    assert_eq!(ints(&[1, 2]), get("again"));
+    // a range stays lazy until it is collected, even when huge
+    assert_eq!(Primitive::String("iterator".into()), get("arr_kind"));
+    assert_eq!(ints(&[1, 2, 3]), get("collected"));
+    assert_eq!(ints(&[1, 2, 3]), get("big_firsts"));
+    assert_eq!(Primitive::Int(6), get("big_nth"));
+    assert_eq!(Primitive::Int(1), get("one"));
+    assert_eq!(Primitive::Int(2), get("two"));
+    assert_eq!(ints(&[1, 2]), get("nested_firsts"));
+}
+
+#[test]
+fn test_range_stored_context() {
+    let mut ctx = BTreeMap::new();
+    let _ = compute("r = 1..4", &mut ctx, "N/A").unwrap();
+    // the way the cache stores a context
+    let stored = bincode::serialize(&ctx).unwrap();
+    let mut restored: BTreeMap<String, RefPrimitive> =
+        bincode::deserialize(&stored).unwrap();
+    let res = compute("to_array(r) + to_array(r)", &mut restored, "N/A");
+    assert_eq!(ints(&[1, 2, 3, 1, 2, 3]), res.unwrap());
+
+    // a generator can only be consumed once, so it can't be stored
+    let program = r#"
+        gen = () => {
+            yield 1
+        }
+        g = gen()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert!(bincode::serialize(&ctx).is_err());
+}
+
+#[test]
+fn test_iterator_restarts() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        r = iter(1..4)
+        total = 0
+        for x in r {
+            total = total + x
+        }
+        for x in r {
+            total = total + x
+        }
+        firsts = take(chain(["a"], "bc"), 2)
+        joined = to_array(firsts) + to_array(firsts)
+        numbered = enumerate(r)
+        pairs = to_array(numbered).length() + to_array(numbered).length()
+        count = () => {
+            yield 1
+            yield 2
+            yield 3
+        }
+        gen = count()
+        shared = to_array(take(gen, 2)) + to_array(gen)
+        gen = count()
+        mixed = chain(gen, [4])
+        once = to_array(mixed)
+        twice = to_array(mixed)
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::Int(12), get("total"));
+    assert_eq!(strings(&["a", "b", "a", "b"]), get("joined"));
+    assert_eq!(Primitive::Int(6), get("pairs"));
+    // a generator runs once, its clones share its position
+    assert_eq!(u8s(&[1, 2, 3]), get("shared"));
+    assert_eq!(u8s(&[1, 2, 3, 4]), get("once"));
+    assert_eq!(u8s(&[]), get("twice"));
+}
+
+#[test]
+fn test_lazy_combinators() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        skipped = to_array(skip(take(1..=10, 5), 2))
+        chained = to_array(chain([1, 2], "ab"))
+        pairs = to_array(enumerate(["a", "b"]))
+        doubled = map(take(1..1000000000000, 3), (x) => { x * 2 })
+        labels = []
+        for [i, v] in enumerate(chain([1], iter(set { 2 }))) {
+            label = i + ":" + v
+            labels = labels + [label]
+        }
+        hundred = 1..100
+        method = hundred.skip(97).to_array()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(ints(&[3, 4, 5]), get("skipped"));
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(1),
+            Primitive::U8(2),
+            Primitive::String("a".into()),
+            Primitive::String("b".into()),
+        ]),
+        get("chained")
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Array(vec![
+                Primitive::Int(0),
+                Primitive::String("a".into())
+            ]),
+            Primitive::Array(vec![
+                Primitive::Int(1),
+                Primitive::String("b".into())
+            ]),
+        ]),
+        get("pairs")
+    );
+    assert_eq!(ints(&[98, 99]), get("method"));
+    assert_eq!(ints(&[2, 4, 6]), get("doubled"));
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("0:1".into()),
+            Primitive::String("1:2".into()),
+        ]),
+        get("labels")
+    );
+
+    for (expr, message) in [
+        ("take(1, 2)", "take: expected an iterable, got u8"),
+        ("take([1], -1)", "take: expected a positive integer, got -1"),
+    ] {
+        let res = compute(expr, &mut ctx, "N/A");
+        assert!(
+            matches!(&res, Ok(Primitive::Error(e)) if e.to_string().contains(message)),
+            "{res:?}"
+        );
+    }
+}
+
+#[test]
+fn test_generator() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        calls = 0
+        naturals = (start) => {
+            n = start
+            while true {
+                calls = calls + 1
+                yield n
+                n = n + 1
+            }
+        }
+        gen = naturals(10)
+        before = calls
+        first = to_array(take(gen, 3))
+        after = calls
+        fib = () => {
+            a = 0
+            b = 1
+            while true {
+                yield a
+                next = a + b
+                a = b
+                b = next
+            }
+        }
+        fibs = fib().take(8).to_array()
+        finite = (items) => {
+            for item in items {
+                if item == 3 {
+                    return
+                }
+                yield item * 10
+            }
+        }
+        collected = []
+        for x in finite([1, 2, 3, 4]) {
+            collected = collected + [x]
+        }
+        kind = type_of(finite([]))
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::U8(0), get("before"));
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(10),
+            Primitive::Int(11),
+            Primitive::Int(12)
+        ]),
+        get("first")
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(0),
+            Primitive::U8(1),
+            Primitive::Int(1),
+            Primitive::Int(2),
+            Primitive::Int(3),
+            Primitive::Int(5),
+            Primitive::Int(8),
+            Primitive::Int(13),
+        ]),
+        get("fibs")
+    );
+    assert_eq!(
+        Primitive::Array(vec![Primitive::Int(10), Primitive::Int(20)]),
+        get("collected")
+    );
+    assert_eq!(Primitive::String("iterator".into()), get("kind"));
+}
+
+#[test]
+fn test_generator_errors() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        failing = () => {
+            yield 1
+            throw "boom"
+            yield 2
+        }
+        seen = []
+        message = "not thrown"
+        try {
+            for v in failing() {
+                seen = seen + [v]
+            }
+        } catch e {
+            message = e.message
+        }
+        collected = []
+        try {
+            collected = to_array(failing())
+        } catch e {
+            collected = "caught " + e.message
+        }
+        guarded = () => {
+            try {
+                yield 1
+                yield 2
+            } catch e {
+                yield "caught"
+            }
+        }
+        first = to_array(take(guarded(), 1))
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::Array(vec![Primitive::U8(1)]), get("seen"));
+    assert_eq!(Primitive::String("boom".into()), get("message"));
+    assert_eq!(Primitive::String("caught boom".into()), get("collected"));
+    assert_eq!(Primitive::Array(vec![Primitive::U8(1)]), get("first"));
+
+    let err = compute("yield 1", &mut ctx, "N/A").unwrap_err();
+    assert!(err.to_string().contains("yield outside of a generator"));
+    // `f(yield 1)`, the parser only reads `yield` as an instruction
+    let program = compile(vec![Value::FunctionCall {
+        parameters: Box::new(Value::BlockParen(vec![Value::Yield(Box::new(
+            Value::U8(1),
+        ))])),
+        function: Box::new(Value::Variable("f".into())),
+    }]);
+    assert!(
+        format!("{:?}", program.code)
+            .contains("yield cannot be used inside an expression"),
+        "{program:?}"
+    );
+}
+
+#[test]
+fn test_generator_paused_in_blocks() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        gen = (items) => {
+            for item in items {
+                if item % 2 == 0 {
+                    try {
+                        yield item
+                    } finally {
+                        yield "finally"
+                    }
+                } else {
+                    match item {
+                        1 => {
+                            yield "one"
+                        }
+                        n => {
+                            yield n * 10
+                        }
+                    }
+                }
+            }
+            after = "done"
+            yield after
+        }
+        produced = to_array(gen([1, 2, 3]))
+        retry = () => {
+            try {
+                throw "nope"
+            } catch e {
+                yield e.message
+                yield "again"
+            }
+        }
+        caught = to_array(retry())
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("one".into()),
+            Primitive::U8(2),
+            Primitive::String("finally".into()),
+            Primitive::Int(30),
+            Primitive::String("done".into()),
+        ]),
+        get("produced")
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("nope".into()),
+            Primitive::String("again".into()),
+        ]),
+        get("caught")
+    );
+}
+
+#[test]
+fn test_generator_runs_in_the_caller_context() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        const k = [1]
+        mutate = () => {
+            r = &k
+            r[0] = 7
+            yield 1
+        }
+        res = null
+        try {
+            res = to_array(mutate())
+        } catch e {
+            res = e.message
+        }
+        failing = () => {
+            yield 1
+            throw "boom"
+        }
+        stack = null
+        try {
+            stack = to_array(failing())
+        } catch e {
+            stack = e.stack
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert!(
+        matches!(get("res"), Primitive::String(s) if s.contains("constant k")),
+        "{:?}",
+        get("res")
+    );
+    assert_eq!(Primitive::Array(vec![Primitive::U8(1)]), get("k"));
+    let Primitive::Array(stack) = get("stack") else {
+        panic!("stack should be an array");
+    };
+    let functions = stack
+        .iter()
+        .map(|frame| frame.index_at(&Primitive::String("function".into())))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            Primitive::String("failing".into()),
+            Primitive::String("main".into())
+        ],
+        functions
+    );
+}
+
+#[test]
+#[serial]
+fn test_read_lines() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        count = 0
+        first = ""
+        for [n, line] in enumerate(read_lines("file_tests/test1.adana")) {
+            if n == 2 {
+                first = line
+            }
+            count = count + 1
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::Int(14), get("count"));
+    assert_eq!(
+        Primitive::String("# oops another comment here".into()),
+        get("first")
+    );
+
+    let missing = compute(
+        r#"read_lines("file_tests/does_not_exist.adana")"#,
+        &mut ctx,
+        "N/A",
+    );
+    assert!(matches!(missing, Ok(Primitive::Error(_))), "{missing:?}");
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::compute;
+
+#[test]
+fn test_continue() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        odds = []
+        for n in 0..10 {
+            if n % 2 == 0 {
+                continue
+            }
+            odds = odds + n
+        }
+        count = 0
+        skipped = 0
+        while count < 5 {
+            count = count + 1
+            if count == 3 {
+                skipped = skipped + 1
+                continue
+            }
+            total = count
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(
+            [1, 3, 5, 7, 9].into_iter().map(Primitive::Int).collect()
+        ),
+        ctx["odds"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(5), ctx["count"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(1), ctx["skipped"].read().unwrap().clone());
+}
+
+#[test]
+fn test_break_stops_the_block() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        after = 0
+        for n in [1, 2, 3] {
+            if n == 2 {
+                break
+                after = 1
+            }
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(0), ctx["after"].read().unwrap().clone());
+}
+
+#[test]
+fn test_labeled_break_and_continue() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        pairs = []
+        'outer: for i in 0..4 {
+            j = 0
+            while j < 4 {
+                j = j + 1
+                if j > i {
+                    continue 'outer
+                }
+                if i == 3 {
+                    break 'outer
+                }
+                pairs = pairs + [[i, j]]
+            }
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let pair =
+        |i, j| Primitive::Array(vec![Primitive::Int(i), Primitive::Int(j)]);
+    assert_eq!(
+        Primitive::Array(vec![pair(1, 1), pair(2, 1), pair(2, 2)]),
+        ctx["pairs"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_break_with_value() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        people = [struct {name: "Ada", age: 36}, struct {name: "Sam", age: 12}]
+        found = for p in people {
+            if p.age < 18 {
+                break p.name
+            }
+        }
+        grid = [[1, 2], [3, 4]]
+        cell = 'rows: for row in grid {
+            for x in row {
+                match x {
+                    3 => break 'rows "found 3",
+                    _ => continue
+                }
+            }
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("Sam".into()),
+        ctx["found"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("found 3".into()),
+        ctx["cell"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_loop_jump_outside_of_a_loop() {
+    for program in [
+        "continue",
+        "if true { break }",
+        "for x in [1] { break 'nope }",
+        "f = () => { continue }\nfor x in [1] { f() }",
+    ] {
+        let mut ctx = BTreeMap::new();
+        assert!(compute(program, &mut ctx, "N/A").is_err(), "{program}");
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::compute;
+
+#[test]
+fn test_match_literals_and_wildcard() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        describe = (x) => {
+            match x {
+                0 => "zero",
+                -1 => "minus one",
+                "hello" => "greeting",
+                true => "yes",
+                null => "nothing",
+                _ => "something else"
+            }
+        }
+        [describe(0), describe(-1), describe("hello"), describe(true),
+         describe(null), describe(3.5)]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("zero".into()),
+            Primitive::String("minus one".into()),
+            Primitive::String("greeting".into()),
+            Primitive::String("yes".into()),
+            Primitive::String("nothing".into()),
+            Primitive::String("something else".into()),
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_match_ranges() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        max = 100
+        grade = (score) => {
+            match score {
+                0..50 => "fail",
+                50..=79 => "pass",
+                80..=max => "distinction",
+                _ => "invalid"
+            }
+        }
+        [grade(12), grade(50), grade(79), grade(100), grade(101)]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("fail".into()),
+            Primitive::String("pass".into()),
+            Primitive::String("pass".into()),
+            Primitive::String("distinction".into()),
+            Primitive::String("invalid".into()),
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_match_types() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        normalize = (v) => {
+            match v {
+                n: u8 => n * 2,
+                s: string => length(s),
+                array => "array",
+                struct => "struct",
+                _ => type_of(v)
+            }
+        }
+        [normalize(4), normalize("abc"), normalize([1]), normalize(struct {a: 1}),
+         normalize(1.5)]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Int(8),
+            Primitive::Int(3),
+            Primitive::String("array".into()),
+            Primitive::String("struct".into()),
+            Primitive::String("double".into()),
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_match_array_destructuring() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        shape = (arr) => {
+            match arr {
+                [] => "empty",
+                [x] => "one: " + to_string(x),
+                [first, .., 0] => "ends with zero",
+                [first, ..rest] => to_string(first) + " then " + to_string(length(rest)),
+            }
+        }
+        [shape([]), shape([7]), shape([1, 2, 0]), shape([1, 2, 3])]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("empty".into()),
+            Primitive::String("one: 7".into()),
+            Primitive::String("ends with zero".into()),
+            Primitive::String("1 then 2".into()),
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_match_struct_destructuring() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        greet = (p) => {
+            match p {
+                struct { name, age: 0..18, .. } => "hi " + name,
+                struct { name, age } => "hello " + name,
+                struct { name, ..others } => name + " has " + to_string(length(others)) + " other fields",
+                _ => "who?"
+            }
+        }
+        [
+            greet(struct { name: "Sam", age: 12, city: "Paris" }),
+            greet(struct { name: "Ada", age: 36 }),
+            greet(struct { name: "Bob", age: 40, city: "Rome" }),
+            greet("Bob")
+        ]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("hi Sam".into()),
+            Primitive::String("hello Ada".into()),
+            Primitive::String("Bob has 2 other fields".into()),
+            Primitive::String("who?".into()),
+        ]),
+        res
+    );
+}
+
+#[test]
+fn test_match_guards_and_scope() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        count = 0
+        sign = (n) => {
+            match n {
+                x if x < 0 => "negative",
+                0 => "zero",
+                _ => "positive"
+            }
+        }
+        res = match sign(-5) {
+            "negative" => {
+                count = count + 1
+                "neg"
+            }
+            s => s
+        }
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::String("neg".into()), res);
+    assert_eq!(Primitive::Int(1), ctx["count"].read().unwrap().clone());
+    assert!(!ctx.contains_key("x"));
+    assert!(!ctx.contains_key("s"));
+}
+
+#[test]
+fn test_match_no_arm() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        match 5 {
+            1 => "one"
+        }
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+}
+
+#[test]
+fn test_match_error_and_regex_builtin() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        check = (n) => {
+            if n > 2 {
+                make_err("too big")
+            } else {
+                n
+            }
+        }
+        safe = (n) => {
+            match check(n) {
+                e: error => e.message,
+                v => v
+            }
+        }
+        [safe(5), safe(1), match("a1", "\\d")]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("too big".into()),
+            Primitive::U8(1),
+            Primitive::Array(vec![Primitive::String("1".into())]),
+        ]),
+        res
+    );
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::{compute, tests::u8s};
+
+#[test]
+fn test_builtin_method_calls() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        upper = "abc".to_upper()
+        arr = [3, 1, 2]
+        len = arr.length()
+        sorted = arr.sort().reverse()
+        doubled = arr.map((x) => { x * 2 }).filter((x) => { x > 2 })
+        joined = "a,b,c".split(",").join("-")
+        s = "  hello  "
+        trimmed = s.trim().to_upper().length()
+        d = dict { 1: "one" }
+        k = d.keys()
+        u = set { 1, 2 }.union(set { 3 })
+        n = 16
+        root = n.sqrt()
+        starts = "adana".starts_with("ad")
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("ABC".into()),
+        ctx["upper"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(3), ctx["len"].read().unwrap().clone());
+    assert_eq!(u8s(&[3, 2, 1]), ctx["sorted"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::Int(6), Primitive::Int(4)]),
+        ctx["doubled"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("a-b-c".into()),
+        ctx["joined"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(5), ctx["trimmed"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![Primitive::Int(1)]),
+        ctx["k"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Int(3),
+        compute("length(u)", &mut ctx, "N/A").unwrap()
+    );
+    assert_eq!(Primitive::Double(4.), ctx["root"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(true), ctx["starts"].read().unwrap().clone());
+    // the receiver is left untouched
+    assert_eq!(u8s(&[3, 1, 2]), ctx["arr"].read().unwrap().clone());
+}
+
+#[test]
+fn test_struct_self() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        counter = struct {
+            count: 0,
+            increment: () => {
+                self.count = self.count + 1
+                self
+            },
+            describe: () => { """count is ${self.count}""" },
+        }
+        description = counter.describe()
+        copy = counter.increment()
+        copy_count = copy.count
+        count_after_copy = counter.count
+        &counter.increment()
+        &counter.increment()
+        count = counter.count
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("count is 0".into()),
+        ctx["description"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(1), ctx["copy_count"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::U8(0),
+        ctx["count_after_copy"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(2), ctx["count"].read().unwrap().clone());
+}
+
+#[test]
+fn test_member_shadows_builtin() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        s = struct {
+            length: () => { 42 },
+            name: "adana",
+        }
+        own = s.length()
+        keys_of = s.keys()
+        name_len = s.name.length()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(42), ctx["own"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("length".into()),
+            Primitive::String("name".into())
+        ]),
+        ctx["keys_of"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(5), ctx["name_len"].read().unwrap().clone());
+}
+
+#[test]
+fn test_method_errors() {
+    for program in [
+        "arr = [1]\narr.length(1)",
+        r#""abc".unknown()"#,
+        r#"x = 1
+        x.split(",")"#,
+    ] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+    }
+}
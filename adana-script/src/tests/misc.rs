@@ -125,7 +125,7 @@ fn test_variable_expr_2() {
 #[test]
 fn test_modulo() {
     let mut ctx = BTreeMap::new();
-    assert_eq!(Primitive::U8(1), compute("3%2", &mut ctx, "N/A").unwrap());
+    assert_eq!(Primitive::Int(1), compute("3%2", &mut ctx, "N/A").unwrap());
     assert_eq!(
         Primitive::Double(1.),
         compute("3%2.", &mut ctx, "N/A").unwrap()
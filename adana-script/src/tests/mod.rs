@@ -1,21 +1,86 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::compute;
+
 mod array;
 mod bitwise;
 mod builtin;
 mod chaining;
+mod check;
+mod closure;
+mod constant;
+mod decimal;
+mod destructure;
+mod diagnostic;
+mod dict;
 mod drop;
 mod dynload;
 mod empty_block;
+mod enums;
+mod error;
 mod examples;
 mod file;
 mod foreach;
 mod funct;
+mod higher_order;
+mod integer;
 mod is_type;
+mod iterator;
+mod loops;
+mod match_expr;
+mod method;
 mod misc;
+mod module;
+mod null_safe;
 mod opassign;
 mod parser;
+mod quantity;
 mod range;
 mod reference;
 mod scope_ctx;
+mod set;
+mod slice;
 mod strings;
 mod struc;
+mod try_catch;
 mod unused;
+mod vm;
+
+/// the result of a script run in an empty context
+fn eval(expr: &str) -> Primitive {
+    let mut ctx = BTreeMap::new();
+    compute(expr, &mut ctx, "N/A").unwrap()
+}
+
+/// the value with the ranges it holds collected into arrays
+fn collected(p: Primitive) -> Primitive {
+    match p {
+        Primitive::Iterator(it) => Primitive::Array(it.restart().collect()),
+        Primitive::Array(arr) => {
+            Primitive::Array(arr.into_iter().map(collected).collect())
+        }
+        Primitive::Struct(s) => Primitive::Struct(
+            s.into_iter().map(|(k, v)| (k, collected(v))).collect(),
+        ),
+        p => p,
+    }
+}
+
+/// an array of ints
+fn ints(values: &[i128]) -> Primitive {
+    Primitive::Array(values.iter().copied().map(Primitive::Int).collect())
+}
+
+/// an array of u8, the type of small integer literals
+fn u8s(values: &[u8]) -> Primitive {
+    Primitive::Array(values.iter().copied().map(Primitive::U8).collect())
+}
+
+/// an array of strings
+fn strings(values: &[&str]) -> Primitive {
+    Primitive::Array(
+        values.iter().map(|s| Primitive::String(s.to_string())).collect(),
+    )
+}
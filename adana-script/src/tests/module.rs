@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+use serial_test::serial;
+
+use crate::compute;
+
+#[test]
+#[serial]
+fn test_import_exports() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        import "file_tests/modules/utils.adana" as utils
+        doubled = utils.double(4)
+        fact = utils.fact(5)
+        version = utils.version
+        color = utils.Color.Green
+        hidden = utils.base
+        names = length(utils)
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::Int(8), get("doubled"));
+    assert_eq!(Primitive::Int(120), get("fact"));
+    assert_eq!(Primitive::String("1.0".into()), get("version"));
+    assert_eq!("Color.Green", get("color").to_string());
+    assert_eq!(Primitive::Null, get("hidden"));
+    assert_eq!(Primitive::Int(4), get("names"));
+    assert!(!ctx.contains_key("twice"));
+    assert!(!ctx.contains_key("base"));
+}
+
+#[test]
+#[serial]
+fn test_import_relative_to_module() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        import "file_tests/modules/geometry.adana" as geometry
+        import "./file_tests/modules/utils.adana" as utils
+        area = geometry.area(3, 4)
+        version = utils.version
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(12), ctx["area"].read().unwrap().clone());
+}
+
+#[test]
+#[serial]
+fn test_import_errors() {
+    let mut ctx = BTreeMap::new();
+    let err = compute(
+        r#"import "file_tests/modules/cycle_a.adana" as a"#,
+        &mut ctx,
+        "N/A",
+    )
+    .unwrap_err();
+    let message = format!("{err:#}");
+    assert!(message.contains("circular import"), "{message}");
+    assert!(message.contains("cycle_a.adana -> "), "{message}");
+
+    let err = compute(
+        r#"import "file_tests/modules/does_not_exist.adana" as a"#,
+        &mut ctx,
+        "N/A",
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("could not import"));
+
+    let program = r#"
+        if true {
+            export x = 1
+        }
+    "#;
+    assert!(compute(program, &mut ctx, "N/A").is_err());
+}
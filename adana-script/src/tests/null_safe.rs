@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::compute;
+
+#[test]
+fn test_optional_access() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        data = parse_json("""{"user": {"name": "ada", "tags": ["a", "b"], "address": null}}""")
+        name = data?.user?.name
+        city = data?.user?.address?.city
+        postcode = data?.user?.address?.city.zip
+        tag = data?.user?.tags?[1]
+        missing_tag = data?.user?.tags?[5]
+        n = null
+        skipped = n?.a.b.c
+        s = struct { name: "x", greet: () => { "hi " + self.name } }
+        greeting = s?.greet()
+        no_greeting = n?.greet()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let s = |s: &str| Primitive::String(s.into());
+    assert_eq!(s("ada"), ctx["name"].read().unwrap().clone());
+    assert_eq!(s("b"), ctx["tag"].read().unwrap().clone());
+    assert_eq!(s("hi x"), ctx["greeting"].read().unwrap().clone());
+    for name in ["city", "postcode", "missing_tag", "skipped", "no_greeting"] {
+        assert_eq!(
+            Primitive::Null,
+            ctx[name].read().unwrap().clone(),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_null_coalesce() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        n = null
+        a = n ?? "default"
+        b = to_int("abc") ?? 0
+        c = 3 ?? 4
+        d = n?.x ?? "fallback"
+        e = null ?? 1 + 2
+        f = [1, 2]?[7] ?? 9
+        g = false ?? true
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::String("default".into()),
+        ctx["a"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::U8(0), ctx["b"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(3), ctx["c"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("fallback".into()),
+        ctx["d"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(3), ctx["e"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(9), ctx["f"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(false), ctx["g"].read().unwrap().clone());
+}
+
+#[test]
+fn test_null_coalesce_catches_and_short_circuits() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        j = struct { x: 1 }
+        a = j.x.y ?? 8
+        b = missing_variable ?? 2
+        fail = () => {
+            throw "boom"
+        }
+        c = fail() ?? "caught"
+        # the right operand only runs when the left one is missing
+        d = 5 ?? fail()
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let get = |name: &str| ctx[name].read().unwrap().clone();
+    assert_eq!(Primitive::U8(8), get("a"));
+    assert_eq!(Primitive::U8(2), get("b"));
+    assert_eq!(Primitive::String("caught".into()), get("c"));
+    assert_eq!(Primitive::U8(5), get("d"));
+}
+
+#[test]
+fn test_null_safe_errors() {
+    for program in ["n = null\nn.a", "s = struct { a: 1 }\ns?.a = 2", "?? 1"] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+    }
+}
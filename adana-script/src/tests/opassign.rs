@@ -25,7 +25,7 @@ fn test_op_assign_sub() {
         x-=1
         x
     "#;
-    assert_eq!(Primitive::U8(1), compute(script, &mut ctx, "N/A").unwrap());
+    assert_eq!(Primitive::Int(1), compute(script, &mut ctx, "N/A").unwrap());
 }
 
 #[test]
@@ -61,5 +61,5 @@ fn test_op_assign_mod() {
         x%=5
         x
     "#;
-    assert_eq!(Primitive::U8(2), compute(script, &mut ctx, "N/A").unwrap());
+    assert_eq!(Primitive::Int(2), compute(script, &mut ctx, "N/A").unwrap());
 }
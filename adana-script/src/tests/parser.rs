@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use indexmap::IndexMap;
 
 use crate::parser::parse_instructions;
 use adana_script_core::{
@@ -240,7 +240,7 @@ fn test_struct1() {
     let (res, struc) = parse_instructions(expr).unwrap();
     assert_eq!("", res);
     assert_eq!(
-        vec![Value::Struct(BTreeMap::from([("x".into(), Value::U8(99))]))],
+        vec![Value::Struct(IndexMap::from([("x".into(), Value::U8(99))]))],
         struc
     );
 }
@@ -256,7 +256,7 @@ fn test_struct_array() {
             expr: Box::new(Value::Array(vec![
                 Value::U8(1,),
                 Value::U8(2,),
-                Value::Struct(BTreeMap::from([(
+                Value::Struct(IndexMap::from([(
                     "x".to_string(),
                     Value::Function {
                         parameters: Box::new(Value::BlockParen(vec![
@@ -294,7 +294,7 @@ fn test_struct_array2() {
             expr: Box::new(Value::Array(vec![
                 Value::U8(1,),
                 Value::U8(2,),
-                Value::Struct(BTreeMap::from([(
+                Value::Struct(IndexMap::from([(
                     "x".to_string(),
                     Value::Function {
                         parameters: Box::new(Value::BlockParen(vec![
@@ -350,7 +350,7 @@ fn test_struct2() {
         struc,
         vec![Value::VariableExpr {
             name: Box::new(Value::Variable("my".to_string(),)),
-            expr: Box::new(Value::Struct(BTreeMap::from([
+            expr: Box::new(Value::Struct(IndexMap::from([
                 ("a".into(), Value::U8(7,)),
                 (
                     "aa".into(),
@@ -598,7 +598,7 @@ fn test_struct_access_1() {
         vec![
             Value::VariableExpr {
                 name: Box::new(Value::Variable("person".to_string(),)),
-                expr: Box::new(Value::Struct(BTreeMap::from([
+                expr: Box::new(Value::Struct(IndexMap::from([
                     ("name".to_string(), Value::String("nordine".to_string(),)),
                     ("age".to_string(), Value::U8(34,),)
                 ]),)),
@@ -734,3 +734,68 @@ fn test_bug_fn_hello_a() {
         },]
     )
 }
+
+#[test]
+fn test_parse_null_safe() {
+    let expr = r#"a?.b?[0].c ?? 1"#;
+    let (r, v) = parse_instructions(expr).unwrap();
+    assert_eq!("", r);
+    assert_eq!(
+        v,
+        vec![Expression(vec![
+            Value::MultiDepthAccess {
+                root: Box::new(Variable("a".into())),
+                next_keys: vec![
+                    KeyAccess::Optional(Box::new(KeyAccess::Key(
+                        Primitive::String("b".into())
+                    ))),
+                    KeyAccess::Optional(Box::new(KeyAccess::Variable(
+                        Value::U8(0)
+                    ))),
+                    KeyAccess::Key(Primitive::String("c".into())),
+                ],
+            },
+            Operation(Operator::NullCoalesce),
+            Value::U8(1),
+        ])]
+    );
+}
+
+#[test]
+fn test_parse_import_export() {
+    let expr = r#"
+        import "./utils.adana" as utils
+        export const x = utils.y
+        export f = (a) => { a }
+    "#;
+    let (r, v) = parse_instructions(expr).unwrap();
+    assert_eq!("", r);
+    assert_eq!(
+        v[0],
+        Value::Import { path: "./utils.adana".into(), alias: "utils".into() }
+    );
+    let Value::Export(declaration) = v[1].unspanned() else {
+        panic!("expected an export, got {:?}", v[1]);
+    };
+    assert!(matches!(
+        declaration.unspanned(),
+        Value::Declaration { constant: true, .. }
+    ));
+    assert!(matches!(v[2].unspanned(), Value::Export(_)));
+
+    // only variables can be exported
+    assert!(!matches!(
+        parse_instructions("export utils.x = 1"),
+        Ok((r, _)) if r.is_empty()
+    ));
+}
+
+#[test]
+fn test_parse_yield() {
+    let (r, v) = parse_instructions("yield x * 2").unwrap();
+    assert_eq!("", r);
+    let Value::Yield(value) = v[0].unspanned() else {
+        panic!("expected a yield, got {:?}", v[0]);
+    };
+    assert!(matches!(value.unspanned(), Value::Expression(_)));
+}
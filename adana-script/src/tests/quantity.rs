@@ -0,0 +1,134 @@
+use adana_script_core::primitive::{Primitive, Quantity, Unit};
+
+use super::eval;
+
+fn quantity(value: f64, unit: Unit) -> Primitive {
+    Primitive::Quantity(Quantity::new(value, unit))
+}
+
+#[test]
+fn test_quantity_arithmetic() {
+    assert_eq!(quantity(5., Unit::Kilobyte), eval("5kb"));
+    assert_eq!(quantity(20., Unit::Celsius), eval("20 °C"));
+    assert_eq!(quantity(3005., Unit::Kilobyte), eval("5kb + 3mb"));
+    assert_eq!(quantity(2.5, Unit::Hour), eval("3h - 30min"));
+    assert_eq!(quantity(6., Unit::Hour), eval("2 * 3h"));
+    assert_eq!(quantity(1.5, Unit::Metre), eval("3m / 2"));
+    assert_eq!(quantity(10., Unit::Minute), eval("70min % 1h"));
+    assert_eq!(quantity(-5., Unit::Kilobyte), eval("-5kb"));
+    assert_eq!(Primitive::Double(1000.), eval("1gb / 1mb"));
+    assert_eq!(quantity(180., Unit::Minute), eval("x = 90min\nx * 2"));
+    assert_eq!(quantity(2.5, Unit::Centimetre), eval("round(2.54cm, 1)"));
+
+    assert_eq!(Primitive::Bool(true), eval("5kb > 4999b"));
+    assert_eq!(Primitive::Bool(true), eval("1h == 60min"));
+    assert_eq!(Primitive::Bool(true), eval("1mi < 2km"));
+    assert_eq!(Primitive::String("quantity".into()), eval("type_of(5kb)"));
+    assert_eq!(
+        Primitive::String("total: 5 kb".into()),
+        eval(r#""total: " + 5kb"#)
+    );
+    assert_eq!(Primitive::Double(5.), eval("to_double(5kb)"));
+}
+
+#[test]
+fn test_unit_shadowed_by_variable() {
+    // a variable in scope keeps the implicit multiplication, with or without
+    // a space
+    assert_eq!(Primitive::Int(6), eval("m = 2\n3m"));
+    assert_eq!(Primitive::Int(7), eval("s = 2\n3s + 1"));
+    assert_eq!(Primitive::Int(10), eval("f = (h) => { 5h }\nf(2)"));
+    assert_eq!(quantity(3., Unit::Metre), eval("f = () => { 3m }\nf()"));
+    assert_eq!(Primitive::Int(6), eval("m = 2\n3 m"));
+    assert_eq!(quantity(3., Unit::Metre), eval("3 m"));
+    assert_eq!(quantity(2., Unit::Minute), eval("h = 1\n2min"));
+    assert_eq!(quantity(1.5, Unit::Hour), eval("h = 1\n90min to h"));
+}
+
+#[test]
+fn test_quantity_conversion() {
+    assert_eq!(quantity(1.5, Unit::Hour), eval("90min to h"));
+    assert_eq!(quantity(68., Unit::Fahrenheit), eval("20 °C to °F"));
+    assert_eq!(quantity(20., Unit::Celsius), eval("68 °F to °C"));
+    assert_eq!(quantity(26.85, Unit::Celsius), eval("300K to °C"));
+    assert_eq!(quantity(293.15, Unit::Kelvin), eval("68 °F to K"));
+    assert_eq!(quantity(91.44, Unit::Centimetre), eval("3 ft in cm"));
+    assert_eq!(quantity(1.609344, Unit::Kilometre), eval("1mi to km"));
+    assert_eq!(quantity(453.59237, Unit::Gram), eval("1lb to g"));
+    assert_eq!(quantity(1024., Unit::Byte), eval("1kib to b"));
+    assert_eq!(quantity(3005., Unit::Kilobyte), eval("5kb + 3mb to kb"));
+    assert_eq!(quantity(3.005, Unit::Megabyte), eval("(5kb + 3mb) to mb"));
+    assert_eq!(quantity(1.5, Unit::Hour), eval("x = 90min\ny = x to h\ny"));
+    assert_eq!(
+        Primitive::String("1.5 h".into()),
+        eval("x = 90min\nto_string(x to h)")
+    );
+}
+
+#[test]
+fn test_quantity_in_expressions() {
+    let kbs = |values: &[f64]| {
+        Primitive::Array(
+            values.iter().map(|v| quantity(*v, Unit::Kilobyte)).collect(),
+        )
+    };
+    assert_eq!(
+        quantity(5000., Unit::Kilobyte),
+        eval("f = (x) => { x to kb }\nf(5mb)")
+    );
+    assert_eq!(
+        quantity(2000., Unit::Byte),
+        eval(
+            r#"
+            f = (x) => {
+                y = x + 1kb
+                y to b
+            }
+            f(1kb)
+        "#
+        )
+    );
+    assert_eq!(
+        quantity(1., Unit::Kilobyte),
+        eval("f = (x) => {\n return x to kb\n}\nf(1000b)")
+    );
+    assert_eq!(kbs(&[5.]), eval("[5kb]"));
+    assert_eq!(kbs(&[1., 2000.]), eval("[1kb, 2mb to kb]"));
+    assert_eq!(kbs(&[1., 2.]), eval("[1 kb, 2kb]"));
+    assert_eq!(
+        quantity(1000., Unit::Byte),
+        eval("s = struct { a: 1kb to b }\ns.a")
+    );
+    assert_eq!(
+        quantity(1000., Unit::Byte),
+        eval("d = dict { 1: 1kb to b }\nd[1]")
+    );
+    assert_eq!(Primitive::Bool(true), eval("(1kb to b) > 10b"));
+}
+
+#[test]
+fn test_quantity_errors() {
+    for (expr, message) in [
+        ("5kb + 3s", "cannot mix data size and time"),
+        ("5kb + 1", "expected a quantity"),
+        ("2kb * 3kb", "compound unit"),
+        ("2 / 3s", "compound unit"),
+        ("20 °C + 10 °F", "convert °F to °C first"),
+        ("5kb to h", "cannot convert kb (data size) to h (time)"),
+        ("5 to h", "illegal call to convert()"),
+    ] {
+        let res = eval(expr);
+        assert!(
+            matches!(&res, Primitive::Error(e) if e.to_string().contains(message)),
+            "{expr}: {res:?}"
+        );
+    }
+}
+
+#[test]
+fn test_unit_symbols() {
+    for unit in Unit::ALL {
+        assert_eq!(Some(unit), Unit::from_symbol(unit.symbol()));
+    }
+    assert_eq!(None, Unit::from_symbol("parsec"));
+}
@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
 
-use crate::compute;
+use indexmap::IndexMap;
+
+use crate::{compute, tests::collected};
 use adana_script_core::primitive::Primitive;
 #[test]
 fn simple_foreach_range() {
@@ -30,7 +32,7 @@ fn simple_range() {
             Primitive::Int(3),
             Primitive::Int(4),
         ]),
-        ctx["arr"].read().unwrap().clone()
+        collected(ctx["arr"].read().unwrap().clone())
     );
     assert!(!ctx.contains_key("a"));
 }
@@ -55,7 +57,7 @@ fn simple_range_in_array() {
                 Primitive::Int(4),
             ])
         ]),
-        ctx["arr"].read().unwrap().clone()
+        collected(ctx["arr"].read().unwrap().clone())
     );
     assert_eq!(
         Primitive::Array(vec![
@@ -71,7 +73,7 @@ fn simple_range_in_array() {
                 Primitive::Int(5),
             ])
         ]),
-        ctx["arr2"].read().unwrap().clone()
+        collected(ctx["arr2"].read().unwrap().clone())
     );
 }
 #[test]
@@ -91,7 +93,7 @@ fn simple_range_in_function() {
             Primitive::Int(3),
             Primitive::Int(4),
         ]),
-        ctx["arr"].read().unwrap().clone()
+        collected(ctx["arr"].read().unwrap().clone())
     );
     assert_eq!(
         Primitive::Array(vec![
@@ -101,7 +103,7 @@ fn simple_range_in_function() {
             Primitive::Int(4),
             Primitive::Int(5),
         ]),
-        ctx["arr2"].read().unwrap().clone()
+        collected(ctx["arr2"].read().unwrap().clone())
     );
 }
 #[test]
@@ -118,7 +120,7 @@ fn simple_range_struct() {
     let mut ctx = BTreeMap::new();
     let _ = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "a".to_string(),
                 Primitive::Array(vec![
@@ -140,7 +142,7 @@ fn simple_range_struct() {
             ),
             ("x".to_string(), Primitive::U8(2)),
         ])),
-        ctx["s"].read().unwrap().clone()
+        collected(ctx["s"].read().unwrap().clone())
     );
 }
 
@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use adana_script_core::primitive::{DictKey, Primitive};
+
+use crate::compute;
+
+#[test]
+fn test_set_literal() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        x = 2
+        s = set {
+            3,
+            1, # comment
+            x,
+            "a",
+            true,
+            [1, 2],
+            1,
+        }
+        len = length(s)
+        t = type_of(s)
+        is_s = is_set(s)
+        is_a = is_set([1])
+        empty = set {}
+        l = set { 1, 1.0 / 1.0 == 1, 200 }
+        r = set { 200, 1, true }
+        normalized = l == r
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Set(BTreeSet::from([
+            DictKey::Int(1),
+            DictKey::Int(2),
+            DictKey::Int(3),
+            DictKey::String("a".into()),
+            DictKey::Bool(true),
+            DictKey::Array(vec![DictKey::Int(1), DictKey::Int(2)]),
+        ])),
+        ctx["s"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Int(6), ctx["len"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("set".into()),
+        ctx["t"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Bool(true), ctx["is_s"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(false), ctx["is_a"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Set(BTreeSet::new()),
+        ctx["empty"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Bool(true),
+        ctx["normalized"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_set_builtins() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        s = set { 1, 2, 3 }
+        added = add(s, 4)
+        added_twice = add(added, 4)
+        removed = remove(s, 2)
+        has_one = contains(s, 1)
+        has_ten = contains(s, 10)
+        u = union(s, set { 3, 4 })
+        u_arr = union(s, [5, 5])
+        i = intersection(s, set { 2, 3, 4 })
+        d = difference(s, [1, 4])
+        len = length(s)
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let set = |values: &[i128]| {
+        Primitive::Set(values.iter().map(|v| DictKey::Int(*v)).collect())
+    };
+    assert_eq!(set(&[1, 2, 3, 4]), ctx["added"].read().unwrap().clone());
+    assert_eq!(set(&[1, 2, 3, 4]), ctx["added_twice"].read().unwrap().clone());
+    assert_eq!(set(&[1, 3]), ctx["removed"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(true), ctx["has_one"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(false), ctx["has_ten"].read().unwrap().clone());
+    assert_eq!(set(&[1, 2, 3, 4]), ctx["u"].read().unwrap().clone());
+    assert_eq!(set(&[1, 2, 3, 5]), ctx["u_arr"].read().unwrap().clone());
+    assert_eq!(set(&[2, 3]), ctx["i"].read().unwrap().clone());
+    assert_eq!(set(&[2, 3]), ctx["d"].read().unwrap().clone());
+    // builtins return a new set
+    assert_eq!(Primitive::Int(3), ctx["len"].read().unwrap().clone());
+}
+
+#[test]
+fn test_set_foreach() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        s = set { 3, 1, 2 }
+        vals = []
+        indexes = []
+        for i, v in s {
+            vals = vals + v
+            indexes = indexes + i
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Int(1),
+            Primitive::Int(2),
+            Primitive::Int(3)
+        ]),
+        ctx["vals"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Int(0),
+            Primitive::Int(1),
+            Primitive::Int(2)
+        ]),
+        ctx["indexes"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_set_conversions() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        s = to_set([2, 1, 2, "a"])
+        arr = to_array(s)
+        same = to_set(s)
+        json = jsonify(set { 1, "b" })
+        back = to_set(parse_json(json))
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    let expected = Primitive::Set(BTreeSet::from([
+        DictKey::Int(1),
+        DictKey::Int(2),
+        DictKey::String("a".into()),
+    ]));
+    assert_eq!(expected, ctx["s"].read().unwrap().clone());
+    assert_eq!(expected, ctx["same"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::Int(1),
+            Primitive::Int(2),
+            Primitive::String("a".into())
+        ]),
+        ctx["arr"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("[\n  1,\n  \"b\"\n]".into()),
+        ctx["json"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::Set(BTreeSet::from([
+            DictKey::Int(1),
+            DictKey::String("b".into())
+        ])),
+        ctx["back"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_set_errors() {
+    for program in [
+        "set { 1.5 }",
+        "set { struct {} }",
+        "add(set {}, 1.5)",
+        "union(set {}, 1)",
+        "to_set([null])",
+        "to_array(1)",
+    ] {
+        let mut ctx = BTreeMap::new();
+        let res = compute(program, &mut ctx, "N/A");
+        assert!(
+            matches!(res, Err(_) | Ok(Primitive::Error(_))),
+            "{program} => {res:?}"
+        );
+    }
+}
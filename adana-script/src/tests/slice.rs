@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::{Array, Primitive};
+
+use crate::{compute, tests::u8s};
+
+#[test]
+fn test_negative_index() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        arr = [1, 2, 3]
+        s = "héllo"
+        res = [arr[-1], arr[-3], s[-1], s[1]]
+        arr[-1] = 9
+        s[-1] = "a"
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::U8(3),
+            Primitive::U8(1),
+            Primitive::String("o".into()),
+            Primitive::String("é".into()),
+        ]),
+        ctx["res"].read().unwrap().clone()
+    );
+    assert_eq!(u8s(&[1, 2, 9]), ctx["arr"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("hélla".into()),
+        ctx["s"].read().unwrap().clone()
+    );
+    let res = compute("[1, 2, 3][-4]", &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+}
+
+#[test]
+fn test_slice() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        arr = [1, 2, 3, 4, 5]
+        start = 1
+        a = arr[1..3]
+        b = arr[..=2]
+        c = arr[3..]
+        d = arr[-2..]
+        e = arr[start..-1]
+        f = arr[..]
+        g = arr[4..100]
+        h = arr[3..1]
+        s = "héllo world"
+        i = s[2..]
+        j = s[..=4]
+        k = "adana"[1..4]
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(u8s(&[2, 3]), ctx["a"].read().unwrap().clone());
+    assert_eq!(u8s(&[1, 2, 3]), ctx["b"].read().unwrap().clone());
+    assert_eq!(u8s(&[4, 5]), ctx["c"].read().unwrap().clone());
+    assert_eq!(u8s(&[4, 5]), ctx["d"].read().unwrap().clone());
+    assert_eq!(u8s(&[2, 3, 4]), ctx["e"].read().unwrap().clone());
+    assert_eq!(u8s(&[1, 2, 3, 4, 5]), ctx["f"].read().unwrap().clone());
+    assert_eq!(u8s(&[5]), ctx["g"].read().unwrap().clone());
+    assert_eq!(u8s(&[]), ctx["h"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("llo world".into()),
+        ctx["i"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("héllo".into()),
+        ctx["j"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("dan".into()),
+        ctx["k"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_slice_assignment() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        arr = [1, 2, 3, 4, 5]
+        arr[0..2] = [9, 9]
+        grow = [1, 2, 3]
+        grow[1..2] = [7, 7, 7]
+        shrink = [1, 2, 3, 4]
+        shrink[1..] = []
+        nested = struct { values: [1, 2, 3] }
+        nested.values[..=1] = [0]
+        s = "hello"
+        s[..1] = "J"
+        cut = [1, 2, 3, 4]
+        drop(cut[1..3])
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(u8s(&[9, 9, 3, 4, 5]), ctx["arr"].read().unwrap().clone());
+    assert_eq!(u8s(&[1, 7, 7, 7, 3]), ctx["grow"].read().unwrap().clone());
+    assert_eq!(u8s(&[1]), ctx["shrink"].read().unwrap().clone());
+    assert_eq!(
+        u8s(&[0, 3]),
+        ctx["nested"]
+            .read()
+            .unwrap()
+            .index_at(&Primitive::String("values".into()))
+    );
+    assert_eq!(
+        Primitive::String("Jello".into()),
+        ctx["s"].read().unwrap().clone()
+    );
+    assert_eq!(u8s(&[1, 4]), ctx["cut"].read().unwrap().clone());
+
+    let res = compute(r#"arr[0..1] = "x""#, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+}
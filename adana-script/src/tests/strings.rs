@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use crate::compute;
+use crate::{compute, tests::strings};
 
 use adana_script_core::primitive::Primitive;
 #[test]
@@ -228,3 +228,79 @@ fn parse_f_strings_fn() {
         ctx["s0"].read().unwrap().clone()
     );
 }
+
+#[test]
+fn test_split_join_lines_chars() {
+    let mut ctx = BTreeMap::new();
+    let res = compute(r#"split("a,b,,c", ",")"#, &mut ctx, "N/A").unwrap();
+    assert_eq!(strings(&["a", "b", "", "c"]), res);
+    let res = compute(r#"split("héllo", "")"#, &mut ctx, "N/A").unwrap();
+    assert_eq!(strings(&["h", "é", "l", "l", "o"]), res);
+    let res = compute(r#"join(["a", 1, true], "-")"#, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::String("a-1-true".into()), res);
+    let res =
+        compute(r#"join(split("a b c", " "), "")"#, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::String("abc".into()), res);
+    let res =
+        compute(r#"lines("first\nsecond\r\nthird")"#, &mut ctx, "N/A").unwrap();
+    assert_eq!(strings(&["first", "second", "third"]), res);
+    let res = compute(r#"chars("ça va")"#, &mut ctx, "N/A").unwrap();
+    assert_eq!(strings(&["ç", "a", " ", "v", "a"]), res);
+    let res = compute(r#"split(1, ",")"#, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+}
+
+#[test]
+fn test_trim_and_pad() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        s = "  adana  "
+        res = [trim(s), trim_start(s), trim_end(s), pad_left("7", 3, "0"),
+         pad_right("ab", 5), pad_left("abcdef", 3), pad_left("x", 4, "ab"),
+         repeat("ab", 3), repeat("ab", 0)]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        strings(&[
+            "adana", "adana  ", "  adana", "007", "ab   ", "abcdef", "abax",
+            "ababab", ""
+        ]),
+        res
+    );
+    let res = compute(r#"repeat("ab", -1)"#, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+    let res = compute(r#"pad_left("ab", 5, "")"#, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+}
+
+#[test]
+fn test_substring_and_search() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        s = "héllo world"
+        res = [substring(s, 1, 5), substring(s, 6), substring(s, 6, 100),
+         contains(s, "wor"), contains(s, "xyz"), starts_with(s, "hé"),
+         ends_with(s, "world"), ends_with(s, "hello"), index_of(s, "o"),
+         index_of(s, "xyz")]
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("éllo".into()),
+            Primitive::String("world".into()),
+            Primitive::String("world".into()),
+            Primitive::Bool(true),
+            Primitive::Bool(false),
+            Primitive::Bool(true),
+            Primitive::Bool(true),
+            Primitive::Bool(false),
+            Primitive::Int(4),
+            Primitive::Null,
+        ]),
+        res
+    );
+    let res = compute(r#"substring("abc", 2, 1)"#, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+    let res = compute(r#"contains("abc")"#, &mut ctx, "N/A").unwrap();
+    assert!(matches!(res, Primitive::Error(_)));
+}
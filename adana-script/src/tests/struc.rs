@@ -1,10 +1,15 @@
 use std::collections::BTreeMap;
 
+use indexmap::IndexMap;
+
 use serial_test::serial;
 
 use crate::compute;
 
-use adana_script_core::{primitive::Primitive, Value};
+use adana_script_core::{
+    primitive::{Captured, CompiledBody, Primitive},
+    Value,
+};
 #[test]
 #[serial]
 fn test_simple_struc() {
@@ -14,7 +19,7 @@ fn test_simple_struc() {
     assert_eq!(ctx.len(), 1);
     assert_eq!(
         ctx["x"].read().unwrap().clone(),
-        Primitive::Struct(BTreeMap::from([(
+        Primitive::Struct(IndexMap::from([(
             "x".to_string(),
             Primitive::U8(8)
         )]))
@@ -34,12 +39,14 @@ fn test_simple_struc_with_more_stuff_in_it() {
     assert_eq!(ctx.len(), 1);
     assert_eq!(
         ctx["x"].read().unwrap().clone(),
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             ("x".to_string(), Primitive::U8(8)),
             ("y".to_string(), Primitive::String("hello;".to_string())),
             (
                 "z".to_string(),
                 Primitive::Function {
+                    captured: Captured::default(),
+                    body: CompiledBody::default(),
                     parameters: vec![],
                     exprs: vec![Value::BlockParen(vec![
                         Value::BuiltInFunction {
@@ -353,12 +360,12 @@ fn test_struct_from_readme_example() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
         r,
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             ("name".to_string(), Primitive::String("hello".to_string())),
             ("age".to_string(), Primitive::U8(20)),
             (
                 "headers".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "Content-Type".to_string(),
                     Primitive::String("application/json".to_string())
                 )]))
@@ -436,10 +443,10 @@ fn test_struc_access_key11() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
         r,
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "body".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "response".to_string(),
                     Primitive::String("hello nordine!".to_string())
                 ),]))
@@ -447,7 +454,7 @@ fn test_struc_access_key11() {
             ("status".to_string(), Primitive::U8(200)),
             (
                 "headers".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "Content-Type".to_string(),
                     Primitive::String("application/json".to_string())
                 )]))
@@ -474,10 +481,10 @@ fn test_struc_access_key12() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
         r,
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "body".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "response".to_string(),
                     Primitive::String("hello nordine!".to_string())
                 ),]))
@@ -485,7 +492,7 @@ fn test_struc_access_key12() {
             ("status".to_string(), Primitive::U8(200)),
             (
                 "headers".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "Content-Type".to_string(),
                     Primitive::String("application/json".to_string())
                 )]))
@@ -529,10 +536,10 @@ fn test_struc_access_key13() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
         r,
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "body".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "response".to_string(),
                     Primitive::String("hello nordine!".to_string())
                 ),]))
@@ -540,7 +547,7 @@ fn test_struc_access_key13() {
             ("status".to_string(), Primitive::U8(200)),
             (
                 "headers".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "Content-Type".to_string(),
                     Primitive::String("application/json".to_string())
                 )]))
@@ -567,10 +574,10 @@ fn test_struc_access_key14() {
     let r = compute(expr, &mut ctx, "N/A").unwrap();
     assert_eq!(
         r,
-        Primitive::Struct(BTreeMap::from([
+        Primitive::Struct(IndexMap::from([
             (
                 "body".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "response".to_string(),
                     Primitive::String("hello nordine!".to_string())
                 ),]))
@@ -578,7 +585,7 @@ fn test_struc_access_key14() {
             ("status".to_string(), Primitive::U8(200)),
             (
                 "headers".to_string(),
-                Primitive::Struct(BTreeMap::from([(
+                Primitive::Struct(IndexMap::from([(
                     "Content-Type".to_string(),
                     Primitive::String("application/json".to_string())
                 )]))
@@ -586,3 +593,67 @@ fn test_struc_access_key14() {
         ]))
     );
 }
+
+#[test]
+#[serial]
+fn test_struc_field_named_after_keyword() {
+    let mut ctx = BTreeMap::new();
+    let expr = r#"
+        s = struct { values: [1, 2], keys: "k" }
+        s.values[0] = 3
+        res = [s.keys, s.values]
+    "#;
+    let _ = compute(expr, &mut ctx, "N/A").unwrap();
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("k".into()),
+            Primitive::Array(vec![Primitive::U8(3), Primitive::U8(2)]),
+        ]),
+        ctx["res"].read().unwrap().clone()
+    );
+}
+
+#[test]
+#[serial]
+fn test_struc_insertion_order() {
+    let mut ctx = BTreeMap::new();
+    let expr = r#"
+        s = struct { zeta: 1, alpha: 2, mid: 3 }
+        s.beta = 4
+        drop(s.alpha)
+        fields = []
+        for entry in s {
+            fields = fields + entry.key
+        }
+        json = jsonify(s)
+        parsed = keys(parse_json("""{"b": 1, "a": 2, "c": {"z": 1, "y": 2}}"""))
+        l = struct { a: 1, b: 2 }
+        r = struct { b: 2, a: 1 }
+        same = l == r
+    "#;
+    let _ = compute(expr, &mut ctx, "N/A").unwrap();
+    let strings = |values: &[&str]| {
+        Primitive::Array(
+            values.iter().map(|v| Primitive::String(v.to_string())).collect(),
+        )
+    };
+    assert_eq!(
+        strings(&["zeta", "mid", "beta"]),
+        ctx["fields"].read().unwrap().clone()
+    );
+    assert_eq!(
+        "struct {\n\tzeta: 1, \n\tmid: 3, \n\tbeta: 4\n}",
+        ctx["s"].read().unwrap().to_string()
+    );
+    assert_eq!(
+        Primitive::String(
+            "{\n  \"zeta\": 1,\n  \"mid\": 3,\n  \"beta\": 4\n}".into()
+        ),
+        ctx["json"].read().unwrap().clone()
+    );
+    assert_eq!(
+        strings(&["b", "a", "c"]),
+        ctx["parsed"].read().unwrap().clone()
+    );
+    assert_eq!(Primitive::Bool(true), ctx["same"].read().unwrap().clone());
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::{compute, Diagnostic};
+
+#[test]
+fn test_try_catch_runtime_error() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        x = 1
+        caught = false
+        try {
+            y = parse_json("{bad")
+            x = 2
+        } catch err {
+            caught = is_error(err)
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["x"].read().unwrap().clone());
+    assert_eq!(Primitive::Bool(true), ctx["caught"].read().unwrap().clone());
+    assert!(!ctx.contains_key("err"));
+    assert!(!ctx.contains_key("y"));
+}
+
+#[test]
+fn test_try_catch_include_and_require() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        a = 0
+        try {
+            include("file_tests/does_not_exist.adana")
+        } catch {
+            a = a + 1
+        }
+        try {
+            lib = require("does_not_exist.so")
+        } catch {
+            a = a + 1
+        }
+        a
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(2), res);
+}
+
+#[test]
+fn test_throw() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        check = (n) => {
+            if (n > 2) {
+                throw "too big"
+            }
+            n
+        }
+        a = check(1)
+        b = null
+        c = null
+        d = null
+        try {
+            b = check(5)
+        } catch e {
+            b = to_string(e)
+        }
+        try {
+            throw make_err("oops")
+        } catch e {
+            c = to_string(e)
+        }
+        try {
+            make_err("as value")
+        } catch e {
+            d = to_string(e)
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["a"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::String("Err: too big".into()),
+        ctx["b"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("Err: oops".into()),
+        ctx["c"].read().unwrap().clone()
+    );
+    assert_eq!(
+        Primitive::String("Err: as value".into()),
+        ctx["d"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_uncaught_throw() {
+    let mut ctx = BTreeMap::new();
+    let program = "x = 1\nthrow \"boom\"\nx = 2";
+    let err = compute(program, &mut ctx, "N/A").unwrap_err();
+    let diagnostic = err.downcast::<Diagnostic>().unwrap();
+    assert_eq!(diagnostic.message, "boom");
+    assert_eq!((diagnostic.line, diagnostic.column), (2, 1));
+    assert_eq!(Primitive::U8(1), ctx["x"].read().unwrap().clone());
+}
+
+#[test]
+fn test_finally() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        steps = []
+        f = () => {
+            try {
+                return 1
+            } finally {
+                println("cleanup")
+            }
+            2
+        }
+        r = f()
+        try {
+            try {
+                throw "inner"
+            } finally {
+                steps = steps + ["finally"]
+            }
+        } catch e {
+            steps = steps + [to_string(e)]
+        }
+    "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["r"].read().unwrap().clone());
+    assert_eq!(
+        Primitive::Array(vec![
+            Primitive::String("finally".into()),
+            Primitive::String("Err: inner".into()),
+        ]),
+        ctx["steps"].read().unwrap().clone()
+    );
+}
+
+#[test]
+fn test_try_as_value() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+        for i in [1, 2, 3] {
+            try {
+                if (i == 2) {
+                    throw "skip"
+                }
+                last = i
+            } catch {
+                break
+            }
+        }
+        try {
+            40 + 2
+        } catch {
+            0
+        }
+    "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(42), res);
+}
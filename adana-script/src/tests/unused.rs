@@ -1,8 +1,10 @@
 use std::collections::BTreeMap;
 
+use indexmap::IndexMap;
+
 use adana_script_core::primitive::Primitive;
 
-use crate::compute;
+use crate::{compute, tests::collected};
 
 #[test]
 fn test_simple_unused_array() {
@@ -14,7 +16,7 @@ fn test_simple_unused_array() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
+        collected(r),
         Primitive::Array(vec![
             Primitive::U8(1),
             Primitive::U8(2),
@@ -33,7 +35,7 @@ fn test_simple_unused_range() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
+        collected(r),
         Primitive::Array(vec![
             Primitive::Int(1),
             Primitive::Int(2),
@@ -53,7 +55,7 @@ fn test_simple_unused_fn_call_range() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
+        collected(r),
         Primitive::Array(vec![
             Primitive::Int(1),
             Primitive::Int(2),
@@ -73,7 +75,7 @@ fn test_simple_unused_fn_parameter() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
+        collected(r),
         Primitive::Array(vec![
             Primitive::Int(1),
             Primitive::Int(2),
@@ -92,7 +94,7 @@ fn test_simple_unused_fn_multiple_parameters() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
+        collected(r),
         Primitive::Array(vec![
             Primitive::Int(1),
             Primitive::Int(2),
@@ -111,7 +113,7 @@ fn test_simple_unused_fn_multiple_parameters2() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
+        collected(r),
         Primitive::Array(vec![
             Primitive::Int(1),
             Primitive::Int(2),
@@ -136,8 +138,8 @@ fn test_simple_unused_struct() {
 
     assert!(ctx.is_empty());
     assert_eq!(
-        r,
-        Primitive::Struct(BTreeMap::from([(
+        collected(r),
+        Primitive::Struct(IndexMap::from([(
             "arr".into(),
             Primitive::Array(vec![
                 Primitive::Int(1),
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use adana_script_core::primitive::Primitive;
+
+use crate::{compute, print_ast};
+
+#[test]
+fn test_foreach_restores_shadowed_variable() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+            x = 1
+            i = 7
+            s = 0
+            for i, x in [5, 6, 7] {
+                s = s + x + i
+            }
+        "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["x"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(7), ctx["i"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(21), ctx["s"].read().unwrap().clone());
+}
+
+#[test]
+fn test_drop_and_eval_in_scope() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+            x = 1
+            if (x == 1) {
+                drop(x)
+                eval("k = 3")
+                y = k
+            }
+        "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::U8(1), ctx["x"].read().unwrap().clone());
+    assert!(!ctx.contains_key("k"));
+    assert!(!ctx.contains_key("y"));
+}
+
+#[test]
+fn test_early_return_unwinds_scopes() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+            f = (n) => {
+                r = 0..n
+                for x in r {
+                    while (true) {
+                        if (x == 3) {
+                            return x * 10
+                        }
+                        break
+                    }
+                }
+                return 0
+            }
+            a = f(10)
+            b = f(2)
+            c = 0
+            while (c < 5) {
+                c = c + 1
+                if (c == 2) {
+                    tmp = c
+                    break
+                }
+            }
+        "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(30), ctx["a"].read().unwrap().clone());
+    assert_eq!(Primitive::U8(0), ctx["b"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(2), ctx["c"].read().unwrap().clone());
+    assert!(!ctx.contains_key("x"));
+    assert!(!ctx.contains_key("tmp"));
+}
+
+#[test]
+fn test_redefined_function() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+            f = (n) => { n + 1 }
+            a = f(1)
+            f = (n) => { n * 10 }
+            b = f(2)
+        "#;
+    let _ = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(2), ctx["a"].read().unwrap().clone());
+    assert_eq!(Primitive::Int(20), ctx["b"].read().unwrap().clone());
+}
+
+#[test]
+fn test_long_loop() {
+    let mut ctx = BTreeMap::new();
+    let program = r#"
+            i = 0
+            s = 0
+            while (i < 100000) {
+                s = s + i
+                i = i + 1
+            }
+            s
+        "#;
+    let res = compute(program, &mut ctx, "N/A").unwrap();
+    assert_eq!(Primitive::Int(4999950000), res);
+}
+
+#[test]
+fn test_print_ast() {
+    print_ast("x = 1\nwhile (x < 3) {\n x = x + 1\n}").unwrap();
+}
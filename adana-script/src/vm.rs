@@ -0,0 +1,3099 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    fs::read_to_string,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context};
+use indexmap::IndexMap;
+
+use adana_script_core::{
+    constants::SELF,
+    primitive::{
+        Abs, Add, And, Array, BitShift, Captured, CellValue, CompiledBody,
+        ConvertUnit, Cos, DictKey, Dictionary, DisplayBinary, DisplayHex, Div,
+        FixedWidth, Json, LazyIterator, Logarithm, Mul, NativeLibrary, Neg,
+        Not, Or, Pow, Primitive, ReadValue, RefPrimitive, Rem, Round,
+        ScriptError, SetOperations, Sin, Sqrt, StackFrame, StringManipulation,
+        Sub, Tan, ToBool, ToNumber, TypeOf, ARGUMENT_ERROR_KIND,
+        DEFAULT_ERROR_KIND, IO_ERROR_KIND, PARSE_ERROR_KIND, TYPE_ARRAY,
+        TYPE_BOOL, TYPE_DICT, TYPE_DOUBLE, TYPE_ERROR, TYPE_ERROR_KIND,
+        TYPE_FUNCTION, TYPE_I8, TYPE_INT, TYPE_SET, TYPE_STRUCT, TYPE_U8,
+    },
+    BuiltInFunctionType, KeyAccess, Operator, Source, Span, Value,
+};
+
+use crate::{
+    bytecode::{
+        Args, Arm, AssignAt, Call, Callee, Closure, Code, DropTarget, Function,
+        Key, Op, Pattern, Program, Try,
+    },
+    compiler::{compile, compile_expression},
+    compute::{compute, compute_named},
+    diagnostic::{self, caught, locate, Diagnostic, Suspended, Thrown},
+    prelude::BTreeMap,
+    require_dynamic_lib::require_dynamic_lib,
+};
+
+/// name of the outermost frame in stack traces
+const MAIN_FRAME: &str = "main";
+/// name of the frame of a function that isn't bound to a variable
+const ANONYMOUS_FRAME: &str = "anonymous";
+
+/// a running loop: its items, the current index and the keys of a dict
+type LoopIterator = (
+    Box<dyn Iterator<Item = Primitive> + Send>,
+    usize,
+    Option<std::vec::IntoIter<Primitive>>,
+);
+
+thread_local! {
+    /// adana functions being called, outermost first
+    static CALL_STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    /// exports of the modules already imported, by canonical path
+    static MODULES: RefCell<HashMap<PathBuf, Primitive>> =
+        RefCell::new(HashMap::new());
+    /// modules being imported, outermost first
+    static IMPORTING: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+    /// the error that ended a generator, raised again where its values are
+    /// consumed
+    static RAISED: RefCell<Option<anyhow::Error>> =
+        const { RefCell::new(None) };
+}
+
+/// makes the variable held by the cell a constant
+fn add_constant(name: &str, cell: &RefPrimitive) -> anyhow::Result<()> {
+    let mut value = cell
+        .write()
+        .map_err(|e| anyhow::format_err!("could not acquire lock {e}"))?;
+    if !matches!(*value, Primitive::Const(..)) {
+        let v = std::mem::replace(&mut *value, Primitive::Unit);
+        *value = Primitive::Const(name.to_string(), Box::new(v));
+    }
+    Ok(())
+}
+
+/// the name of the constant held by the cell, if any
+fn constant_name(cell: &RefPrimitive) -> Option<String> {
+    match &*cell.read().ok()? {
+        Primitive::Const(name, _) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+struct Frame {
+    function: String,
+    /// the statement that called the function
+    site: Option<Span>,
+}
+
+/// pops the frame pushed by `enter_frame` once the call is done
+struct FrameGuard;
+
+impl Drop for FrameGuard {
+    fn drop(&mut self) {
+        CALL_STACK.with_borrow_mut(|frames| frames.pop());
+    }
+}
+
+fn enter_frame(function: &str, site: Option<&Span>) -> FrameGuard {
+    let frame = Frame { function: function.to_string(), site: site.cloned() };
+    CALL_STACK.with_borrow_mut(|frames| frames.push(frame));
+    FrameGuard
+}
+
+/// the current call stack, innermost frame first, for an error raised at
+/// `span`
+fn trace(span: Option<&Span>) -> Vec<StackFrame> {
+    CALL_STACK.with_borrow(|frames| {
+        let mut trace = Vec::with_capacity(frames.len() + 1);
+        let mut location = span.and_then(diagnostic::location);
+        for frame in frames.iter().rev() {
+            trace.push(StackFrame {
+                function: frame.function.clone(),
+                location,
+            });
+            location = frame.site.as_ref().and_then(diagnostic::location);
+        }
+        trace.push(StackFrame { function: MAIN_FRAME.to_string(), location });
+        trace
+    })
+}
+
+/// attach the call stack and the span of the current instruction to an
+/// error, unless it already has a stack
+fn traced(p: Primitive, code: &Code, pc: usize) -> Primitive {
+    match p {
+        Primitive::Error(mut e) if e.stack.is_empty() => {
+            let span = code.span_at(pc.saturating_sub(1));
+            e.stack = trace(span);
+            e.span = span.cloned();
+            Primitive::Error(e)
+        }
+        p => p,
+    }
+}
+
+/// the compiled body and default values of a function, compiled on its
+/// first call
+fn function_body(
+    parameters: &[Value],
+    exprs: &[Value],
+    body: &CompiledBody,
+) -> Arc<Function> {
+    body.get_or_init(|| Function {
+        body: Arc::new(compile(exprs.to_vec())),
+        defaults: parameters
+            .iter()
+            .map(|param| match param {
+                Value::VariableExpr { expr, .. } => {
+                    Some(compile_expression(expr.as_ref().clone()))
+                }
+                _ => None,
+            })
+            .collect(),
+    })
+}
+
+/// copy existing functions in a new ctx
+fn scoped_ctx(
+    ctx: &BTreeMap<String, RefPrimitive>,
+) -> anyhow::Result<BTreeMap<String, RefPrimitive>> {
+    let mut scope_ctx = BTreeMap::new();
+
+    // copy also the function definition to the scoped ctx
+    for (k, p) in ctx.iter() {
+        let maybe_fn = read(p)?;
+        if matches!(
+            *maybe_fn,
+            Primitive::Function { .. } | Primitive::NativeLibrary(_)
+        ) {
+            scope_ctx.insert(k.to_string(), p.clone());
+        }
+    }
+
+    Ok(scope_ctx)
+}
+
+pub(crate) fn run(
+    program: &Program,
+    ctx: &mut BTreeMap<String, RefPrimitive>,
+    shared_lib: &Path,
+) -> anyhow::Result<Primitive> {
+    let mut vm = Vm {
+        env: Env { vars: ctx, undo: vec![], scopes: vec![] },
+        names: &program.names,
+        slots: vec![None; program.names.len()],
+        stack: Vec::with_capacity(16),
+        shared_lib,
+        jump: None,
+        generator: false,
+        yielded: None,
+        paused: vec![],
+    };
+    vm.exec(&program.code)
+}
+
+pub(crate) fn compute_lazy(
+    value: Value,
+    ctx: &mut BTreeMap<String, RefPrimitive>,
+    shared_lib: &Path,
+) -> anyhow::Result<Primitive> {
+    run(&compile_expression(value), ctx, shared_lib)
+}
+
+/// The variables visible by the running code.
+/// Entering a scope doesn't copy the context: the changes made to it are
+/// logged instead, then reverted when leaving the scope.
+struct Env<'a> {
+    vars: &'a mut BTreeMap<String, RefPrimitive>,
+    undo: Vec<(String, Option<RefPrimitive>)>,
+    scopes: Vec<usize>,
+}
+
+impl Env<'_> {
+    fn enter(&mut self) {
+        self.scopes.push(self.undo.len());
+    }
+
+    /// returns true if the context changed
+    fn exit(&mut self) -> bool {
+        let mark = self.scopes.pop().unwrap_or_default();
+        let changed = self.undo.len() > mark;
+        while self.undo.len() > mark {
+            if let Some((name, old)) = self.undo.pop() {
+                match old {
+                    Some(old) => self.vars.insert(name, old),
+                    None => self.vars.remove(&name),
+                };
+            }
+        }
+        changed
+    }
+
+    fn unwind(&mut self, depth: usize) -> bool {
+        let mut changed = false;
+        while self.scopes.len() > depth {
+            changed |= self.exit();
+        }
+        changed
+    }
+
+    fn insert(&mut self, name: &str, value: RefPrimitive, log: bool) {
+        let old = self.vars.insert(name.to_string(), value);
+        if log && !self.scopes.is_empty() {
+            self.undo.push((name.to_string(), old));
+        }
+    }
+
+    fn remove(&mut self, name: &str) {
+        if let Some(old) = self.vars.remove(name) {
+            if !self.scopes.is_empty() {
+                self.undo.push((name.to_string(), Some(old)));
+            }
+        }
+    }
+
+    /// run code accessing the context by name, logging what it changed
+    fn untracked<T>(
+        &mut self,
+        f: impl FnOnce(&mut BTreeMap<String, RefPrimitive>) -> T,
+    ) -> T {
+        if self.scopes.is_empty() {
+            return f(self.vars);
+        }
+        let before = self.vars.clone();
+        let res = f(self.vars);
+        for (name, old) in before.iter() {
+            match self.vars.get(name) {
+                Some(new) if Arc::ptr_eq(old, new) => {}
+                _ => self.undo.push((name.clone(), Some(old.clone()))),
+            }
+        }
+        for name in self.vars.keys() {
+            if !before.contains_key(name) {
+                self.undo.push((name.clone(), None));
+            }
+        }
+        res
+    }
+}
+
+#[derive(Debug)]
+enum ResolvedKey<'a> {
+    Index(Primitive),
+    Key(Primitive),
+    Slice { start: Primitive, end: Primitive, incl_both_end: bool },
+    FunctionCall { key: &'a Key, args: &'a Args, site: Option<&'a Span> },
+}
+
+/// the evaluated arguments of a function call
+struct Arguments {
+    values: Vec<Primitive>,
+    /// `f(b = 2)`
+    named: Vec<(String, Primitive)>,
+}
+
+/// a `break` or `continue` on its way out of the blocks nested in a loop
+enum LoopJump {
+    Break { label: Option<String>, value: Option<Primitive> },
+    Continue(Option<String>),
+}
+
+impl LoopJump {
+    /// whether the jump is meant for the loop with the label
+    fn targets(&self, loop_label: &Option<String>) -> bool {
+        let (LoopJump::Break { label, .. } | LoopJump::Continue(label)) = self;
+        label.is_none() || label == loop_label
+    }
+}
+
+/// where a block of code is at
+struct Running {
+    pc: usize,
+    /// result of the last instruction
+    result: Primitive,
+    /// loops being run, innermost last
+    iterators: Vec<LoopIterator>,
+}
+
+/// what a generator was running when it paused at a `yield`
+enum Paused {
+    /// a block of code, resumed at the op that paused
+    Code {
+        running: Running,
+        /// scopes entered before the block
+        depth: usize,
+        /// values pushed by the block
+        stack: Vec<Primitive>,
+    },
+    /// a `try` paused in one of its blocks
+    Try(TryStage),
+    /// a `match` paused in the body of the arm at this index
+    Match(usize),
+}
+
+enum TryStage {
+    Body,
+    Catch,
+    /// the result of the `try`, once its `finally` block is done
+    Finally(anyhow::Result<Primitive>),
+}
+
+struct Vm<'a> {
+    env: Env<'a>,
+    names: &'a [String],
+    /// variables already looked up in the context
+    slots: Vec<Option<RefPrimitive>>,
+    stack: Vec<Primitive>,
+    shared_lib: &'a Path,
+    /// the `break` or `continue` being executed
+    jump: Option<LoopJump>,
+    /// set when running the body of a generator
+    generator: bool,
+    /// the value handed to the consumer of the generator by `yield`
+    yielded: Option<Primitive>,
+    /// the blocks a generator paused in, innermost first. Resuming runs them
+    /// again from where they were, outermost first
+    paused: Vec<Paused>,
+}
+
+impl Vm<'_> {
+    fn exec(&mut self, code: &Code) -> anyhow::Result<Primitive> {
+        let base = self.stack.len();
+        let (mut running, depth) = match self.resumed() {
+            Some(Paused::Code { running, depth, stack }) => {
+                self.stack.extend(stack);
+                (running, depth)
+            }
+            Some(_) => return Err(anyhow!("VM BUG: not paused in a block")),
+            None => {
+                let running = Running {
+                    pc: 0,
+                    result: Primitive::Unit,
+                    iterators: vec![],
+                };
+                (running, self.env.scopes.len())
+            }
+        };
+        let res = self.exec_ops(code, base, &mut running);
+        if suspended(&res) {
+            // the op that paused runs again when resumed, unless it is the
+            // `yield` itself
+            if !matches!(code.ops.get(running.pc - 1), Some(Op::Yield)) {
+                running.pc -= 1;
+            }
+            let stack = self.stack.split_off(base);
+            self.paused.push(Paused::Code { running, depth, stack });
+            return res;
+        }
+        let res =
+            res.map_err(|e| match code.span_at(running.pc.saturating_sub(1)) {
+                Some(span) => locate(e, span, || trace(Some(span))),
+                None => e,
+            });
+        self.stack.truncate(base);
+        if self.env.unwind(depth) {
+            self.invalidate_slots();
+        }
+        res
+    }
+
+    fn exec_ops(
+        &mut self,
+        code: &Code,
+        base: usize,
+        running: &mut Running,
+    ) -> anyhow::Result<Primitive> {
+        let Running { pc, result, iterators } = running;
+
+        while let Some(op) = code.ops.get(*pc) {
+            *pc += 1;
+            match op {
+                Op::Const(p) => self.stack.push(p.clone()),
+                Op::Load(slot) => {
+                    let p = self.load(*slot)?;
+                    self.stack.push(p);
+                }
+                Op::LoadOr(slot, fallback) => {
+                    let p = match self.lookup(*slot) {
+                        Some(cell) => read(&cell)?.clone(),
+                        None => fallback.clone(),
+                    };
+                    self.stack.push(p);
+                }
+                Op::LoadNeg(slot) => {
+                    let p = self.load(*slot)?.neg();
+                    self.stack.push(p);
+                }
+                Op::LoadRef(slot) => {
+                    let cell = self.lookup(*slot).with_context(|| {
+                        format!(
+                            "ref {} not found in context!",
+                            self.names[*slot]
+                        )
+                    })?;
+                    let p = match &*read(&cell)? {
+                        r @ Primitive::Ref(_) => r.clone(),
+                        _ => Primitive::Ref(cell.clone()),
+                    };
+                    self.stack.push(p);
+                }
+                Op::Unary(operator) => {
+                    let p = self.pop();
+                    self.stack.push(match operator {
+                        Operator::Not => p.not(),
+                        Operator::BitwiseNot => p.bitwise_not(),
+                        _ => p.neg(),
+                    });
+                }
+                Op::Binary(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(binary(*operator, &left, &right));
+                }
+                Op::Range { incl_both_end } => {
+                    let end = range_bound(self.pop())?;
+                    let start = range_bound(self.pop())?;
+                    let end = if *incl_both_end { end + 1 } else { end };
+                    self.stack.push(Primitive::Iterator(
+                        LazyIterator::restartable(move || {
+                            (start..end).map(Primitive::Int)
+                        }),
+                    ));
+                }
+                Op::FString(fstring) => {
+                    let (template, parameters) = fstring.as_ref();
+                    let p = self.fstring(template, parameters)?;
+                    self.stack.push(p);
+                }
+                Op::Array(arr) => {
+                    let p = self.array(arr)?;
+                    self.stack.push(p);
+                }
+                Op::Struct(struc) => {
+                    let p = self.structure(struc)?;
+                    self.stack.push(p);
+                }
+                Op::Dict(dict) => {
+                    let p = self.dict(dict)?;
+                    self.stack.push(p);
+                }
+                Op::Set(set) => {
+                    let p = self.set(set)?;
+                    self.stack.push(p);
+                }
+                Op::BuiltIn(fn_type) => {
+                    let v = self.pop();
+                    let p = self.builtin(*fn_type, v)?;
+                    raised()?;
+                    self.stack.push(traced(p, code, *pc));
+                }
+                Op::MakeError(struc) => {
+                    let p = self.make_error(struc)?;
+                    self.stack.push(p);
+                }
+                Op::Access(access) => {
+                    let root = self.pop();
+                    let p =
+                        self.access(root, &access.keys, &access.next_keys)?;
+                    self.stack.push(p);
+                }
+                Op::Call(call) => {
+                    let p = self.call(call)?;
+                    self.stack.push(traced(p, code, *pc));
+                }
+                Op::Closure(closure) => {
+                    let p = self.closure(closure)?;
+                    self.stack.push(p);
+                }
+                Op::Try(t) => {
+                    let p = self.try_catch(t)?;
+                    self.stack.push(p);
+                }
+                Op::Match(arms) => {
+                    let p = match self.resumed() {
+                        Some(Paused::Match(arm)) => self.arm_body(arms, arm)?,
+                        Some(_) => {
+                            return Err(anyhow!(
+                                "VM BUG: not paused in a match"
+                            ))
+                        }
+                        None => {
+                            let value = self.pop();
+                            self.match_arms(arms, value)?
+                        }
+                    };
+                    self.stack.push(p);
+                }
+                Op::Break { label, with_value } => {
+                    let value = with_value.then(|| self.pop());
+                    self.jump =
+                        Some(LoopJump::Break { label: label.clone(), value });
+                    self.stack.push(Primitive::NoReturn);
+                }
+                Op::Continue(label) => {
+                    self.jump = Some(LoopJump::Continue(label.clone()));
+                    self.stack.push(Primitive::NoReturn);
+                }
+                Op::Throw => {
+                    let p = match self.pop() {
+                        e @ Primitive::Error(_) => e,
+                        p => Primitive::Error(p.to_string().into()),
+                    };
+                    let p = traced(p, code, *pc);
+                    return Err(anyhow::Error::new(Thrown(p)));
+                }
+                Op::Yield => {
+                    let p = deref(self.pop())?;
+                    if !self.generator {
+                        return Err(anyhow!("yield outside of a generator"));
+                    }
+                    self.yielded = Some(p);
+                    self.stack.push(Primitive::Unit);
+                    return Err(anyhow::Error::new(Suspended));
+                }
+                Op::Assign(slot) => {
+                    let v = self.stack.last().context("VM BUG: empty stack")?;
+                    if !matches!(v, Primitive::Error(_)) {
+                        let v = v.clone();
+                        self.assign(*slot, v)?;
+                    }
+                }
+                Op::Import(path) => {
+                    let module = import(path, self.shared_lib)?;
+                    self.stack.push(module);
+                }
+                Op::Declare { slot, constant } => {
+                    let v = self.stack.last().context("VM BUG: empty stack")?;
+                    if !matches!(v, Primitive::Error(_)) {
+                        let v = v.clone();
+                        self.declare(*slot, v, *constant)?;
+                    }
+                }
+                Op::AssignAt(at) => {
+                    let v = self.pop();
+                    let p = self.assign_at(at, v)?;
+                    self.stack.push(p);
+                }
+                Op::Destructure(pattern) => {
+                    let v = self.pop();
+                    let p = self.destructure(pattern, v)?;
+                    self.stack.push(p);
+                }
+                Op::Drop(targets) => {
+                    for target in targets.iter() {
+                        match target {
+                            DropTarget::Variable(slot) => {
+                                if let Some(name) = self.constant(*slot) {
+                                    return Err(anyhow!(
+                                        "cannot drop constant {name}"
+                                    ));
+                                }
+                                self.env.remove(&self.names[*slot]);
+                                self.slots[*slot] = None;
+                            }
+                            DropTarget::At(at) => {
+                                self.assign_at(at, Primitive::Unit)?;
+                            }
+                        }
+                    }
+                    self.stack.push(Primitive::Unit);
+                }
+                Op::Unwrap => {
+                    if let Some(Primitive::EarlyReturn(_)) = self.stack.last() {
+                        if let Primitive::EarlyReturn(p) = self.pop() {
+                            self.stack.push(*p);
+                        }
+                    }
+                }
+                Op::Return => {
+                    let p = self.pop();
+                    self.stack.push(Primitive::EarlyReturn(Box::new(p)));
+                }
+                Op::Block(code) => {
+                    let p = self.exec(code)?;
+                    self.stack.push(p);
+                }
+                Op::Shadowable(slot, codes) => {
+                    let (builtin, variable) = &**codes;
+                    let code = match self.lookup(*slot) {
+                        Some(_) => variable,
+                        None => builtin,
+                    };
+                    let p = self.exec(code)?;
+                    self.stack.push(p);
+                }
+                Op::Coalesce(codes) => {
+                    let (left, right) = &**codes;
+                    // like `try`, an error raised by the left operand is
+                    // caught
+                    let res = self.exec(left);
+                    if suspended(&res) {
+                        return res;
+                    }
+                    let p = match res {
+                        Ok(p) if !missing(&p) => p,
+                        _ => self.exec(right)?,
+                    };
+                    self.stack.push(p);
+                }
+                Op::Scoped(code) => {
+                    if !self.resuming() {
+                        self.env.enter();
+                    }
+                    let p = self.exec(code);
+                    if suspended(&p) {
+                        return p;
+                    }
+                    self.exit_scope();
+                    self.stack.push(p?);
+                }
+                Op::Fail(msg) => return Err(anyhow::Error::msg(msg.clone())),
+                Op::Jump(to) => *pc = *to,
+                Op::JumpUnlessTrue(to) => {
+                    if !matches!(self.pop(), Primitive::Bool(true)) {
+                        *pc = *to;
+                    }
+                }
+                Op::ExitIfError => {
+                    if let Some(Primitive::Error(_)) = self.stack.last() {
+                        return Ok(traced(self.pop(), code, *pc));
+                    }
+                }
+                Op::Exit => return Ok(traced(self.pop(), code, *pc)),
+                Op::Store => match self.pop() {
+                    Primitive::EarlyReturn(p) => return Ok(*p),
+                    e @ Primitive::Error(_) => return Ok(traced(e, code, *pc)),
+                    p => *result = p,
+                },
+                Op::StoreInBlock => match self.pop() {
+                    v @ (Primitive::EarlyReturn(_) | Primitive::NoReturn) => {
+                        return Ok(v)
+                    }
+                    e @ Primitive::Error(_) => return Ok(traced(e, code, *pc)),
+                    p => *result = p,
+                },
+                Op::StoreInLoop { label, start, end } => match self.pop() {
+                    Primitive::NoReturn => match self.jump.take() {
+                        Some(jump) if !jump.targets(label) => {
+                            self.jump = Some(jump);
+                            return Ok(Primitive::NoReturn);
+                        }
+                        Some(LoopJump::Continue(_)) => *pc = *start,
+                        Some(LoopJump::Break { value, .. }) => {
+                            if let Some(value) = value {
+                                *result = value;
+                            }
+                            *pc = *end;
+                        }
+                        None => *pc = *end,
+                    },
+                    v @ Primitive::EarlyReturn(_) => return Ok(v),
+                    e @ Primitive::Error(_) => return Ok(traced(e, code, *pc)),
+                    p => *result = p,
+                },
+                Op::EnterScope => self.env.enter(),
+                Op::ExitScope => self.exit_scope(),
+                Op::IterStart => {
+                    let iterator = self.pop();
+                    if let Primitive::Dict(dict) = iterator {
+                        let keys: Vec<_> =
+                            dict.keys().map(Primitive::from).collect();
+                        let values: Vec<_> = dict.into_values().collect();
+                        iterators.push((
+                            Box::new(values.into_iter()),
+                            0,
+                            Some(keys.into_iter()),
+                        ));
+                        continue;
+                    }
+                    match elements(iterator) {
+                        Ok(elements) => iterators.push((elements, 0, None)),
+                        Err(iterator) => {
+                            return Ok(Primitive::Error(
+                                format!("not an iterable {iterator:?}").into(),
+                            ));
+                        }
+                    }
+                }
+                Op::IterNext { var, index_var, done } => {
+                    let (iterator, i, keys) =
+                        iterators.last_mut().context("VM BUG: no iterator")?;
+                    let Some(it) = iterator.next() else {
+                        iterators.pop();
+                        raised()?;
+                        *pc = *done;
+                        continue;
+                    };
+                    // the first binding is enough to restore the variables
+                    // when leaving the loop
+                    let first = *i == 0;
+                    let index = match keys {
+                        Some(keys) => keys.next().unwrap_or(Primitive::Null),
+                        None => Primitive::Int(*i as i128),
+                    };
+                    *i += 1;
+                    if first {
+                        for slot in var.iter().chain(index_var.iter()) {
+                            self.ensure_mutable(*slot)?;
+                        }
+                    }
+                    if let Some(var) = var {
+                        self.bind(*var, it.ref_prim(), first);
+                    }
+                    if let Some(index_var) = index_var {
+                        self.bind(*index_var, index.ref_prim(), first);
+                    }
+                }
+                Op::IterEnd => {
+                    iterators.pop();
+                }
+            }
+        }
+
+        Ok(if self.stack.len() > base {
+            self.pop()
+        } else {
+            std::mem::replace(result, Primitive::Unit)
+        })
+    }
+
+    fn pop(&mut self) -> Primitive {
+        self.stack.pop().expect("VM BUG: empty stack")
+    }
+
+    /// whether a paused generator is on its way back to its `yield`
+    fn resuming(&self) -> bool {
+        !self.paused.is_empty()
+    }
+
+    /// the next block to resume, outermost first
+    fn resumed(&mut self) -> Option<Paused> {
+        self.paused.pop()
+    }
+
+    // region: variables
+    fn lookup(&mut self, slot: usize) -> Option<RefPrimitive> {
+        if self.slots[slot].is_none() {
+            self.slots[slot] = self.env.vars.get(&self.names[slot]).cloned();
+        }
+        self.slots[slot].clone()
+    }
+
+    fn load(&mut self, slot: usize) -> anyhow::Result<Primitive> {
+        if self.slots[slot].is_none() {
+            self.slots[slot] = self.env.vars.get(&self.names[slot]).cloned();
+        }
+        match &self.slots[slot] {
+            Some(cell) => Ok(read(cell)?.clone()),
+            None => Ok(Primitive::Error(
+                format!("variable {} not found in ctx", self.names[slot])
+                    .into(),
+            )),
+        }
+    }
+
+    fn assign(&mut self, slot: usize, v: Primitive) -> anyhow::Result<()> {
+        self.ensure_mutable(slot)?;
+        let cell = match self.lookup(slot) {
+            Some(cell) => cell,
+            None => {
+                let cell = Primitive::Unit.ref_prim();
+                self.bind(slot, cell.clone(), true);
+                cell
+            }
+        };
+        match &v {
+            Primitive::Ref(v) if Arc::ptr_eq(&cell, v) => (),
+            _ => {
+                let mut old = cell.write().map_err(|e| {
+                    anyhow::format_err!("could not acquire lock {e}")
+                })?;
+                *old = v;
+            }
+        }
+        Ok(())
+    }
+
+    /// `const x = 1` or `let x = 1`. The variable gets a new cell, so it
+    /// shadows the one of an outer scope instead of changing it
+    fn declare(
+        &mut self,
+        slot: usize,
+        v: Primitive,
+        constant: bool,
+    ) -> anyhow::Result<()> {
+        if let Some(name) = self.constant(slot) {
+            return Err(anyhow!("constant {name} is already declared"));
+        }
+        let cell = v.ref_prim();
+        self.bind(slot, cell.clone(), true);
+        if constant {
+            add_constant(&self.names[slot], &cell)?;
+        }
+        Ok(())
+    }
+
+    /// the name of the constant held by the variable, if any
+    fn constant(&mut self, slot: usize) -> Option<String> {
+        self.lookup(slot).and_then(|cell| constant_name(&cell))
+    }
+
+    fn ensure_mutable(&mut self, slot: usize) -> anyhow::Result<()> {
+        match self.constant(slot) {
+            Some(name) => Err(anyhow!("cannot assign to constant {name}")),
+            None => Ok(()),
+        }
+    }
+
+    fn bind(&mut self, slot: usize, cell: RefPrimitive, log: bool) {
+        self.env.insert(&self.names[slot], cell.clone(), log);
+        self.slots[slot] = Some(cell);
+    }
+
+    fn exit_scope(&mut self) {
+        if self.env.exit() {
+            self.invalidate_slots();
+        }
+    }
+
+    fn invalidate_slots(&mut self) {
+        self.slots.iter_mut().for_each(|s| *s = None);
+    }
+
+    /// run code that uses the context by name, e.g. `eval`
+    fn untracked<T>(
+        &mut self,
+        f: impl FnOnce(&mut BTreeMap<String, RefPrimitive>, &Path) -> T,
+    ) -> T {
+        let shared_lib = self.shared_lib;
+        let res = self.env.untracked(|vars| f(vars, shared_lib));
+        self.invalidate_slots();
+        res
+    }
+    // endregion: variables
+
+    // region: values
+    fn fstring(
+        &mut self,
+        template: &str,
+        parameters: &[(String, Code)],
+    ) -> anyhow::Result<Primitive> {
+        let mut s = template.to_string();
+        for (key, param) in parameters {
+            let primitive = self.exec(param)?;
+            if let err @ Primitive::Error(_) = primitive {
+                return Ok(err);
+            }
+            s = s.replacen(key, &primitive.to_string(), 1);
+        }
+        Ok(Primitive::String(s))
+    }
+
+    fn array(&mut self, arr: &[Code]) -> anyhow::Result<Primitive> {
+        let mut primitives = Vec::with_capacity(arr.len());
+        for v in arr {
+            match self.exec(v)? {
+                v @ Primitive::Error(_) => return Ok(v),
+                Primitive::Unit => {
+                    return Ok(Primitive::Error(
+                        "cannot push unit () to array".into(),
+                    ))
+                }
+                p => primitives.push(p),
+            }
+        }
+        Ok(Primitive::Array(primitives))
+    }
+
+    fn structure(
+        &mut self,
+        struc: &[(String, Code)],
+    ) -> anyhow::Result<Primitive> {
+        let mut primitives = IndexMap::with_capacity(struc.len());
+        for (k, v) in struc {
+            match self.exec(v)? {
+                v @ Primitive::Error(_) => return Ok(v),
+                Primitive::Unit => {
+                    return Ok(Primitive::Error(
+                        "cannot push unit () to struct".into(),
+                    ))
+                }
+                p => {
+                    primitives.insert(k.to_string(), p);
+                }
+            }
+        }
+        Ok(Primitive::Struct(primitives))
+    }
+
+    fn dict(&mut self, dict: &[(Code, Code)]) -> anyhow::Result<Primitive> {
+        let mut primitives = BTreeMap::new();
+        for (k, v) in dict {
+            let key = match DictKey::try_from(&self.exec(k)?) {
+                Ok(key) => key,
+                Err(e) => return Ok(e),
+            };
+            match self.exec(v)? {
+                v @ Primitive::Error(_) => return Ok(v),
+                Primitive::Unit => {
+                    return Ok(Primitive::Error(
+                        "cannot push unit () to dict".into(),
+                    ))
+                }
+                p => {
+                    primitives.insert(key, p);
+                }
+            }
+        }
+        Ok(Primitive::Dict(primitives))
+    }
+
+    fn set(&mut self, set: &[Code]) -> anyhow::Result<Primitive> {
+        let mut values = BTreeSet::new();
+        for v in set {
+            match DictKey::try_from(&self.exec(v)?) {
+                Ok(value) => {
+                    values.insert(value);
+                }
+                Err(e) => return Ok(e),
+            }
+        }
+        Ok(Primitive::Set(values))
+    }
+
+    /// unlike a struct, an error field may hold an error, e.g. its cause
+    fn make_error(
+        &mut self,
+        struc: &[(String, Code)],
+    ) -> anyhow::Result<Primitive> {
+        let mut fields = IndexMap::with_capacity(struc.len());
+        for (k, v) in struc {
+            match self.exec(v)? {
+                Primitive::Unit => {
+                    return Ok(Primitive::Error(
+                        "cannot push unit () to error".into(),
+                    ))
+                }
+                p => {
+                    fields.insert(k.to_string(), p);
+                }
+            }
+        }
+        Ok(Primitive::Error(Box::new(ScriptError::from_struct(fields))))
+    }
+    // endregion: values
+
+    // region: key access
+    fn resolve_key<'k>(
+        &mut self,
+        key: &'k Key,
+    ) -> anyhow::Result<ResolvedKey<'k>> {
+        fn resolve_primitive(
+            key: &Primitive,
+        ) -> anyhow::Result<ResolvedKey<'static>> {
+            match key {
+                Primitive::U8(_) | Primitive::I8(_) | Primitive::Int(_) => {
+                    Ok(ResolvedKey::Index(key.clone()))
+                }
+                Primitive::Ref(r) => resolve_primitive(&*read(r)?),
+                Primitive::String(s) => {
+                    Ok(ResolvedKey::Key(Primitive::String(s.to_string())))
+                }
+                // dict keys
+                Primitive::Bool(_) | Primitive::Array(_) => {
+                    Ok(ResolvedKey::Key(key.clone()))
+                }
+                _ => Err(anyhow!("illegal key access {key:?}")),
+            }
+        }
+        match key {
+            Key::Const(KeyAccess::Index(p)) => {
+                Ok(ResolvedKey::Index(p.clone()))
+            }
+            Key::Const(KeyAccess::Key(p)) => Ok(ResolvedKey::Key(p.clone())),
+            Key::Const(k) => Err(anyhow!("illegal key access {k:?}")),
+            Key::Variable(code) => resolve_primitive(&self.exec(code)?),
+            Key::Slice { start, end, incl_both_end } => {
+                Ok(ResolvedKey::Slice {
+                    start: self.exec(start)?,
+                    end: self.exec(end)?,
+                    incl_both_end: *incl_both_end,
+                })
+            }
+            Key::Call { key, args, site } => {
+                Ok(ResolvedKey::FunctionCall { key, args, site: site.as_ref() })
+            }
+            Key::Optional(_) => {
+                Err(anyhow!("cannot assign to an optional access ?."))
+            }
+        }
+    }
+
+    fn access(
+        &mut self,
+        root: Primitive,
+        keys: &[Key],
+        next_keys: &[KeyAccess],
+    ) -> anyhow::Result<Primitive> {
+        let Some((key, rest)) = keys.split_first() else {
+            return Err(anyhow!("access error. not enough argument {keys:?}"));
+        };
+        let rest_keys = next_keys.get(1..).unwrap_or_default();
+        // `a?.b`, null without going further when `a` is null
+        if let Key::Optional(key) = key {
+            let is_null = match &root {
+                Primitive::Ref(r) => matches!(*read(r)?, Primitive::Null),
+                root => matches!(root, Primitive::Null),
+            };
+            if is_null {
+                return Ok(Primitive::Null);
+            }
+            let p = match self.access(
+                root,
+                std::slice::from_ref(key),
+                next_keys,
+            )? {
+                Primitive::Error(_) => return Ok(Primitive::Null),
+                p => p,
+            };
+            return if rest.is_empty() {
+                Ok(p)
+            } else {
+                self.access(p, rest, rest_keys)
+            };
+        }
+        // `&counter.increment()`, the method can change the struct itself
+        let (root, receiver) = match root {
+            Primitive::Ref(r) => {
+                let p = read(&r)?.clone();
+                (p, Some(r))
+            }
+            root => (root, None),
+        };
+        if let Some((fn_type, args)) = builtin_method(&root, key) {
+            let p = self.call_builtin_method(fn_type, root, args)?;
+            return if rest.is_empty() {
+                Ok(p)
+            } else {
+                self.access(p, rest, rest_keys)
+            };
+        }
+        let root = match root {
+            v @ Primitive::String(_) => {
+                if !rest.is_empty() {
+                    return Err(anyhow!(
+                        "string access error. too many argument {next_keys:?}"
+                    ));
+                }
+                return match self.resolve_key(key)? {
+                    ResolvedKey::Index(i) => Ok(v.index_at(&i)),
+                    ResolvedKey::Slice { start, end, incl_both_end } => {
+                        Ok(v.slice(&start, &end, incl_both_end))
+                    }
+                    key => Err(anyhow!(
+                        "cannot use that key in this context {key:?} {v:?}"
+                    )),
+                };
+            }
+            Primitive::NativeLibrary(lib) => match self.resolve_key(key)? {
+                ResolvedKey::Key(idx) => {
+                    return Ok(Primitive::NativeFunction(idx.to_string(), lib))
+                }
+                ResolvedKey::FunctionCall { key, args, site } => {
+                    let ResolvedKey::Key(idx) = self.resolve_key(key)? else {
+                        return Err(anyhow!("native lib can only be accessed with a key str {rest_keys:?}"));
+                    };
+                    let name = idx.to_string();
+                    self.call_primitive(
+                        Primitive::NativeFunction(name.clone(), lib),
+                        args,
+                        &name,
+                        site,
+                    )?
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "native lib can only be accessed with a key str {rest_keys:?}"
+                    ))
+                }
+            },
+            v @ (Primitive::Array(_) | Primitive::Iterator(_)) => match self
+                .resolve_key(key)?
+            {
+                ResolvedKey::Index(idx) => v.index_at(&idx),
+                ResolvedKey::Slice { start, end, incl_both_end } => {
+                    v.slice(&start, &end, incl_both_end)
+                }
+                ResolvedKey::FunctionCall { key, args, site } => {
+                    let ResolvedKey::Index(idx) = self.resolve_key(key)? else {
+                        return Err(anyhow!( "array can only be accessed with an idx  {rest_keys:?}"));
+                    };
+                    let name = format!("[{idx}]");
+                    self.call_primitive(v.index_at(&idx), args, &name, site)?
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "array can only be accessed with an idx  {rest_keys:?}"
+                    ))
+                }
+            },
+            v @ (Primitive::Struct(_)
+            | Primitive::Error(_)
+            | Primitive::Variant(_)) => match self.resolve_key(key)? {
+                ResolvedKey::Key(idx) => v.index_at(&idx),
+                ResolvedKey::FunctionCall { key, args, site } => {
+                    let ResolvedKey::Key(idx) = self.resolve_key(key)? else {
+                        return Err(anyhow!( "struct can only be accessed with a key {rest_keys:?}"));
+                    };
+                    let name = idx.to_string();
+                    let function = v.index_at(&idx);
+                    let receiver = receiver.unwrap_or_else(|| v.ref_prim());
+                    self.call_method(function, receiver, args, &name, site)?
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "struct can only be accessed with a key {rest_keys:?}"
+                    ))
+                }
+            },
+            v @ Primitive::Dict(_) => match self.resolve_key(key)? {
+                ResolvedKey::Index(idx) | ResolvedKey::Key(idx) => {
+                    v.index_at(&idx)
+                }
+                ResolvedKey::FunctionCall { key, args, site } => {
+                    let (ResolvedKey::Index(idx) | ResolvedKey::Key(idx)) =
+                        self.resolve_key(key)?
+                    else {
+                        return Err(anyhow!(
+                            "dict can only be accessed with a key {rest_keys:?}"
+                        ));
+                    };
+                    let name = idx.to_string();
+                    self.call_primitive(v.index_at(&idx), args, &name, site)?
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "dict can only be accessed with a key {rest_keys:?}"
+                    ))
+                }
+            },
+            Primitive::Enum(e) => match self.resolve_key(key)? {
+                ResolvedKey::Key(variant) => e.variant(&variant.to_string()),
+                ResolvedKey::FunctionCall { key, args, .. } => {
+                    let ResolvedKey::Key(variant) = self.resolve_key(key)?
+                    else {
+                        return Err(anyhow!("enum can only be accessed with a variant name {rest_keys:?}"));
+                    };
+                    let args = match args {
+                        Args::List(args, named) if named.is_empty() => args,
+                        _ => {
+                            return Ok(Primitive::Error(
+                                format!("invalid call to {}.{variant}", e.name)
+                                    .into(),
+                            ))
+                        }
+                    };
+                    let mut values = Vec::with_capacity(args.len());
+                    for (arg, _) in args {
+                        values.push(self.exec(arg)?);
+                    }
+                    e.construct(&variant.to_string(), values)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "enum can only be accessed with a variant name {rest_keys:?}"
+                    ))
+                }
+            },
+            root => {
+                return Err(anyhow!(
+                    "illegal usage of multidepth access {root:?} => {next_keys:?}"
+                ))
+            }
+        };
+        if rest.is_empty() {
+            Ok(root)
+        } else {
+            self.access(root, rest, rest_keys)
+        }
+    }
+
+    fn assign_at(
+        &mut self,
+        at: &AssignAt,
+        mut new_value: Primitive,
+    ) -> anyhow::Result<Primitive> {
+        fn fold(
+            acc: &mut Primitive,
+            new_value: &mut Primitive,
+            keys: &[ResolvedKey],
+            next_keys: &[KeyAccess],
+        ) -> anyhow::Result<Primitive> {
+            if matches!(new_value, Primitive::Error(_)) {
+                return Ok(new_value.clone());
+            }
+            // `r = &x` then `r[0] = 1` changes x
+            if let Primitive::Ref(r) = acc {
+                if let Some(name) = constant_name(r) {
+                    return Err(anyhow!("cannot mutate constant {name}"));
+                }
+            }
+            let Some((key, rest)) = keys.split_first() else {
+                return Err(anyhow!("not enough keys {next_keys:?}"));
+            };
+            let rest_keys = next_keys.get(1..).unwrap_or_default();
+            match key {
+                ResolvedKey::Index(key) | ResolvedKey::Key(key) => {
+                    if rest.is_empty() {
+                        if matches!(new_value, Primitive::Unit) {
+                            // handle drop as user cannot
+                            // construct a Primitive::Unit
+                            acc.remove(key)?;
+                            return Ok(Primitive::Unit);
+                        }
+                        let res = acc.swap_mem(new_value, key);
+                        if matches!(res, Primitive::Error(_)) {
+                            return Ok(res);
+                        }
+                    } else {
+                        let mut new_value = fold(
+                            &mut acc.index_at(key),
+                            new_value,
+                            rest,
+                            rest_keys,
+                        )?;
+                        if matches!(new_value, Primitive::Error(_)) {
+                            return Ok(new_value);
+                        }
+                        acc.swap_mem(&mut new_value, key);
+                    }
+                }
+                ResolvedKey::Slice { start, end, incl_both_end } => {
+                    if !rest.is_empty() {
+                        return Err(anyhow!(
+                            "cannot assign through a slice {rest_keys:?}"
+                        ));
+                    }
+                    let res = acc.splice(start, end, *incl_both_end, new_value);
+                    if matches!(res, Primitive::Error(_)) {
+                        return Ok(res);
+                    }
+                    if matches!(new_value, Primitive::Unit) {
+                        return Ok(Primitive::Unit);
+                    }
+                }
+                ResolvedKey::FunctionCall { .. } => {
+                    return Err(anyhow!("illegal assignement {rest_keys:?} "))
+                }
+            }
+            Ok(acc.clone())
+        }
+
+        if at.keys.is_empty() {
+            return Err(anyhow!("not enough keys {:?}", at.next_keys));
+        }
+        let Some(root) = at.root else {
+            return Ok(new_value);
+        };
+        let cell = self.lookup(root).context("array not found in context")?;
+        if matches!(new_value, Primitive::Error(_)) {
+            return Ok(new_value);
+        }
+        if let Some(name) = constant_name(&cell) {
+            return Err(anyhow!("cannot mutate constant {name}"));
+        }
+        // keys are computed before locking the variable, as they may use it
+        let mut keys = Vec::with_capacity(at.keys.len());
+        for key in &at.keys {
+            keys.push(self.resolve_key(key)?);
+        }
+        let mut acc = cell
+            .write()
+            .map_err(|e| anyhow::format_err!("could not acquire lock {e}"))?;
+        fold(&mut acc, &mut new_value, &keys, &at.next_keys)
+    }
+    // endregion: key access
+
+    fn try_catch(&mut self, t: &Try) -> anyhow::Result<Primitive> {
+        let stage = match self.resumed() {
+            Some(Paused::Try(stage)) => stage,
+            Some(_) => return Err(anyhow!("VM BUG: not paused in a try")),
+            None => TryStage::Body,
+        };
+        let res = match (stage, &t.catch) {
+            (TryStage::Finally(res), _) => res,
+            (TryStage::Catch, Some(catch)) => self.catch(catch, None),
+            (TryStage::Catch, None) => {
+                return Err(anyhow!("VM BUG: no catch block to resume"))
+            }
+            (TryStage::Body, catch) => {
+                let res = self.exec(&t.body);
+                if suspended(&res) {
+                    self.paused.push(Paused::Try(TryStage::Body));
+                    return res;
+                }
+                match (catch, res) {
+                    (Some(catch), Ok(err @ Primitive::Error(_))) => {
+                        self.catch(catch, Some(err))
+                    }
+                    (Some(catch), Err(err)) => {
+                        self.catch(catch, Some(caught(err)))
+                    }
+                    (_, res) => res,
+                }
+            }
+        };
+        if suspended(&res) {
+            self.paused.push(Paused::Try(TryStage::Catch));
+            return res;
+        }
+        if let Some(finally) = &t.finally {
+            let done = self.exec(finally);
+            if suspended(&done) {
+                self.paused.push(Paused::Try(TryStage::Finally(res)));
+                return done;
+            }
+            done?;
+        }
+        res
+    }
+
+    /// run the catch block with the error caught, or resume it
+    fn catch(
+        &mut self,
+        (var, code): &(Option<usize>, Code),
+        err: Option<Primitive>,
+    ) -> anyhow::Result<Primitive> {
+        if let Some(err) = err {
+            self.env.enter();
+            if let Some(var) = var {
+                self.bind(*var, err.ref_prim(), true);
+            }
+        }
+        let res = self.exec(code);
+        if !suspended(&res) {
+            self.exit_scope();
+        }
+        res
+    }
+
+    // region: match
+    fn match_arms(
+        &mut self,
+        arms: &[Arm],
+        value: Primitive,
+    ) -> anyhow::Result<Primitive> {
+        let value = match value {
+            Primitive::Ref(cell) => read(&cell)?.clone(),
+            value => value,
+        };
+        for (idx, arm) in arms.iter().enumerate() {
+            let mut bindings = vec![];
+            if !self.matches(&arm.pattern, &value, &mut bindings)? {
+                continue;
+            }
+            self.env.enter();
+            for (slot, v) in bindings {
+                self.bind(slot, v.ref_prim(), true);
+            }
+            let res = match arm.guard.as_ref().map(|guard| self.exec(guard)) {
+                None | Some(Ok(Primitive::Bool(true))) => {
+                    return self.arm_body(arms, idx);
+                }
+                Some(Ok(e @ Primitive::Error(_))) => Some(Ok(e)),
+                Some(Ok(_)) => None,
+                Some(Err(e)) => Some(Err(e)),
+            };
+            self.exit_scope();
+            if let Some(res) = res {
+                return res;
+            }
+        }
+        Ok(match value {
+            e @ Primitive::Error(_) => e,
+            value => Primitive::Error(
+                format!("no match arm for value {value}").into(),
+            ),
+        })
+    }
+
+    /// run the body of the arm that matched, in the scope of its bindings
+    fn arm_body(
+        &mut self,
+        arms: &[Arm],
+        idx: usize,
+    ) -> anyhow::Result<Primitive> {
+        let res = self.exec(&arms[idx].body);
+        if suspended(&res) {
+            self.paused.push(Paused::Match(idx));
+            return res;
+        }
+        self.exit_scope();
+        res
+    }
+
+    fn destructure(
+        &mut self,
+        pattern: &Pattern,
+        value: Primitive,
+    ) -> anyhow::Result<Primitive> {
+        let value = match value {
+            Primitive::Ref(cell) => read(&cell)?.clone(),
+            e @ Primitive::Error(_) => return Ok(e),
+            value => value,
+        };
+        let mut bindings = vec![];
+        if !self.matches(pattern, &value, &mut bindings)? {
+            return Ok(Primitive::Error(
+                format!("cannot destructure {value}").into(),
+            ));
+        }
+        for (slot, v) in bindings {
+            self.assign(slot, v)?;
+        }
+        Ok(value)
+    }
+
+    /// whether the value matches the pattern, collecting the variables
+    /// it binds
+    fn matches(
+        &mut self,
+        pattern: &Pattern,
+        value: &Primitive,
+        bindings: &mut Vec<(usize, Primitive)>,
+    ) -> anyhow::Result<bool> {
+        let matched = match pattern {
+            Pattern::Any(var) => {
+                if let Some(var) = var {
+                    bindings.push((*var, value.clone()));
+                }
+                true
+            }
+            Pattern::Literal(literal) => same_value(value, literal),
+            Pattern::Range { start, end, incl_both_end } => {
+                let start = self.exec(start)?;
+                let end = self.exec(end)?;
+                matches!(
+                    value.partial_cmp(&start),
+                    Some(Ordering::Greater | Ordering::Equal)
+                ) && match value.partial_cmp(&end) {
+                    Some(Ordering::Less) => true,
+                    Some(Ordering::Equal) => *incl_both_end,
+                    _ => false,
+                }
+            }
+            Pattern::Type { type_name, var } => {
+                let matched = value.type_of_str() == type_name;
+                if let (true, Some(var)) = (matched, var) {
+                    bindings.push((*var, value.clone()));
+                }
+                matched
+            }
+            Pattern::Array { head, rest, tail } => {
+                let fixed = head.len() + tail.len();
+                // an iterator is only consumed as far as the pattern needs
+                let collected: Vec<_>;
+                let values = match value {
+                    Primitive::Array(values) => values,
+                    Primitive::Iterator(it) if rest.is_none() => {
+                        collected = it.restart().take(fixed + 1).collect();
+                        &collected
+                    }
+                    Primitive::Iterator(it) => {
+                        collected = it.restart().collect();
+                        &collected
+                    }
+                    _ => return Ok(false),
+                };
+                if values.len() < fixed
+                    || (rest.is_none() && values.len() != fixed)
+                {
+                    return Ok(false);
+                }
+                let (values_head, values) = values.split_at(head.len());
+                let (values, values_tail) =
+                    values.split_at(values.len() - tail.len());
+                self.matches_all(head, values_head, bindings)?
+                    && self.matches_all(tail, values_tail, bindings)?
+                    && match rest {
+                        Some(rest) => self.matches(
+                            rest,
+                            &Primitive::Array(values.to_vec()),
+                            bindings,
+                        )?,
+                        None => true,
+                    }
+            }
+            Pattern::Struct { fields, rest } => {
+                let Primitive::Struct(struc) = value else {
+                    return Ok(false);
+                };
+                if rest.is_none() && struc.len() != fields.len() {
+                    return Ok(false);
+                }
+                for (key, pattern) in fields {
+                    let Some(value) = struc.get(key) else {
+                        return Ok(false);
+                    };
+                    if !self.matches(pattern, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                match rest {
+                    Some(rest) => {
+                        let others = struc
+                            .iter()
+                            .filter(|(k, _)| {
+                                !fields.iter().any(|(f, _)| f == *k)
+                            })
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .collect();
+                        self.matches(
+                            rest,
+                            &Primitive::Struct(others),
+                            bindings,
+                        )?
+                    }
+                    None => true,
+                }
+            }
+        };
+        Ok(matched)
+    }
+
+    fn matches_all(
+        &mut self,
+        patterns: &[Pattern],
+        values: &[Primitive],
+        bindings: &mut Vec<(usize, Primitive)>,
+    ) -> anyhow::Result<bool> {
+        for (pattern, value) in patterns.iter().zip(values) {
+            if !self.matches(pattern, value, bindings)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+    // endregion: match
+
+    // region: function call
+    fn closure(&mut self, closure: &Closure) -> anyhow::Result<Primitive> {
+        let mut function = closure.function.clone();
+        let Primitive::Function { captured, .. } = &mut function else {
+            return Ok(function);
+        };
+        for (slot, by_ref) in &closure.captures {
+            let Some(cell) = self.lookup(*slot) else {
+                continue;
+            };
+            let cell = if *by_ref {
+                cell
+            } else {
+                // a copy of a constant is still constant
+                let copy = read(&cell)?.clone().ref_prim();
+                if let Some(name) = constant_name(&cell) {
+                    add_constant(&name, &copy)?;
+                }
+                copy
+            };
+            captured.insert(self.names[*slot].clone(), cell);
+        }
+        Ok(function)
+    }
+
+    fn call(&mut self, call: &Call) -> anyhow::Result<Primitive> {
+        let names = self.names;
+        let function = match &call.function {
+            Callee::Variable(slot) => match self.lookup(*slot) {
+                Some(cell) => {
+                    let guard = read(&cell)?;
+                    match (&*guard, &call.args) {
+                        (
+                            Primitive::Function {
+                                parameters,
+                                exprs,
+                                captured,
+                                body,
+                            },
+                            Args::List(args, named),
+                        ) => {
+                            // avoid cloning the body of the function
+                            let function =
+                                function_body(parameters, exprs, body);
+                            let parameters = parameters.clone();
+                            let captured = captured.clone();
+                            drop(guard);
+                            let args = self.arguments(args, named)?;
+                            return self.run_function(
+                                &parameters,
+                                &function,
+                                captured,
+                                args,
+                                &names[*slot],
+                                call.site.as_ref(),
+                            );
+                        }
+                        (Primitive::EarlyReturn(p), _) => *p.clone(),
+                        (p, _) => p.clone(),
+                    }
+                }
+                None => Primitive::Error(
+                    format!("variable {} not found in ctx", self.names[*slot])
+                        .into(),
+                ),
+            },
+            Callee::Block(code) => self.exec(code)?,
+        };
+        let name = match &call.function {
+            Callee::Variable(slot) => &self.names[*slot],
+            Callee::Block(_) => ANONYMOUS_FRAME,
+        };
+        self.call_primitive(function, &call.args, name, call.site.as_ref())
+    }
+
+    fn call_primitive(
+        &mut self,
+        function: Primitive,
+        args: &Args,
+        name: &str,
+        site: Option<&Span>,
+    ) -> anyhow::Result<Primitive> {
+        let (args, named) = match args {
+            Args::List(args, named) => (args, named),
+            Args::Invalid(parameters) => {
+                return Ok(Primitive::Error(
+                    format!(
+                        "invalid function call: {parameters:?} => {function:?}"
+                    )
+                    .into(),
+                ))
+            }
+        };
+        let function = match function {
+            Primitive::Ref(r) => read(&r)?.clone(),
+            function => function,
+        };
+        match function {
+            Primitive::Function { parameters, exprs, captured, body } => {
+                let function = function_body(&parameters, &exprs, &body);
+                let args = self.arguments(args, named)?;
+                self.run_function(
+                    &parameters,
+                    &function,
+                    captured,
+                    args,
+                    name,
+                    site,
+                )
+            }
+            Primitive::NativeLibrary(_) => {
+                for (arg, is_variable) in args {
+                    if *is_variable {
+                        self.exec(arg)?;
+                    }
+                }
+                Ok(Primitive::Error("debug".into()))
+            }
+            Primitive::NativeFunction(_, _) if !named.is_empty() => {
+                Ok(Primitive::Error(
+                    format!("native function {name} takes no named argument")
+                        .into(),
+                ))
+            }
+            Primitive::NativeFunction(key, lib) => {
+                let mut parameters = Vec::with_capacity(args.len());
+                for (arg, _) in args {
+                    parameters.push(self.exec(arg)?);
+                }
+                self.call_native(&key, &lib, parameters)
+            }
+            // `Status(parse_json(json))`
+            Primitive::Enum(e) => match (&args[..], &named[..]) {
+                ([(arg, _)], []) => {
+                    let value = self.exec(arg)?;
+                    Ok(e.from_primitive(&value))
+                }
+                _ => Ok(Primitive::Error(
+                    format!("{name} expects one argument").into(),
+                )),
+            },
+            _ => Ok(Primitive::Error(
+                format!(" not a function: {function}").into(),
+            )),
+        }
+    }
+
+    /// `obj.method()`, the function sees the struct it belongs to as `self`
+    fn call_method(
+        &mut self,
+        function: Primitive,
+        receiver: RefPrimitive,
+        args: &Args,
+        name: &str,
+        site: Option<&Span>,
+    ) -> anyhow::Result<Primitive> {
+        let function = match function {
+            Primitive::Function { parameters, exprs, mut captured, body } => {
+                captured.insert(SELF.to_string(), receiver);
+                Primitive::Function { parameters, exprs, captured, body }
+            }
+            function => function,
+        };
+        self.call_primitive(function, args, name, site)
+    }
+
+    /// `"abc".to_upper()` or `arr.map(f)`, a builtin called with the value
+    /// as its first argument
+    fn call_builtin_method(
+        &mut self,
+        fn_type: BuiltInFunctionType,
+        receiver: Primitive,
+        args: &Args,
+    ) -> anyhow::Result<Primitive> {
+        let args = match args {
+            Args::List(args, named) if named.is_empty() => args,
+            _ => {
+                return Ok(argument_error(format!(
+                    "invalid call to {}",
+                    fn_type.as_str()
+                )))
+            }
+        };
+        let mut values = Vec::with_capacity(args.len() + 1);
+        values.push(receiver);
+        for (arg, _) in args {
+            values.push(self.exec(arg)?);
+        }
+        let res = if fn_type.takes_many_args() {
+            self.builtin(fn_type, Primitive::Array(values))
+        } else {
+            match <[Primitive; 1]>::try_from(values) {
+                Ok([v]) => self.builtin(fn_type, v),
+                Err(_) => Ok(argument_error(format!(
+                    "{} takes no argument",
+                    fn_type.as_str()
+                ))),
+            }
+        };
+        raised()?;
+        res
+    }
+
+    fn arguments(
+        &mut self,
+        args: &[(Code, bool)],
+        named: &[(String, Code)],
+    ) -> anyhow::Result<Arguments> {
+        let mut values = Vec::with_capacity(args.len());
+        for (arg, _) in args {
+            values.push(self.exec(arg)?);
+        }
+        let mut named_values = Vec::with_capacity(named.len());
+        for (key, arg) in named {
+            named_values.push((key.clone(), self.exec(arg)?));
+        }
+        Ok(Arguments { values, named: named_values })
+    }
+
+    fn run_function(
+        &mut self,
+        parameters: &[Value],
+        function: &Function,
+        captured: Captured,
+        args: Arguments,
+        name: &str,
+        site: Option<&Span>,
+    ) -> anyhow::Result<Primitive> {
+        let mut scope_ctx = scoped_ctx(self.env.vars)?;
+        scope_ctx.extend(captured.0);
+        if let Err(e) = self.bind_arguments(
+            parameters,
+            function,
+            args,
+            name,
+            &mut scope_ctx,
+        )? {
+            return Ok(Primitive::Error(e.into()));
+        }
+        let program = &function.body;
+        if program.generator {
+            let generator = Generator::new(
+                name,
+                program.clone(),
+                scope_ctx,
+                self.shared_lib,
+            );
+            return Ok(Primitive::Iterator(LazyIterator::new(generator)));
+        }
+        let _frame = enter_frame(name, site);
+        match run(program, &mut scope_ctx, self.shared_lib)? {
+            Primitive::EarlyReturn(v) => Ok(*v),
+            res => Ok(res),
+        }
+    }
+
+    /// bind the arguments to the parameters of the function, positional
+    /// arguments first, then named ones, then default values. Default values
+    /// can use the parameters before them
+    fn bind_arguments(
+        &mut self,
+        parameters: &[Value],
+        function: &Function,
+        args: Arguments,
+        name: &str,
+        scope_ctx: &mut BTreeMap<String, RefPrimitive>,
+    ) -> anyhow::Result<Result<(), String>> {
+        let Arguments { values, mut named } = args;
+        let given = values.len();
+        let mut values = values.into_iter();
+        for (param, default) in parameters.iter().zip(&function.defaults) {
+            let variable = match param {
+                Value::Rest(variable) => {
+                    let rest = Primitive::Array(values.by_ref().collect());
+                    scope_ctx.insert(variable.clone(), rest.ref_prim());
+                    continue;
+                }
+                Value::Variable(variable) => Some(variable),
+                Value::VariableExpr { name: variable, .. } => {
+                    match variable.as_ref() {
+                        Value::Variable(variable) => Some(variable),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            let named_arg = variable
+                .and_then(|v| named.iter().position(|(n, _)| n == v))
+                .map(|idx| named.remove(idx).1);
+            let parameter = variable.map(String::as_str).unwrap_or("_");
+            let arg = match (values.next(), named_arg, default) {
+                (Some(_), Some(_), _) => {
+                    return Ok(Err(format!(
+                        "{name} got two values for parameter {parameter}"
+                    )));
+                }
+                (Some(arg), None, _) | (None, Some(arg), _) => arg,
+                (None, None, Some(default)) => {
+                    run(default, scope_ctx, self.shared_lib)?
+                }
+                (None, None, None) => {
+                    return Ok(Err(format!(
+                        "{name} is missing parameter {parameter}"
+                    )));
+                }
+            };
+            if let Some(variable) = variable {
+                scope_ctx.insert(variable.clone(), arg.ref_prim());
+            }
+        }
+        if values.next().is_some() {
+            let expected = parameters.len();
+            return Ok(Err(format!(
+                "{name} takes {expected} argument(s), got {given}"
+            )));
+        }
+        if let Some((key, _)) = named.first() {
+            return Ok(Err(format!("{name} has no parameter {key}")));
+        }
+        Ok(Ok(()))
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn call_native(
+        &mut self,
+        key: &str,
+        lib: &Arc<NativeLibrary>,
+        parameters: Vec<Primitive>,
+    ) -> anyhow::Result<Primitive> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(expected) = unsafe { lib.arity(key) } {
+                let given = parameters.len();
+                if given != expected {
+                    return Ok(argument_error(format!(
+                        "{key} takes {expected} argument(s), got {given}"
+                    )));
+                }
+            }
+            let mut scope_ctx = scoped_ctx(self.env.vars)?;
+
+            let slb = self.shared_lib.to_path_buf();
+            let fun = move |v, extra_ctx| {
+                scope_ctx.extend(extra_ctx);
+                compute_lazy(v, &mut scope_ctx, &slb)
+            };
+            unsafe { lib.call_function(key, parameters, Box::new(fun)) }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            Ok(Primitive::Error(format!("Loading native function {key} doesn't work in wasm context! {lib:?}").into()))
+        }
+    }
+
+    /// call a function value with already evaluated arguments, e.g. the
+    /// callback given to a builtin like `map`
+    fn apply(
+        &mut self,
+        function: &Primitive,
+        args: Vec<Primitive>,
+    ) -> anyhow::Result<Primitive> {
+        match function {
+            Primitive::Ref(r) => {
+                let function = read(r)?.clone();
+                self.apply(&function, args)
+            }
+            Primitive::Function { parameters, exprs, captured, body } => {
+                let function = function_body(parameters, exprs, body);
+                self.run_function(
+                    parameters,
+                    &function,
+                    captured.clone(),
+                    Arguments { values: args, named: vec![] },
+                    ANONYMOUS_FRAME,
+                    None,
+                )
+            }
+            Primitive::NativeFunction(key, lib) => {
+                self.call_native(key, lib, args)
+            }
+            _ => Ok(Primitive::Error(
+                format!(" not a function: {function}").into(),
+            )),
+        }
+    }
+    // endregion: function call
+
+    /// a builtin call. Its own failures get the kind of the builtin, while
+    /// the errors it is given are passed on as they are
+    fn builtin(
+        &mut self,
+        fn_type: BuiltInFunctionType,
+        v: Primitive,
+    ) -> anyhow::Result<Primitive> {
+        let given_errors: Vec<_> = match &v {
+            e @ Primitive::Error(_) => vec![e.clone()],
+            Primitive::Array(args) => args
+                .iter()
+                .filter(|a| matches!(a, Primitive::Error(_)))
+                .cloned()
+                .collect(),
+            _ => vec![],
+        };
+        let Some(kind) = builtin_error_kind(fn_type) else {
+            return self.run_builtin(fn_type, v);
+        };
+        match self.run_builtin(fn_type, v) {
+            Ok(Primitive::Error(mut e))
+                if e.kind == DEFAULT_ERROR_KIND
+                    && e.stack.is_empty()
+                    && !given_errors.iter().any(
+                        |g| matches!(g, Primitive::Error(g) if *g == e),
+                    ) =>
+            {
+                e.kind = kind.to_string();
+                Ok(Primitive::Error(e))
+            }
+            // a failure of the builtin itself, not of the script it runs
+            Err(e)
+                if e.downcast_ref::<Thrown>().is_none()
+                    && e.downcast_ref::<Suspended>().is_none()
+                    && e.downcast_ref::<Diagnostic>().is_none() =>
+            {
+                let error = ScriptError::with_kind(kind, format!("{e:#}"));
+                Err(anyhow::Error::new(Thrown(Primitive::Error(Box::new(
+                    error,
+                )))))
+            }
+            res => res,
+        }
+    }
+
+    fn run_builtin(
+        &mut self,
+        fn_type: BuiltInFunctionType,
+        v: Primitive,
+    ) -> anyhow::Result<Primitive> {
+        match fn_type {
+            BuiltInFunctionType::Sqrt => Ok(v.sqrt()),
+            BuiltInFunctionType::Abs => Ok(v.abs()),
+            BuiltInFunctionType::Log => Ok(v.log()),
+            BuiltInFunctionType::Ln => Ok(v.ln()),
+            BuiltInFunctionType::Sin => Ok(v.sin()),
+            BuiltInFunctionType::Cos => Ok(v.cos()),
+            BuiltInFunctionType::Eval => match v {
+                Primitive::String(script) => {
+                    self.untracked(|ctx, shared_lib| {
+                        compute(&script, ctx, shared_lib)
+                    })
+                }
+                _ => Ok(Primitive::Error(format!("invalid script {v}").into())),
+            },
+            BuiltInFunctionType::Tan => Ok(v.tan()),
+            BuiltInFunctionType::ToInt => Ok(v.to_int()),
+            BuiltInFunctionType::ToHex => Ok(v.to_hex()),
+            BuiltInFunctionType::ToBinary => Ok(v.to_binary()),
+            BuiltInFunctionType::ToDouble => Ok(v.to_double()),
+            BuiltInFunctionType::ToBool => Ok(v.to_bool()),
+            BuiltInFunctionType::ToString => {
+                Ok(Primitive::String(v.to_string()))
+            }
+            BuiltInFunctionType::Length => Ok(v.len()),
+            BuiltInFunctionType::Println => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    println!("{v}");
+                    Ok(Primitive::Unit)
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    web_sys::console::log_1(&wasm_bindgen::JsValue::from_str(
+                        &format!("{v}\n"),
+                    ));
+                    Ok(Primitive::Unit)
+                }
+            }
+            BuiltInFunctionType::Print => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    print!("{v}");
+                    Ok(Primitive::Unit)
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    web_sys::console::log_1(&wasm_bindgen::JsValue::from_str(
+                        &format!("{v}"),
+                    ));
+                    Ok(Primitive::Unit)
+                }
+            }
+            BuiltInFunctionType::Require => match v {
+                Primitive::String(file_path) => {
+                    let native_lib = require_dynamic_lib(
+                        file_path.as_str(),
+                        self.shared_lib,
+                    )?;
+                    Ok(Primitive::NativeLibrary(Arc::new(native_lib)))
+                }
+                _ => Ok(Primitive::Error("wrong include call".into())),
+            },
+            BuiltInFunctionType::Include => match v {
+                Primitive::String(file_path) => {
+                    self.untracked(|ctx, shared_lib| {
+                        include(&file_path, ctx, shared_lib)
+                    })
+                }
+                _ => Ok(Primitive::Error("wrong include call".into())),
+            },
+            BuiltInFunctionType::TypeOf => Ok(v.type_of()),
+            BuiltInFunctionType::Floor => Ok(v.floor()),
+            BuiltInFunctionType::Ceil => Ok(v.ceil()),
+            BuiltInFunctionType::Round => match v {
+                Primitive::Array(arr) => {
+                    if arr.is_empty() {
+                        return Ok(Primitive::Error(
+                            format!("Invalid argument len {}", arr.len())
+                                .into(),
+                        ));
+                    }
+                    let s = &arr[0];
+                    let decimals = if arr.len() == 2 {
+                        &arr[1]
+                    } else {
+                        &Primitive::Int(2)
+                    };
+                    Ok(s.round(decimals))
+                }
+                _ => Ok(Primitive::Error(
+                    "invalid call to builtin fn match".into(),
+                )),
+            },
+            BuiltInFunctionType::ToUpper => Ok(v.to_upper()),
+            BuiltInFunctionType::ToLower => Ok(v.to_lower()),
+            BuiltInFunctionType::Capitalize => Ok(v.capitalize()),
+            BuiltInFunctionType::Replace => match v {
+                Primitive::Array(arr) => {
+                    let [s, r, p] = &arr[0..=2] else {
+                        return Ok(Primitive::Error(
+                            format!("Invalid argument len {}", arr.len())
+                                .into(),
+                        ));
+                    };
+                    Ok(s.replace(r, p))
+                }
+                _ => Ok(Primitive::Error(
+                    "invalid call to builtin fn replace".into(),
+                )),
+            },
+            BuiltInFunctionType::ReplaceAll => match v {
+                Primitive::Array(arr) => {
+                    let [s, r, p] = &arr[0..=2] else {
+                        return Ok(Primitive::Error(
+                            format!("Invalid argument len {}", arr.len())
+                                .into(),
+                        ));
+                    };
+                    Ok(s.replace_all(r, p))
+                }
+                _ => Ok(Primitive::Error(
+                    "invalid call to builtin fn replace_all".into(),
+                )),
+            },
+            BuiltInFunctionType::Match => match v {
+                Primitive::Array(arr) => {
+                    let [s, r] = &arr[0..=1] else {
+                        return Ok(Primitive::Error(
+                            format!("Invalid argument len {}", arr.len())
+                                .into(),
+                        ));
+                    };
+                    Ok(s.match_regex(r))
+                }
+                _ => Ok(Primitive::Error(
+                    "invalid call to builtin fn match".into(),
+                )),
+            },
+            BuiltInFunctionType::IsMatch => match v {
+                Primitive::Array(arr) => {
+                    let [s, r] = &arr[0..=1] else {
+                        return Ok(Primitive::Error(
+                            format!("Invalid argument len {}", arr.len())
+                                .into(),
+                        ));
+                    };
+                    Ok(s.is_match(r))
+                }
+                _ => Ok(Primitive::Error(
+                    "invalid call to builtin fn is_match".into(),
+                )),
+            },
+            BuiltInFunctionType::IsError => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_ERROR))
+            }
+            BuiltInFunctionType::IsU8 => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_U8))
+            }
+            BuiltInFunctionType::IsI8 => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_I8))
+            }
+            BuiltInFunctionType::IsStruct => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_STRUCT))
+            }
+            BuiltInFunctionType::IsBool => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_BOOL))
+            }
+            BuiltInFunctionType::IsInt => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_INT))
+            }
+            BuiltInFunctionType::IsDouble => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_DOUBLE))
+            }
+            BuiltInFunctionType::IsFunction => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_FUNCTION))
+            }
+            BuiltInFunctionType::IsArray => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_ARRAY))
+            }
+            BuiltInFunctionType::IsDict => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_DICT))
+            }
+            BuiltInFunctionType::IsSet => {
+                Ok(Primitive::Bool(v.type_of_str() == TYPE_SET))
+            }
+            BuiltInFunctionType::MakeError => match v {
+                Primitive::Struct(fields) => Ok(Primitive::Error(Box::new(
+                    ScriptError::from_struct(fields),
+                ))),
+                v => Ok(Primitive::Error(v.to_string().into())),
+            },
+            BuiltInFunctionType::Jsonify => Ok(Primitive::String(v.to_json()?)),
+            BuiltInFunctionType::ParseJson => {
+                Primitive::from_json(&v.to_string())
+            }
+            BuiltInFunctionType::Trim => Ok(v.trim()),
+            BuiltInFunctionType::TrimStart => Ok(v.trim_start()),
+            BuiltInFunctionType::TrimEnd => Ok(v.trim_end()),
+            BuiltInFunctionType::Lines => Ok(v.lines()),
+            BuiltInFunctionType::Chars => Ok(v.chars()),
+            BuiltInFunctionType::ToDict => Ok(v.to_dict()),
+            BuiltInFunctionType::Keys => Ok(v.keys()),
+            BuiltInFunctionType::Values => Ok(v.values()),
+            BuiltInFunctionType::Entries => Ok(v.entries()),
+            BuiltInFunctionType::ToSet => Ok(v.to_set()),
+            BuiltInFunctionType::ToArray => Ok(v.to_array()),
+            BuiltInFunctionType::Iter
+            | BuiltInFunctionType::Take
+            | BuiltInFunctionType::Skip
+            | BuiltInFunctionType::Enumerate
+            | BuiltInFunctionType::Chain
+            | BuiltInFunctionType::ReadLines => {
+                match iterator_builtin(fn_type, deref(v)?) {
+                    Ok(p) | Err(BuiltinError::Value(p)) => Ok(p),
+                    Err(BuiltinError::Fatal(e)) => Err(e),
+                }
+            }
+            BuiltInFunctionType::HasKey
+            | BuiltInFunctionType::Remove
+            | BuiltInFunctionType::Add
+            | BuiltInFunctionType::Union
+            | BuiltInFunctionType::Intersection
+            | BuiltInFunctionType::Difference => {
+                match builtin_args(fn_type.as_str(), v) {
+                    Ok([l, r]) => Ok(match fn_type {
+                        BuiltInFunctionType::HasKey => l.has_key(&r),
+                        BuiltInFunctionType::Remove => l.remove_key(&r),
+                        BuiltInFunctionType::Add => l.add_value(&r),
+                        BuiltInFunctionType::Union => l.union(&r),
+                        BuiltInFunctionType::Intersection => l.intersection(&r),
+                        _ => l.difference(&r),
+                    }),
+                    Err(BuiltinError::Value(p)) => Ok(p),
+                    Err(BuiltinError::Fatal(e)) => Err(e),
+                }
+            }
+            BuiltInFunctionType::WrappingAdd
+            | BuiltInFunctionType::WrappingSub
+            | BuiltInFunctionType::WrappingMul
+            | BuiltInFunctionType::CheckedAdd
+            | BuiltInFunctionType::CheckedSub
+            | BuiltInFunctionType::CheckedMul => {
+                match builtin_args(fn_type.as_str(), v) {
+                    Ok([l, r]) => Ok(match fn_type {
+                        BuiltInFunctionType::WrappingAdd => {
+                            l.wrapping(&r, Operator::Add)
+                        }
+                        BuiltInFunctionType::WrappingSub => {
+                            l.wrapping(&r, Operator::Subtr)
+                        }
+                        BuiltInFunctionType::WrappingMul => {
+                            l.wrapping(&r, Operator::Mult)
+                        }
+                        BuiltInFunctionType::CheckedAdd => {
+                            l.checked(&r, Operator::Add)
+                        }
+                        BuiltInFunctionType::CheckedSub => {
+                            l.checked(&r, Operator::Subtr)
+                        }
+                        _ => l.checked(&r, Operator::Mult),
+                    }),
+                    Err(BuiltinError::Value(p)) => Ok(p),
+                    Err(BuiltinError::Fatal(e)) => Err(e),
+                }
+            }
+            BuiltInFunctionType::Split
+            | BuiltInFunctionType::Join
+            | BuiltInFunctionType::Substring
+            | BuiltInFunctionType::Contains
+            | BuiltInFunctionType::StartsWith
+            | BuiltInFunctionType::EndsWith
+            | BuiltInFunctionType::IndexOf
+            | BuiltInFunctionType::PadLeft
+            | BuiltInFunctionType::PadRight
+            | BuiltInFunctionType::Repeat => Ok(string_builtin(fn_type, v)),
+            BuiltInFunctionType::Map
+            | BuiltInFunctionType::Filter
+            | BuiltInFunctionType::Reduce
+            | BuiltInFunctionType::Sort
+            | BuiltInFunctionType::SortBy
+            | BuiltInFunctionType::Find
+            | BuiltInFunctionType::Any
+            | BuiltInFunctionType::All
+            | BuiltInFunctionType::Zip
+            | BuiltInFunctionType::Flatten
+            | BuiltInFunctionType::Reverse
+            | BuiltInFunctionType::Unique
+            | BuiltInFunctionType::GroupBy => {
+                match self.higher_order(fn_type, deref(v)?) {
+                    Ok(p) | Err(BuiltinError::Value(p)) => Ok(p),
+                    Err(BuiltinError::Fatal(e)) => Err(e),
+                }
+            }
+        }
+    }
+
+    // region: array builtins
+    fn higher_order(
+        &mut self,
+        fn_type: BuiltInFunctionType,
+        v: Primitive,
+    ) -> Result<Primitive, BuiltinError> {
+        let name = fn_type.as_str();
+        let res = match fn_type {
+            BuiltInFunctionType::Map => {
+                let [arr, f] = builtin_args(name, v)?;
+                let mut res = vec![];
+                for item in array_arg(name, arr)? {
+                    res.push(callback(self.apply(&f, vec![item])?)?);
+                }
+                Primitive::Array(res)
+            }
+            BuiltInFunctionType::Filter => {
+                let [arr, f] = builtin_args(name, v)?;
+                let mut res = vec![];
+                for item in array_arg(name, arr)? {
+                    if self.predicate(&f, &item)? {
+                        res.push(item);
+                    }
+                }
+                Primitive::Array(res)
+            }
+            // without an initial value, the first item is the accumulator
+            BuiltInFunctionType::Reduce => {
+                let (arr, f, init) = match v {
+                    Primitive::Array(args) if args.len() == 2 => {
+                        let [arr, f] =
+                            builtin_args(name, Primitive::Array(args))?;
+                        (arr, f, None)
+                    }
+                    v => {
+                        let [arr, f, init] = builtin_args(name, v)?;
+                        (arr, f, Some(init))
+                    }
+                };
+                let mut items = array_arg(name, arr)?.into_iter();
+                let Some(mut acc) = init.or_else(|| items.next()) else {
+                    let msg = "reduce: empty array with no initial value";
+                    return Err(Primitive::Error(msg.into()).into());
+                };
+                for item in items {
+                    acc = callback(self.apply(&f, vec![acc, item])?)?;
+                }
+                acc
+            }
+            BuiltInFunctionType::Sort => {
+                let mut arr = array_arg(name, v)?;
+                let mut incomparable = None;
+                arr.sort_by(|l, r| {
+                    compare(l, r).unwrap_or_else(|| {
+                        incomparable.get_or_insert((l.clone(), r.clone()));
+                        Ordering::Equal
+                    })
+                });
+                if let Some((l, r)) = incomparable {
+                    let msg = format!("sort: cannot compare {l} with {r}");
+                    return Err(Primitive::Error(msg.into()).into());
+                }
+                Primitive::Array(arr)
+            }
+            BuiltInFunctionType::SortBy => {
+                let [arr, f] = builtin_args(name, v)?;
+                let mut arr = array_arg(name, arr)?;
+                // the comparator can fail: its first error is returned once
+                // the array is sorted
+                let mut failed = None;
+                arr.sort_by(|l, r| {
+                    if failed.is_some() {
+                        return Ordering::Equal;
+                    }
+                    let order = self
+                        .apply(&f, vec![l.clone(), r.clone()])
+                        .map_err(BuiltinError::from)
+                        .and_then(|order| Ok(callback(order)?));
+                    match order.map(|o| (compare(&o, &Primitive::Int(0)), o)) {
+                        Ok((Some(ordering), _)) => ordering,
+                        Ok((None, order)) => {
+                            let msg = format!(
+                                "sort_by: expected a number, got {order}"
+                            );
+                            failed = Some(Primitive::Error(msg.into()).into());
+                            Ordering::Equal
+                        }
+                        Err(e) => {
+                            failed = Some(e);
+                            Ordering::Equal
+                        }
+                    }
+                });
+                if let Some(e) = failed {
+                    return Err(e);
+                }
+                Primitive::Array(arr)
+            }
+            BuiltInFunctionType::Find => {
+                let [arr, f] = builtin_args(name, v)?;
+                let mut found = Primitive::Null;
+                for item in array_arg(name, arr)? {
+                    if self.predicate(&f, &item)? {
+                        found = item;
+                        break;
+                    }
+                }
+                found
+            }
+            BuiltInFunctionType::Any | BuiltInFunctionType::All => {
+                let [arr, f] = builtin_args(name, v)?;
+                let any = matches!(fn_type, BuiltInFunctionType::Any);
+                let mut res = !any;
+                for item in array_arg(name, arr)? {
+                    if self.predicate(&f, &item)? == any {
+                        res = any;
+                        break;
+                    }
+                }
+                Primitive::Bool(res)
+            }
+            BuiltInFunctionType::Zip => {
+                let [l, r] = builtin_args(name, v)?;
+                let (l, r) = (array_arg(name, l)?, array_arg(name, r)?);
+                Primitive::Array(
+                    l.into_iter()
+                        .zip(r)
+                        .map(|(l, r)| Primitive::Array(vec![l, r]))
+                        .collect(),
+                )
+            }
+            BuiltInFunctionType::Flatten => {
+                let mut res = vec![];
+                for item in array_arg(name, v)? {
+                    match deref(item)? {
+                        Primitive::Array(arr) => res.extend(arr),
+                        item => res.push(item),
+                    }
+                }
+                Primitive::Array(res)
+            }
+            BuiltInFunctionType::Reverse => match v {
+                Primitive::String(s) => {
+                    Primitive::String(s.chars().rev().collect())
+                }
+                v => {
+                    let mut arr = array_arg(name, v)?;
+                    arr.reverse();
+                    Primitive::Array(arr)
+                }
+            },
+            BuiltInFunctionType::Unique => {
+                let mut res: Vec<Primitive> = vec![];
+                for item in array_arg(name, v)? {
+                    if !res.iter().any(|seen| same_value(seen, &item)) {
+                        res.push(item);
+                    }
+                }
+                Primitive::Array(res)
+            }
+            BuiltInFunctionType::GroupBy => {
+                let [arr, f] = builtin_args(name, v)?;
+                let mut groups = BTreeMap::new();
+                for item in array_arg(name, arr)? {
+                    let key = callback(self.apply(&f, vec![item.clone()])?)?;
+                    let group = groups
+                        .entry(DictKey::try_from(&key)?)
+                        .or_insert_with(|| Primitive::Array(vec![]));
+                    if let Primitive::Array(group) = group {
+                        group.push(item);
+                    }
+                }
+                Primitive::Dict(groups)
+            }
+            _ => unreachable!("{name} is not an array builtin"),
+        };
+        Ok(res)
+    }
+
+    /// whether the callback returns true for the item
+    fn predicate(
+        &mut self,
+        f: &Primitive,
+        item: &Primitive,
+    ) -> Result<bool, BuiltinError> {
+        match callback(self.apply(f, vec![item.clone()])?)?.to_bool() {
+            Primitive::Bool(b) => Ok(b),
+            e => Err(BuiltinError::Value(e)),
+        }
+    }
+    // endregion: array builtins
+}
+
+/// string builtins taking more than one argument. The optional last
+/// argument of `substring`, `pad_left` and `pad_right` defaults to null
+fn string_builtin(fn_type: BuiltInFunctionType, v: Primitive) -> Primitive {
+    let Primitive::Array(mut args) = v else {
+        return Primitive::Error(
+            format!("invalid call to builtin fn {}", fn_type.as_str()).into(),
+        );
+    };
+    let optional = matches!(
+        fn_type,
+        BuiltInFunctionType::Substring
+            | BuiltInFunctionType::PadLeft
+            | BuiltInFunctionType::PadRight
+    );
+    if optional && args.len() == 2 {
+        args.push(Primitive::Null);
+    }
+    match (fn_type, args.as_slice()) {
+        (BuiltInFunctionType::Split, [s, sep]) => s.split(sep),
+        (BuiltInFunctionType::Join, [arr, sep]) => arr.join(sep),
+        (BuiltInFunctionType::Substring, [s, start, end]) => {
+            s.substring(start, end)
+        }
+        (BuiltInFunctionType::Contains, [s, p]) => s.contains(p),
+        (BuiltInFunctionType::StartsWith, [s, p]) => s.starts_with(p),
+        (BuiltInFunctionType::EndsWith, [s, p]) => s.ends_with(p),
+        (BuiltInFunctionType::IndexOf, [s, p]) => s.index_of(p),
+        (BuiltInFunctionType::PadLeft, [s, width, pad]) => {
+            s.pad_left(width, pad)
+        }
+        (BuiltInFunctionType::PadRight, [s, width, pad]) => {
+            s.pad_right(width, pad)
+        }
+        (BuiltInFunctionType::Repeat, [s, n]) => s.repeat(n),
+        (fn_type, args) => Primitive::Error(
+            format!(
+                "Invalid argument len {} for builtin fn {}",
+                args.len(),
+                fn_type.as_str()
+            )
+            .into(),
+        ),
+    }
+}
+
+fn deref(p: Primitive) -> anyhow::Result<Primitive> {
+    match p {
+        Primitive::Ref(r) => Ok(read(&r)?.clone()),
+        p => Ok(p),
+    }
+}
+
+/// an array builtin stops either with an error value returned to the
+/// script, e.g. a wrong argument or an error from a callback, or with an
+/// error of the vm
+enum BuiltinError {
+    Value(Primitive),
+    Fatal(anyhow::Error),
+}
+
+impl From<Primitive> for BuiltinError {
+    fn from(p: Primitive) -> Self {
+        BuiltinError::Value(p)
+    }
+}
+
+impl From<anyhow::Error> for BuiltinError {
+    fn from(e: anyhow::Error) -> Self {
+        BuiltinError::Fatal(e)
+    }
+}
+
+fn builtin_error_kind(fn_type: BuiltInFunctionType) -> Option<&'static str> {
+    match fn_type {
+        // the errors of the script evaluated, or made by the script
+        BuiltInFunctionType::Eval | BuiltInFunctionType::MakeError => None,
+        BuiltInFunctionType::ToInt
+        | BuiltInFunctionType::ToDouble
+        | BuiltInFunctionType::ToBool
+        | BuiltInFunctionType::ParseJson
+        | BuiltInFunctionType::Match
+        | BuiltInFunctionType::IsMatch => Some(PARSE_ERROR_KIND),
+        BuiltInFunctionType::Include
+        | BuiltInFunctionType::Require
+        | BuiltInFunctionType::ReadLines => Some(IO_ERROR_KIND),
+        _ => Some(TYPE_ERROR_KIND),
+    }
+}
+
+fn argument_error(message: String) -> Primitive {
+    Primitive::Error(Box::new(ScriptError::with_kind(
+        ARGUMENT_ERROR_KIND,
+        message,
+    )))
+}
+
+/// the arguments of a builtin parsed with many args
+fn builtin_args<const N: usize>(
+    name: &str,
+    v: Primitive,
+) -> Result<[Primitive; N], BuiltinError> {
+    let Primitive::Array(args) = v else {
+        return Err(BuiltinError::Value(argument_error(format!(
+            "invalid call to builtin fn {name}"
+        ))));
+    };
+    let args =
+        args.into_iter().map(deref).collect::<anyhow::Result<Vec<_>>>()?;
+    <[Primitive; N]>::try_from(args).map_err(|args| {
+        BuiltinError::Value(argument_error(format!(
+            "Invalid argument len {} for builtin fn {name}, expected {N}",
+            args.len()
+        )))
+    })
+}
+
+fn array_arg(name: &str, v: Primitive) -> Result<Vec<Primitive>, Primitive> {
+    match v {
+        Primitive::Array(arr) => Ok(arr),
+        Primitive::Iterator(it) => Ok(it.restart().collect()),
+        v => Err(Primitive::Error(
+            format!("{name}: expected an array, got {}", v.type_of_str())
+                .into(),
+        )),
+    }
+}
+
+/// errors returned by a callback stop the builtin
+fn callback(p: Primitive) -> Result<Primitive, Primitive> {
+    match p {
+        e @ Primitive::Error(_) => Err(e),
+        p => Ok(p),
+    }
+}
+
+fn is_number(p: &Primitive) -> bool {
+    matches!(
+        p,
+        Primitive::U8(_)
+            | Primitive::I8(_)
+            | Primitive::Int(_)
+            | Primitive::BigInt(_)
+            | Primitive::Double(_)
+            | Primitive::Decimal(_)
+            | Primitive::Rational(_)
+    )
+}
+
+/// `==` would cast to bool, e.g. `true == 1`, so only numbers are compared
+/// across types
+fn same_value(l: &Primitive, r: &Primitive) -> bool {
+    if is_number(l) && is_number(r) {
+        matches!(l.is_equal(r), Primitive::Bool(true))
+    } else {
+        l == r
+    }
+}
+
+/// same as `same_value`, values of different types other than numbers
+/// cannot be compared
+fn compare(l: &Primitive, r: &Primitive) -> Option<Ordering> {
+    if (is_number(l) && is_number(r))
+        || std::mem::discriminant(l) == std::mem::discriminant(r)
+    {
+        l.partial_cmp(r)
+    } else {
+        None
+    }
+}
+
+fn read(cell: &RefPrimitive) -> anyhow::Result<CellValue<'_>> {
+    cell.read_value()
+        .map_err(|e| anyhow::format_err!("could not acquire lock {e}"))
+}
+
+pub(crate) fn binary(
+    operator: Operator,
+    left: &Primitive,
+    right: &Primitive,
+) -> Primitive {
+    match operator {
+        Operator::Add => left.add(right),
+        Operator::Subtr => left.sub(right),
+        Operator::Mult => left.mul(right),
+        Operator::Div => left.div(right),
+        Operator::Mod => left.rem(right),
+        Operator::Pow => left.pow(right),
+        Operator::And => left.and(right),
+        Operator::Or => left.or(right),
+        Operator::NullCoalesce => {
+            if missing(left) {
+                right.clone()
+            } else {
+                left.clone()
+            }
+        }
+        Operator::BitwiseAnd => left.bitwise_and(right),
+        Operator::BitwiseOr => left.bitwise_or(right),
+        Operator::BitwiseXor => left.bitwise_xor(right),
+        Operator::BitwiseLShift => left.left_shift(right),
+        Operator::BitwiseRShift => left.right_shift(right),
+        Operator::Equal => left.is_equal(right),
+        Operator::NotEqual => left.is_equal(right).not(),
+        Operator::Is => left.is_variant(right),
+        Operator::Less => left.is_less_than(right),
+        Operator::Greater => left.is_greater_than(right),
+        Operator::LessOrEqual => left.is_less_or_equal(right),
+        Operator::GreaterOrEqual => left.is_greater_or_equal(right),
+        Operator::Convert => left.convert(right),
+        Operator::Not
+        | Operator::BitwiseNot
+        | Operator::Pow2
+        | Operator::Pow3 => unreachable!("VM BUG: {operator:?} is not binary"),
+    }
+}
+
+/// a dot call to a builtin, unless the value has a member of that name,
+/// e.g. a function stored in a struct
+fn builtin_method<'k>(
+    root: &Primitive,
+    key: &'k Key,
+) -> Option<(BuiltInFunctionType, &'k Args)> {
+    let Key::Call { key, args, .. } = key else {
+        return None;
+    };
+    let Key::Const(KeyAccess::Key(Primitive::String(name))) = key.as_ref()
+    else {
+        return None;
+    };
+    let has_member = match root {
+        Primitive::Struct(s) => s.contains_key(name),
+        Primitive::Dict(d) => d.contains_key(&DictKey::String(name.clone())),
+        Primitive::Variant(v) => v.fields.contains_key(name),
+        Primitive::Error(e) => !matches!(e.get(name), Primitive::Null),
+        Primitive::Enum(_) | Primitive::NativeLibrary(_) => true,
+        _ => false,
+    };
+    if has_member {
+        return None;
+    }
+    BuiltInFunctionType::from_name(name).map(|fn_type| (fn_type, args))
+}
+
+// region: iterators
+/// the lazy builtins. An array, a set or a string given to them is
+/// iterated as is
+fn iterator_builtin(
+    fn_type: BuiltInFunctionType,
+    v: Primitive,
+) -> Result<Primitive, BuiltinError> {
+    let name = fn_type.as_str();
+    let iterable = |p: Primitive| {
+        lazy(p).map_err(|p| {
+            Primitive::Error(
+                format!(
+                    "{name}: expected an iterable, got {}",
+                    p.type_of_str()
+                )
+                .into(),
+            )
+        })
+    };
+    let count = |p: Primitive| match p {
+        Primitive::U8(n) => Ok(n as usize),
+        Primitive::I8(n) if n >= 0 => Ok(n as usize),
+        Primitive::Int(n) if n >= 0 => Ok(n as usize),
+        _ => Err(Primitive::Error(
+            format!("{name}: expected a positive integer, got {p}").into(),
+        )),
+    };
+    let iterator = match fn_type {
+        BuiltInFunctionType::Take => {
+            let [it, n] = builtin_args(name, v)?;
+            let n = count(n)?;
+            iterable(it)?.then(move |it| it.take(n))
+        }
+        BuiltInFunctionType::Skip => {
+            let [it, n] = builtin_args(name, v)?;
+            let n = count(n)?;
+            iterable(it)?.then(move |it| it.skip(n))
+        }
+        BuiltInFunctionType::Chain => {
+            let [l, r] = builtin_args(name, v)?;
+            let (l, r) = (iterable(l)?, iterable(r)?);
+            if l.is_restartable() && r.is_restartable() {
+                LazyIterator::restartable(move || {
+                    l.restart().chain(r.restart())
+                })
+            } else {
+                LazyIterator::new(l.chain(r))
+            }
+        }
+        BuiltInFunctionType::Enumerate => iterable(v)?.then(|it| {
+            it.enumerate().map(|(i, p)| {
+                Primitive::Array(vec![Primitive::Int(i as i128), p])
+            })
+        }),
+        BuiltInFunctionType::ReadLines => read_lines(v)?,
+        _ => iterable(v)?,
+    };
+    Ok(Primitive::Iterator(iterator))
+}
+
+/// the lines of a file, read as they are iterated. A line that can't be
+/// read ends the iteration with an error
+fn read_lines(path: Primitive) -> Result<LazyIterator, Primitive> {
+    let Primitive::String(path) = path else {
+        return Err(Primitive::Error(
+            format!("read_lines: expected a path, got {}", path.type_of_str())
+                .into(),
+        ));
+    };
+    let file = std::fs::File::open(&path).map_err(|e| {
+        Primitive::Error(format!("could not read {path}: {e}").into())
+    })?;
+    let mut failed = false;
+    let lines = BufReader::new(file).lines().map_while(move |line| {
+        if failed {
+            return None;
+        }
+        Some(match line {
+            Ok(line) => Primitive::String(line),
+            Err(e) => {
+                failed = true;
+                Primitive::Error(format!("could not read {path}: {e}").into())
+            }
+        })
+    });
+    Ok(LazyIterator::new(lines))
+}
+
+/// A generator runs on the thread of its consumer: each value asked for
+/// resumes its body where the previous `yield` paused it
+struct Generator {
+    name: String,
+    program: Arc<Program>,
+    ctx: BTreeMap<String, RefPrimitive>,
+    shared_lib: PathBuf,
+    /// the scopes of the paused body
+    undo: Vec<(String, Option<RefPrimitive>)>,
+    scopes: Vec<usize>,
+    paused: Vec<Paused>,
+    done: bool,
+}
+
+impl Generator {
+    fn new(
+        name: &str,
+        program: Arc<Program>,
+        ctx: BTreeMap<String, RefPrimitive>,
+        shared_lib: &Path,
+    ) -> Generator {
+        Generator {
+            name: name.to_string(),
+            program,
+            ctx,
+            shared_lib: shared_lib.to_path_buf(),
+            undo: vec![],
+            scopes: vec![],
+            paused: vec![],
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Generator {
+    type Item = Primitive;
+
+    fn next(&mut self) -> Option<Primitive> {
+        if self.done {
+            return None;
+        }
+        let _frame = enter_frame(&self.name, None);
+        let mut vm = Vm {
+            env: Env {
+                vars: &mut self.ctx,
+                undo: std::mem::take(&mut self.undo),
+                scopes: std::mem::take(&mut self.scopes),
+            },
+            names: &self.program.names,
+            slots: vec![None; self.program.names.len()],
+            stack: vec![],
+            shared_lib: &self.shared_lib,
+            jump: None,
+            generator: true,
+            yielded: None,
+            paused: std::mem::take(&mut self.paused),
+        };
+        let res = vm.exec(&self.program.code);
+        if suspended(&res) {
+            self.undo = vm.env.undo;
+            self.scopes = vm.env.scopes;
+            self.paused = vm.paused;
+            return vm.yielded;
+        }
+        self.done = true;
+        let err = match res {
+            Ok(Primitive::EarlyReturn(p)) => match *p {
+                e @ Primitive::Error(_) => anyhow::Error::new(Thrown(e)),
+                _ => return None,
+            },
+            Ok(e @ Primitive::Error(_)) => anyhow::Error::new(Thrown(e)),
+            Ok(_) => return None,
+            Err(e) => e,
+        };
+        RAISED.with_borrow_mut(|raised| *raised = Some(err));
+        None
+    }
+}
+
+/// the left operand of `??` that gives the right one
+fn missing(p: &Primitive) -> bool {
+    match p {
+        Primitive::Ref(r) => read(r)
+            .is_ok_and(|p| matches!(*p, Primitive::Null | Primitive::Error(_))),
+        p => matches!(p, Primitive::Null | Primitive::Error(_)),
+    }
+}
+
+fn suspended(res: &anyhow::Result<Primitive>) -> bool {
+    matches!(res, Err(e) if e.is::<Suspended>())
+}
+
+/// fails with the error that ended a generator consumed since the last call
+fn raised() -> anyhow::Result<()> {
+    match RAISED.with_borrow_mut(Option::take) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// an iterable value as an iterator. The elements of a collection are
+/// copied, so it can be restarted
+fn lazy(p: Primitive) -> Result<LazyIterator, Primitive> {
+    let elements: Vec<Primitive> = match p {
+        Primitive::Iterator(it) => return Ok(it.restart()),
+        p => elements(p)?.collect(),
+    };
+    Ok(LazyIterator::restartable(move || elements.clone().into_iter()))
+}
+
+/// the elements of an iterable value, taken one at a time
+fn elements(
+    p: Primitive,
+) -> Result<Box<dyn Iterator<Item = Primitive> + Send>, Primitive> {
+    Ok(match p {
+        Primitive::Iterator(it) => Box::new(it.restart()),
+        Primitive::Array(arr) => Box::new(arr.into_iter()),
+        Primitive::Set(set) => Box::new(
+            set.iter().map(Primitive::from).collect::<Vec<_>>().into_iter(),
+        ),
+        Primitive::Struct(s) => Box::new(s.into_iter().map(|(k, v)| {
+            Primitive::Struct(IndexMap::from([
+                ("key".into(), Primitive::String(k)),
+                ("value".into(), v),
+            ]))
+        })),
+        Primitive::String(s) => Box::new(
+            s.chars()
+                .map(|c| Primitive::String(c.to_string()))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        ),
+        p => return Err(p),
+    })
+}
+
+// endregion: iterators
+
+fn range_bound(p: Primitive) -> anyhow::Result<i128> {
+    match p.to_int() {
+        Primitive::Int(num) => Ok(num),
+        Primitive::U8(num) => Ok(num as i128),
+        Primitive::I8(num) => Ok(num as i128),
+        _ => Err(anyhow::format_err!("range error: {p:?} is not an integer")),
+    }
+}
+
+fn include(
+    file_path: &str,
+    ctx: &mut BTreeMap<String, RefPrimitive>,
+    shared_lib: &Path,
+) -> anyhow::Result<Primitive> {
+    let curr_path =
+        std::env::current_dir().context("no current dir! wasn't expected")?;
+    let temp_path = Path::new(file_path);
+    if temp_path.is_absolute() || temp_path.exists() {
+        let parent = temp_path.parent().context("parent doesn't exist")?;
+
+        std::env::set_current_dir(PathBuf::from(&parent))?;
+    }
+
+    let res = temp_path
+        .file_name()
+        .context("file name not found")
+        .and_then(|p| read_to_string(p).map_err(anyhow::Error::new))
+        .and_then(move |file| {
+            compute_named(&file, Some(file_path), ctx, shared_lib)
+        });
+    std::env::set_current_dir(curr_path)?; // todo this might be quiet fragile
+    res
+}
+
+/// evaluate a module once, in its own context, and keep the names it
+/// exports in a struct. A relative path is relative to the importing module
+fn import(file_path: &str, shared_lib: &Path) -> anyhow::Result<Primitive> {
+    let importer = IMPORTING.with_borrow(|importing| {
+        importing.last().and_then(|p| p.parent()).map(Path::to_path_buf)
+    });
+    let path = std::fs::canonicalize(match importer {
+        Some(dir) => dir.join(file_path),
+        None => PathBuf::from(file_path),
+    })
+    .with_context(|| format!("could not import {file_path}"))?;
+    if let Some(module) = MODULES.with_borrow(|m| m.get(&path).cloned()) {
+        return Ok(module);
+    }
+    if let Some(cycle) = IMPORTING.with_borrow(|importing| {
+        let start = importing.iter().position(|p| p == &path)?;
+        let cycle = importing[start..]
+            .iter()
+            .chain([&path])
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>();
+        Some(cycle.join(" -> "))
+    }) {
+        return Err(anyhow!("circular import {cycle}"));
+    }
+    let text = read_to_string(&path)
+        .with_context(|| format!("could not import {file_path}"))?;
+
+    IMPORTING.with_borrow_mut(|importing| importing.push(path.clone()));
+    let res = load_module(&path, text, shared_lib);
+    IMPORTING.with_borrow_mut(|importing| importing.pop());
+
+    let module = res?;
+    MODULES.with_borrow_mut(|m| m.insert(path, module.clone()));
+    Ok(module)
+}
+
+fn load_module(
+    path: &Path,
+    text: String,
+    shared_lib: &Path,
+) -> anyhow::Result<Primitive> {
+    let source =
+        Arc::new(Source { name: Some(path.display().to_string()), text });
+    let instructions = crate::compute::parse(&source)?;
+    let exported = instructions
+        .iter()
+        .filter_map(|v| match v.unspanned() {
+            Value::Export(v) => match v.unspanned() {
+                Value::VariableExpr { name, .. } => match name.as_ref() {
+                    Value::Variable(name) => Some(name.clone()),
+                    _ => None,
+                },
+                Value::Declaration { name, .. } => Some(name.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut ctx = BTreeMap::new();
+    run(&compile(instructions), &mut ctx, shared_lib)?;
+
+    // exported functions still see the functions of the module, even those
+    // that are not exported
+    let functions = scoped_ctx(&ctx)?;
+    let mut exports = IndexMap::new();
+    for name in exported {
+        let value = match ctx.get(&name) {
+            Some(cell) => read(cell)?.clone(),
+            None => Primitive::Null,
+        };
+        let value = match value {
+            Primitive::Function { parameters, exprs, mut captured, body } => {
+                for (k, f) in functions.iter() {
+                    captured.entry(k.clone()).or_insert_with(|| f.clone());
+                }
+                Primitive::Function { parameters, exprs, captured, body }
+            }
+            value => value,
+        };
+        exports.insert(name, value);
+    }
+    Ok(Primitive::Struct(exports))
+}
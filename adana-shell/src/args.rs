@@ -10,6 +10,8 @@ pub enum Argument {
     HistoryPath(String),
     SharedLibPath(String),
     DefaultCache(String),
+    /// `adana check script.adana`, report problems without running it
+    Check(String),
 }
 
 pub fn parse_args(
@@ -116,6 +118,14 @@ pub fn parse_args(
                     args.next().context("default cache missing!!")?;
                 arguments.push(Argument::DefaultCache(default_cache));
             }
+            "check" => {
+                anyhow::ensure!(
+                    !arguments.iter().any(|a| matches!(a, Argument::Check(_))),
+                    "check should be specified only once!"
+                );
+                let path = args.next().context("script path missing!!")?;
+                arguments.push(Argument::Check(path));
+            }
 
             _ => (), // ignore unknown argument
         }
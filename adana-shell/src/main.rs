@@ -3,8 +3,8 @@ mod editor;
 
 use adana_cache_command::{clear_terminal, get_default_cache, process_command};
 use adana_db::{Config, Db, DbOp};
-use adana_script::compute;
-use adana_script_core::primitive::Primitive;
+use adana_script::{Diagnostic, check, compute, compute_named};
+use adana_script_core::primitive::{Primitive, ScriptError};
 use anyhow::Context;
 use args::*;
 use log::debug;
@@ -35,6 +35,23 @@ fn main() -> anyhow::Result<()> {
 
     let args = parse_args(std::env::args())?;
 
+    if let Some(script_path) = args.iter().find_map(|a| {
+        if let Argument::Check(path) = a { Some(path) } else { None }
+    }) {
+        let script = std::fs::read_to_string(script_path)
+            .with_context(|| format!("could not read {script_path}"))?;
+        let diagnostics = check(&script, Some(script_path))?;
+        for diagnostic in &diagnostics {
+            eprintln!("{diagnostic}\n");
+        }
+        if !diagnostics.is_empty() {
+            eprintln!("{} problem(s) found", diagnostics.len());
+            std::process::exit(1);
+        }
+        println!("no problem found");
+        return Ok(());
+    }
+
     let config = if args.is_empty() {
         Config::default()
     } else {
@@ -127,8 +144,13 @@ fn main() -> anyhow::Result<()> {
         let mut script_context = BTreeMap::new();
 
         let script_res = {
-            match compute(&script, &mut script_context, &path_to_shared_lib) {
-                Ok(Primitive::Error(e)) => Err(anyhow::Error::msg(e)),
+            match compute_named(
+                &script,
+                script_path.map(String::as_str),
+                &mut script_context,
+                &path_to_shared_lib,
+            ) {
+                Ok(Primitive::Error(e)) => Err(value_error(&e)),
                 Ok(calc) => Ok(calc),
                 e @ Err(_) => e,
             }
@@ -136,7 +158,7 @@ fn main() -> anyhow::Result<()> {
         match script_res {
             Ok(Primitive::Unit) => {}
             Ok(calc) => println!("{calc}"),
-            Err(calc_err) => eprintln!("Error: {calc_err:?}"),
+            Err(calc_err) => eprintln!("Error: {}", script_error(&calc_err)),
         }
         if is_daemon {
             loop {
@@ -163,6 +185,23 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
+/// a located error already holds its stack trace and causes
+fn script_error(err: &anyhow::Error) -> String {
+    match err.downcast_ref::<Diagnostic>() {
+        Some(diagnostic) => diagnostic.to_string(),
+        None => format!("{err:?}"),
+    }
+}
+
+/// an error returned as the result of a script, with its snippet when
+/// it knows where it was raised
+fn value_error(e: &ScriptError) -> anyhow::Error {
+    match Diagnostic::from_error(e) {
+        Some(diagnostic) => anyhow::Error::new(diagnostic),
+        None => anyhow::Error::msg(e.report()),
+    }
+}
+
 fn start_app(
     db: &mut impl DbOp<String, String>,
     history_path: Option<impl AsRef<Path> + Copy>,
@@ -196,7 +235,7 @@ fn start_app(
 
                 let script_res = {
                     match compute(&line, &mut script_context, shared_lib_path) {
-                        Ok(Primitive::Error(e)) => Err(anyhow::Error::msg(e)),
+                        Ok(Primitive::Error(e)) => Err(value_error(&e)),
                         Ok(calc) => Ok(calc),
                         e @ Err(_) => e,
                     }
@@ -206,7 +245,7 @@ fn start_app(
                     Ok(calc) => println!("{calc}"),
                     Err(calc_err) => {
                         if cfg!(debug_assertions) {
-                            eprintln!("Error: {calc_err:?}");
+                            eprintln!("Error: {}", script_error(&calc_err));
                         }
                         match process_command(
                             db,
@@ -217,7 +256,7 @@ fn start_app(
                         ) {
                             Ok(_) => (),
                             Err(err) => {
-                                eprintln!("Error: {calc_err:?}");
+                                eprintln!("Error: {}", script_error(&calc_err));
                                 eprintln!("Err: {err}");
                             }
                         }